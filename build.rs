@@ -0,0 +1,19 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Only the `grpc` feature needs the generated service code
+    #[cfg(feature = "grpc")]
+    compile_protos();
+}
+
+#[cfg(feature = "grpc")]
+fn compile_protos() {
+    println!("cargo:rerun-if-changed=proto");
+
+    // protox parses the definitions in Rust, so building doesn't need protoc
+    let descriptors = protox::compile(["proto/transcription.proto"], ["proto"])
+        .expect("proto/transcription.proto is invalid");
+    tonic_build::configure()
+        .compile_fds(descriptors)
+        .expect("Failed to generate gRPC code");
+}
@@ -0,0 +1,142 @@
+#ifndef AUDIO_TRANSCRIBE_H
+#define AUDIO_TRANSCRIBE_H
+
+/* Generated by cbindgen from crates/audio_transcribe_ffi. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Error code for invalid arguments passed through the C API. Pipeline
+ * failures use the library's stable codes, which start at 10.
+ */
+#define AT_ERROR_INVALID_ARGUMENT 1
+
+/**
+ * Error code for a panic inside the library, caught before it could
+ * unwind into the caller
+ */
+#define AT_ERROR_PANIC 2
+
+/**
+ * Opaque handle owning a transcriber and the runtime it runs on
+ */
+typedef struct AtTranscriber AtTranscriber;
+
+/**
+ * Opaque handle owning the segments of one finished transcription
+ */
+typedef struct AtTranscript AtTranscript;
+
+/**
+ * One transcribed segment. `text` is owned by the transcript and stays
+ * valid until `at_transcript_free`.
+ */
+typedef struct AtSegment {
+  /**
+   * Start time in seconds
+   */
+  double start;
+  /**
+   * End time in seconds
+   */
+  double end;
+  /**
+   * UTF-8, NUL-terminated text
+   */
+  const char *text;
+  /**
+   * Speaker ID, or -1 when unknown
+   */
+  int32_t speaker;
+} AtSegment;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create a transcriber. `model` is one of "tiny", "base", "small",
+ * "medium", "large-v2", "large-v3" or "large-v3-turbo"; "large" is
+ * "large-v3". Append ".en" to tiny to medium for English-only models.
+ * Returns NULL on error.
+ *
+ * # Safety
+ * `model` must be NULL or a valid NUL-terminated string.
+ */
+struct AtTranscriber *at_transcriber_new(const char *model, bool use_gpu);
+
+/**
+ * Free a transcriber created by `at_transcriber_new`. NULL is ignored.
+ *
+ * # Safety
+ * `transcriber` must be NULL or a pointer returned by `at_transcriber_new`
+ * that has not been freed yet.
+ */
+void at_transcriber_free(struct AtTranscriber *transcriber);
+
+/**
+ * Transcribe an audio file, blocking until done. Returns NULL on error.
+ *
+ * # Safety
+ * `transcriber` must be a live transcriber handle and `path` a valid
+ * NUL-terminated string.
+ */
+struct AtTranscript *at_transcribe_file(struct AtTranscriber *transcriber, const char *path);
+
+/**
+ * Number of segments in a transcript
+ *
+ * # Safety
+ * `transcript` must be NULL or a live transcript handle.
+ */
+size_t at_transcript_segment_count(const struct AtTranscript *transcript);
+
+/**
+ * Whether the transcription was cancelled before every chunk finished
+ *
+ * # Safety
+ * `transcript` must be NULL or a live transcript handle.
+ */
+bool at_transcript_is_partial(const struct AtTranscript *transcript);
+
+/**
+ * Copy segment `index` into `out`. Returns false when out of range.
+ *
+ * # Safety
+ * `transcript` must be a live transcript handle and `out` must point to
+ * writable memory for one `AtSegment`.
+ */
+bool at_transcript_segment(const struct AtTranscript *transcript,
+                           size_t index,
+                           struct AtSegment *out);
+
+/**
+ * Free a transcript returned by `at_transcribe_file`. NULL is ignored.
+ *
+ * # Safety
+ * `transcript` must be NULL or a pointer returned by `at_transcribe_file`
+ * that has not been freed yet.
+ */
+void at_transcript_free(struct AtTranscript *transcript);
+
+/**
+ * Message of the last error on this thread, or NULL. Valid until the next
+ * failing call on the same thread.
+ */
+const char *at_last_error(void);
+
+/**
+ * Stable code of the last error on this thread (see
+ * `AudioTranscriptionError::code`), or 0 when no call has failed
+ */
+int32_t at_last_error_code(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* AUDIO_TRANSCRIBE_H */
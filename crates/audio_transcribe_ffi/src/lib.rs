@@ -0,0 +1,286 @@
+//! C ABI for the audio transcription engine.
+//!
+//! Typical use from C:
+//!
+//! ```c
+//! AtTranscriber *t = at_transcriber_new("small", true);
+//! AtTranscript *r = at_transcribe_file(t, "meeting.wav");
+//! for (size_t i = 0; i < at_transcript_segment_count(r); i++) {
+//!     AtSegment seg;
+//!     at_transcript_segment(r, i, &seg);
+//!     printf("[%.1f] %s\n", seg.start, seg.text);
+//! }
+//! at_transcript_free(r);
+//! at_transcriber_free(t);
+//! ```
+//!
+//! Functions that can fail return NULL or false; the reason is available
+//! from `at_last_error` and `at_last_error_code` on the same thread. A
+//! panic inside the library is reported the same way, with
+//! `AT_ERROR_PANIC`, instead of unwinding into C.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use audio_transcription_cli::{AudioTranscriptionError, ModelSize, Transcriber};
+
+/// Error code for invalid arguments passed through the C API. Pipeline
+/// failures use the library's stable codes, which start at 10.
+pub const AT_ERROR_INVALID_ARGUMENT: i32 = 1;
+
+/// Error code for a panic inside the library, caught before it could
+/// unwind into the caller
+pub const AT_ERROR_PANIC: i32 = 2;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<(i32, CString)>> = const { RefCell::new(None) };
+}
+
+/// Opaque handle owning a transcriber and the runtime it runs on
+pub struct AtTranscriber {
+    runtime: tokio::runtime::Runtime,
+    transcriber: Transcriber,
+}
+
+/// Opaque handle owning the segments of one finished transcription
+pub struct AtTranscript {
+    segments: Vec<SegmentData>,
+    partial: bool,
+}
+
+struct SegmentData {
+    start: f32,
+    end: f32,
+    text: CString,
+    speaker: i32,
+}
+
+/// One transcribed segment. `text` is owned by the transcript and stays
+/// valid until `at_transcript_free`.
+#[repr(C)]
+pub struct AtSegment {
+    /// Start time in seconds
+    pub start: f64,
+    /// End time in seconds
+    pub end: f64,
+    /// UTF-8, NUL-terminated text
+    pub text: *const c_char,
+    /// Speaker ID, or -1 when unknown
+    pub speaker: i32,
+}
+
+/// Create a transcriber. `model` is one of "tiny", "base", "small",
+/// "medium", "large-v2", "large-v3" or "large-v3-turbo"; "large" is
+/// "large-v3". Append ".en" to tiny to medium for English-only models.
+/// Returns NULL on error.
+///
+/// # Safety
+/// `model` must be NULL or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn at_transcriber_new(model: *const c_char, use_gpu: bool) -> *mut AtTranscriber {
+    guard(ptr::null_mut(), || {
+        let model = match parse_model(model) {
+            Ok(model) => model,
+            Err(message) => return fail(AT_ERROR_INVALID_ARGUMENT, message),
+        };
+
+        let runtime = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(e) => return fail_with(e.into()),
+        };
+
+        match Transcriber::builder().model(model).gpu(use_gpu).build() {
+            Ok(transcriber) => Box::into_raw(Box::new(AtTranscriber { runtime, transcriber })),
+            Err(e) => fail_with(e),
+        }
+    })
+}
+
+/// Free a transcriber created by `at_transcriber_new`. NULL is ignored.
+///
+/// # Safety
+/// `transcriber` must be NULL or a pointer returned by `at_transcriber_new`
+/// that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn at_transcriber_free(transcriber: *mut AtTranscriber) {
+    guard((), || {
+        if !transcriber.is_null() {
+            drop(Box::from_raw(transcriber));
+        }
+    })
+}
+
+/// Transcribe an audio file, blocking until done. Returns NULL on error.
+///
+/// # Safety
+/// `transcriber` must be a live transcriber handle and `path` a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn at_transcribe_file(
+    transcriber: *mut AtTranscriber,
+    path: *const c_char,
+) -> *mut AtTranscript {
+    guard(ptr::null_mut(), || {
+        let Some(handle) = transcriber.as_ref() else {
+            return fail(AT_ERROR_INVALID_ARGUMENT, "Transcriber handle is NULL");
+        };
+        if path.is_null() {
+            return fail(AT_ERROR_INVALID_ARGUMENT, "Path is NULL");
+        }
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(path) => path,
+            Err(_) => return fail(AT_ERROR_INVALID_ARGUMENT, "Path is not valid UTF-8"),
+        };
+
+        let result = match handle.runtime.block_on(handle.transcriber.transcribe(path)) {
+            Ok(result) => result,
+            Err(e) => return fail_with(e),
+        };
+
+        let segments = result.segments
+            .into_iter()
+            .map(|segment| SegmentData {
+                start: segment.start,
+                end: segment.end,
+                // Interior NULs cannot be represented in a C string
+                text: CString::new(segment.text.replace('\0', "")).unwrap_or_default(),
+                speaker: segment.speaker.map(i32::from).unwrap_or(-1),
+            })
+            .collect();
+
+        Box::into_raw(Box::new(AtTranscript { segments, partial: result.partial }))
+    })
+}
+
+/// Number of segments in a transcript
+///
+/// # Safety
+/// `transcript` must be NULL or a live transcript handle.
+#[no_mangle]
+pub unsafe extern "C" fn at_transcript_segment_count(transcript: *const AtTranscript) -> usize {
+    guard(0, || transcript.as_ref().map(|t| t.segments.len()).unwrap_or(0))
+}
+
+/// Whether the transcription was cancelled before every chunk finished
+///
+/// # Safety
+/// `transcript` must be NULL or a live transcript handle.
+#[no_mangle]
+pub unsafe extern "C" fn at_transcript_is_partial(transcript: *const AtTranscript) -> bool {
+    guard(false, || transcript.as_ref().map(|t| t.partial).unwrap_or(false))
+}
+
+/// Copy segment `index` into `out`. Returns false when out of range.
+///
+/// # Safety
+/// `transcript` must be a live transcript handle and `out` must point to
+/// writable memory for one `AtSegment`.
+#[no_mangle]
+pub unsafe extern "C" fn at_transcript_segment(
+    transcript: *const AtTranscript,
+    index: usize,
+    out: *mut AtSegment,
+) -> bool {
+    guard(false, || {
+        let Some(segment) = transcript.as_ref().and_then(|t| t.segments.get(index)) else {
+            set_error(AT_ERROR_INVALID_ARGUMENT, "Segment index out of range");
+            return false;
+        };
+        if out.is_null() {
+            set_error(AT_ERROR_INVALID_ARGUMENT, "Output pointer is NULL");
+            return false;
+        }
+
+        out.write(AtSegment {
+            start: segment.start as f64,
+            end: segment.end as f64,
+            text: segment.text.as_ptr(),
+            speaker: segment.speaker,
+        });
+        true
+    })
+}
+
+/// Free a transcript returned by `at_transcribe_file`. NULL is ignored.
+///
+/// # Safety
+/// `transcript` must be NULL or a pointer returned by `at_transcribe_file`
+/// that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn at_transcript_free(transcript: *mut AtTranscript) {
+    guard((), || {
+        if !transcript.is_null() {
+            drop(Box::from_raw(transcript));
+        }
+    })
+}
+
+/// Message of the last error on this thread, or NULL. Valid until the next
+/// failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn at_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error.borrow().as_ref().map(|(_, message)| message.as_ptr()).unwrap_or(ptr::null())
+    })
+}
+
+/// Stable code of the last error on this thread (see
+/// `AudioTranscriptionError::code`), or 0 when no call has failed
+#[no_mangle]
+pub extern "C" fn at_last_error_code() -> i32 {
+    LAST_ERROR.with(|error| error.borrow().as_ref().map(|(code, _)| *code).unwrap_or(0))
+}
+
+unsafe fn parse_model(model: *const c_char) -> Result<ModelSize, String> {
+    if model.is_null() {
+        return Ok(ModelSize::Medium);
+    }
+    let name = CStr::from_ptr(model).to_str().map_err(|_| "Model name is not valid UTF-8".to_string())?;
+    name.parse()
+}
+
+/// Run the body of an exported function, turning a panic into an
+/// `AT_ERROR_PANIC` error and `on_panic`; unwinding into C is undefined
+/// behaviour
+fn guard<T>(on_panic: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown cause");
+        set_error(AT_ERROR_PANIC, format!("Internal panic: {}", message));
+        on_panic
+    })
+}
+
+fn set_error(code: i32, message: impl Into<String>) {
+    let message = CString::new(message.into().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some((code, message)));
+}
+
+fn fail<T>(code: i32, message: impl Into<String>) -> *mut T {
+    set_error(code, message);
+    ptr::null_mut()
+}
+
+fn fail_with<T>(error: AudioTranscriptionError) -> *mut T {
+    fail(error.code().into(), error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_becomes_an_error() {
+        let transcript: *mut AtTranscript = guard(ptr::null_mut(), || panic!("segment out of bounds"));
+
+        assert!(transcript.is_null());
+        assert_eq!(at_last_error_code(), AT_ERROR_PANIC);
+        let message = unsafe { CStr::from_ptr(at_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "Internal panic: segment out of bounds");
+    }
+}
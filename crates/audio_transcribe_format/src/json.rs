@@ -0,0 +1,313 @@
+//! JSON transcript documents.
+//!
+//! The document layout is versioned by [`SCHEMA_VERSION`]. Within a version,
+//! fields may be added but are never renamed, removed or changed in meaning.
+//! All times and durations are in seconds.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "audio": { "duration": 62.5, "sample_rate": 44100, "channels": 2, "codec": "mp3", "file_size": 1003520 },
+//!   "model": { "whisper": "base", "diarization": "pyannote", "language": "en", "task": "transcribe", "load_time": 0.6, "refine": null },
+//!   "stats": {
+//!     "processing_time": 12.1,
+//!     "realtime_factor": 5.17,
+//!     "stages": { "vad": 0.4, "chunking": 0.0, "transcription": 9.8, "refinement": 0.0, "diarization": 1.8, "merging": 0.1 },
+//!     "tokens": 412,
+//!     "tokens_per_second": 44.9,
+//!     "partial": false,
+//!     "skipped": []
+//!   },
+//!   "speakers": [
+//!     { "speaker": 1, "name": "Alice", "speaking_time": 1.2, "words": 2, "turns": 1, "interruptions": 0, "longest_monologue": 1.2 }
+//!   ],
+//!   "segments": [
+//!     {
+//!       "start": 0.0, "end": 1.2, "speaker": 1, "text": "Good morning.", "confidence": 0.93,
+//!       "words": [
+//!         { "start": 0.0, "end": 0.5, "text": "Good", "confidence": 0.97 },
+//!         { "start": 0.5, "end": 1.2, "text": "morning.", "confidence": 0.89 }
+//!       ],
+//!       "speaker_name": "Alice"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `speaker`, `confidence` and `language` are `null` when unknown, as is
+//! `speaker_name` unless the speaker was recognized as an enrolled voice;
+//! `words` is empty unless word timestamps were requested. `task` is
+//! `translate` when the text is Whisper's English translation of speech in
+//! `language`. `load_time` is how long loading the Whisper model took, and
+//! is included in the `transcription` stage. `refine` names the larger
+//! model that transcribed low-confidence segments again, in the
+//! `refinement` stage. `tokens` counts the tokens the transcription stage
+//! decoded, timestamp tokens included, and `tokens_per_second`
+//! divides them by the transcription time without the model load. A segment whose chunk failed has the text
+//! `[untranscribed audio]`; its range is also listed under `stats.skipped`.
+//! `speakers` sums up each speaker's segments, longest talk time first: see
+//! [`SpeakerStats`](crate::stats::SpeakerStats). It is derived from
+//! `segments` and not read back.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::stats::{self, SpeakerStats};
+use crate::{AudioMetadata, ModelInfo, SkippedRange, SpeechSegment, StageTimings, TranscriptResult};
+
+/// Version written to, and understood in, the `version` field
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A transcript as written by the `json` format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Document {
+    pub version: u32,
+    pub audio: Audio,
+    pub model: Model,
+    pub stats: Stats,
+    #[serde(default)]
+    pub speakers: Vec<SpeakerStats>,  // Derived from the segments; ignored when parsing
+    pub segments: Vec<SpeechSegment>,
+}
+
+/// The source recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Audio {
+    pub duration: f64,
+    pub sample_rate: u32,  // Native rate, before resampling to 16 kHz
+    pub channels: u16,
+    pub codec: String,
+    pub file_size: u64,    // Bytes
+}
+
+/// Models the transcript was produced with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Model {
+    pub whisper: String,
+    pub diarization: String,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub task: Task,
+    #[serde(default)]
+    pub load_time: f64,
+    #[serde(default)]
+    pub refine: Option<String>,
+}
+
+/// What Whisper did with the speech
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Task {
+    #[default]
+    Transcribe,
+    Translate,  // Into English
+}
+
+/// How the run went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stats {
+    pub processing_time: f64,
+    pub realtime_factor: f32,  // Seconds of audio per second of processing
+    pub stages: Stages,
+    #[serde(default)]
+    pub tokens: u64,
+    #[serde(default)]
+    pub tokens_per_second: f32,
+    pub partial: bool,         // Cancelled before every chunk finished
+    pub skipped: Vec<SkippedRange>,
+}
+
+/// Wall-clock time of each pipeline stage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stages {
+    pub vad: f64,
+    pub chunking: f64,
+    pub transcription: f64,
+    #[serde(default)]
+    pub refinement: f64,
+    pub diarization: f64,
+    pub merging: f64,
+}
+
+impl From<&TranscriptResult> for Document {
+    fn from(result: &TranscriptResult) -> Self {
+        let timings = &result.stage_timings;
+        Self {
+            version: SCHEMA_VERSION,
+            audio: Audio {
+                duration: result.audio_duration.as_secs_f64(),
+                sample_rate: result.metadata.sample_rate,
+                channels: result.metadata.channels,
+                codec: result.metadata.codec.clone(),
+                file_size: result.metadata.file_size,
+            },
+            model: Model {
+                whisper: result.model_info.whisper_model.clone(),
+                diarization: result.model_info.diarization_model.clone(),
+                language: result.model_info.language.clone(),
+                task: if result.model_info.translated { Task::Translate } else { Task::Transcribe },
+                load_time: result.model_info.load_time.as_secs_f64(),
+                refine: result.model_info.refine_model.clone(),
+            },
+            stats: Stats {
+                processing_time: result.processing_time.as_secs_f64(),
+                realtime_factor: result.realtime_factor(),
+                stages: Stages {
+                    vad: timings.vad.as_secs_f64(),
+                    chunking: timings.chunking.as_secs_f64(),
+                    transcription: timings.transcription.as_secs_f64(),
+                    refinement: timings.refinement.as_secs_f64(),
+                    diarization: timings.diarization.as_secs_f64(),
+                    merging: timings.merging.as_secs_f64(),
+                },
+                tokens: result.tokens,
+                tokens_per_second: result.tokens_per_second(),
+                partial: result.partial,
+                skipped: result.skipped.clone(),
+            },
+            speakers: stats::speaker_stats(&result.segments),
+            segments: result.segments.clone(),
+        }
+    }
+}
+
+impl From<Document> for TranscriptResult {
+    fn from(document: Document) -> Self {
+        let seconds = |secs: f64| Duration::try_from_secs_f64(secs).unwrap_or_default();
+        let stages = &document.stats.stages;
+        let audio_duration = seconds(document.audio.duration);
+        let processing_time = seconds(document.stats.processing_time);
+        Self {
+            segments: document.segments,
+            audio_duration,
+            processing_time,
+            stage_timings: StageTimings {
+                vad: seconds(stages.vad),
+                chunking: seconds(stages.chunking),
+                transcription: seconds(stages.transcription),
+                refinement: seconds(stages.refinement),
+                diarization: seconds(stages.diarization),
+                merging: seconds(stages.merging),
+            },
+            model_info: ModelInfo {
+                whisper_model: document.model.whisper,
+                diarization_model: document.model.diarization,
+                processing_time,
+                load_time: seconds(document.model.load_time),
+                language: document.model.language,
+                translated: document.model.task == Task::Translate,
+                refine_model: document.model.refine,
+            },
+            metadata: AudioMetadata {
+                duration: audio_duration,
+                sample_rate: document.audio.sample_rate,
+                channels: document.audio.channels,
+                codec: document.audio.codec,
+                file_size: document.audio.file_size,
+            },
+            partial: document.stats.partial,
+            skipped: document.stats.skipped,
+            tokens: document.stats.tokens,
+        }
+    }
+}
+
+/// Serialize a transcript result as a pretty-printed JSON document
+pub fn format(result: &TranscriptResult) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&Document::from(result))
+}
+
+/// Parse a transcript previously written by [`format`]. Files from before
+/// the schema was versioned, which held the raw result, are read as well.
+pub fn parse(json: &str) -> serde_json::Result<TranscriptResult> {
+    match serde_json::from_str::<Document>(json) {
+        Ok(document) => Ok(document.into()),
+        Err(e) => serde_json::from_str::<TranscriptResult>(json).map_err(|_| e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use crate::WordTiming;
+
+    fn sample_result() -> TranscriptResult {
+        TranscriptResult {
+            segments: vec![SpeechSegment {
+                start: 0.0,
+                end: 1.5,
+                text: "Hello there.".to_string(),
+                speaker: Some(1),
+                confidence: Some(0.9),
+                words: vec![WordTiming { start: 0.0, end: 0.6, text: "Hello".to_string(), confidence: 0.95 }],
+                speaker_name: None,
+            }],
+            audio_duration: Duration::from_secs(30),
+            processing_time: Duration::from_secs(10),
+            stage_timings: StageTimings { transcription: Duration::from_millis(8_500), ..StageTimings::default() },
+            model_info: ModelInfo {
+                whisper_model: "base".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(10),
+                language: Some("en".to_string()),
+                ..Default::default()
+            },
+            metadata: AudioMetadata { duration: Duration::from_secs(30), sample_rate: 44_100, ..AudioMetadata::default() },
+            tokens: 170,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_document_layout() {
+        let json: serde_json::Value = serde_json::from_str(&format(&sample_result()).unwrap()).unwrap();
+
+        assert_eq!(json["version"], SCHEMA_VERSION);
+        assert_eq!(json["audio"]["duration"], 30.0);
+        assert_eq!(json["model"]["language"], "en");
+        assert_eq!(json["model"]["task"], "transcribe");
+        assert_eq!(json["stats"]["realtime_factor"], 3.0);
+        assert_eq!(json["stats"]["stages"]["transcription"], 8.5);
+        assert_eq!(json["stats"]["tokens"], 170);
+        assert_eq!(json["stats"]["tokens_per_second"], 20.0);
+        assert_eq!(json["segments"][0]["speaker"], 1);
+        assert_eq!(json["segments"][0]["words"][0]["text"], "Hello");
+        assert_eq!(json["speakers"][0]["name"], "SPEAKER_01");
+        assert_eq!(json["speakers"][0]["speaking_time"], 1.5);
+        assert_eq!(json["speakers"][0]["words"], 2);
+    }
+
+    #[test]
+    fn test_round_trip_and_legacy_files() {
+        let mut result = sample_result();
+        result.model_info.translated = true;
+        result.model_info.load_time = Duration::from_millis(1_250);
+        result.model_info.refine_model = Some("large-v3".to_string());
+        result.stage_timings.refinement = Duration::from_millis(700);
+        let parsed = parse(&format(&result).unwrap()).unwrap();
+        assert!(parsed.model_info.translated);
+        assert_eq!(parsed.model_info.load_time, Duration::from_millis(1_250));
+        assert_eq!(parsed.tokens, 170);
+        assert_eq!(parsed.model_info.refine_model.as_deref(), Some("large-v3"));
+        assert_eq!(parsed.stage_timings.refinement, Duration::from_millis(700));
+        assert!((parsed.tokens_per_second() - 23.448).abs() < 1e-3);
+        assert_eq!(parsed.segments[0].words, result.segments[0].words);
+        assert_eq!(parsed.stage_timings.transcription, Duration::from_millis(8_500));
+        assert_eq!(parsed.metadata.sample_rate, 44_100);
+
+        let mut legacy: serde_json::Value = serde_json::to_value(&result).unwrap();
+        legacy["segments"][0].as_object_mut().unwrap().retain(|key, _| key != "confidence" && key != "words");
+        legacy["model_info"].as_object_mut().unwrap().remove("load_time");
+        legacy["stage_timings"].as_object_mut().unwrap().remove("refinement");
+        legacy.as_object_mut().unwrap().remove("tokens");
+        let parsed = parse(&legacy.to_string()).unwrap();
+        assert_eq!(parsed.model_info.load_time, Duration::ZERO);
+        assert_eq!(parsed.tokens, 0);
+        assert_eq!(parsed.stage_timings.refinement, Duration::ZERO);
+        assert_eq!(parsed.segments[0].text, "Hello there.");
+        assert!(parsed.segments[0].words.is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+//! Transcript data types and output formatters shared by the CLI, the
+//! library and web frontends.
+//!
+//! The crate is `no_std` (it only needs `alloc`), so it compiles to
+//! `wasm32-unknown-unknown` and formats stored results with exactly the same
+//! logic as the CLI. Enable the `wasm` feature for JavaScript bindings.
+
+#![no_std]
+
+extern crate alloc;
+
+mod result;
+mod timestamp;
+
+pub mod markdown;
+pub mod notes;
+pub mod srt;
+pub mod stats;
+pub mod text;
+pub mod vtt;
+
+#[cfg(feature = "json")]
+pub mod json;
+
+#[cfg(feature = "wasm")]
+mod wasm;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+pub use result::{realtime_factor, AudioMetadata, ModelInfo, SkippedRange, SpeechSegment, StageTimings, TranscriptResult, WordTiming};
+pub use timestamp::format_timestamp;
+
+/// Renders a transcript into one output file format.
+///
+/// The built-in formats implement this through [`OutputFormat`]; library
+/// users can implement it for their own formats and register them with the
+/// transcript generator.
+pub trait OutputFormatter: Send + Sync {
+    /// Name used to select the format, e.g. `"srt"`
+    fn name(&self) -> &str;
+
+    /// File extension of the output, without the dot
+    fn extension(&self) -> &str;
+
+    /// Rendered file contents; UTF-8 for text formats
+    fn format(&self, result: &TranscriptResult) -> Vec<u8>;
+}
+
+/// Transcript output formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Srt,
+    Vtt,
+    Markdown,
+    Notes,  // Markdown with YAML frontmatter for Obsidian, Notion and similar
+    #[cfg(feature = "json")]
+    Json,
+}
+
+impl OutputFormat {
+    /// Every built-in format
+    pub const ALL: &'static [OutputFormat] = &[
+        Self::Text,
+        Self::Srt,
+        Self::Vtt,
+        Self::Markdown,
+        Self::Notes,
+        #[cfg(feature = "json")]
+        Self::Json,
+    ];
+
+    /// Look up a format by name or file extension, e.g. `"srt"` or `"md"`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "txt" | "text" => Some(Self::Text),
+            "srt" => Some(Self::Srt),
+            "vtt" | "webvtt" => Some(Self::Vtt),
+            "md" | "markdown" => Some(Self::Markdown),
+            "notes" | "obsidian" | "notion" => Some(Self::Notes),
+            #[cfg(feature = "json")]
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Name used to select the format; the extension, except for notes,
+    /// which share `md` with plain Markdown
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Notes => "notes",
+            other => other.extension(),
+        }
+    }
+
+    /// File extension written for this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Markdown | Self::Notes => "md",
+            #[cfg(feature = "json")]
+            Self::Json => "json",
+        }
+    }
+
+    /// Render a transcript result in this format
+    pub fn render(self, result: &TranscriptResult) -> String {
+        match self {
+            Self::Text => text::format(&result.segments, &text::TextOptions::default()),
+            Self::Srt => srt::format(&result.segments),
+            Self::Vtt => vtt::format(&result.segments),
+            Self::Markdown => markdown::format(&result.segments),
+            Self::Notes => notes::format(result, &notes::NoteOptions::default()),
+            // Plain structs with string keys always serialize
+            #[cfg(feature = "json")]
+            Self::Json => json::format(result).unwrap_or_default(),
+        }
+    }
+}
+
+impl OutputFormatter for OutputFormat {
+    fn name(&self) -> &str {
+        OutputFormat::name(*self)
+    }
+
+    fn extension(&self) -> &str {
+        OutputFormat::extension(*self)
+    }
+
+    fn format(&self, result: &TranscriptResult) -> Vec<u8> {
+        self.render(result).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use core::time::Duration;
+
+    fn sample_result() -> TranscriptResult {
+        TranscriptResult {
+            segments: vec![SpeechSegment { start: 0.0, end: 1.5, text: "Hello.".to_string(), speaker: Some(1), ..Default::default() }],
+            audio_duration: Duration::from_secs(2),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                language: Some("en".to_string()),
+                ..Default::default()
+            },
+            metadata: AudioMetadata {
+                duration: Duration::from_secs(2),
+                sample_rate: 44_100,
+                channels: 2,
+                codec: "mp3".to_string(),
+                file_size: 32_000,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_names() {
+        assert_eq!(OutputFormat::from_name("SRT"), Some(OutputFormat::Srt));
+        assert_eq!(OutputFormat::from_name("md"), Some(OutputFormat::Markdown));
+        assert_eq!(OutputFormat::from_name("docx"), None);
+        assert_eq!(OutputFormat::Vtt.extension(), "vtt");
+        assert_eq!(OutputFormat::from_name("obsidian"), Some(OutputFormat::Notes));
+        assert_eq!(OutputFormat::Notes.extension(), "md");
+    }
+
+    #[test]
+    fn test_render_dispatches_on_format() {
+        let result = sample_result();
+
+        assert_eq!(OutputFormat::Text.render(&result), "[SPEAKER_01]\nHello.\n");
+        assert!(OutputFormat::Srt.render(&result).starts_with("1\n00:00:00,000 --> 00:00:01,500\n"));
+    }
+
+    #[test]
+    fn test_builtin_formatters_round_trip_names() {
+        for format in OutputFormat::ALL {
+            assert_eq!(OutputFormat::from_name(format.name()), Some(*format));
+        }
+    }
+
+    #[test]
+    fn test_skipped_range_display() {
+        let range = SkippedRange { start: 120.0, end: 240.5, attempts: 3, error: "CUDA error".to_string() };
+        assert_eq!(range.to_string(), "00:02:00.000 - 00:04:00.500 (3 attempts): CUDA error");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_round_trip() {
+        let result = sample_result();
+        let parsed = json::parse(&json::format(&result).unwrap()).unwrap();
+
+        assert_eq!(parsed.segments[0].text, "Hello.");
+        assert_eq!(parsed.audio_duration, Duration::from_secs(2));
+        assert_eq!(parsed.metadata.codec, "mp3");
+        assert_eq!(parsed.model_info.language.as_deref(), Some("en"));
+        assert_eq!(OutputFormat::Text.render(&parsed), OutputFormat::Text.render(&result));
+    }
+}
@@ -0,0 +1,53 @@
+//! Markdown transcript with a bold speaker label and start time per turn
+
+use alloc::format;
+use alloc::string::String;
+use crate::{format_timestamp, SpeechSegment};
+
+/// Format segments as Markdown, one paragraph per speaker turn
+pub fn format(segments: &[SpeechSegment]) -> String {
+    let mut output = String::new();
+    let mut current_speaker: Option<Option<u8>> = None;
+
+    for segment in segments {
+        if current_speaker != Some(segment.speaker) {
+            if current_speaker.is_some() {
+                output.push_str("\n\n");
+            }
+
+            let timestamp = format_timestamp(segment.start, '.');
+            output.push_str(&format!("**{}** `{}`\n", segment.speaker_label(), &timestamp[..8]));
+            current_speaker = Some(segment.speaker);
+        } else {
+            output.push(' ');
+        }
+
+        output.push_str(segment.text.trim());
+    }
+
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_paragraph_per_turn() {
+        let segments = vec![
+            SpeechSegment { start: 0.0, end: 1.0, text: "Hello.".to_string(), speaker: Some(1), ..Default::default() },
+            SpeechSegment { start: 1.0, end: 2.0, text: " Welcome.".to_string(), speaker: Some(1), ..Default::default() },
+            SpeechSegment { start: 65.0, end: 66.0, text: "Thanks.".to_string(), speaker: Some(2), ..Default::default() },
+        ];
+
+        assert_eq!(
+            format(&segments),
+            "**SPEAKER_01** `00:00:00`\nHello. Welcome.\n\n**SPEAKER_02** `00:01:05`\nThanks.\n"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Markdown meeting notes for knowledge bases such as Obsidian or Notion:
+//! YAML frontmatter followed by one paragraph per speaker turn, with
+//! speakers written as `[[wiki links]]`
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::{format_timestamp, OutputFormatter, TranscriptResult};
+
+/// Frontmatter values that don't come from the transcript itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteOptions {
+    pub title: Option<String>,  // Also used as the heading
+    pub date: Option<String>,   // Meeting date, e.g. `2024-05-01`
+    pub tags: Vec<String>,
+    pub attendees: Vec<String>, // Names given to speakers in order of first appearance
+}
+
+impl Default for NoteOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            date: None,
+            tags: vec!["meeting".to_string(), "transcript".to_string()],
+            attendees: Vec::new(),
+        }
+    }
+}
+
+/// Page name used for a speaker; stable across transcripts so the same
+/// link collects all of a speaker's meetings
+pub fn speaker_name(speaker: Option<u8>) -> String {
+    match speaker {
+        Some(id) => format!("Speaker {}", id),
+        None => "Unknown speaker".to_string(),
+    }
+}
+
+/// Page name for a speaker in this note: the name the speaker was
+/// recognized as, or else the attendee at the speaker's position in
+/// `participants` when there is one
+fn participant_name(speaker: Option<u8>, participants: &[(u8, Option<&str>)], options: &NoteOptions) -> String {
+    let position = speaker.and_then(|id| participants.iter().position(|&(p, _)| p == id));
+    position
+        .and_then(|index| participants[index].1.or(options.attendees.get(index).map(String::as_str)))
+        .map(str::to_string)
+        .unwrap_or_else(|| speaker_name(speaker))
+}
+
+/// Double-quoted YAML scalar
+fn yaml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Format a transcript as a Markdown note
+pub fn format(result: &TranscriptResult, options: &NoteOptions) -> String {
+    // Participants in order of first appearance, with recognized names
+    let mut participants: Vec<(u8, Option<&str>)> = Vec::new();
+    for segment in &result.segments {
+        if let Some(speaker) = segment.speaker.filter(|&speaker| !participants.iter().any(|&(p, _)| p == speaker)) {
+            participants.push((speaker, segment.speaker_name.as_deref()));
+        }
+    }
+
+    let mut output = String::from("---\n");
+    if let Some(title) = &options.title {
+        output.push_str(&format!("title: {}\n", yaml_string(title)));
+    }
+    if let Some(date) = &options.date {
+        output.push_str(&format!("date: {}\n", date));
+    }
+    let duration = format_timestamp(result.audio_duration.as_secs_f32(), '.');
+    output.push_str(&format!("duration: {}\n", yaml_string(&duration[..8])));
+    output.push_str("participants:\n");
+    for &(speaker, _) in &participants {
+        let name = participant_name(Some(speaker), &participants, options);
+        output.push_str(&format!("  - {}\n", yaml_string(&format!("[[{}]]", name))));
+    }
+    // Attendees who were not heard are still listed
+    for name in options.attendees.iter().skip(participants.len()) {
+        output.push_str(&format!("  - {}\n", yaml_string(&format!("[[{}]]", name))));
+    }
+    output.push_str("tags:\n");
+    for tag in &options.tags {
+        output.push_str(&format!("  - {}\n", tag));
+    }
+    output.push_str("---\n");
+
+    if let Some(title) = &options.title {
+        output.push_str(&format!("\n# {}\n", title));
+    }
+
+    let mut current_speaker: Option<Option<u8>> = None;
+    for segment in &result.segments {
+        if current_speaker != Some(segment.speaker) {
+            if current_speaker.is_some() {
+                output.push('\n');
+            }
+            let timestamp = format_timestamp(segment.start, '.');
+            output.push_str(&format!("\n**[[{}]]** `{}`\n", participant_name(segment.speaker, &participants, options), &timestamp[..8]));
+            current_speaker = Some(segment.speaker);
+        } else {
+            output.push(' ');
+        }
+
+        output.push_str(segment.text.trim());
+    }
+
+    if current_speaker.is_some() {
+        output.push('\n');
+    }
+    output
+}
+
+/// The note format with custom frontmatter values; register it in place of
+/// the built-in `notes` format, which has no title or date
+#[derive(Debug, Clone, Default)]
+pub struct NotesFormatter {
+    pub options: NoteOptions,
+}
+
+impl OutputFormatter for NotesFormatter {
+    fn name(&self) -> &str {
+        "notes"
+    }
+
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn format(&self, result: &TranscriptResult) -> Vec<u8> {
+        format(result, &self.options).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+    use crate::{ModelInfo, SpeechSegment};
+
+    fn sample_result() -> TranscriptResult {
+        TranscriptResult {
+            segments: vec![
+                SpeechSegment { start: 0.0, end: 1.0, text: "Hello.".to_string(), speaker: Some(1), ..Default::default() },
+                SpeechSegment { start: 1.0, end: 2.0, text: " Welcome.".to_string(), speaker: Some(1), ..Default::default() },
+                SpeechSegment { start: 65.0, end: 66.0, text: "Thanks.".to_string(), speaker: Some(2), ..Default::default() },
+            ],
+            audio_duration: Duration::from_secs(2712),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_frontmatter_and_wiki_links() {
+        let options = NoteOptions {
+            title: Some("Weekly \"sync\"".to_string()),
+            date: Some("2024-05-01".to_string()),
+            ..NoteOptions::default()
+        };
+
+        assert_eq!(
+            format(&sample_result(), &options),
+            "---\n\
+             title: \"Weekly \\\"sync\\\"\"\n\
+             date: 2024-05-01\n\
+             duration: \"00:45:12\"\n\
+             participants:\n  - \"[[Speaker 1]]\"\n  - \"[[Speaker 2]]\"\n\
+             tags:\n  - meeting\n  - transcript\n\
+             ---\n\
+             \n# Weekly \"sync\"\n\
+             \n**[[Speaker 1]]** `00:00:00`\nHello. Welcome.\n\
+             \n**[[Speaker 2]]** `00:01:05`\nThanks.\n"
+        );
+    }
+
+    #[test]
+    fn test_attendees_name_speakers() {
+        let options = NoteOptions {
+            attendees: vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()],
+            ..NoteOptions::default()
+        };
+
+        let note = format(&sample_result(), &options);
+        assert!(note.contains("participants:\n  - \"[[Alice]]\"\n  - \"[[Bob]]\"\n  - \"[[Carol]]\"\n"));
+        assert!(note.contains("**[[Alice]]** `00:00:00`"));
+        assert!(note.contains("**[[Bob]]** `00:01:05`"));
+    }
+}
@@ -0,0 +1,171 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+use crate::timestamp::format_timestamp;
+
+/// A segment of speech with timing and optional speaker
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpeechSegment {
+    pub start: f32,           // Start time in seconds
+    pub end: f32,             // End time in seconds
+    pub text: String,         // Transcribed text
+    pub speaker: Option<u8>,  // Speaker ID (assigned after diarization)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub confidence: Option<f32>,  // Mean token probability, 0-1
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub words: Vec<WordTiming>,  // Empty unless word timestamps were requested
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub speaker_name: Option<String>,  // Enrolled voice the speaker was recognized as
+}
+
+impl SpeechSegment {
+    /// The speaker's name when it was recognized, else `SPEAKER_NN`
+    pub fn speaker_label(&self) -> String {
+        match &self.speaker_name {
+            Some(name) => name.clone(),
+            None => format!("SPEAKER_{:02}", self.speaker.unwrap_or(0)),
+        }
+    }
+}
+
+/// A word of a segment with its own timing
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WordTiming {
+    pub start: f32,       // Seconds
+    pub end: f32,
+    pub text: String,     // Including trailing punctuation, without spaces
+    pub confidence: f32,  // Mean probability of its tokens, 0-1
+}
+
+/// Model information for the transcript
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModelInfo {
+    pub whisper_model: String,
+    pub diarization_model: String,
+    pub processing_time: Duration,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub load_time: Duration,       // Loading the Whisper model, part of the transcription stage
+    pub language: Option<String>,  // Spoken language detected by the model
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub translated: bool,          // Text is Whisper's English translation of the speech
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub refine_model: Option<String>,  // Larger model that transcribed low-confidence segments again
+}
+
+/// Properties of the source audio, probed once before processing
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioMetadata {
+    pub duration: Duration,
+    pub sample_rate: u32,  // Native sample rate, before resampling to 16 kHz
+    pub channels: u16,
+    pub codec: String,     // Short codec name, e.g. "pcm_s16le" or "mp3"
+    pub file_size: u64,    // Bytes
+}
+
+/// Wall-clock time spent in each pipeline stage
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StageTimings {
+    pub vad: Duration,
+    pub chunking: Duration,
+    pub transcription: Duration,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub refinement: Duration,  // Second pass over low-confidence segments
+    pub diarization: Duration,
+    pub merging: Duration,
+}
+
+impl StageTimings {
+    /// Stage names paired with their durations, in pipeline order
+    pub fn stages(&self) -> [(&'static str, Duration); 6] {
+        [
+            ("vad", self.vad),
+            ("chunking", self.chunking),
+            ("transcription", self.transcription),
+            ("refinement", self.refinement),
+            ("diarization", self.diarization),
+            ("merging", self.merging),
+        ]
+    }
+}
+
+/// Final transcript result. `Default` is an empty transcript, mostly
+/// useful to fill in the fields a caller doesn't care about.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TranscriptResult {
+    pub segments: Vec<SpeechSegment>,
+    pub audio_duration: Duration,
+    pub processing_time: Duration,
+    pub stage_timings: StageTimings,
+    pub model_info: ModelInfo,
+    pub metadata: AudioMetadata,
+    pub partial: bool,  // Processing was cancelled before every chunk finished
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub skipped: Vec<SkippedRange>,  // Chunks that failed every attempt, in file order
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tokens: u64,  // Tokens decoded in the transcription stage of this run, timestamps included
+}
+
+/// Audio left untranscribed because its chunk failed on every attempt; the
+/// transcript has a gap marker segment in its place
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SkippedRange {
+    pub start: f32,     // Seconds
+    pub end: f32,
+    pub attempts: u32,
+    pub error: String,  // Error of the last attempt
+}
+
+impl TranscriptResult {
+    /// Seconds of audio processed per second of wall-clock time
+    pub fn realtime_factor(&self) -> f32 {
+        realtime_factor(self.audio_duration, self.processing_time)
+    }
+
+    /// Tokens decoded per second of transcription, not counting the time
+    /// spent loading the model; 0.0 when nothing was decoded
+    pub fn tokens_per_second(&self) -> f32 {
+        let decoding = self.stage_timings.transcription.saturating_sub(self.model_info.load_time);
+        if decoding.is_zero() {
+            return 0.0;
+        }
+        self.tokens as f32 / decoding.as_secs_f32()
+    }
+
+    /// Realtime factor of each stage measured against the full audio duration
+    pub fn stage_realtime_factors(&self) -> Vec<(&'static str, f32)> {
+        self.stage_timings
+            .stages()
+            .into_iter()
+            .map(|(name, elapsed)| (name, realtime_factor(self.audio_duration, elapsed)))
+            .collect()
+    }
+}
+
+impl core::fmt::Display for SkippedRange {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} - {} ({} attempts): {}",
+            format_timestamp(self.start, '.'),
+            format_timestamp(self.end, '.'),
+            self.attempts,
+            self.error
+        )
+    }
+}
+
+/// Ratio of audio duration to processing time; 0.0 when nothing was measured
+pub fn realtime_factor(audio_duration: Duration, elapsed: Duration) -> f32 {
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+    audio_duration.as_secs_f32() / elapsed.as_secs_f32()
+}
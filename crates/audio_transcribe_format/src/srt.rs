@@ -0,0 +1,49 @@
+//! SubRip (`.srt`) subtitles
+
+use alloc::format;
+use alloc::string::String;
+use crate::{format_timestamp, SpeechSegment};
+
+/// Format segments as numbered SRT cues, prefixing the text with the
+/// speaker label, or name, when one is known
+pub fn format(segments: &[SpeechSegment]) -> String {
+    let mut output = String::new();
+
+    for (index, segment) in segments.iter().enumerate() {
+        output.push_str(&format!(
+            "{}\n{} --> {}\n",
+            index + 1,
+            format_timestamp(segment.start, ','),
+            format_timestamp(segment.end, ',')
+        ));
+
+        if segment.speaker.is_some() {
+            output.push_str(&format!("[{}] ", segment.speaker_label()));
+        }
+        output.push_str(segment.text.trim());
+        output.push_str("\n\n");
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_numbered_cues() {
+        let segments = vec![
+            SpeechSegment { start: 0.0, end: 2.5, text: " Hello.".to_string(), speaker: Some(1), ..Default::default() },
+            SpeechSegment { start: 2.5, end: 4.0, text: "Hi.".to_string(), ..Default::default() },
+        ];
+
+        assert_eq!(
+            format(&segments),
+            "1\n00:00:00,000 --> 00:00:02,500\n[SPEAKER_01] Hello.\n\n\
+             2\n00:00:02,500 --> 00:00:04,000\nHi.\n\n"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Talk-time statistics per speaker
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::timestamp::format_timestamp;
+use crate::SpeechSegment;
+
+/// Overlap with the previous speaker a turn needs to count as an
+/// interruption; shorter ones are timing jitter at a speaker change
+pub const MIN_INTERRUPTION_SECS: f32 = 0.3;
+
+/// How much one speaker said
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpeakerStats {
+    pub speaker: u8,
+    pub name: String,            // Label the transcripts show, e.g. SPEAKER_01
+    pub speaking_time: f32,      // Seconds, summed over the speaker's segments
+    pub words: usize,
+    pub turns: usize,            // Runs of consecutive segments
+    pub interruptions: usize,    // Turns started while another speaker was still talking
+    pub longest_monologue: f32,  // Seconds of the longest turn
+}
+
+/// Statistics of every speaker, the one who spoke longest first. Segments
+/// without a speaker are left out.
+pub fn speaker_stats(segments: &[SpeechSegment]) -> Vec<SpeakerStats> {
+    let mut stats: Vec<SpeakerStats> = Vec::new();
+    // The turn in progress: its speaker, start and end
+    let mut turn: Option<(u8, f32, f32)> = None;
+
+    for segment in segments {
+        let Some(speaker) = segment.speaker else { continue };
+        let index = match stats.iter().position(|entry| entry.speaker == speaker) {
+            Some(index) => index,
+            None => {
+                stats.push(SpeakerStats { speaker, name: segment.speaker_label(), ..SpeakerStats::default() });
+                stats.len() - 1
+            }
+        };
+        let entry = &mut stats[index];
+        entry.speaking_time += (segment.end - segment.start).max(0.0);
+        entry.words += segment.text.split_whitespace().count();
+
+        match &mut turn {
+            Some((current, _, end)) if *current == speaker => *end = end.max(segment.end),
+            previous => {
+                if let Some((_, _, previous_end)) = previous {
+                    if *previous_end - segment.start >= MIN_INTERRUPTION_SECS {
+                        entry.interruptions += 1;
+                    }
+                }
+                entry.turns += 1;
+                *previous = Some((speaker, segment.start, segment.end));
+            }
+        }
+        if let Some((_, start, end)) = turn {
+            entry.longest_monologue = entry.longest_monologue.max(end - start);
+        }
+    }
+
+    stats.sort_by(|a, b| b.speaking_time.total_cmp(&a.speaking_time));
+    stats
+}
+
+/// A `[SPEAKER STATISTICS]` block for the end of a plain-text transcript,
+/// one line per speaker; empty when no segment has a speaker
+pub fn format(stats: &[SpeakerStats]) -> String {
+    if stats.is_empty() {
+        return String::new();
+    }
+    let total: f32 = stats.iter().map(|entry| entry.speaking_time).sum();
+    let mut output = String::from("[SPEAKER STATISTICS]\n");
+    for entry in stats {
+        let share = if total > 0.0 { entry.speaking_time / total * 100.0 } else { 0.0 };
+        output.push_str(&format!(
+            "{}: {} ({:.0}%), {} words, {} turns, {} interruptions, longest monologue {}\n",
+            entry.name,
+            &format_timestamp(entry.speaking_time, '.')[..8],
+            share,
+            entry.words,
+            entry.turns,
+            entry.interruptions,
+            &format_timestamp(entry.longest_monologue, '.')[..8],
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn segment(start: f32, end: f32, text: &str, speaker: Option<u8>) -> SpeechSegment {
+        SpeechSegment { start, end, text: text.to_string(), speaker, ..Default::default() }
+    }
+
+    #[test]
+    fn test_talk_time_turns_and_interruptions() {
+        let segments = vec![
+            segment(0.0, 30.0, "Welcome to the show.", Some(1)),
+            segment(30.0, 90.0, "Today we talk about databases and why they matter.", Some(1)),
+            segment(89.0, 95.0, "Sorry, quick question.", Some(2)),  // Cuts in a second early
+            segment(95.0, 100.0, "Go ahead.", Some(1)),
+            segment(100.0, 101.0, "[untranscribed audio]", None),
+            segment(101.0, 111.0, "Thanks.", Some(2)),
+        ];
+
+        let stats = speaker_stats(&segments);
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].name.as_str(), stats[0].speaking_time, stats[0].words), ("SPEAKER_01", 95.0, 15));
+        assert_eq!((stats[0].turns, stats[0].interruptions, stats[0].longest_monologue), (2, 0, 90.0));
+        assert_eq!((stats[1].speaking_time, stats[1].turns, stats[1].interruptions), (16.0, 2, 1));
+        assert_eq!(stats[1].longest_monologue, 10.0);
+
+        assert_eq!(
+            format(&stats),
+            "[SPEAKER STATISTICS]\n\
+             SPEAKER_01: 00:01:35 (86%), 15 words, 2 turns, 0 interruptions, longest monologue 00:01:30\n\
+             SPEAKER_02: 00:00:16 (14%), 4 words, 2 turns, 1 interruptions, longest monologue 00:00:10\n"
+        );
+        assert_eq!(format(&speaker_stats(&[segment(0.0, 1.0, "Hi.", None)])), "");
+    }
+}
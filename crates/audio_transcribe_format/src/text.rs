@@ -0,0 +1,163 @@
+//! Plain text transcript with a speaker label at every speaker change,
+//! optionally followed by talk-time statistics
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::{stats, OutputFormatter, SpeechSegment, TranscriptResult};
+
+/// Marker written before a segment below the confidence floor
+pub const UNRELIABLE_MARKER: &str = "[?] ";
+
+/// How segments Whisper was unsure of are written, and what follows them
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TextOptions {
+    pub min_confidence: Option<f32>,  // Segments below it are unreliable
+    pub drop_unreliable: bool,        // Leave unreliable segments out instead of marking them
+    pub speaker_stats: bool,          // Append each speaker's talk time, words and interruptions
+}
+
+impl TextOptions {
+    /// Segments without a confidence, such as gap markers, are never
+    /// unreliable
+    fn is_unreliable(&self, segment: &SpeechSegment) -> bool {
+        matches!((self.min_confidence, segment.confidence), (Some(min), Some(confidence)) if confidence < min)
+    }
+}
+
+/// Format segments as plain text, starting a new `[SPEAKER_NN]` block, or
+/// `[Name]` for a recognized speaker, with an empty line in between
+/// whenever the speaker changes. Segments below
+/// `options.min_confidence` are marked with [`UNRELIABLE_MARKER`] or left out.
+/// With `options.speaker_stats`, a [`stats::format`] block follows after an
+/// empty line.
+pub fn format(segments: &[SpeechSegment], options: &TextOptions) -> String {
+    let mut output = String::new();
+    let mut current_speaker: Option<u8> = None;
+
+    for segment in segments {
+        let unreliable = options.is_unreliable(segment);
+        if unreliable && options.drop_unreliable {
+            continue;
+        }
+
+        // Check if speaker changed
+        if segment.speaker != current_speaker {
+            if current_speaker.is_some() {
+                output.push('\n'); // Empty line between speakers
+            }
+
+            output.push_str(&format!("[{}]\n", segment.speaker_label()));
+            current_speaker = segment.speaker;
+        }
+
+        // Add the transcribed text
+        if unreliable {
+            output.push_str(UNRELIABLE_MARKER);
+        }
+        output.push_str(&segment.text);
+        output.push('\n');
+    }
+
+    if options.speaker_stats {
+        let stats = stats::format(&stats::speaker_stats(segments));
+        if !stats.is_empty() {
+            output.push('\n');
+            output.push_str(&stats);
+        }
+    }
+    output
+}
+
+/// Text output with [`TextOptions`], registered in place of the built-in
+/// `txt` format
+pub struct TextFormatter {
+    pub options: TextOptions,
+}
+
+impl OutputFormatter for TextFormatter {
+    fn name(&self) -> &str {
+        "txt"
+    }
+
+    fn extension(&self) -> &str {
+        "txt"
+    }
+
+    fn format(&self, result: &TranscriptResult) -> Vec<u8> {
+        format(&result.segments, &self.options).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn segment(text: &str, speaker: Option<u8>) -> SpeechSegment {
+        SpeechSegment { start: 0.0, end: 1.0, text: text.to_string(), speaker, ..Default::default() }
+    }
+
+    fn scored(text: &str, speaker: Option<u8>, confidence: Option<f32>) -> SpeechSegment {
+        SpeechSegment { confidence, ..segment(text, speaker) }
+    }
+
+    #[test]
+    fn test_speaker_blocks() {
+        let segments = vec![
+            segment("Hello.", Some(1)),
+            segment("Welcome.", Some(1)),
+            segment("Thanks.", Some(2)),
+        ];
+
+        assert_eq!(
+            format(&segments, &TextOptions::default()),
+            "[SPEAKER_01]\nHello.\nWelcome.\n\n[SPEAKER_02]\nThanks.\n"
+        );
+    }
+
+    #[test]
+    fn test_unreliable_segments() {
+        let segments = vec![
+            scored("Hello.", Some(1), Some(0.9)),
+            scored("Mumbled.", Some(1), Some(0.3)),
+            scored("Mumbled too.", Some(2), Some(0.4)),
+            scored("[untranscribed audio]", Some(1), None),
+        ];
+
+        let mark = TextOptions { min_confidence: Some(0.5), ..TextOptions::default() };
+        assert_eq!(
+            format(&segments, &mark),
+            "[SPEAKER_01]\nHello.\n[?] Mumbled.\n\n[SPEAKER_02]\n[?] Mumbled too.\n\n[SPEAKER_01]\n[untranscribed audio]\n"
+        );
+
+        // A speaker whose every segment is dropped gets no block
+        let drop = TextOptions { drop_unreliable: true, ..mark };
+        assert_eq!(format(&segments, &drop), "[SPEAKER_01]\nHello.\n[untranscribed audio]\n");
+    }
+
+    #[test]
+    fn test_recognized_speakers_by_name() {
+        let alice = |text: &str| SpeechSegment { speaker_name: Some("Alice".to_string()), ..segment(text, Some(1)) };
+        let segments = vec![alice("Hello."), segment("Hi.", Some(2)), alice("Welcome.")];
+
+        assert_eq!(
+            format(&segments, &TextOptions::default()),
+            "[Alice]\nHello.\n\n[SPEAKER_02]\nHi.\n\n[Alice]\nWelcome.\n"
+        );
+    }
+
+    #[test]
+    fn test_speaker_stats_are_appended() {
+        let segments = vec![segment("Hello there.", Some(1))];
+        let options = TextOptions { speaker_stats: true, ..TextOptions::default() };
+
+        assert_eq!(
+            format(&segments, &options),
+            "[SPEAKER_01]\nHello there.\n\n[SPEAKER STATISTICS]\n\
+             SPEAKER_01: 00:00:01 (100%), 2 words, 1 turns, 0 interruptions, longest monologue 00:00:01\n"
+        );
+        assert_eq!(format(&[segment("Hi.", None)], &options), "Hi.\n");
+    }
+}
@@ -0,0 +1,27 @@
+use alloc::format;
+use alloc::string::String;
+
+/// Format seconds as `HH:MM:SS<sep>mmm`, e.g. `00:01:02,500` for SRT
+/// (`','`) or `00:01:02.500` for WebVTT (`'.'`). Negative times clamp to 0.
+pub fn format_timestamp(seconds: f32, millis_separator: char) -> String {
+    let total_millis = if seconds > 0.0 { (seconds as f64 * 1000.0 + 0.5) as u64 } else { 0 };
+    let hours = total_millis / 3_600_000;
+    let minutes = (total_millis / 60_000) % 60;
+    let secs = (total_millis / 1000) % 60;
+    let millis = total_millis % 1000;
+
+    format!("{:02}:{:02}:{:02}{}{:03}", hours, minutes, secs, millis_separator, millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0.0, ','), "00:00:00,000");
+        assert_eq!(format_timestamp(62.5, ','), "00:01:02,500");
+        assert_eq!(format_timestamp(3725.042, '.'), "01:02:05.042");
+        assert_eq!(format_timestamp(-1.0, ','), "00:00:00,000");
+    }
+}
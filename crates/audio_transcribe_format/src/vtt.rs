@@ -0,0 +1,123 @@
+//! WebVTT (`.vtt`) subtitles for HTML5 `<track>` elements
+
+use alloc::format;
+use alloc::string::String;
+use crate::{format_timestamp, SpeechSegment};
+
+/// Settings on every cue: centred, near the bottom of the video
+pub const CUE_SETTINGS: &str = "line:90% align:center";
+
+/// Format segments as a WebVTT file. The speaker of each cue is marked
+/// with a voice span (`<v Speaker 1>`, or `<v Alice>` for a recognized
+/// speaker), which players can style and
+/// screen readers announce. Segments with word timings get a timestamp tag
+/// before each word, for karaoke-style highlighting.
+pub fn format(segments: &[SpeechSegment]) -> String {
+    let mut output = String::from("WEBVTT\n\n");
+
+    for segment in segments {
+        output.push_str(&format!(
+            "{} --> {} {}\n",
+            format_timestamp(segment.start, '.'),
+            format_timestamp(segment.end, '.'),
+            CUE_SETTINGS
+        ));
+
+        let text = if segment.words.is_empty() { escape(segment.text.trim()) } else { timed_words(segment) };
+        match (&segment.speaker_name, segment.speaker) {
+            (Some(name), Some(_)) => output.push_str(&format!("<v {}>{}</v>", escape(name), text)),
+            (None, Some(speaker)) => output.push_str(&format!("<v Speaker {}>{}</v>", speaker, text)),
+            (_, None) => output.push_str(&text),
+        }
+        output.push_str("\n\n");
+    }
+
+    output
+}
+
+/// The segment's words, each after the moment it is spoken. Tags must lie
+/// strictly inside the cue and increase, so others are left out.
+fn timed_words(segment: &SpeechSegment) -> String {
+    let mut text = String::new();
+    let mut last = segment.start;
+    for word in &segment.words {
+        if !text.is_empty() {
+            text.push(' ');
+            if word.start > last && word.start < segment.end {
+                text.push_str(&format!("<{}>", format_timestamp(word.start, '.')));
+                last = word.start;
+            }
+        }
+        text.push_str(&escape(&word.text));
+    }
+    text
+}
+
+/// Escape markup characters and fold line breaks, since a blank line
+/// would end the cue early
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if !escaped.is_empty() {
+            escaped.push('\n');
+        }
+        for c in line.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                c => escaped.push(c),
+            }
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use crate::WordTiming;
+
+    #[test]
+    fn test_header_and_cues() {
+        let segments = vec![
+            SpeechSegment { start: 1.0, end: 2.25, text: "Hello.".to_string(), speaker: Some(2), ..Default::default() },
+            SpeechSegment { start: 2.5, end: 3.0, text: " Hi.".to_string(), ..Default::default() },
+        ];
+
+        assert_eq!(
+            format(&segments),
+            "WEBVTT\n\n\
+             00:00:01.000 --> 00:00:02.250 line:90% align:center\n<v Speaker 2>Hello.</v>\n\n\
+             00:00:02.500 --> 00:00:03.000 line:90% align:center\nHi.\n\n"
+        );
+    }
+
+    #[test]
+    fn test_word_timestamp_tags() {
+        let word = |start: f32, text: &str| WordTiming { start, end: start + 0.4, text: text.to_string(), confidence: 0.9 };
+        let segment = SpeechSegment {
+            start: 1.0,
+            end: 3.0,
+            text: "Good morning, R&D. Bye.".to_string(),
+            speaker: Some(1),
+            words: vec![word(1.0, "Good"), word(1.5, "morning,"), word(1.5, "R&D."), word(3.0, "Bye.")],
+            ..Default::default()
+        };
+
+        assert!(format(&[segment]).contains(
+            "<v Speaker 1>Good <00:00:01.500>morning, R&amp;D. Bye.</v>\n\n"
+        ));
+    }
+
+    #[test]
+    fn test_cue_text_is_escaped() {
+        let segments = vec![
+            SpeechSegment { start: 0.0, end: 1.0, text: "R&D <draft> -->\n\nnext".to_string(), speaker: Some(1), ..Default::default() },
+        ];
+
+        assert!(format(&segments).contains("<v Speaker 1>R&amp;D &lt;draft&gt; --&gt;\nnext</v>\n\n"));
+    }
+}
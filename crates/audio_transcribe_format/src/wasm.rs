@@ -0,0 +1,16 @@
+use alloc::format;
+use alloc::string::String;
+use wasm_bindgen::prelude::*;
+use crate::{json, OutputFormat};
+
+/// Reformat a stored transcript, as written by the `json` format, into
+/// another output format, e.g. `"srt"` or `"vtt"`
+#[wasm_bindgen(js_name = formatTranscript)]
+pub fn format_transcript(result_json: &str, format: &str) -> Result<String, JsValue> {
+    let result = json::parse(result_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid transcript: {}", e)))?;
+    let output_format = OutputFormat::from_name(format)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown format: {}", format)))?;
+
+    Ok(output_format.render(&result))
+}
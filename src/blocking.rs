@@ -0,0 +1,163 @@
+//! Synchronous wrappers for callers that don't run a tokio runtime, such as
+//! simple command-line tools and build scripts.
+//!
+//! Each wrapper owns a runtime and blocks the calling thread on it, so these
+//! types must not be used from inside an async context.
+//!
+//! ```no_run
+//! # fn run() -> audio_transcription_cli::Result<()> {
+//! use audio_transcription_cli::{blocking, ModelSize, Transcriber};
+//!
+//! let transcriber = blocking::Transcriber::new(Transcriber::builder().model(ModelSize::Small))?;
+//! let result = transcriber.transcribe("meeting.wav")?;
+//! println!("{} segments", result.segments.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::{Path, PathBuf};
+use futures_util::StreamExt;
+use tokio::runtime::Runtime;
+use tokio_util::sync::CancellationToken;
+use crate::core::audio_processor::{ProcessingConfig, SpeechSegment, TranscriptResult};
+use crate::core::model::{self, DownloadPolicy, DownloadRetry, ModelKind, ModelProvider, ModelSize};
+use crate::error::Result;
+use crate::transcriber::{self, TranscriberBuilder};
+
+fn runtime() -> Result<Runtime> {
+    Ok(tokio::runtime::Builder::new_multi_thread().enable_all().build()?)
+}
+
+/// Blocking counterpart of [`crate::Transcriber`]
+pub struct Transcriber {
+    runtime: Runtime,
+    inner: transcriber::Transcriber,
+}
+
+impl Transcriber {
+    pub fn new(builder: TranscriberBuilder) -> Result<Self> {
+        Ok(Self {
+            runtime: runtime()?,
+            inner: builder.build()?,
+        })
+    }
+
+    /// Transcribe and diarize a single audio file, then apply the
+    /// configured post-processors
+    pub fn transcribe(&self, path: impl AsRef<Path>) -> Result<TranscriptResult> {
+        self.runtime.block_on(self.inner.transcribe(path))
+    }
+
+    /// Iterate over segments in timestamp order as soon as their chunk is
+    /// done. Speakers are not assigned, as with
+    /// [`crate::Transcriber::transcribe_stream`].
+    pub fn transcribe_iter(&self, path: impl Into<PathBuf>) -> impl Iterator<Item = Result<SpeechSegment>> + '_ {
+        let mut stream = Box::pin(self.inner.transcribe_stream(path));
+        std::iter::from_fn(move || self.runtime.block_on(stream.next()))
+    }
+
+    pub fn config(&self) -> &ProcessingConfig {
+        self.inner.config()
+    }
+
+    /// Stop scheduling new chunks; can be called from another thread while
+    /// [`transcribe`](Self::transcribe) is blocking; like
+    /// [`crate::Transcriber::cancel`], later calls start out cancelled too
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        self.inner.cancellation_token()
+    }
+}
+
+/// Blocking counterpart of [`crate::ModelManager`]
+pub struct ModelManager {
+    runtime: Runtime,
+    inner: model::ModelManager,
+}
+
+impl ModelManager {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            runtime: runtime()?,
+            inner: model::ModelManager::new()?,
+        })
+    }
+
+    /// Use a custom cache directory instead of the platform default
+    pub fn with_cache_dir(cache_dir: PathBuf) -> Result<Self> {
+        Ok(Self {
+            runtime: runtime()?,
+            inner: model::ModelManager::with_cache_dir(cache_dir)?,
+        })
+    }
+
+    /// Decide whether missing models are downloaded, and whether to ask first
+    pub fn with_download_policy(self, policy: DownloadPolicy) -> Self {
+        Self { runtime: self.runtime, inner: self.inner.with_download_policy(policy) }
+    }
+
+    /// How often an interrupted download is retried, and how long to wait
+    pub fn with_download_retry(self, retry: DownloadRetry) -> Self {
+        Self { runtime: self.runtime, inner: self.inner.with_download_retry(retry) }
+    }
+
+    pub fn cache_dir(&self) -> &PathBuf {
+        self.inner.cache_dir()
+    }
+
+    /// Check the models for a transcription run, prompting to download
+    /// missing ones unless the download policy says otherwise. Returns
+    /// `Ok(false)` if the user declined.
+    pub fn ensure_models_available(&self, model_size: &ModelSize) -> Result<bool> {
+        self.runtime.block_on(self.inner.ensure_models_available(model_size))
+    }
+
+    /// Make one model available locally, fetching it if needed, and return
+    /// its path
+    pub fn ensure_available(&self, model: &ModelKind) -> Result<PathBuf> {
+        self.runtime.block_on(self.inner.ensure_available(model))
+    }
+
+    pub fn resolve(&self, model: &ModelKind) -> PathBuf {
+        self.inner.resolve(model)
+    }
+
+    pub fn verify(&self, model: &ModelKind) -> Result<()> {
+        self.inner.verify(model)
+    }
+
+    /// The async manager, e.g. to pass as a [`ModelProvider`]
+    pub fn into_inner(self) -> model::ModelManager {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcribe_reports_missing_input() {
+        let transcriber = Transcriber::new(transcriber::Transcriber::builder()).unwrap();
+
+        assert_eq!(transcriber.transcribe("does-not-exist.wav").unwrap_err().code(), 12);
+
+        let items: Vec<_> = transcriber.transcribe_iter("does-not-exist.wav").collect();
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+
+    #[test]
+    fn test_model_manager_uses_cache_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::with_cache_dir(dir.path().to_path_buf()).unwrap();
+        let model = ModelKind::Whisper(ModelSize::Tiny);
+
+        assert_eq!(manager.cache_dir(), dir.path());
+        assert!(manager.resolve(&model).starts_with(dir.path()));
+        assert_eq!(manager.verify(&model).unwrap_err().code(), 31);
+    }
+}
@@ -1,9 +1,7 @@
 use std::path::PathBuf;
 use crate::error::{Result, AudioTranscriptionError};
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent},
-    terminal::{self},
-};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crate::cli::terminal::RawModeGuard;
 use std::io::{self, Write};
 
 #[derive(Debug, Clone)]
@@ -187,9 +185,14 @@ impl FileBrowser {
 
     pub fn handle_input(&mut self) -> Result<Option<PathBuf>> {
         loop {
-            if let Event::Key(KeyEvent { code, .. }) = event::read()
+            if let Event::Key(KeyEvent { code, modifiers, .. }) = event::read()
                 .map_err(|e| AudioTranscriptionError::FileBrowser(format!("Input error: {}", e)))? 
             {
+                // Raw mode delivers Ctrl-C as a key press rather than a signal
+                if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+                    return Ok(None);
+                }
+
                 match code {
                     KeyCode::Up => {
                         self.move_selection(Direction::Up);
@@ -219,28 +222,12 @@ impl FileBrowser {
     }
 
     pub fn run_interactive(&mut self) -> Result<Option<PathBuf>> {
-        // Enable raw mode for terminal input
-        terminal::enable_raw_mode()
-            .map_err(|e| AudioTranscriptionError::FileBrowser(format!("Failed to enable raw mode: {}", e)))?;
-
-        let result = {
-            // Clear screen completely and reset terminal state
-            print!("\x1b[2J\x1b[H\x1b[0m");
-            io::stdout().flush().map_err(|e| AudioTranscriptionError::FileBrowser(format!("IO error: {}", e)))?;
-            
-            self.render_to_terminal()?;
-            self.handle_input()
-        };
-
-        // Disable raw mode and clean up terminal before returning
-        terminal::disable_raw_mode()
-            .map_err(|e| AudioTranscriptionError::FileBrowser(format!("Failed to disable raw mode: {}", e)))?;
-        
-        // Clear screen on exit
-        print!("\x1b[2J\x1b[H\x1b[0m");
-        io::stdout().flush().map_err(|e| AudioTranscriptionError::FileBrowser(format!("IO error: {}", e)))?;
+        // Raw mode on the alternate screen; restored when the guard drops,
+        // even if input handling fails or panics
+        let _terminal = RawModeGuard::fullscreen()?;
 
-        result
+        self.render_to_terminal()?;
+        self.handle_input()
     }
 
     pub fn current_path(&self) -> &PathBuf {
@@ -339,7 +326,8 @@ impl FileBrowser {
     }
 }
 
-fn format_file_size(size: u64) -> String {
+/// Size in bytes, e.g. `1.4 GB`
+pub fn format_file_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
     let mut size = size as f64;
     let mut unit_index = 0;
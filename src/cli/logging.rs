@@ -0,0 +1,227 @@
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use indicatif::MultiProgress;
+use tracing::level_filters::LevelFilter;
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard, TraceStyle};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::EnvFilter;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// What the run log records regardless of `RUST_LOG` and `--verbose`
+const RUN_LOG_FILTER: &str = "info,audio_transcription_cli=debug,audio_transcribe=debug";
+
+/// whisper.cpp logs every model load at info level; on stderr that is only
+/// shown with `--verbose` or an explicit `RUST_LOG`
+const WHISPER_QUIET: &str = "whisper_rs=warn";
+
+/// Target of events meant only for the run log, such as the final error
+/// that the CLI already prints itself
+pub const RUN_LOG_TARGET: &str = "run_log";
+
+/// How much the log on stderr shows when `RUST_LOG` doesn't say
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    Quiet,    // Warnings and errors only (`--quiet`)
+    Normal,
+    Verbose,  // Debug events, whisper.cpp's included (`--verbose`)
+}
+
+/// Install the CLI's tracing subscriber: human-readable events on stderr,
+/// filtered by `RUST_LOG` (default `info`, `warn` when quiet or `debug`
+/// when verbose), plus an optional chrome-trace file with every span,
+/// viewable in `chrome://tracing`, Perfetto or speedscope, and an optional
+/// JSON-lines run log. Events on stderr are printed around `stderr`'s progress bars,
+/// so they don't tear a live display; without bars, stderr is left to
+/// machine-readable progress and only the trace and run log are written.
+///
+/// The trace is written when the returned guard is dropped, so keep it alive
+/// until processing has finished.
+pub fn init_tracing(verbosity: Verbosity, stderr: Option<MultiProgress>, trace_path: Option<&Path>, run_log: Option<RunLog>) -> Result<Option<FlushGuard>> {
+    let level = match verbosity {
+        Verbosity::Quiet => LevelFilter::WARN,
+        Verbosity::Normal => LevelFilter::INFO,
+        Verbosity::Verbose => LevelFilter::DEBUG,
+    };
+    let mut filter = EnvFilter::builder()
+        .with_default_directive(level.into())
+        .from_env_lossy()
+        .add_directive(format!("{}=off", RUN_LOG_TARGET).parse().expect("valid directive"));
+    if verbosity != Verbosity::Verbose && std::env::var_os(EnvFilter::DEFAULT_ENV).is_none() {
+        filter = filter.add_directive(WHISPER_QUIET.parse().expect("valid directive"));
+    }
+
+    let fmt_layer = stderr.map(|bars| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(move || AboveBars(bars.clone()))
+            .with_filter(filter)
+    });
+
+    let (chrome_layer, guard) = match trace_path {
+        Some(trace_path) => {
+            if let Some(parent) = trace_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            // Async style keeps spans intact when tokio moves tasks between threads
+            let (layer, guard) = ChromeLayerBuilder::new()
+                .file(trace_path)
+                .trace_style(TraceStyle::Async)
+                .include_args(true)
+                .build();
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    // Closing spans are logged with their busy and idle time, which gives
+    // the duration of every stage and chunk
+    let run_log_layer = run_log.map(|run_log| {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(run_log)
+            .with_filter(EnvFilter::new(RUN_LOG_FILTER))
+    });
+
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(chrome_layer)
+        .with(run_log_layer)
+        .try_init()
+        .map_err(|e| AudioTranscriptionError::Configuration(
+            format!("Failed to initialise tracing: {}", e)
+        ))?;
+
+    if let Some(trace_path) = trace_path {
+        tracing::info!(path = %trace_path.display(), "Writing profiling trace");
+    }
+    Ok(guard)
+}
+
+/// Stderr for log events, printed above the progress bars by lifting them
+/// off while the event is written
+struct AboveBars(MultiProgress);
+
+impl Write for AboveBars {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.suspend(|| io::stderr().write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// A JSON-lines log of one run, one event per line with its timestamp,
+/// level, fields and enclosing spans.
+///
+/// Where the log goes is only known once the input has been chosen, so
+/// events are kept in memory until [`open`](Self::open) is called and then
+/// written ahead of everything that follows. Each line is flushed as it is
+/// written, so the log is complete up to the moment a run dies.
+#[derive(Clone, Default)]
+pub struct RunLog {
+    sink: Arc<Mutex<Sink>>,
+}
+
+enum Sink {
+    Pending(Vec<u8>),
+    File { path: PathBuf, writer: LineWriter<File> },
+}
+
+impl Default for Sink {
+    fn default() -> Self {
+        Sink::Pending(Vec::new())
+    }
+}
+
+impl RunLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start writing to `path`, replacing an earlier log there
+    pub fn open(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|source| AudioTranscriptionError::File { path: parent.to_path_buf(), source })?;
+        }
+        let file_error = |source| AudioTranscriptionError::File { path: path.to_path_buf(), source };
+        let mut writer = LineWriter::new(File::create(path).map_err(file_error)?);
+
+        let mut sink = self.sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Sink::Pending(pending) = &*sink {
+            writer.write_all(pending).map_err(file_error)?;
+        }
+        *sink = Sink::File { path: path.to_path_buf(), writer };
+        Ok(())
+    }
+
+    /// The file being written, once opened
+    pub fn path(&self) -> Option<PathBuf> {
+        match &*self.sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+            Sink::File { path, .. } => Some(path.clone()),
+            Sink::Pending(_) => None,
+        }
+    }
+}
+
+/// Writes one formatted event into the run log
+pub struct RunLogWriter {
+    sink: Arc<Mutex<Sink>>,
+}
+
+impl Write for RunLogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Logging must never take the run down, so a poisoned lock is used as is
+        match &mut *self.sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+            Sink::Pending(pending) => {
+                pending.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            Sink::File { writer, .. } => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut *self.sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+            Sink::Pending(_) => Ok(()),
+            Sink::File { writer, .. } => writer.flush(),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for RunLog {
+    type Writer = RunLogWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RunLogWriter { sink: Arc::clone(&self.sink) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_log_keeps_events_written_before_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("meeting.log.jsonl");
+        let run_log = RunLog::new();
+
+        run_log.make_writer().write_all(b"{\"stage\":\"startup\"}\n").unwrap();
+        assert_eq!(run_log.path(), None);
+
+        run_log.open(&path).unwrap();
+        run_log.make_writer().write_all(b"{\"stage\":\"vad\"}\n").unwrap();
+
+        assert_eq!(run_log.path(), Some(path.clone()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"stage\":\"startup\"}\n{\"stage\":\"vad\"}\n");
+    }
+}
@@ -1,3 +1,9 @@
 pub mod file_browser;
+pub mod logging;
+pub mod progress;
+pub mod speaker_names;
+pub mod terminal;
 
-pub use file_browser::FileBrowser;
+pub use file_browser::{format_file_size, FileBrowser};
+pub use logging::{init_tracing, RunLog, Verbosity, RUN_LOG_TARGET};
+pub use progress::JsonProgress;
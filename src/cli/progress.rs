@@ -0,0 +1,204 @@
+//! Progress as newline-delimited JSON, for GUIs and scripts that wrap the
+//! CLI and draw their own progress (`--progress json`).
+//!
+//! Each line is one object whose `event` field says what happened:
+//!
+//! ```text
+//! {"event":"start","input":"talk.mp3","model":"medium","audio_secs":3600.0}
+//! {"event":"stage","stage":"transcription","name":"Transcription","elapsed_secs":1.2}
+//! {"event":"chunk_started","index":0,"worker":0,"device":0,"elapsed_secs":1.3}
+//! {"event":"chunk","index":0,"completed":1,"total":31,"completed_audio_secs":118.5,"total_audio_secs":3600.0,"percent":3.3,"eta_secs":410.0,"elapsed_secs":14.9}
+//! {"event":"summary","input":"talk.mp3","audio_secs":3600.0,"processing_secs":422.7,"realtime_factor":8.5,...}
+//! {"event":"error","code":31,"message":"Model not found: ..."}
+//! ```
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+use serde::Serialize;
+use crate::core::audio_processor::TranscriptResult;
+use crate::core::progress::{ProgressEvent, ProgressSink};
+use crate::error::AudioTranscriptionError;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JsonEvent<'a> {
+    Start {
+        input: &'a Path,
+        model: &'a str,
+        audio_secs: f32,
+    },
+    Stage {
+        stage: &'static str,
+        name: String,
+        elapsed_secs: f32,
+    },
+    ChunkStarted {
+        index: usize,
+        worker: usize,
+        device: Option<usize>,  // GPU, or null on the CPU
+        elapsed_secs: f32,
+    },
+    Chunk {
+        index: usize,
+        completed: usize,
+        total: usize,         // Chunks found so far
+        completed_audio_secs: f32,
+        total_audio_secs: f32,
+        percent: f32,         // Of the audio
+        eta_secs: Option<f32>,
+        elapsed_secs: f32,
+    },
+    Summary {
+        input: &'a Path,
+        audio_secs: f32,
+        processing_secs: f32,
+        realtime_factor: f32,
+        segments: usize,
+        speakers: usize,
+        tokens: u64,
+        partial: bool,
+        skipped_chunks: usize,
+        outputs: &'a [PathBuf],
+    },
+    Error {
+        code: u8,
+        message: String,
+    },
+}
+
+/// Writes progress events as JSON lines, each flushed as it is written
+pub struct JsonProgress<W> {
+    writer: Mutex<W>,
+    start: Instant,
+}
+
+impl JsonProgress<io::Stderr> {
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+}
+
+impl<W: Write + Send> JsonProgress<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer: Mutex::new(writer), start: Instant::now() }
+    }
+
+    /// A recording is about to be transcribed
+    pub fn start(&self, input: &Path, model: &str, audio_secs: f32) {
+        self.emit(&JsonEvent::Start { input, model, audio_secs });
+    }
+
+    /// A recording is done and its transcripts are written to `outputs`
+    pub fn summary(&self, input: &Path, result: &TranscriptResult, outputs: &[PathBuf]) {
+        let mut speakers: Vec<u8> = result.segments.iter().filter_map(|segment| segment.speaker).collect();
+        speakers.sort_unstable();
+        speakers.dedup();
+        self.emit(&JsonEvent::Summary {
+            input,
+            audio_secs: result.audio_duration.as_secs_f32(),
+            processing_secs: result.processing_time.as_secs_f32(),
+            realtime_factor: result.realtime_factor(),
+            segments: result.segments.len(),
+            speakers: speakers.len(),
+            tokens: result.tokens,
+            partial: result.partial,
+            skipped_chunks: result.skipped.len(),
+            outputs,
+        });
+    }
+
+    /// The run, or one recording of it, failed
+    pub fn error(&self, error: &AudioTranscriptionError) {
+        self.emit(&JsonEvent::Error { code: error.code(), message: error.to_string() });
+    }
+
+    fn emit(&self, event: &JsonEvent) {
+        let Ok(mut line) = serde_json::to_vec(event) else { return };
+        line.push(b'\n');
+        // Progress must never take the run down, so write errors are ignored
+        let mut writer = self.writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let _ = writer.write_all(&line).and_then(|()| writer.flush());
+    }
+
+    fn elapsed_secs(&self) -> f32 {
+        self.start.elapsed().as_secs_f32()
+    }
+}
+
+impl<W: Write + Send> ProgressSink for JsonProgress<W> {
+    fn report(&self, event: ProgressEvent) {
+        let event = match event {
+            ProgressEvent::StageStarted(stage) => JsonEvent::Stage {
+                stage: stage.name(),
+                name: stage.to_string(),
+                elapsed_secs: self.elapsed_secs(),
+            },
+            ProgressEvent::ChunkStarted { index, worker, device } => JsonEvent::ChunkStarted {
+                index,
+                worker,
+                device,
+                elapsed_secs: self.elapsed_secs(),
+            },
+            ProgressEvent::ChunkCompleted { index, completed, total, completed_audio_secs, total_audio_secs, eta } => JsonEvent::Chunk {
+                index,
+                completed,
+                total,
+                completed_audio_secs,
+                total_audio_secs,
+                percent: if total_audio_secs > 0.0 { (completed_audio_secs / total_audio_secs).min(1.0) * 100.0 } else { 0.0 },
+                eta_secs: eta.map(|eta| eta.as_secs_f32()),
+                elapsed_secs: self.elapsed_secs(),
+            },
+        };
+        self.emit(&event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::core::progress::ProcessingStage;
+
+    #[derive(Clone, Default)]
+    struct Buffer(std::sync::Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_events_are_json_lines() {
+        let buffer = Buffer::default();
+        let progress = JsonProgress::new(buffer.clone());
+        progress.start(Path::new("talk.mp3"), "medium", 60.0);
+        progress.report(ProgressEvent::StageStarted(ProcessingStage::Transcription));
+        progress.report(ProgressEvent::ChunkCompleted {
+            index: 0,
+            completed: 1,
+            total: 2,
+            completed_audio_secs: 15.0,
+            total_audio_secs: 60.0,
+            eta: Some(Duration::from_secs(30)),
+        });
+        progress.error(&AudioTranscriptionError::Configuration("bad".to_string()));
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let events: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0]["event"], "start");
+        assert_eq!((events[0]["input"].as_str(), events[0]["audio_secs"].as_f64()), (Some("talk.mp3"), Some(60.0)));
+        assert_eq!((events[1]["event"].as_str(), events[1]["stage"].as_str()), (Some("stage"), Some("transcription")));
+        assert_eq!((events[2]["event"].as_str(), events[2]["percent"].as_f64()), (Some("chunk"), Some(25.0)));
+        assert_eq!(events[2]["eta_secs"].as_f64(), Some(30.0));
+        assert_eq!((events[3]["event"].as_str(), events[3]["code"].as_u64()), (Some("error"), Some(80)));
+    }
+}
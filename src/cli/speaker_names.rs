@@ -0,0 +1,147 @@
+//! Naming speakers by hand once a recording is transcribed.
+//!
+//! Each speaker is shown with a line they said, and whatever name is typed
+//! replaces their label in the transcript. An empty answer keeps the label.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use audio_transcribe_format::format_timestamp;
+use crate::core::audio_processor::{TranscriptResult, GAP_MARKER};
+use crate::error::Result;
+
+/// Quotes longer than this are cut short
+const MAX_QUOTE_CHARS: usize = 120;
+
+/// What is shown for one speaker when asking for their name
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeakerSample {
+    pub speaker: u8,
+    pub label: String,  // Current label, `SPEAKER_NN` or a recognized name
+    pub start: f32,     // When the quote starts, in seconds
+    pub quote: String,
+}
+
+/// One sample per speaker in order of first appearance, quoting the
+/// longest thing each said
+pub fn samples(result: &TranscriptResult) -> Vec<SpeakerSample> {
+    let mut samples: Vec<SpeakerSample> = Vec::new();
+    for segment in result.segments.iter().filter(|segment| segment.text != GAP_MARKER) {
+        let Some(speaker) = segment.speaker else { continue };
+        let quote = segment.text.trim();
+        match samples.iter_mut().find(|sample| sample.speaker == speaker) {
+            Some(sample) if quote.chars().count() <= sample.quote.chars().count() => {}
+            Some(sample) => {
+                sample.start = segment.start;
+                sample.quote = quote.to_string();
+            }
+            None => samples.push(SpeakerSample {
+                speaker,
+                label: segment.speaker_label(),
+                start: segment.start,
+                quote: quote.to_string(),
+            }),
+        }
+    }
+
+    for sample in &mut samples {
+        if sample.quote.chars().count() > MAX_QUOTE_CHARS {
+            sample.quote = sample.quote.chars().take(MAX_QUOTE_CHARS).collect::<String>().trim_end().to_string() + "…";
+        }
+    }
+    samples
+}
+
+/// Show each speaker's sample on `output` and read a name for them from
+/// `input`, one line each. Speakers left blank are not in the map.
+pub fn ask(result: &TranscriptResult, input: &mut impl BufRead, output: &mut impl Write) -> Result<BTreeMap<u8, String>> {
+    let samples = samples(result);
+    let mut names = BTreeMap::new();
+    if samples.is_empty() {
+        return Ok(names);
+    }
+
+    writeln!(output, "\n🗣️  Name the speakers, or press Enter to keep a label:")?;
+    for sample in samples {
+        writeln!(output, "\n{} at {}: \"{}\"", sample.label, &format_timestamp(sample.start, '.')[..8], sample.quote)?;
+        write!(output, "Name: ")?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            break;
+        }
+        let name = line.trim();
+        if !name.is_empty() && name != sample.label {
+            names.insert(sample.speaker, name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// The transcript with `names` in place of the speakers' labels
+pub fn apply(result: &TranscriptResult, names: &BTreeMap<u8, String>) -> TranscriptResult {
+    let mut named = result.clone();
+    for segment in &mut named.segments {
+        if let Some(name) = segment.speaker.and_then(|speaker| names.get(&speaker)) {
+            segment.speaker_name = Some(name.clone());
+        }
+    }
+    named
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::core::audio_processor::{ModelInfo, SpeechSegment};
+
+    fn result_with(segments: Vec<SpeechSegment>) -> TranscriptResult {
+        TranscriptResult {
+            segments,
+            audio_duration: Duration::from_secs(60),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn segment(start: f32, text: &str, speaker: u8) -> SpeechSegment {
+        SpeechSegment { start, end: start + 1.0, text: text.to_string(), speaker: Some(speaker), ..Default::default() }
+    }
+
+    #[test]
+    fn test_longest_line_is_quoted() {
+        let result = result_with(vec![
+            segment(0.0, "Hi.", 2),
+            segment(5.0, " Thanks for having me, let's start.", 1),
+            segment(75.0, "Sure.", 1),
+            segment(80.0, &"word ".repeat(40), 2),
+            SpeechSegment { speaker_name: Some("Alice".to_string()), ..segment(90.0, "Alice here.", 3) },
+        ]);
+
+        let samples = samples(&result);
+        assert_eq!(samples.iter().map(|sample| sample.label.as_str()).collect::<Vec<_>>(), ["SPEAKER_02", "SPEAKER_01", "Alice"]);
+        assert_eq!((samples[0].start, samples[0].quote.chars().count()), (80.0, 120));
+        assert!(samples[0].quote.ends_with("word…"));
+        assert_eq!(samples[1].quote, "Thanks for having me, let's start.");
+    }
+
+    #[test]
+    fn test_typed_names_replace_labels() {
+        let result = result_with(vec![segment(0.0, "Hello.", 1), segment(1.0, "Hi.", 2), segment(2.0, "Bye.", 1)]);
+
+        let mut output = Vec::new();
+        let names = ask(&result, &mut "Anna\n\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(names, BTreeMap::from([(1, "Anna".to_string())]));
+        assert!(String::from_utf8(output).unwrap().contains("SPEAKER_02 at 00:00:01: \"Hi.\""));
+
+        let named = apply(&result, &names);
+        let labels: Vec<String> = named.segments.iter().map(|segment| segment.speaker_label()).collect();
+        assert_eq!(labels, ["Anna", "SPEAKER_02", "Anna"]);
+    }
+}
@@ -0,0 +1,70 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use crossterm::{cursor, execute, terminal};
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Whether a guard switched to the alternate screen, so `restore` knows to
+/// switch back
+static ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
+static PANIC_HOOK: Once = Once::new();
+
+/// Put the terminal back into a usable state: line-buffered input, the
+/// normal screen, default colours and a visible cursor. Safe to call when
+/// nothing was changed, and more than once.
+pub fn restore() {
+    let _ = terminal::disable_raw_mode();
+    if ALTERNATE_SCREEN.swap(false, Ordering::SeqCst) {
+        let _ = execute!(io::stdout(), terminal::LeaveAlternateScreen);
+    }
+    let _ = execute!(io::stdout(), cursor::Show);
+    print!("\x1b[0m");
+    let _ = io::stdout().flush();
+}
+
+/// Restore the terminal before the panic message is printed. Unwinding
+/// drops the guard too, but only after the message, which raw mode would
+/// have mangled; and with `panic = "abort"` nothing is dropped at all.
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore();
+            previous(info);
+        }));
+    });
+}
+
+/// Raw mode, and optionally a full-screen view, for as long as the guard
+/// lives. Dropping it restores the terminal, including when the owner
+/// returns early with an error or panics. Only one guard should be alive at
+/// a time.
+pub struct RawModeGuard(());
+
+impl RawModeGuard {
+    /// Raw mode only; output stays on the normal screen
+    pub fn enable() -> Result<Self> {
+        install_panic_hook();
+        terminal::enable_raw_mode()
+            .map_err(|e| AudioTranscriptionError::FileBrowser(format!("Failed to enable raw mode: {}", e)))?;
+        Ok(Self(()))
+    }
+
+    /// Raw mode on the alternate screen with the cursor hidden, for
+    /// interactive views; the shell's scrollback is back untouched when the
+    /// guard drops
+    pub fn fullscreen() -> Result<Self> {
+        let guard = Self::enable()?;
+        ALTERNATE_SCREEN.store(true, Ordering::SeqCst);
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)
+            .map_err(|e| AudioTranscriptionError::FileBrowser(format!("Failed to switch screens: {}", e)))?;
+        Ok(guard)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
@@ -0,0 +1,279 @@
+//! Local SQLite archive of every transcription run: the input's content
+//! hash, the settings used, timing statistics and the full transcript, with
+//! one row per segment so transcripts can be queried with plain SQL.
+//!
+//! The content hash identifies a recording even after it is renamed or
+//! moved, so earlier runs of the same audio can be found.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use crate::core::audio_processor::TranscriptResult;
+use crate::error::{Result, AudioTranscriptionError};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id                 INTEGER PRIMARY KEY AUTOINCREMENT,
+        recorded_at        TEXT NOT NULL DEFAULT (datetime('now')),
+        input              TEXT NOT NULL,
+        input_hash         TEXT NOT NULL,
+        config             TEXT NOT NULL,
+        audio_seconds      REAL NOT NULL,
+        processing_seconds REAL NOT NULL,
+        segment_count      INTEGER NOT NULL,
+        speaker_count      INTEGER NOT NULL,
+        language           TEXT,
+        result             TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS runs_input_hash ON runs (input_hash);
+    CREATE TABLE IF NOT EXISTS segments (
+        run_id   INTEGER NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        start    REAL NOT NULL,
+        end      REAL NOT NULL,
+        speaker  INTEGER,
+        text     TEXT NOT NULL,
+        PRIMARY KEY (run_id, position)
+    );
+";
+
+const RUN_COLUMNS: &str = "id, recorded_at, input, input_hash, config, audio_seconds, processing_seconds, \
+                           segment_count, speaker_count, language";
+
+/// `<data dir>/audio-transcribe/archive.sqlite3`
+pub fn default_archive_path() -> Result<PathBuf> {
+    let data_dir = dirs::data_dir().ok_or_else(|| AudioTranscriptionError::Configuration(
+        "Unable to determine data directory".to_string()
+    ))?;
+    Ok(data_dir.join("audio-transcribe").join("archive.sqlite3"))
+}
+
+/// SHA-256 of the file's contents, as lowercase hex
+pub fn hash_file(path: &Path) -> Result<String> {
+    crate::core::model::sha256_file(path)
+}
+
+/// Summary of an archived run; the transcript itself is loaded with
+/// [`RunArchive::transcript`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedRun {
+    pub id: i64,
+    pub recorded_at: String,        // UTC, `YYYY-MM-DD HH:MM:SS`
+    pub input: PathBuf,
+    pub input_hash: String,
+    pub config: serde_json::Value,  // Settings the run used, as given to `record`
+    pub audio_duration: Duration,
+    pub processing_time: Duration,
+    pub segments: usize,
+    pub speakers: usize,
+    pub language: Option<String>,
+}
+
+impl ArchivedRun {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        let input: String = row.get("input")?;
+        let config: String = row.get("config")?;
+        let audio_seconds: f64 = row.get("audio_seconds")?;
+        let processing_seconds: f64 = row.get("processing_seconds")?;
+
+        Ok(ArchivedRun {
+            id: row.get("id")?,
+            recorded_at: row.get("recorded_at")?,
+            input: PathBuf::from(input),
+            input_hash: row.get("input_hash")?,
+            // Written by this module, so it always parses
+            config: serde_json::from_str(&config).unwrap_or_default(),
+            audio_duration: Duration::from_secs_f64(audio_seconds),
+            processing_time: Duration::from_secs_f64(processing_seconds),
+            segments: row.get("segment_count")?,
+            speakers: row.get("speaker_count")?,
+            language: row.get("language")?,
+        })
+    }
+}
+
+/// Archive of transcription runs persisted in SQLite
+#[derive(Debug)]
+pub struct RunArchive {
+    connection: Connection,
+}
+
+impl RunArchive {
+    /// Open the archive at `path`, creating it and its directory if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|source| AudioTranscriptionError::File { path: dir.to_path_buf(), source })?;
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    /// An archive that is lost when dropped, for tests
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> Result<Self> {
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Store a finished run and return its ID
+    pub fn record(
+        &mut self,
+        input: &Path,
+        input_hash: &str,
+        config: &serde_json::Value,
+        result: &TranscriptResult,
+    ) -> Result<i64> {
+        let mut speakers: Vec<u8> = result.segments.iter().filter_map(|segment| segment.speaker).collect();
+        speakers.sort_unstable();
+        speakers.dedup();
+
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO runs (input, input_hash, config, audio_seconds, processing_seconds,
+                               segment_count, speaker_count, language, result)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                input.to_string_lossy(),
+                input_hash,
+                config.to_string(),
+                result.audio_duration.as_secs_f64(),
+                result.processing_time.as_secs_f64(),
+                result.segments.len(),
+                speakers.len(),
+                result.model_info.language,
+                serde_json::to_string(result)?,
+            ],
+        )?;
+        let id = transaction.last_insert_rowid();
+
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO segments (run_id, position, start, end, speaker, text) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
+            )?;
+            for (position, segment) in result.segments.iter().enumerate() {
+                insert.execute(params![id, position, segment.start, segment.end, segment.speaker, segment.text])?;
+            }
+        }
+        transaction.commit()?;
+
+        tracing::info!(run = id, input = %input.display(), "Archived run");
+        Ok(id)
+    }
+
+    /// The most recent runs, newest first
+    pub fn history(&self, limit: usize) -> Result<Vec<ArchivedRun>> {
+        self.query(&format!("SELECT {} FROM runs ORDER BY id DESC LIMIT ?1", RUN_COLUMNS), params![limit])
+    }
+
+    /// Earlier runs of the same audio, newest first
+    pub fn find_by_hash(&self, input_hash: &str) -> Result<Vec<ArchivedRun>> {
+        self.query(&format!("SELECT {} FROM runs WHERE input_hash = ?1 ORDER BY id DESC", RUN_COLUMNS), params![input_hash])
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<ArchivedRun>> {
+        let run = self.connection
+            .query_row(&format!("SELECT {} FROM runs WHERE id = ?1", RUN_COLUMNS), [id], ArchivedRun::from_row)
+            .optional()?;
+        Ok(run)
+    }
+
+    /// The full transcript of a run
+    pub fn transcript(&self, id: i64) -> Result<Option<TranscriptResult>> {
+        let json: Option<String> = self.connection
+            .query_row("SELECT result FROM runs WHERE id = ?1", [id], |row| row.get(0))
+            .optional()?;
+        json.map(|json| serde_json::from_str(&json).map_err(AudioTranscriptionError::from)).transpose()
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<ArchivedRun>> {
+        let mut statement = self.connection.prepare(sql)?;
+        let runs = statement.query_map(params, ArchivedRun::from_row)?.collect::<rusqlite::Result<_>>()?;
+        Ok(runs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::core::audio_processor::{ModelInfo, SpeechSegment};
+
+    fn sample_result() -> TranscriptResult {
+        TranscriptResult {
+            segments: vec![
+                SpeechSegment { start: 0.0, end: 1.5, text: "Hello.".to_string(), speaker: Some(1), ..Default::default() },
+                SpeechSegment { start: 1.5, end: 3.0, text: "Hi there.".to_string(), speaker: Some(2), ..Default::default() },
+                SpeechSegment { start: 3.0, end: 4.0, text: "Let's begin.".to_string(), speaker: Some(1), ..Default::default() },
+            ],
+            audio_duration: Duration::from_secs(4),
+            processing_time: Duration::from_secs(2),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(2),
+                language: Some("en".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_record_and_history() {
+        let mut archive = RunArchive::in_memory().unwrap();
+        let config = json!({ "model": "medium" });
+
+        let first = archive.record(Path::new("standup.wav"), "abc", &config, &sample_result()).unwrap();
+        let second = archive.record(Path::new("review.wav"), "def", &config, &sample_result()).unwrap();
+
+        let history = archive.history(10).unwrap();
+        assert_eq!(history.iter().map(|run| run.id).collect::<Vec<_>>(), vec![second, first]);
+        assert_eq!(history[1].input, PathBuf::from("standup.wav"));
+        assert_eq!(history[1].segments, 3);
+        assert_eq!(history[1].speakers, 2);
+        assert_eq!(history[1].audio_duration, Duration::from_secs(4));
+        assert_eq!(history[1].config["model"], "medium");
+        assert_eq!(history[1].language.as_deref(), Some("en"));
+        assert_eq!(archive.history(1).unwrap().len(), 1);
+
+        assert_eq!(archive.transcript(first).unwrap().unwrap().segments[1].text, "Hi there.");
+        assert!(archive.get(99).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_segments_are_queryable() {
+        let mut archive = RunArchive::in_memory().unwrap();
+        let id = archive.record(Path::new("standup.wav"), "abc", &json!({}), &sample_result()).unwrap();
+
+        let text: String = archive.connection
+            .query_row(
+                "SELECT group_concat(text, ' ') FROM segments WHERE run_id = ?1 AND speaker = 1 ORDER BY position",
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(text, "Hello. Let's begin.");
+    }
+
+    #[test]
+    fn test_same_audio_is_found_by_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("standup.wav");
+        let renamed = dir.path().join("renamed.wav");
+        std::fs::write(&original, b"RIFF....WAVE").unwrap();
+        std::fs::write(&renamed, b"RIFF....WAVE").unwrap();
+
+        let hash = hash_file(&original).unwrap();
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash_file(&renamed).unwrap(), hash);
+
+        let mut archive = RunArchive::open(&dir.path().join("archive").join("runs.sqlite3")).unwrap();
+        archive.record(&original, &hash, &json!({}), &sample_result()).unwrap();
+        assert_eq!(archive.find_by_hash(&hash_file(&renamed).unwrap()).unwrap()[0].input, original);
+        assert_eq!(hash_file(&dir.path().join("missing.wav")).unwrap_err().code(), 11);
+    }
+}
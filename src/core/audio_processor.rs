@@ -1,15 +1,95 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
 use crate::error::{Result, AudioTranscriptionError};
-// use crate::core::ModelManager;
-use crate::{ModelSize};
+use crate::core::checkpoint::{Checkpoint, CheckpointLocation};
+use crate::core::chunking::{ChunkLog, ChunkPlanner, ChunkStream};
+use crate::core::model::{ModelKind, ModelManager, ModelProvider};
+use crate::core::merge;
+use crate::core::metadata;
+use crate::core::diarization::{self, DiarizationOptions, Diarizer, DEFAULT_MAX_SPEAKERS};
+use crate::core::hallucination;
+use crate::core::prefetch::Prefetcher;
+use crate::core::refine::{self, RefineChunks};
+use crate::core::progress::{self, ProcessingStage, ProgressEvent, ProgressSink};
+use crate::core::scheduler::{DispatchOrder, ReorderBuffer};
+use crate::core::selection::{self, TimeRange};
+use crate::core::speakers::Voiceprint;
+use crate::core::transcription::{self, DecodingOptions, GpuBackend, TranscriptionWorker};
+use crate::core::vad::{SileroVad, SpeechDetector, Vad, VadOptions, VadStream, DEFAULT_VAD_THRESHOLD, VAD_WINDOW_SECS};
+use crate::core::decoder::{AudioDecoder, AudioStream};
+use crate::core::model::ModelSize;
+
+pub use audio_transcribe_format::{
+    realtime_factor, AudioMetadata, ModelInfo, SkippedRange, SpeechSegment, StageTimings, TranscriptResult, WordTiming,
+};
+
+/// Sample rate expected by Whisper and the diarization models
+pub const SAMPLE_RATE: u32 = 16_000;
+
+/// Number of prepared chunks kept ready per transcription worker. Together
+/// with the chunks being transcribed this bounds how far decoding runs
+/// ahead, however long the recording.
+const PREFETCH_DEPTH: usize = 1;
+
+/// Receives segments in timestamp order as soon as their chunk is transcribed
+pub type SegmentSender = mpsc::UnboundedSender<SpeechSegment>;
+
+/// Shortest allowed chunk, matching Whisper's minimum input length
+pub const MIN_CHUNK_SECS: f32 = 1.0;
+
+/// Longest allowed chunk; longer chunks hold too much audio per worker
+pub const MAX_CHUNK_SECS: f32 = 1800.0;
+
+/// Seconds each chunk after the first repeats from before its start
+pub const DEFAULT_CHUNK_OVERLAP: f32 = 1.0;
+
+/// Extra attempts for a chunk whose inference fails before it is skipped
+pub const DEFAULT_CHUNK_RETRIES: u32 = 2;
+
+/// Mean token probability below which `refine_model` transcribes a
+/// segment again
+pub const DEFAULT_REFINE_THRESHOLD: f32 = 0.6;
+
+/// Pause before retrying a failed chunk, multiplied by the attempt number
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Text of the segment standing in for a skipped chunk
+pub const GAP_MARKER: &str = "[untranscribed audio]";
+
+/// Input file extensions the CLI accepts, lowercase
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["wav", "mp3", "m4a", "flac", "ogg", "webm"];
 
 #[derive(Debug, Clone)]
 pub struct ProcessingConfig {
     pub model_size: ModelSize,
     pub chunk_duration: f32,
+    pub chunk_overlap: f32,       // Seconds shared by neighbouring chunks
     pub parallel_jobs: usize,
     pub use_gpu: bool,
+    pub gpu_devices: Vec<usize>,  // Empty means the default device
+    pub gpu_backend: GpuBackend,
+    pub chunk_retries: u32,       // Extra attempts before a failing chunk is skipped
+    pub vad_threshold: f32,       // Speech probability at which audio counts as speech
+    pub max_speakers: usize,      // Upper bound on distinct speakers labelled
+    pub word_timestamps: bool,    // Time every word, not just every segment
+    pub language: Option<String>, // Spoken language code; None detects it per chunk
+    pub detect_language: bool,    // Detect the language once, from the first speech
+    pub translate: bool,          // Translate the speech into English
+    pub model_fallback: bool,     // Step down to a smaller model when loading one fails
+    pub max_memory: Option<u64>,  // Cap in bytes on the audio held in memory at once
+    pub refine_model: Option<ModelSize>,  // Larger model for a second pass over low-confidence segments
+    pub refine_threshold: f32,            // Confidence below which a segment gets the second pass
+    pub hallucination_filter: bool,       // Drop text invented over silence and repetition loops
+    pub decoding: DecodingOptions,        // Beam size, temperatures and other Whisper decoding knobs
+    pub initial_prompt: Option<String>,   // Domain terms and names Whisper is primed with
+    pub ranges: Vec<TimeRange>,           // Parts of the file to transcribe; empty for all of it
+    pub rebase_timestamps: bool,          // Time the transcript from the start of the selection, not the file
+    pub split_channels: bool,             // Transcribe each channel alone, as its own speaker
+    pub voiceprints: Vec<Voiceprint>,     // Enrolled speakers diarization names when it recognizes them
 }
 
 impl Default for ProcessingConfig {
@@ -17,19 +97,325 @@ impl Default for ProcessingConfig {
         Self {
             model_size: ModelSize::Medium,
             chunk_duration: 120.0, // 2 minutes
+            chunk_overlap: DEFAULT_CHUNK_OVERLAP,
             parallel_jobs: num_cpus::get(),
             use_gpu: true,
+            gpu_devices: Vec::new(),
+            gpu_backend: GpuBackend::Auto,
+            chunk_retries: DEFAULT_CHUNK_RETRIES,
+            vad_threshold: DEFAULT_VAD_THRESHOLD,
+            max_speakers: DEFAULT_MAX_SPEAKERS,
+            word_timestamps: false,
+            language: None,
+            detect_language: false,
+            translate: false,
+            model_fallback: false,
+            max_memory: None,
+            refine_model: None,
+            refine_threshold: DEFAULT_REFINE_THRESHOLD,
+            hallucination_filter: true,
+            decoding: DecodingOptions::default(),
+            initial_prompt: None,
+            ranges: Vec::new(),
+            rebase_timestamps: false,
+            split_channels: false,
+            voiceprints: Vec::new(),
         }
     }
 }
 
-/// A segment of speech with timing and optional speaker
-#[derive(Debug, Clone)]
-pub struct SpeechSegment {
-    pub start: f32,           // Start time in seconds
-    pub end: f32,             // End time in seconds
-    pub text: String,         // Transcribed text
-    pub speaker: Option<u8>,  // Speaker ID (assigned after diarization)
+impl ProcessingConfig {
+    pub fn builder() -> ProcessingConfigBuilder {
+        ProcessingConfigBuilder::default()
+    }
+
+    /// Check that the options can be used together
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(AudioTranscriptionError::Configuration(message));
+
+        if !(MIN_CHUNK_SECS..=MAX_CHUNK_SECS).contains(&self.chunk_duration) {
+            return invalid(format!(
+                "Chunk duration must be between {} and {} seconds, got {}",
+                MIN_CHUNK_SECS, MAX_CHUNK_SECS, self.chunk_duration
+            ));
+        }
+        if !(self.chunk_overlap >= 0.0 && self.chunk_overlap < self.chunk_duration) {
+            return invalid(format!(
+                "Chunk overlap must be at least 0 and shorter than the chunk duration ({}s), got {}",
+                self.chunk_duration, self.chunk_overlap
+            ));
+        }
+        if !(self.vad_threshold > 0.0 && self.vad_threshold < 1.0) {
+            return invalid(format!("VAD threshold must be between 0 and 1, got {}", self.vad_threshold));
+        }
+        if !(1..=u8::MAX as usize).contains(&self.max_speakers) {
+            return invalid(format!("Max speakers must be between 1 and {}, got {}", u8::MAX, self.max_speakers));
+        }
+        if let Some(language) = self.language.as_deref().filter(|code| !transcription::LANGUAGES.contains(code)) {
+            return invalid(format!("Unknown language '{}'; use a code such as en, de or ja", language));
+        }
+        if self.language.is_some() && self.detect_language {
+            return invalid("A fixed language and language detection can't be combined".to_string());
+        }
+        let other_language = self.language.as_deref().is_some_and(|code| code != "en");
+        for model in std::iter::once(&self.model_size).chain(&self.refine_model) {
+            if model.is_english_only() && (other_language || self.detect_language || self.translate) {
+                return invalid(format!(
+                    "The {} model only transcribes English; use a multilingual model for other languages, detection or translation",
+                    model
+                ));
+            }
+        }
+        if self.refine_model.as_ref() == Some(&self.model_size) {
+            return invalid(format!("The refine model must differ from the {} model transcribing the file", self.model_size));
+        }
+        if !(self.refine_threshold > 0.0 && self.refine_threshold < 1.0) {
+            return invalid(format!("Refine threshold must be between 0 and 1, got {}", self.refine_threshold));
+        }
+        self.decoding.validate()?;
+        if self.initial_prompt.as_deref().is_some_and(|prompt| prompt.contains('\0')) {
+            return invalid("The initial prompt must not contain NUL characters".to_string());
+        }
+        selection::validate(&self.ranges)?;
+        if self.parallel_jobs == 0 {
+            return invalid("At least one parallel job is required".to_string());
+        }
+        if !self.use_gpu && !self.gpu_devices.is_empty() {
+            return invalid(format!("GPU devices {:?} were given but GPU acceleration is disabled", self.gpu_devices));
+        }
+        if self.use_gpu {
+            self.gpu_backend.ensure_compiled()?;
+        } else if self.gpu_backend != GpuBackend::Auto {
+            return invalid(format!("The {} backend was given but GPU acceleration is disabled", self.gpu_backend));
+        }
+        let mut devices = self.gpu_devices.clone();
+        devices.sort_unstable();
+        devices.dedup();
+        if devices.len() != self.gpu_devices.len() {
+            return invalid(format!("GPU devices must not repeat, got {:?}", self.gpu_devices));
+        }
+        if let Some(max_memory) = self.max_memory.filter(|&max_memory| max_memory < self.audio_memory(1)) {
+            return invalid(format!(
+                "A memory cap of {} MB is below the {} MB needed for {}s chunks on {} workers; use shorter chunks or fewer jobs",
+                max_memory / 1_000_000,
+                self.audio_memory(1).div_ceil(1_000_000),
+                self.chunk_duration,
+                transcription::worker_devices(self).len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Estimated peak of the audio held in memory with `queued` prepared
+    /// chunks waiting for the workers: the chunks being transcribed and
+    /// queued, the one being gathered and the VAD windows, or the window
+    /// being diarized if that is more. The models come on top.
+    pub fn audio_memory(&self, queued: usize) -> u64 {
+        let workers = transcription::worker_devices(self).len();
+        let chunk = audio_bytes(self.chunk_duration + self.chunk_overlap);
+        let transcription = chunk * (workers + queued + 2) as u64 + audio_bytes(2.0 * VAD_WINDOW_SECS);
+        // Samples and their 16-bit copy for the segmentation model
+        let diarization = audio_bytes(diarization::WINDOW_SECS) * 3 / 2;
+        transcription.max(diarization)
+    }
+
+    /// Prepared chunks to keep queued: [`PREFETCH_DEPTH`] per worker, or
+    /// fewer to stay within `max_memory`
+    fn lookahead(&self) -> usize {
+        let wanted = PREFETCH_DEPTH * transcription::worker_devices(self).len().max(1);
+        match self.max_memory {
+            Some(max_memory) => (1..=wanted).rev().find(|&queued| self.audio_memory(queued) <= max_memory).unwrap_or(1),
+            None => wanted,
+        }
+    }
+}
+
+/// Bytes of 16 kHz mono `f32` audio
+fn audio_bytes(seconds: f32) -> u64 {
+    (seconds * SAMPLE_RATE as f32) as u64 * 4
+}
+
+/// Builder for [`ProcessingConfig`] that validates on [`build`](Self::build)
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingConfigBuilder {
+    config: ProcessingConfig,
+}
+
+impl ProcessingConfigBuilder {
+    pub fn model(mut self, model_size: ModelSize) -> Self {
+        self.config.model_size = model_size;
+        self
+    }
+
+    /// Longest chunk in seconds; chunks end in the last pause before it
+    pub fn chunk_duration(mut self, seconds: f32) -> Self {
+        self.config.chunk_duration = seconds;
+        self
+    }
+
+    /// Seconds of audio repeated at the start of each chunk
+    pub fn chunk_overlap(mut self, seconds: f32) -> Self {
+        self.config.chunk_overlap = seconds;
+        self
+    }
+
+    /// Number of parallel transcription jobs
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.config.parallel_jobs = jobs;
+        self
+    }
+
+    pub fn gpu(mut self, use_gpu: bool) -> Self {
+        self.config.use_gpu = use_gpu;
+        self
+    }
+
+    /// GPU devices to distribute chunks across
+    pub fn gpu_devices(mut self, devices: impl IntoIterator<Item = usize>) -> Self {
+        self.config.gpu_devices = devices.into_iter().collect();
+        self
+    }
+
+    /// GPU backend to require; building fails if it isn't compiled in
+    pub fn gpu_backend(mut self, backend: GpuBackend) -> Self {
+        self.config.gpu_backend = backend;
+        self
+    }
+
+    /// Extra attempts for a chunk whose inference fails; after the last one
+    /// the chunk is skipped and marked as a gap in the transcript
+    pub fn chunk_retries(mut self, retries: u32) -> Self {
+        self.config.chunk_retries = retries;
+        self
+    }
+
+    /// Speech probability, between 0 and 1, above which the voice activity
+    /// detector treats audio as speech; lower values keep more quiet speech
+    pub fn vad_threshold(mut self, threshold: f32) -> Self {
+        self.config.vad_threshold = threshold;
+        self
+    }
+
+    /// Upper bound on distinct speakers; once reached, further voices are
+    /// attributed to the closest known speaker
+    pub fn max_speakers(mut self, max_speakers: usize) -> Self {
+        self.config.max_speakers = max_speakers;
+        self
+    }
+
+    /// Spoken language as a code such as `en`, instead of detecting it
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.config.language = Some(language.into());
+        self
+    }
+
+    /// Detect the language once from the first 30 seconds of speech and
+    /// transcribe every chunk in it, rather than detecting it per chunk
+    pub fn detect_language(mut self, enabled: bool) -> Self {
+        self.config.detect_language = enabled;
+        self
+    }
+
+    /// Give each segment the start, end and confidence of its words
+    pub fn word_timestamps(mut self, enabled: bool) -> Self {
+        self.config.word_timestamps = enabled;
+        self
+    }
+
+    /// Run Whisper's translate task, producing English text from speech in
+    /// any supported language
+    pub fn translate(mut self, enabled: bool) -> Self {
+        self.config.translate = enabled;
+        self
+    }
+
+    /// Try the next smaller model when the chosen one fails to load, e.g.
+    /// because it doesn't fit in memory
+    pub fn model_fallback(mut self, enabled: bool) -> Self {
+        self.config.model_fallback = enabled;
+        self
+    }
+
+    /// Cap in bytes on the audio held in memory at once; fewer chunks are
+    /// decoded ahead to stay under it, and building fails if even one
+    /// chunk per worker wouldn't fit
+    pub fn max_memory(mut self, bytes: u64) -> Self {
+        self.config.max_memory = Some(bytes);
+        self
+    }
+
+    /// Transcribe segments whose mean token probability is below the
+    /// refine threshold again with `model`, after the whole file is done.
+    /// Segments already streamed are from the first pass.
+    pub fn refine_model(mut self, model: ModelSize) -> Self {
+        self.config.refine_model = Some(model);
+        self
+    }
+
+    /// Confidence, between 0 and 1, below which a segment is transcribed
+    /// again by the refine model
+    pub fn refine_threshold(mut self, threshold: f32) -> Self {
+        self.config.refine_threshold = threshold;
+        self
+    }
+
+    /// Drop segments over silence and known stock phrases, and collapse
+    /// repetition loops (on by default). Segments already streamed are
+    /// unfiltered.
+    pub fn hallucination_filter(mut self, enabled: bool) -> Self {
+        self.config.hallucination_filter = enabled;
+        self
+    }
+
+    /// Decode with beam search, other temperatures or a different
+    /// no-speech threshold, trading speed for accuracy or the reverse
+    pub fn decoding(mut self, options: DecodingOptions) -> Self {
+        self.config.decoding = options;
+        self
+    }
+
+    /// Prime Whisper with `prompt` before every chunk, e.g. a list of the
+    /// product names and people a meeting is about. Whisper reads only
+    /// the last 224 tokens of it.
+    pub fn initial_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.config.initial_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Transcribe only these parts of the file, in order and apart; the
+    /// audio in between isn't even decoded where the format can seek
+    pub fn ranges(mut self, ranges: impl IntoIterator<Item = TimeRange>) -> Self {
+        self.config.ranges = ranges.into_iter().collect();
+        self
+    }
+
+    /// Time the transcript from zero, with the selected ranges back to
+    /// back, instead of keeping the times of the file
+    pub fn rebase_timestamps(mut self, enabled: bool) -> Self {
+        self.config.rebase_timestamps = enabled;
+        self
+    }
+
+    /// Transcribe each channel on its own and label its speech as one
+    /// speaker, channel 1 as speaker 1, instead of diarizing the mix; for
+    /// call recordings with a caller per channel. Streamed segments are
+    /// sent once every channel is done.
+    pub fn split_channels(mut self, enabled: bool) -> Self {
+        self.config.split_channels = enabled;
+        self
+    }
+
+    /// Enrolled speakers; diarized speakers whose voice matches one are
+    /// labelled with its name
+    pub fn voiceprints(mut self, voiceprints: Vec<Voiceprint>) -> Self {
+        self.config.voiceprints = voiceprints;
+        self
+    }
+
+    pub fn build(self) -> Result<ProcessingConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
 }
 
 /// A chunk of audio to be processed
@@ -55,97 +441,956 @@ pub struct DiarizationSegment {
     pub start: f32,
     pub end: f32,
     pub speaker: u8,
+    pub name: Option<String>,  // Enrolled speaker the voice was recognized as
 }
 
-/// Model information for the transcript
-#[derive(Debug, Clone)]
-pub struct ModelInfo {
-    pub whisper_model: String,
-    pub diarization_model: String,
-    pub processing_time: Duration,
+/// Segments from the transcription stage and whether every chunk finished
+struct TranscriptionOutcome {
+    segments: Vec<SpeechSegment>,
+    complete: bool,
+    skipped: Vec<SkippedRange>,
+    language: Option<String>,  // Detected before transcribing, when asked to
+    model_size: ModelSize,     // Smaller than configured after a fallback
+    load_time: Duration,       // Spent loading the model
+    tokens: u64,               // Decoded by all workers
+    refined: bool,             // The refine model had a second pass
 }
 
-/// Final transcript result
-#[derive(Debug)]
-pub struct TranscriptResult {
-    pub segments: Vec<SpeechSegment>,
-    pub processing_time: Duration,
-    pub model_info: ModelInfo,
+/// Queue of prepared chunks for `workers` workers, handed out in
+/// [`DispatchOrder`] within the `lookahead` window
+fn dispatch_in_order<I>(chunks: I, lookahead: usize, workers: usize) -> Prefetcher<AudioChunk>
+where
+    I: IntoIterator<Item = Result<AudioChunk>> + Send + 'static,
+    I::IntoIter: Send,
+{
+    let mut order = DispatchOrder::new(workers);
+    Prefetcher::spawn_scheduled(chunks, lookahead, |chunk| chunk, move |waiting: &[AudioChunk]| {
+        let durations: Vec<f32> = waiting.iter().map(|chunk| chunk.end - chunk.start).collect();
+        order.pick(&durations)
+    })
+}
+
+/// What [`run_worker`] needs of a worker, so tests can stand in for Whisper
+trait ChunkWorker: Send + 'static {
+    fn device(&self) -> Option<usize>;
+    fn tokens(&self) -> u64;
+    fn transcribe(&mut self, chunk: &AudioChunk) -> Result<Vec<SpeechSegment>>;
+}
+
+impl ChunkWorker for TranscriptionWorker {
+    fn device(&self) -> Option<usize> {
+        TranscriptionWorker::device(self)
+    }
+
+    fn tokens(&self) -> u64 {
+        TranscriptionWorker::tokens(self)
+    }
+
+    fn transcribe(&mut self, chunk: &AudioChunk) -> Result<Vec<SpeechSegment>> {
+        TranscriptionWorker::transcribe(self, chunk)
+    }
+}
+
+/// What a worker reports for each chunk it took
+enum ChunkOutcome {
+    Transcribed(Vec<SpeechSegment>),
+    Failed { attempts: u32, error: String },
 }
 
 pub struct AudioProcessor {
-    // model_manager: ModelManager,
     config: ProcessingConfig,
+    progress: Option<Arc<dyn ProgressSink>>,
+    model_provider: Option<Arc<dyn ModelProvider>>,
+    checkpoint: Option<CheckpointLocation>,
 }
 
 impl AudioProcessor {
     pub fn new(config: ProcessingConfig) -> Self {
         Self {
-            // model_manager,
             config,
+            progress: None,
+            model_provider: None,
+            checkpoint: None,
         }
     }
 
-    pub async fn process_file(&self, path: &Path) -> Result<TranscriptResult> {
+    /// Save finished chunks in `dir` and skip chunks saved by an earlier,
+    /// interrupted run of the same file
+    pub fn with_checkpoint_dir(mut self, dir: PathBuf) -> Self {
+        self.checkpoint = Some(CheckpointLocation::Dir(dir));
+        self
+    }
+
+    /// Like [`AudioProcessor::with_checkpoint_dir`], with the checkpoint
+    /// kept in `path`
+    pub fn with_checkpoint_file(mut self, path: PathBuf) -> Self {
+        self.checkpoint = Some(CheckpointLocation::File(path));
+        self
+    }
+
+    /// Load models from `provider` instead of the default model cache
+    pub fn with_model_provider(mut self, provider: Arc<dyn ModelProvider>) -> Self {
+        self.model_provider = Some(provider);
+        self
+    }
+
+    /// The configured model provider, or the default cache-backed
+    /// [`ModelManager`]
+    pub fn model_provider(&self) -> Result<Arc<dyn ModelProvider>> {
+        match &self.model_provider {
+            Some(provider) => Ok(Arc::clone(provider)),
+            None => Ok(Arc::new(ModelManager::new()?)),
+        }
+    }
+
+    /// Report stage changes, chunk completion and ETA to `sink`
+    pub fn with_progress(mut self, sink: Arc<dyn ProgressSink>) -> Self {
+        self.progress = Some(sink);
+        self
+    }
+
+    fn report(&self, event: ProgressEvent) {
+        if let Some(sink) = &self.progress {
+            sink.report(event);
+        }
+    }
+
+    pub fn config(&self) -> &ProcessingConfig {
+        &self.config
+    }
+
+    /// The same processor with other settings, e.g. for one file
+    pub(crate) fn with_config(&self, config: ProcessingConfig) -> Self {
+        Self {
+            config,
+            progress: self.progress.clone(),
+            model_provider: self.model_provider.clone(),
+            checkpoint: self.checkpoint.clone(),
+        }
+    }
+
+    /// Process a file end to end. When `cancel` fires, no new chunks are
+    /// started, chunks already in flight are finished, and the segments
+    /// completed so far are returned with `partial` set.
+    pub async fn process_file(&self, path: &Path, cancel: CancellationToken) -> Result<TranscriptResult> {
+        self.run_pipeline(path, None, cancel).await
+    }
+
+    /// Like [`process_file`](Self::process_file), but also sends each segment
+    /// to `segments` as soon as it is transcribed. Streamed segments are
+    /// emitted before diarization and carry no speaker; the returned result
+    /// has the final speaker labels.
+    pub async fn process_file_streaming(
+        &self,
+        path: &Path,
+        segments: SegmentSender,
+        cancel: CancellationToken,
+    ) -> Result<TranscriptResult> {
+        self.run_pipeline(path, Some(segments), cancel).await
+    }
+
+    #[tracing::instrument(name = "process_file", skip_all, fields(path = %path.display()))]
+    async fn run_pipeline(
+        &self,
+        path: &Path,
+        segment_sender: Option<SegmentSender>,
+        cancel: CancellationToken,
+    ) -> Result<TranscriptResult> {
         let start_time = Instant::now();
-        
-        // TODO: Implement full audio processing pipeline
-        // This will be implemented in subsequent tasks (5-8)
-        log::info!("Processing audio file: {}", path.display());
-        
-        // Placeholder implementation
-        let segments = vec![SpeechSegment {
-            start: 0.0,
-            end: 10.0,
-            text: "Placeholder transcription".to_string(),
-            speaker: Some(1),
-        }];
 
+        tracing::info!("Processing audio file");
+        let metadata = metadata::probe(path)?;
+        tracing::debug!(
+            sample_rate = metadata.sample_rate,
+            channels = metadata.channels,
+            codec = %metadata.codec,
+            duration_secs = metadata.duration.as_secs_f32(),
+            "Probed source audio"
+        );
+        let audio_duration = if self.config.ranges.is_empty() {
+            metadata.duration
+        } else {
+            let selected_secs = selection::selected_secs(&self.config.ranges, metadata.duration.as_secs_f32());
+            if selected_secs == 0.0 && !metadata.duration.is_zero() {
+                return Err(AudioTranscriptionError::Configuration(format!(
+                    "The selected time ranges lie past the end of {} ({:.1}s)",
+                    path.display(),
+                    metadata.duration.as_secs_f32()
+                )));
+            }
+            tracing::info!(ranges = self.config.ranges.len(), selected_secs, "Transcribing the selected time ranges");
+            Duration::from_secs_f32(selected_secs)
+        };
+        let channels: Vec<Option<usize>> = if self.config.split_channels {
+            if metadata.channels < 2 {
+                return Err(AudioTranscriptionError::Configuration(format!(
+                    "Splitting channels needs a recording with two or more, {} has {}",
+                    path.display(),
+                    metadata.channels
+                )));
+            }
+            (0..metadata.channels as usize).map(Some).collect()
+        } else {
+            vec![None]
+        };
+        let mut stage_timings = StageTimings::default();
+
+        // Channels are transcribed one after the other, each on every
+        // worker; their segments are only streamed once all are in, so
+        // they still arrive in timestamp order
+        let mut sources = Vec::with_capacity(channels.len());
+        for &channel in &channels {
+            let sender = segment_sender.as_ref().filter(|_| channel.is_none());
+            sources.push((channel, self.transcribe_source(path, channel, audio_duration.as_secs_f32(), sender, &mut stage_timings, &cancel).await?));
+            if cancel.is_cancelled() {
+                break;
+            }
+        }
+
+        let complete = sources.len() == channels.len() && sources.iter().all(|(_, (outcome, _))| outcome.complete);
+        let mut segments = Vec::new();
+        let mut skipped = Vec::new();
+        let mut checkpoints = Vec::new();
+        let (mut language, mut model_size, mut load_time, mut tokens, mut refined) = (None, None, Duration::ZERO, 0, false);
+        for (channel, (outcome, checkpoint)) in sources {
+            // The channel is the speaker
+            let speaker = channel.map(|channel| (channel + 1) as u8);
+            segments.extend(outcome.segments.into_iter().map(|segment| SpeechSegment { speaker: speaker.or(segment.speaker), ..segment }));
+            skipped.extend(outcome.skipped);
+            checkpoints.extend(checkpoint);
+            language = language.or(outcome.language);
+            model_size.get_or_insert(outcome.model_size);
+            load_time += outcome.load_time;
+            tokens += outcome.tokens;
+            refined |= outcome.refined;
+        }
+
+        if self.config.split_channels {
+            segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+            skipped.sort_by(|a, b| a.start.total_cmp(&b.start));
+            if let Some(sender) = &segment_sender {
+                for segment in &segments {
+                    let _ = sender.send(self.placed(segment));
+                }
+            }
+        } else if complete {
+            // Speakers are only assigned to a complete transcript
+            let stage_start = Instant::now();
+            let diarization = self.run_diarization(path).await?;
+            stage_timings.diarization = stage_start.elapsed();
+
+            let stage_start = Instant::now();
+            segments = self.merge_results(segments, diarization);
+            stage_timings.merging = stage_start.elapsed();
+        }
+
+        // Every stage ran on the selected ranges back to back
+        for segment in &mut segments {
+            selection::place_segment(self.placement(), segment);
+        }
+        for range in &mut skipped {
+            (range.start, range.end) = selection::place(self.placement(), range.start, range.end);
+        }
+
+        // A complete transcript doesn't need resuming; one with skipped
+        // chunks keeps the rest so a rerun only retries those
+        if complete && skipped.is_empty() {
+            for checkpoint in checkpoints {
+                checkpoint.remove()?;
+            }
+        }
+
+        self.report(ProgressEvent::StageStarted(ProcessingStage::Complete));
         let processing_time = start_time.elapsed();
         let model_info = ModelInfo {
-            whisper_model: self.config.model_size.to_string(),
+            whisper_model: model_size.unwrap_or_else(|| self.config.model_size.clone()).to_string(),
             diarization_model: "pyannote".to_string(),
             processing_time,
+            load_time,
+            language: language.or_else(|| self.config.language.clone()),
+            translated: self.config.translate,
+            refine_model: self.config.refine_model.as_ref().filter(|_| refined).map(ModelSize::to_string),
         };
 
         Ok(TranscriptResult {
             segments,
+            audio_duration,
             processing_time,
+            stage_timings,
             model_info,
+            metadata,
+            partial: !complete,
+            skipped,
+            tokens,
         })
     }
 
-    fn run_vad(&self, _audio: &[f32]) -> Result<Vec<VadSegment>> {
-        // TODO: Implement VAD using whisper-rs
-        // This will be implemented in task 5
-        Ok(vec![])
+    /// Everything before speakers are assigned, for the file or one of its
+    /// channels: finding chunks, transcribing them, filtering hallucinations
+    /// and refining. Stage times are added to `stage_timings`. The
+    /// checkpoint is returned too, to be removed once the whole transcript
+    /// is done.
+    async fn transcribe_source(
+        &self,
+        path: &Path,
+        channel: Option<usize>,
+        audio_secs: f32,
+        segment_sender: Option<&SegmentSender>,
+        stage_timings: &mut StageTimings,
+        cancel: &CancellationToken,
+    ) -> Result<(TranscriptionOutcome, Option<Checkpoint>)> {
+        let mut checkpoint = match (&self.checkpoint, channel) {
+            (Some(location), Some(channel)) => Some(Checkpoint::open_channel_at(location, path, channel, &self.config)?),
+            (Some(location), None) => Some(Checkpoint::open_at(location, path, &self.config)?),
+            (None, _) => None,
+        };
+        if let Some(channel) = channel {
+            tracing::info!(channel = channel + 1, "Transcribing channel");
+        }
+
+        // Speech is found and cut into chunks as the file is decoded, while
+        // earlier chunks are transcribed; VAD and chunking are timed by the
+        // time spent in them
+        let stage_start = Instant::now();
+        let chunks = self.chunk_stream(path, channel, checkpoint.as_ref()).await?;
+        let log = chunks.log();
+        let mut outcome = self
+            .transcribe_parallel(chunks, audio_secs, segment_sender, checkpoint.as_mut(), cancel)
+            .await?;
+        stage_timings.transcription += stage_start.elapsed();
+        {
+            let log = log.lock().unwrap();
+            stage_timings.vad += log.vad;
+            stage_timings.chunking += log.chunking;
+        }
+
+        // Before refinement, so the larger model isn't spent on silence.
+        // Speech regions are only complete once the whole file was read.
+        if self.config.hallucination_filter {
+            let log = log.lock().unwrap();
+            let (kept, stats) = hallucination::filter(std::mem::take(&mut outcome.segments), log.finished.then_some(log.speech.as_slice()));
+            tracing::info!(silence = stats.silence, loops = stats.loops, phrases = stats.phrases, "Filtered hallucinated segments");
+            outcome.segments = kept;
+        }
+
+        // Unclear stretches get a second pass once the whole first one is in
+        outcome.complete &= !cancel.is_cancelled();
+        if let Some(model) = self.config.refine_model.as_ref().filter(|_| outcome.complete) {
+            let stage_start = Instant::now();
+            let segments = std::mem::take(&mut outcome.segments);
+            outcome.segments = self.refine(path, channel, segments, model, outcome.language.as_deref(), cancel).await?;
+            stage_timings.refinement += stage_start.elapsed();
+            outcome.refined = true;
+            outcome.complete = !cancel.is_cancelled();
+        }
+
+        Ok((outcome, checkpoint))
+    }
+
+    /// Start decoding `path`, or one channel of it, and finding its chunks,
+    /// with Silero or with the energy detector when the Silero model isn't
+    /// available. Chunks saved in `checkpoint` are found but not prepared.
+    async fn chunk_stream(&self, path: &Path, channel: Option<usize>, checkpoint: Option<&Checkpoint>) -> Result<ChunkStream> {
+        self.report(ProgressEvent::StageStarted(ProcessingStage::VAD));
+        let options = VadOptions { threshold: self.config.vad_threshold, ..VadOptions::default() };
+        let provider = self.model_provider()?;
+
+        let detector = match SileroVad::from_provider(provider.as_ref(), options.clone()).await {
+            Ok(vad) => SpeechDetector::Silero(vad),
+            Err(e @ AudioTranscriptionError::ModelNotFound { .. }) => {
+                tracing::warn!(error = %e, "Silero VAD model unavailable, falling back to energy-based detection");
+                SpeechDetector::Energy(Vad::new(options))
+            }
+            Err(e) => return Err(e),
+        };
+
+        let skip = checkpoint.map(|checkpoint| checkpoint.chunks().map(|(index, _)| index).collect()).unwrap_or_default();
+        let planner = ChunkPlanner::new(self.config.chunk_duration, self.config.chunk_overlap);
+        Ok(ChunkStream::new(self.open_audio(path, channel)?, VadStream::new(detector), planner, skip))
+    }
+
+    /// Start decoding `path`, or only its selected ranges, as a mix of all
+    /// channels or just `channel`
+    fn open_audio(&self, path: &Path, channel: Option<usize>) -> Result<AudioStream> {
+        let mut audio = AudioDecoder::default().stream(path)?;
+        if let Some(channel) = channel {
+            audio = audio.channel(channel);
+        }
+        Ok(if self.config.ranges.is_empty() { audio } else { audio.select(&self.config.ranges) })
     }
 
-    fn create_chunks(&self, _audio: &[f32], _vad_segments: &[VadSegment]) -> Vec<AudioChunk> {
-        // TODO: Implement chunking algorithm
-        // This will be implemented in task 6
-        vec![]
+    /// Ranges that times on the selection are placed back in the file by;
+    /// none when the timestamps are rebased
+    fn placement(&self) -> &[TimeRange] {
+        if self.config.rebase_timestamps { &[] } else { &self.config.ranges }
+    }
+
+    /// `segment` with the times it is output with
+    fn placed(&self, segment: &SpeechSegment) -> SpeechSegment {
+        let mut segment = segment.clone();
+        selection::place_segment(self.placement(), &mut segment);
+        segment
+    }
+
+    /// Transcribe chunks as `chunks` finds them. `audio_secs` is the length
+    /// of the file or selection, which progress is measured against.
+    #[tracing::instrument(name = "transcription", skip_all)]
+    async fn transcribe_parallel(
+        &self,
+        chunks: ChunkStream,
+        audio_secs: f32,
+        segment_sender: Option<&SegmentSender>,
+        checkpoint: Option<&mut Checkpoint>,
+        cancel: &CancellationToken,
+    ) -> Result<TranscriptionOutcome> {
+        let devices = transcription::worker_devices(&self.config);
+        let log = chunks.log();
+
+        // Decoding runs on a blocking thread and stops whenever the queue of
+        // prepared chunks is full, so it stays only a few chunks ahead of
+        // the workers. Workers pull from the queue whenever they go idle,
+        // so a faster device simply ends up taking more chunks. Among the
+        // chunks prepared ahead, short ones go out first.
+        let lookahead = self.config.lookahead();
+        let mut queue = dispatch_in_order(chunks, lookahead, devices.len());
+
+        // The model isn't even loaded when the checkpoint had every chunk
+        let (mut loaded, model_size, load_time) = if queue.peek().await.is_none() {
+            (Vec::new(), self.config.model_size.clone(), Duration::ZERO)
+        } else {
+            self.load_workers(&self.config, &devices).await?
+        };
+        self.report(ProgressEvent::StageStarted(ProcessingStage::Transcription));
+
+        let first_chunk = match queue.peek().await {
+            Some(Ok(chunk)) if self.config.detect_language && !loaded.is_empty() => Some(chunk),
+            _ => None,
+        };
+        let language = match first_chunk {
+            Some(chunk) => {
+                let detection_samples = (transcription::LANGUAGE_DETECTION_SECS * SAMPLE_RATE as f32) as usize;
+                let samples = chunk.samples[..chunk.samples.len().min(detection_samples)].to_vec();
+                let language = detect_language(&mut loaded, samples).await?;
+                for worker in &mut loaded {
+                    worker.set_language(Some(language.clone()));
+                }
+                Some(language)
+            }
+            None => None,
+        };
+
+        tracing::debug!(workers = loaded.len(), lookahead, "Starting transcription workers");
+        let queue = Arc::new(Mutex::new(queue));
+
+        let (result_sender, result_receiver) = mpsc::unbounded_channel();
+        let mut workers = JoinSet::new();
+        for (number, worker) in loaded.into_iter().enumerate() {
+            workers.spawn(run_worker(
+                number,
+                worker,
+                Arc::clone(&queue),
+                self.progress.clone(),
+                result_sender.clone(),
+                self.config.chunk_retries,
+                cancel.clone(),
+            ));
+        }
+        drop(result_sender);
+
+        let outcome = self.collect_chunks(workers, result_receiver, &log, audio_secs, segment_sender, checkpoint).await?;
+        Ok(TranscriptionOutcome { language, model_size, load_time, ..outcome })
+    }
+
+    /// Gather the chunks `workers` send to `results` and put them back in
+    /// file order, streaming segments as soon as the chunks before them are
+    /// in. Chunks restored from `checkpoint` come first and new ones are
+    /// recorded in it; a chunk that failed becomes a gap in the transcript.
+    async fn collect_chunks(
+        &self,
+        mut workers: JoinSet<Result<u64>>,
+        mut result_receiver: mpsc::UnboundedReceiver<(usize, ChunkOutcome)>,
+        log: &std::sync::Mutex<ChunkLog>,
+        audio_secs: f32,
+        segment_sender: Option<&SegmentSender>,
+        mut checkpoint: Option<&mut Checkpoint>,
+    ) -> Result<TranscriptionOutcome> {
+        let started = Instant::now();
+
+        // Chunks saved by an interrupted run are not transcribed again
+        let mut reorder = ReorderBuffer::new();
+        let mut restored = Vec::new();
+        let mut completed = 0;
+        if let Some(checkpoint) = checkpoint.as_deref() {
+            for (index, segments) in checkpoint.chunks() {
+                completed += 1;
+                restored.extend(reorder.push(index, segments.to_vec()).into_iter().flatten());
+            }
+        }
+
+        // Chunks finish out of order; reassemble them by position in the file
+        let mut segments = restored;
+        let mut skipped = Vec::new();
+        if let Some(sender) = segment_sender {
+            for segment in &segments {
+                let _ = sender.send(self.placed(segment));
+            }
+        }
+        let mut completed_audio_secs = segments.last().map_or(0.0, |segment| segment.end);
+        while let Some((index, outcome)) = result_receiver.recv().await {
+            let (start, end) = log.lock().unwrap().bounds[index];
+            let chunk_segments = match outcome {
+                ChunkOutcome::Transcribed(chunk_segments) => {
+                    if let Some(checkpoint) = checkpoint.as_deref_mut() {
+                        checkpoint.record(index, &chunk_segments)?;
+                    }
+                    chunk_segments
+                }
+                // Not checkpointed, so the next run of the file tries it again
+                ChunkOutcome::Failed { attempts, error } => {
+                    tracing::warn!(index, attempts, %error, "Skipping chunk");
+                    skipped.push(SkippedRange { start, end, attempts, error });
+                    vec![SpeechSegment { start, end, text: GAP_MARKER.to_string(), ..Default::default() }]
+                }
+            };
+            completed += 1;
+            completed_audio_secs = completed_audio_secs.max(end);
+            let found = log.lock().unwrap().bounds.len();
+            self.report(ProgressEvent::ChunkCompleted {
+                index,
+                completed,
+                total: found.max(completed),
+                completed_audio_secs,
+                total_audio_secs: audio_secs,
+                eta: progress::estimate_remaining(started.elapsed(), completed_audio_secs, audio_secs),
+            });
+
+            for ready in reorder.push(index, chunk_segments) {
+                if let Some(sender) = segment_sender {
+                    for segment in &ready {
+                        let _ = sender.send(self.placed(segment));
+                    }
+                }
+                segments.extend(ready);
+            }
+        }
+
+        let mut tokens = 0;
+        while let Some(finished) = workers.join_next().await {
+            tokens += finished.map_err(|e| AudioTranscriptionError::Audio(
+                format!("Transcription worker failed: {}", e)
+            ))??;
+        }
+
+        let log = log.lock().unwrap();
+        let speech_secs: f32 = log.bounds.iter().map(|(start, end)| end - start).sum();
+        tracing::debug!(chunks = log.bounds.len(), speech_secs, tokens, "Transcribed the chunks found");
+
+        // After a cancellation, keep chunks that finished behind a gap too
+        let complete = log.finished && completed == log.bounds.len();
+        if !complete {
+            tracing::info!(completed, found = log.bounds.len(), "Transcription cancelled");
+            segments.extend(reorder.drain().into_iter().flatten());
+        }
+
+        skipped.sort_by(|a, b| a.start.total_cmp(&b.start));
+        let model_size = self.config.model_size.clone();
+        Ok(TranscriptionOutcome { segments, complete, skipped, language: None, model_size, load_time: Duration::ZERO, tokens, refined: false })
+    }
+
+    /// Transcribe the segments below the refine threshold again with
+    /// `model`, decoding their audio from the file once more. Segments the
+    /// second pass doesn't improve on, because it failed or heard nothing,
+    /// are kept as they were.
+    #[tracing::instrument(name = "refinement", skip_all, fields(model = %model))]
+    async fn refine(
+        &self,
+        path: &Path,
+        channel: Option<usize>,
+        segments: Vec<SpeechSegment>,
+        model: &ModelSize,
+        language: Option<&str>,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<SpeechSegment>> {
+        let spans = refine::low_confidence_spans(&segments, self.config.refine_threshold, self.config.chunk_duration);
+        if spans.is_empty() {
+            return Ok(segments);
+        }
+        self.report(ProgressEvent::StageStarted(ProcessingStage::Refinement));
+        let speech_secs: f32 = spans.iter().map(|span| span.end - span.start).sum();
+        tracing::info!(spans = spans.len(), speech_secs, "Refining low-confidence segments");
+
+        // Spans are cut at segment edges, so there is no lead-in to skip
+        let config = ProcessingConfig { model_size: model.clone(), chunk_overlap: 0.0, ..self.config.clone() };
+        let devices = transcription::worker_devices(&config);
+        let (mut loaded, _, _) = self.load_workers(&config, &devices).await?;
+        if let Some(language) = language {
+            for worker in &mut loaded {
+                worker.set_language(Some(language.to_string()));
+            }
+        }
+
+        let chunks = RefineChunks::new(self.open_audio(path, channel)?, spans.clone());
+        let queue = Arc::new(Mutex::new(Prefetcher::spawn(chunks, config.lookahead(), |chunk| chunk)));
+        let (result_sender, mut result_receiver) = mpsc::unbounded_channel();
+        let mut workers = JoinSet::new();
+        // Refinement has no chunk progress of its own, so workers go unreported
+        for (number, worker) in loaded.into_iter().enumerate() {
+            workers.spawn(run_worker(number, worker, Arc::clone(&queue), None, result_sender.clone(), config.chunk_retries, cancel.clone()));
+        }
+        drop(result_sender);
+
+        let mut refined = vec![None; spans.len()];
+        while let Some((index, outcome)) = result_receiver.recv().await {
+            match outcome {
+                ChunkOutcome::Transcribed(chunk_segments) => refined[index] = Some(chunk_segments),
+                ChunkOutcome::Failed { attempts, error } => {
+                    tracing::warn!(index, attempts, %error, "Keeping the first pass of a span that failed to refine");
+                }
+            }
+        }
+        let mut tokens = 0;
+        while let Some(finished) = workers.join_next().await {
+            tokens += finished.map_err(|e| AudioTranscriptionError::Audio(
+                format!("Transcription worker failed: {}", e)
+            ))??;
+        }
+        tracing::debug!(refined = refined.iter().flatten().count(), tokens, "Refined low-confidence segments");
+
+        Ok(refine::apply(segments, &spans, refined))
     }
 
-    async fn transcribe_parallel(&self, _chunks: Vec<AudioChunk>) -> Result<Vec<SpeechSegment>> {
-        // TODO: Implement parallel transcription
-        // This will be implemented in task 7
-        Ok(vec![])
+    /// Load the Whisper model of `config` on every device, once for the
+    /// whole run; each worker keeps its state for all the chunks it takes.
+    /// With `model_fallback`, a model that fails to load is replaced by the
+    /// next smaller one until one fits. Returns the workers, the size loaded
+    /// and the time spent loading, downloads excluded.
+    async fn load_workers(&self, config: &ProcessingConfig, devices: &[Option<usize>]) -> Result<(Vec<TranscriptionWorker>, ModelSize, Duration)> {
+        let provider = self.model_provider()?;
+        let mut model_size = config.model_size.clone();
+        let mut load_time = Duration::ZERO;
+        loop {
+            let model_path = provider.ensure_available(&ModelKind::Whisper(model_size.clone())).await?;
+            let (devices, worker_config) = (devices.to_vec(), config.clone());
+            let started = Instant::now();
+            let loaded = tokio::task::spawn_blocking(move || transcription::create_workers(&model_path, &devices, &worker_config))
+                .await
+                .map_err(|e| AudioTranscriptionError::Model(format!("Loading the Whisper model failed: {}", e)))?;
+            load_time += started.elapsed();
+            match (loaded, model_size.smaller().filter(|_| config.model_fallback)) {
+                (Ok(workers), _) => {
+                    tracing::info!(model = %model_size, workers = workers.len(), load_secs = load_time.as_secs_f32(), "Loaded the Whisper model");
+                    return Ok((workers, model_size, load_time));
+                }
+                (Err(e), Some(smaller)) => {
+                    tracing::warn!(model = %model_size, fallback = %smaller, error = %e, "Whisper model failed to load, trying a smaller one");
+                    model_size = smaller;
+                }
+                (Err(e), None) => return Err(e),
+            }
+        }
     }
 
-    async fn run_diarization(&self, _audio: &[f32]) -> Result<Vec<DiarizationSegment>> {
-        // TODO: Implement speaker diarization
-        // This will be implemented in task 8
-        Ok(vec![])
+    /// Label speakers, decoding the file again a window at a time instead of
+    /// keeping all of it from transcription
+    #[tracing::instrument(name = "diarization", skip_all)]
+    async fn run_diarization(&self, path: &Path) -> Result<Vec<DiarizationSegment>> {
+        self.report(ProgressEvent::StageStarted(ProcessingStage::Diarization));
+        let provider = self.model_provider()?;
+        let diarizer = Diarizer::from_provider(provider.as_ref()).await?;
+        let options = DiarizationOptions {
+            max_speakers: self.config.max_speakers,
+            voiceprints: self.config.voiceprints.clone(),
+            ..DiarizationOptions::default()
+        };
+
+        // Segmentation and embedding block for minutes on long recordings
+        let audio = self.open_audio(path, None)?;
+        tokio::task::spawn_blocking(move || diarizer.diarize_stream(audio, &options))
+            .await
+            .map_err(|e| AudioTranscriptionError::Model(format!("Speaker diarization failed: {}", e)))?
     }
 
+    #[tracing::instrument(name = "merging", skip_all)]
     fn merge_results(
         &self,
         transcript: Vec<SpeechSegment>,
-        _diarization: Vec<DiarizationSegment>,
+        diarization: Vec<DiarizationSegment>,
     ) -> Vec<SpeechSegment> {
-        // TODO: Implement speaker assignment algorithm
-        // This will be implemented in task 8
-        transcript
+        self.report(ProgressEvent::StageStarted(ProcessingStage::Merging));
+        let merged = merge::assign_speakers(transcript, &diarization);
+        tracing::debug!(segments = merged.len(), turns = diarization.len(), "Assigned speakers");
+        merged
+    }
+}
+
+/// Detect the spoken language with the first worker, from the start of the
+/// first chunk, at most
+/// [`LANGUAGE_DETECTION_SECS`](transcription::LANGUAGE_DETECTION_SECS) of it
+#[tracing::instrument(skip_all)]
+async fn detect_language(workers: &mut Vec<TranscriptionWorker>, samples: Vec<f32>) -> Result<String> {
+    let mut worker = workers.remove(0);
+
+    let (worker, detected) = tokio::task::spawn_blocking(move || {
+        let detected = worker.detect_language(&samples);
+        (worker, detected)
+    })
+    .await
+    .map_err(|e| AudioTranscriptionError::Model(format!("Language detection failed: {}", e)))?;
+    workers.insert(0, worker);
+
+    let (language, probability) = detected?;
+    tracing::info!(%language, probability, "Detected language");
+    Ok(language)
+}
+
+/// Transcribe chunks from the shared queue until it is drained or `cancel`
+/// fires, sending each chunk's outcome tagged with its index as soon as it
+/// completes. Failed inference is retried up to `retries` times before the
+/// chunk is reported as failed. Each chunk picked up is reported to
+/// `progress` as started by worker `number`. Returns the number of tokens
+/// decoded.
+async fn run_worker<W: ChunkWorker>(
+    number: usize,
+    mut worker: W,
+    queue: Arc<Mutex<Prefetcher<AudioChunk>>>,
+    progress: Option<Arc<dyn ProgressSink>>,
+    results: mpsc::UnboundedSender<(usize, ChunkOutcome)>,
+    retries: u32,
+    cancel: CancellationToken,
+) -> Result<u64> {
+    let mut completed = 0;
+
+    'chunks: loop {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let next = queue.lock().await.next().await;
+        let Some(chunk) = next else { break };
+        let mut chunk = match chunk {
+            Ok(chunk) => chunk,
+            // A chunk that can't be prepared won't get better on retry
+            Err(AudioTranscriptionError::Chunk { index, message }) => {
+                let _ = results.send((index, ChunkOutcome::Failed { attempts: 1, error: message }));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let index = chunk.index;
+        if let Some(progress) = &progress {
+            progress.report(ProgressEvent::ChunkStarted { index, worker: number, device: worker.device() });
+        }
+        let mut attempts = 0;
+        let outcome = loop {
+            attempts += 1;
+
+            // Inference blocks, so run it off the async executor
+            let (returned_worker, returned_chunk, segments) = tokio::task::spawn_blocking(move || {
+                let _span = tracing::info_span!("chunk", index = chunk.index, device = ?worker.device()).entered();
+                let segments = worker.transcribe(&chunk);
+                (worker, chunk, segments)
+            })
+            .await
+            .map_err(|e| AudioTranscriptionError::Audio(format!("Transcription worker failed: {}", e)))?;
+            worker = returned_worker;
+            chunk = returned_chunk;
+
+            match segments {
+                Ok(segments) => break ChunkOutcome::Transcribed(segments),
+                // Left unfinished, so resuming the run picks it up
+                Err(_) if cancel.is_cancelled() => break 'chunks,
+                Err(e) if attempts <= retries => {
+                    tracing::warn!(index, attempt = attempts, error = %e, "Chunk failed, retrying");
+                    tokio::time::sleep(RETRY_DELAY * attempts).await;
+                }
+                Err(e) => break ChunkOutcome::Failed { attempts, error: e.to_string() },
+            }
+        };
+
+        let _ = results.send((index, outcome));
+        completed += 1;
+    }
+
+    tracing::debug!(device = ?worker.device(), completed, tokens = worker.tokens(), "Worker finished");
+    Ok(worker.tokens())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Fails each chunk in `failures` that many times before transcribing
+    /// it, and notes the order chunks were transcribed in
+    #[derive(Default)]
+    struct FakeWorker {
+        failures: HashMap<usize, u32>,
+        transcribed: Arc<std::sync::Mutex<Vec<usize>>>,
     }
-}
\ No newline at end of file
+
+    impl ChunkWorker for FakeWorker {
+        fn device(&self) -> Option<usize> {
+            None
+        }
+
+        fn tokens(&self) -> u64 {
+            0
+        }
+
+        fn transcribe(&mut self, chunk: &AudioChunk) -> Result<Vec<SpeechSegment>> {
+            if let Some(remaining) = self.failures.get_mut(&chunk.index).filter(|remaining| **remaining > 0) {
+                *remaining -= 1;
+                return Err(AudioTranscriptionError::Audio("decoder error".to_string()));
+            }
+            self.transcribed.lock().unwrap().push(chunk.index);
+            let text = format!("chunk {}", chunk.index);
+            Ok(vec![SpeechSegment { start: chunk.start, end: chunk.end, text, ..Default::default() }])
+        }
+    }
+
+    fn chunk(index: usize) -> Result<AudioChunk> {
+        let start = index as f32 * 10.0;
+        Ok(AudioChunk { index, start, end: start + 10.0, samples: vec![0.0; 160] })
+    }
+
+    fn spawn_worker(
+        chunks: Vec<Result<AudioChunk>>,
+        failures: HashMap<usize, u32>,
+        retries: u32,
+    ) -> (JoinSet<Result<u64>>, mpsc::UnboundedReceiver<(usize, ChunkOutcome)>) {
+        let queue = Arc::new(Mutex::new(Prefetcher::spawn(chunks, 2, |chunk| chunk)));
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut workers = JoinSet::new();
+        workers.spawn(run_worker(0, FakeWorker { failures, ..Default::default() }, queue, None, sender, retries, CancellationToken::new()));
+        (workers, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_short_chunks_go_out_first() {
+        let lengths = [30.0, 20.0, 50.0, 10.0, 40.0];
+        let mut start = 0.0;
+        let mut chunks = Vec::new();
+        for (index, length) in lengths.into_iter().enumerate() {
+            chunks.push(Ok(AudioChunk { index, start, end: start + length, samples: vec![0.0; 160] }));
+            start += length;
+        }
+        let bounds = chunks.iter().map(|chunk| chunk.as_ref().map(|c| (c.start, c.end)).unwrap()).collect();
+        let log = std::sync::Mutex::new(ChunkLog { bounds, finished: true, ..Default::default() });
+
+        // Two workers' worth of short chunks first, the first one as soon as it is ready
+        let queue = Arc::new(Mutex::new(dispatch_in_order(chunks, 4, 2)));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let worker = FakeWorker::default();
+        let transcribed = Arc::clone(&worker.transcribed);
+        let (sender, results) = mpsc::unbounded_channel();
+        let mut workers = JoinSet::new();
+        workers.spawn(run_worker(0, worker, queue, None, sender, 0, CancellationToken::new()));
+
+        let processor = AudioProcessor::new(ProcessingConfig::default());
+        let outcome = processor.collect_chunks(workers, results, &log, 150.0, None, None).await.unwrap();
+
+        assert_eq!(*transcribed.lock().unwrap(), vec![0, 3, 2, 4, 1]);
+        let texts: Vec<&str> = outcome.segments.iter().map(|segment| segment.text.as_str()).collect();
+        assert_eq!(texts, vec!["chunk 0", "chunk 1", "chunk 2", "chunk 3", "chunk 4"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_chunk_leaves_a_gap() {
+        let chunks = vec![
+            chunk(0),
+            Err(AudioTranscriptionError::Chunk { index: 1, message: "no samples".to_string() }),
+            chunk(2),
+        ];
+        let (workers, results) = spawn_worker(chunks, HashMap::new(), 0);
+        let bounds = vec![(0.0, 10.0), (10.0, 20.0), (20.0, 30.0)];
+        let log = std::sync::Mutex::new(ChunkLog { bounds, finished: true, ..Default::default() });
+
+        let processor = AudioProcessor::new(ProcessingConfig::default());
+        let outcome = processor.collect_chunks(workers, results, &log, 30.0, None, None).await.unwrap();
+
+        assert!(outcome.complete);
+        assert_eq!(outcome.skipped.len(), 1);
+        assert_eq!((outcome.skipped[0].start, outcome.skipped[0].end), (10.0, 20.0));
+        let texts: Vec<&str> = outcome.segments.iter().map(|segment| segment.text.as_str()).collect();
+        assert_eq!(texts, vec!["chunk 0", GAP_MARKER, "chunk 2"]);
+    }
+
+    #[tokio::test]
+    async fn test_worker_retries_then_skips() {
+        let failures = HashMap::from([(0, u32::MAX), (1, 1)]);
+        let (mut workers, mut results) = spawn_worker(vec![chunk(0), chunk(1)], failures, 1);
+        workers.join_next().await.unwrap().unwrap().unwrap();
+
+        let (index, outcome) = results.recv().await.unwrap();
+        assert_eq!(index, 0);
+        assert!(matches!(outcome, ChunkOutcome::Failed { attempts: 2, .. }));
+        let (index, outcome) = results.recv().await.unwrap();
+        assert_eq!(index, 1);
+        assert!(matches!(outcome, ChunkOutcome::Transcribed(segments) if segments[0].text == "chunk 1"));
+    }
+
+    #[test]
+    fn test_builder_accepts_valid_config() {
+        let config = ProcessingConfig::builder()
+            .model(ModelSize::Small)
+            .chunk_duration(60.0)
+            .chunk_overlap(2.0)
+            .gpu_devices([0, 1])
+            .build()
+            .unwrap();
+
+        assert_eq!(config.chunk_overlap, 2.0);
+        assert_eq!(config.gpu_devices, vec![0, 1]);
+        assert_eq!(config.chunk_retries, DEFAULT_CHUNK_RETRIES);
+    }
+
+    #[test]
+    fn test_memory_cap_limits_lookahead() {
+        let config = ProcessingConfig::builder().jobs(4).gpu(false).chunk_duration(60.0).build().unwrap();
+        assert_eq!(config.lookahead(), 4);
+        // 61s chunks with their lead-in are 3.9 MB each; with 4 workers, 1
+        // queued and 2 held they fit in 37 MB beside the VAD windows
+        assert!(config.audio_memory(1) < 37_000_000 && config.audio_memory(2) > 37_000_000);
+
+        let capped = ProcessingConfig { max_memory: Some(37_000_000), ..config };
+        assert_eq!(capped.lookahead(), 1);
+        assert!(capped.validate().is_ok());
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_combinations() {
+        let error_code = |builder: ProcessingConfigBuilder| builder.build().unwrap_err().code();
+
+        assert_eq!(error_code(ProcessingConfig::builder().chunk_duration(0.5)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().chunk_duration(f32::NAN)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().chunk_duration(7200.0)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().chunk_duration(30.0).chunk_overlap(30.0)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().chunk_overlap(-1.0)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().vad_threshold(0.0)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().vad_threshold(1.5)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().max_speakers(0)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().max_speakers(300)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().language("english")), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().language("de").detect_language(true)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().model(ModelSize::SmallEn).language("de")), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().model(ModelSize::SmallEn).translate(true)), 80);
+        assert!(ProcessingConfig::builder().model(ModelSize::SmallEn).language("en").build().is_ok());
+        assert_eq!(error_code(ProcessingConfig::builder().jobs(0)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().gpu(false).gpu_devices([0])), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().gpu_devices([1, 1])), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().jobs(4).gpu(false).max_memory(50_000_000)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().model(ModelSize::Base).refine_model(ModelSize::Base)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().refine_model(ModelSize::MediumEn).translate(true)), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().refine_model(ModelSize::LargeV3).refine_threshold(1.0)), 80);
+
+        let decoding = |options: DecodingOptions| ProcessingConfig::builder().decoding(options);
+        assert!(decoding(DecodingOptions { beam_size: Some(5), ..DecodingOptions::default() }).build().is_ok());
+        assert_eq!(error_code(decoding(DecodingOptions { beam_size: Some(0), ..DecodingOptions::default() })), 80);
+        assert_eq!(error_code(decoding(DecodingOptions { beam_size: Some(5), best_of: 3, ..DecodingOptions::default() })), 80);
+        assert_eq!(error_code(decoding(DecodingOptions { best_of: 20, ..DecodingOptions::default() })), 80);
+        assert_eq!(error_code(decoding(DecodingOptions { temperature: 1.5, ..DecodingOptions::default() })), 80);
+        assert_eq!(error_code(decoding(DecodingOptions { no_speech_threshold: f32::NAN, ..DecodingOptions::default() })), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().initial_prompt("Grafana\0")), 80);
+        assert_eq!(error_code(ProcessingConfig::builder().ranges([TimeRange::new(60.0, Some(30.0))])), 80);
+    }
+}
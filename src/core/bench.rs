@@ -0,0 +1,248 @@
+//! Comparing Whisper models on one recording, for the `bench` subcommand.
+//!
+//! Speed comes from each run's [`TranscriptResult`]. Memory and GPU load
+//! are sampled from outside while the run goes by a [`ResourceSampler`]:
+//! the resident memory of this process, and the utilization `nvidia-smi`
+//! reports for the devices in use.
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use serde::Serialize;
+use crate::core::audio_processor::TranscriptResult;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// How often memory and GPU load are sampled during a run
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Resources a run used, as sampled while it went
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub peak_rss: Option<u64>,         // Bytes; None where it can't be read
+    pub gpu_utilization: Option<f32>,  // Mean percent over the run and devices; None on CPU or without nvidia-smi
+}
+
+/// Samples memory and GPU load on a background thread until stopped
+pub struct ResourceSampler {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<ResourceUsage>,
+}
+
+impl ResourceSampler {
+    /// Start sampling. `gpu_devices` are the devices whose load is
+    /// measured, empty for CPU runs.
+    pub fn start(gpu_devices: Vec<usize>) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = std::thread::spawn(move || {
+            let mut peak_rss = None;
+            let mut gpu_samples = Vec::new();
+            loop {
+                if let Some(rss) = resident_set_size() {
+                    peak_rss = Some(peak_rss.map_or(rss, |peak: u64| peak.max(rss)));
+                }
+                gpu_samples.extend(gpu_devices.iter().filter_map(|&device| gpu_utilization(device)));
+                if stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                std::thread::sleep(SAMPLE_INTERVAL);
+            }
+            let gpu_utilization = (!gpu_samples.is_empty()).then(|| gpu_samples.iter().sum::<f32>() / gpu_samples.len() as f32);
+            ResourceUsage { peak_rss, gpu_utilization }
+        });
+        Self { stop, thread }
+    }
+
+    /// Stop sampling and return what was measured
+    pub fn stop(self) -> ResourceUsage {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.join().unwrap_or_default()
+    }
+}
+
+/// One model's run over the benchmark recording
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BenchRun {
+    pub model: String,
+    pub audio_duration: f64,           // Seconds
+    pub processing_time: f64,          // Wall-clock seconds, model load included
+    pub realtime_factor: f32,          // Seconds of audio per second of processing
+    pub load_time: f64,                // Seconds spent loading the Whisper model
+    pub tokens_per_second: f32,
+    pub peak_rss: Option<u64>,         // Bytes
+    pub gpu_utilization: Option<f32>,  // Percent
+}
+
+impl BenchRun {
+    pub fn new(result: &TranscriptResult, usage: ResourceUsage) -> Self {
+        Self {
+            model: result.model_info.whisper_model.clone(),
+            audio_duration: result.audio_duration.as_secs_f64(),
+            processing_time: result.processing_time.as_secs_f64(),
+            realtime_factor: result.realtime_factor(),
+            load_time: result.model_info.load_time.as_secs_f64(),
+            tokens_per_second: result.tokens_per_second(),
+            peak_rss: usage.peak_rss,
+            gpu_utilization: usage.gpu_utilization,
+        }
+    }
+}
+
+/// File format of a saved benchmark report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    /// The format named by the extension of `path`
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("json") => Ok(Self::Json),
+            Some("csv") => Ok(Self::Csv),
+            _ => Err(AudioTranscriptionError::Configuration(format!(
+                "Benchmark reports are written as .json or .csv, not {}",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn render(self, runs: &[BenchRun]) -> Result<String> {
+        match self {
+            Self::Json => serde_json::to_string_pretty(runs)
+                .map_err(|e| AudioTranscriptionError::Configuration(format!("Failed to serialize the report: {}", e))),
+            Self::Csv => Ok(render_csv(runs)),
+        }
+    }
+}
+
+/// Comparison table for the terminal, one row per model
+pub fn render_table(runs: &[BenchRun]) -> String {
+    let mut output = format!(
+        "{:<16} {:>9} {:>9} {:>7} {:>9} {:>10} {:>5}\n",
+        "Model", "Realtime", "Time", "Load", "Tokens/s", "Peak RSS", "GPU"
+    );
+    for run in runs {
+        output.push_str(&format!(
+            "{:<16} {:>8.1}x {:>8.1}s {:>6.1}s {:>9.1} {:>10} {:>5}\n",
+            run.model,
+            run.realtime_factor,
+            run.processing_time,
+            run.load_time,
+            run.tokens_per_second,
+            run.peak_rss.map_or("-".to_string(), |bytes| format!("{} MB", bytes / 1_000_000)),
+            run.gpu_utilization.map_or("-".to_string(), |percent| format!("{:.0}%", percent)),
+        ));
+    }
+    output
+}
+
+fn render_csv(runs: &[BenchRun]) -> String {
+    let mut output = String::from(
+        "model,audio_duration,processing_time,realtime_factor,load_time,tokens_per_second,peak_rss,gpu_utilization\n"
+    );
+    for run in runs {
+        output.push_str(&format!(
+            "{},{:.3},{:.3},{:.3},{:.3},{:.1},{},{}\n",
+            run.model,
+            run.audio_duration,
+            run.processing_time,
+            run.realtime_factor,
+            run.load_time,
+            run.tokens_per_second,
+            run.peak_rss.map(|bytes| bytes.to_string()).unwrap_or_default(),
+            run.gpu_utilization.map(|percent| format!("{:.1}", percent)).unwrap_or_default(),
+        ));
+    }
+    output
+}
+
+/// Utilization of a CUDA device in percent, as `nvidia-smi` reports it
+fn gpu_utilization(device: usize) -> Option<f32> {
+    let output = Command::new("nvidia-smi")
+        .args(["--query-gpu=utilization.gpu", "--format=csv,noheader,nounits", "-i", &device.to_string()])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn resident_set_size() -> Option<u64> {
+    parse_status(&std::fs::read_to_string("/proc/self/status").ok()?)
+}
+
+#[cfg(target_os = "macos")]
+fn resident_set_size() -> Option<u64> {
+    let output = Command::new("ps").args(["-o", "rss=", "-p", &std::process::id().to_string()]).output().ok()?;
+    let kibibytes: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(kibibytes * 1024)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn resident_set_size() -> Option<u64> {
+    None
+}
+
+/// `VmRSS` from `/proc/self/status`, in bytes
+#[cfg(any(target_os = "linux", test))]
+fn parse_status(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kibibytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kibibytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(model: &str, peak_rss: Option<u64>, gpu_utilization: Option<f32>) -> BenchRun {
+        BenchRun {
+            model: model.to_string(),
+            audio_duration: 60.0,
+            processing_time: 4.0,
+            realtime_factor: 15.0,
+            load_time: 0.5,
+            tokens_per_second: 120.0,
+            peak_rss,
+            gpu_utilization,
+        }
+    }
+
+    #[test]
+    fn test_reports() {
+        let runs = [run("tiny", Some(412_000_000), None), run("large-v3-turbo", None, Some(87.5))];
+
+        let table = render_table(&runs);
+        assert_eq!(table.lines().count(), 3);
+        assert!(table.lines().nth(1).unwrap().contains("15.0x"));
+        assert!(table.contains("412 MB"));
+        assert!(table.contains("88%"));
+
+        let csv = ReportFormat::from_path(Path::new("bench.CSV")).unwrap().render(&runs).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("tiny,60.000,4.000,15.000,0.500,120.0,412000000,"));
+        assert_eq!(csv.lines().nth(2), Some("large-v3-turbo,60.000,4.000,15.000,0.500,120.0,,87.5"));
+
+        let json: serde_json::Value = serde_json::from_str(&ReportFormat::Json.render(&runs).unwrap()).unwrap();
+        assert_eq!(json[0]["peak_rss"], 412_000_000);
+        assert!(json[0]["gpu_utilization"].is_null());
+
+        assert!(ReportFormat::from_path(Path::new("bench.txt")).is_err());
+    }
+
+    #[test]
+    fn test_sampler_and_status() {
+        let status = "Name:\taudio-transcribe\nVmPeak:\t  900000 kB\nVmRSS:\t  402344 kB\n";
+        assert_eq!(parse_status(status), Some(402_344 * 1024));
+        assert_eq!(parse_status("Name:\tx\n"), None);
+
+        let usage = ResourceSampler::start(Vec::new()).stop();
+        assert_eq!(usage.gpu_utilization, None);
+        #[cfg(target_os = "linux")]
+        assert!(usage.peak_rss.unwrap() > 0);
+    }
+}
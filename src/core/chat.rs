@@ -0,0 +1,189 @@
+//! Minimal client for OpenAI-compatible chat completion APIs, shared by the
+//! summarization and translation steps. A local llama.cpp server works as
+//! well as a hosted API.
+
+use std::time::Duration;
+use serde::Deserialize;
+use serde_json::json;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// llama.cpp's `llama-server` on its default port
+pub const DEFAULT_ENDPOINT: &str = "http://127.0.0.1:8080/v1";
+
+/// Model name sent when none is configured; llama.cpp ignores it
+pub const DEFAULT_MODEL: &str = "default";
+
+/// Environment variable holding the API key for hosted endpoints
+pub const API_KEY_VAR: &str = "OPENAI_API_KEY";
+
+/// Long transcripts take a while to process on local hardware
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Where chat completions are requested from
+#[derive(Debug, Clone)]
+pub struct ChatConfig {
+    pub endpoint: String,         // Base URL; `/chat/completions` is appended
+    pub model: String,
+    pub api_key: Option<String>,  // Sent as a bearer token
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            model: DEFAULT_MODEL.to_string(),
+            api_key: std::env::var(API_KEY_VAR).ok().filter(|key| !key.is_empty()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+pub struct ChatClient {
+    client: reqwest::Client,
+    config: ChatConfig,
+}
+
+impl ChatClient {
+    pub fn new(config: ChatConfig) -> Result<Self> {
+        if !config.endpoint.starts_with("http://") && !config.endpoint.starts_with("https://") {
+            return Err(AudioTranscriptionError::Configuration(
+                format!("Chat endpoint must be an http(s) URL, got '{}'", config.endpoint)
+            ));
+        }
+
+        let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        Ok(Self { client, config })
+    }
+
+    pub fn config(&self) -> &ChatConfig {
+        &self.config
+    }
+
+    /// Send one system and one user message and return the reply. The model
+    /// is asked for a JSON object, which both llama.cpp and hosted APIs
+    /// support.
+    pub async fn complete_json(&self, system: &str, user: &str) -> Result<String> {
+        let url = format!("{}/chat/completions", self.config.endpoint.trim_end_matches('/'));
+        let body = json!({
+            "model": self.config.model,
+            "messages": [
+                { "role": "system", "content": system },
+                { "role": "user", "content": user },
+            ],
+            "response_format": { "type": "json_object" },
+            "temperature": 0.2,
+        });
+
+        let mut request = self.client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        tracing::debug!(url = %url, model = %self.config.model, "Chat completion request");
+        let response = request.send().await?.error_for_status()?;
+        let response: ChatResponse = serde_json::from_slice(&response.bytes().await?)?;
+
+        let choice = response.choices.into_iter().next().ok_or_else(|| AudioTranscriptionError::RemoteModel(
+            format!("{} returned no choices", url)
+        ))?;
+        Ok(strip_code_fence(&choice.message.content).to_string())
+    }
+}
+
+/// Models sometimes wrap JSON in a Markdown code fence despite being asked
+/// not to
+fn strip_code_fence(reply: &str) -> &str {
+    let reply = reply.trim();
+    reply
+        .strip_prefix("```json")
+        .or_else(|| reply.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(str::trim)
+        .unwrap_or(reply)
+}
+
+/// A one-shot HTTP server answering a chat completion request with
+/// `content`. Returns the endpoint and a handle yielding the raw request.
+#[cfg(test)]
+pub(crate) async fn mock_endpoint(content: &str) -> (String, tokio::task::JoinHandle<String>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}/v1", listener.local_addr().unwrap());
+    let reply = json!({ "choices": [{ "message": { "role": "assistant", "content": content } }] }).to_string();
+
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        // Read the headers and the whole body before answering
+        loop {
+            let read = socket.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_ascii_lowercase();
+            if let Some(header_end) = text.find("\r\n\r\n") {
+                let length: usize = text
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map(|value| value.trim().parse().unwrap())
+                    .unwrap_or(0);
+                if request.len() >= header_end + 4 + length || read == 0 {
+                    break;
+                }
+            }
+        }
+
+        let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", reply.len(), reply);
+        socket.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&request).into_owned()
+    });
+
+    (endpoint, server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_code_fence() {
+        assert_eq!(strip_code_fence("```json\n{\"a\": 1}\n```"), "{\"a\": 1}");
+        assert_eq!(strip_code_fence(" {\"a\": 1} "), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn test_endpoint_must_be_http() {
+        let config = ChatConfig { endpoint: "localhost:8080".to_string(), ..ChatConfig::default() };
+        assert_eq!(ChatClient::new(config).err().unwrap().code(), 80);
+    }
+
+    #[tokio::test]
+    async fn test_complete_json_sends_bearer_token() {
+        let (endpoint, server) = mock_endpoint("{\"ok\": true}").await;
+        let client = ChatClient::new(ChatConfig { endpoint, model: "llama".to_string(), api_key: Some("secret".to_string()) }).unwrap();
+
+        assert_eq!(client.complete_json("system", "user").await.unwrap(), "{\"ok\": true}");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions"));
+        assert!(request.to_ascii_lowercase().contains("authorization: bearer secret"));
+        assert!(request.contains("\"model\":\"llama\""));
+    }
+}
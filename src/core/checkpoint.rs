@@ -0,0 +1,289 @@
+//! Resume checkpoints: the segments of every finished chunk are saved as
+//! transcription goes, so an interrupted run picks up where it stopped
+//! instead of transcribing hours of audio again.
+//!
+//! A checkpoint belongs to one input file and the settings that decide how
+//! it is chunked and transcribed; if any of them changed, it is discarded.
+//! The CLI keeps it next to the transcript, e.g. `meeting.checkpoint`;
+//! library users can also keep all checkpoints in one folder.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use serde::{Deserialize, Serialize};
+use crate::core::audio_processor::{ProcessingConfig, SpeechSegment};
+use crate::core::selection::TimeRange;
+use crate::core::transcription::DecodingOptions;
+use crate::core::vad::VAD_WINDOW_SECS;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Extension of a checkpoint kept next to the transcript
+pub const EXTENSION: &str = "checkpoint";
+
+/// `<cache dir>/audio-transcribe/checkpoints`
+pub fn default_checkpoint_dir() -> Result<PathBuf> {
+    let cache_dir = dirs::cache_dir().ok_or_else(|| AudioTranscriptionError::Configuration(
+        "Unable to determine cache directory".to_string()
+    ))?;
+    Ok(cache_dir.join("audio-transcribe").join("checkpoints"))
+}
+
+/// What a checkpoint is valid for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Fingerprint {
+    input: PathBuf,
+    size: u64,
+    modified: u64,        // Seconds since the Unix epoch
+    model: String,
+    chunk_duration: f32,
+    chunk_overlap: f32,
+    vad_threshold: f32,   // Decides which chunks exist, so it changes their indices
+    vad_window: f32,      // Likewise; older checkpoints without it were chunked differently
+    word_timestamps: bool,
+    language: Option<String>,
+    detect_language: bool,
+    translate: bool,
+    #[serde(default)]
+    decoding: DecodingOptions,  // Older checkpoints were decoded with the defaults
+    #[serde(default)]
+    initial_prompt: Option<String>,
+    #[serde(default)]
+    ranges: Vec<TimeRange>,     // Chunks are found on the selected audio only
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointState {
+    fingerprint: Fingerprint,
+    chunks: BTreeMap<usize, Vec<SpeechSegment>>,
+}
+
+/// Where a run keeps its checkpoint
+#[derive(Debug, Clone)]
+pub(crate) enum CheckpointLocation {
+    Dir(PathBuf),   // Shared by all inputs, each with a file named after it
+    File(PathBuf),
+}
+
+/// Finished chunks of one input file, saved to disk after each chunk
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    state: CheckpointState,
+}
+
+impl Checkpoint {
+    /// Open the checkpoint for `input` in `dir`, or start an empty one when
+    /// there is none or it was made for a different file or settings
+    pub fn open(dir: &Path, input: &Path, config: &ProcessingConfig) -> Result<Self> {
+        let canonical = input.canonicalize().unwrap_or_else(|_| input.to_path_buf());
+        Self::open_file(dir.join(file_name(&canonical)), input, config)
+    }
+
+    /// Like [`Checkpoint::open`], but with the checkpoint at `path`
+    pub fn open_file(path: PathBuf, input: &Path, config: &ProcessingConfig) -> Result<Self> {
+        let fingerprint = fingerprint(input, config)?;
+
+        let saved = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<CheckpointState>(&json).ok());
+        let state = match saved {
+            Some(state) if state.fingerprint == fingerprint => {
+                tracing::info!(chunks = state.chunks.len(), path = %path.display(), "Resuming from checkpoint");
+                state
+            }
+            Some(_) => {
+                tracing::info!(path = %path.display(), "Input or settings changed, starting over");
+                CheckpointState { fingerprint, chunks: BTreeMap::new() }
+            }
+            None => CheckpointState { fingerprint, chunks: BTreeMap::new() },
+        };
+
+        Ok(Self { path, state })
+    }
+
+    pub(crate) fn open_at(location: &CheckpointLocation, input: &Path, config: &ProcessingConfig) -> Result<Self> {
+        match location {
+            CheckpointLocation::Dir(dir) => Self::open(dir, input, config),
+            CheckpointLocation::File(path) => Self::open_file(path.clone(), input, config),
+        }
+    }
+
+    /// The checkpoint of one channel of `input`, for recordings whose
+    /// channels are transcribed one by one, e.g. `meeting.ch1.checkpoint`
+    pub(crate) fn open_channel_at(location: &CheckpointLocation, input: &Path, channel: usize, config: &ProcessingConfig) -> Result<Self> {
+        let path = match location {
+            CheckpointLocation::Dir(dir) => dir.join(file_name(&input.canonicalize().unwrap_or_else(|_| input.to_path_buf()))),
+            CheckpointLocation::File(path) => path.clone(),
+        };
+        let extension = match path.extension() {
+            Some(extension) => format!("ch{}.{}", channel + 1, extension.to_string_lossy()),
+            None => format!("ch{}", channel + 1),
+        };
+        Self::open_file(path.with_extension(extension), input, config)
+    }
+
+    /// Where the checkpoint is saved
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of chunks already finished
+    pub fn len(&self) -> usize {
+        self.state.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.chunks.is_empty()
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.state.chunks.contains_key(&index)
+    }
+
+    /// Finished chunks in file order
+    pub fn chunks(&self) -> impl Iterator<Item = (usize, &[SpeechSegment])> {
+        self.state.chunks.iter().map(|(index, segments)| (*index, segments.as_slice()))
+    }
+
+    /// Save a finished chunk. The file is replaced atomically, so an
+    /// interrupt while saving leaves the previous checkpoint intact.
+    pub fn record(&mut self, index: usize, segments: &[SpeechSegment]) -> Result<()> {
+        self.state.chunks.insert(index, segments.to_vec());
+
+        let dir = self.path.parent().unwrap_or_else(|| Path::new("."));
+        std::fs::create_dir_all(dir)
+            .map_err(|source| AudioTranscriptionError::File { path: dir.to_path_buf(), source })?;
+        let temporary = self.path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_vec(&self.state)?)
+            .map_err(|source| AudioTranscriptionError::File { path: temporary.clone(), source })?;
+        std::fs::rename(&temporary, &self.path)
+            .map_err(|source| AudioTranscriptionError::File { path: self.path.clone(), source })?;
+        Ok(())
+    }
+
+    /// Delete the checkpoint once the transcript is complete
+    pub fn remove(self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(AudioTranscriptionError::File { path: self.path, source: e })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn fingerprint(input: &Path, config: &ProcessingConfig) -> Result<Fingerprint> {
+    let file_error = |source| AudioTranscriptionError::File { path: input.to_path_buf(), source };
+    let metadata = std::fs::metadata(input).map_err(file_error)?;
+    let modified = metadata
+        .modified()
+        .map_err(file_error)?
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default();
+
+    Ok(Fingerprint {
+        input: input.canonicalize().unwrap_or_else(|_| input.to_path_buf()),
+        size: metadata.len(),
+        modified,
+        model: config.model_size.to_string(),
+        chunk_duration: config.chunk_duration,
+        chunk_overlap: config.chunk_overlap,
+        vad_threshold: config.vad_threshold,
+        vad_window: VAD_WINDOW_SECS,
+        word_timestamps: config.word_timestamps,
+        language: config.language.clone(),
+        detect_language: config.detect_language,
+        translate: config.translate,
+        decoding: config.decoding.clone(),
+        initial_prompt: config.initial_prompt.clone(),
+        ranges: config.ranges.clone(),
+    })
+}
+
+/// `<stem>-<hash of the full path>.json`, so files with the same name in
+/// different folders get separate state files
+pub(crate) fn file_name(input: &Path) -> String {
+    // FNV-1a: stable across builds, unlike the standard library's hasher
+    let hash = input
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    format!("{}-{:016x}.json", stem, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str) -> SpeechSegment {
+        SpeechSegment { start: 0.0, end: 1.0, text: text.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_checkpoint_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("long.wav");
+        std::fs::write(&input, b"RIFF").unwrap();
+        let config = ProcessingConfig::default();
+
+        let mut checkpoint = Checkpoint::open(dir.path(), &input, &config).unwrap();
+        assert!(checkpoint.is_empty());
+        checkpoint.record(0, &[segment("first")]).unwrap();
+        checkpoint.record(2, &[segment("third")]).unwrap();
+
+        let reopened = Checkpoint::open(dir.path(), &input, &config).unwrap();
+        assert_eq!(reopened.len(), 2);
+        assert!(reopened.contains(2) && !reopened.contains(1));
+        assert_eq!(reopened.chunks().map(|(index, _)| index).collect::<Vec<_>>(), vec![0, 2]);
+
+        let path = reopened.path().to_path_buf();
+        reopened.remove().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_changed_settings_start_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("long.wav");
+        std::fs::write(&input, b"RIFF").unwrap();
+
+        let mut checkpoint = Checkpoint::open(dir.path(), &input, &ProcessingConfig::default()).unwrap();
+        checkpoint.record(0, &[segment("first")]).unwrap();
+
+        let config = ProcessingConfig { chunk_duration: 60.0, ..ProcessingConfig::default() };
+        assert!(Checkpoint::open(dir.path(), &input, &config).unwrap().is_empty());
+
+        std::fs::write(&input, b"RIFF, re-recorded").unwrap();
+        assert!(Checkpoint::open(dir.path(), &input, &ProcessingConfig::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_checkpoint_at_a_given_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("long.wav");
+        std::fs::write(&input, b"RIFF").unwrap();
+        let path = dir.path().join("long.checkpoint");
+        let config = ProcessingConfig::default();
+
+        let mut checkpoint = Checkpoint::open_file(path.clone(), &input, &config).unwrap();
+        checkpoint.record(0, &[segment("first")]).unwrap();
+        assert!(path.exists());
+
+        let location = CheckpointLocation::File(path.clone());
+        assert_eq!(Checkpoint::open_at(&location, &input, &config).unwrap().len(), 1);
+        assert!(Checkpoint::open(dir.path(), &input, &config).unwrap().is_empty());
+
+        // Each channel of a split recording is kept beside it
+        let channel = Checkpoint::open_channel_at(&location, &input, 1, &config).unwrap();
+        assert!(channel.is_empty());
+        assert_eq!(channel.path(), dir.path().join("long.ch2.checkpoint"));
+    }
+
+    #[test]
+    fn test_same_name_in_different_folders() {
+        assert_ne!(file_name(Path::new("/a/meeting.wav")), file_name(Path::new("/b/meeting.wav")));
+        assert!(file_name(Path::new("/a/meeting.wav")).starts_with("meeting-"));
+    }
+}
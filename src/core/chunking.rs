@@ -0,0 +1,348 @@
+//! Chunking as the file is read: audio is decoded, searched for speech and
+//! cut into chunks block by block, so only a few chunks' worth of samples
+//! is held at once, however long the recording.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::core::audio_processor::{AudioChunk, VadSegment, SAMPLE_RATE};
+use crate::core::decoder::AudioStream;
+use crate::core::vad::VadStream;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Whisper rejects inputs shorter than one second
+const MIN_CHUNK_SAMPLES: usize = SAMPLE_RATE as usize;
+
+/// Where a chunk lies in the decoded audio. Its samples are only copied out
+/// once the chunk is about to be transcribed.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ChunkSpan {
+    pub index: usize,
+    pub samples: Range<usize>,
+}
+
+impl ChunkSpan {
+    pub fn start(&self) -> f32 {
+        self.samples.start as f32 / SAMPLE_RATE as f32
+    }
+
+    pub fn end(&self) -> f32 {
+        self.samples.end as f32 / SAMPLE_RATE as f32
+    }
+}
+
+/// Groups speech regions into chunks of at most `duration` seconds as they
+/// are found, in file order.
+///
+/// Chunks start and end at the edges of VAD regions, so every cut falls in
+/// a pause and silence between chunks is never transcribed. A region longer
+/// than `duration` has no pause to cut at and is split into fixed windows.
+/// Every chunk after the first starts `overlap` seconds early, so words cut
+/// at a boundary are heard whole by one of the two chunks.
+#[derive(Debug)]
+pub(crate) struct ChunkPlanner {
+    duration: f32,
+    overlap: f32,
+    open: Option<(f32, f32)>,  // Bounds of the last chunk, which later speech may still extend
+    next_index: usize,
+}
+
+impl ChunkPlanner {
+    pub fn new(duration: f32, overlap: f32) -> Self {
+        Self { duration, overlap, open: None, next_index: 0 }
+    }
+
+    /// Add the next speech region; returns the chunks it closed
+    pub fn push(&mut self, segment: &VadSegment) -> Vec<ChunkSpan> {
+        let (start, end) = (segment.start.max(0.0), segment.end);
+        if end <= start {
+            return Vec::new();
+        }
+        if let Some(open) = self.open.as_mut().filter(|open| end - open.0 <= self.duration) {
+            open.1 = open.1.max(end);
+            return Vec::new();
+        }
+
+        let mut closed: Vec<ChunkSpan> = self.open.take().map(|open| self.span(open)).into_iter().collect();
+        let mut offset = start;
+        while offset < end {
+            let bounds = (offset, (offset + self.duration).min(end));
+            offset += self.duration;
+            if offset < end {
+                closed.push(self.span(bounds));
+            } else {
+                self.open = Some(bounds);
+            }
+        }
+        closed
+    }
+
+    /// Close the last chunk once no speech before `time` is still to come
+    /// and anything after it would make the chunk too long
+    pub fn settle(&mut self, time: f32) -> Option<ChunkSpan> {
+        let open = self.open.filter(|open| time - open.0 > self.duration)?;
+        self.open = None;
+        Some(self.span(open))
+    }
+
+    /// Close the last chunk at the end of the audio
+    pub fn finish(&mut self) -> Option<ChunkSpan> {
+        let open = self.open.take()?;
+        Some(self.span(open))
+    }
+
+    /// Start of the last chunk, lead-in included
+    fn open_start(&self) -> Option<f32> {
+        self.open.map(|open| open.0 - self.lead_in())
+    }
+
+    fn lead_in(&self) -> f32 {
+        if self.next_index > 0 { self.overlap } else { 0.0 }
+    }
+
+    fn span(&mut self, (start, end): (f32, f32)) -> ChunkSpan {
+        let sample = |seconds: f32| (seconds.max(0.0) * SAMPLE_RATE as f32) as usize;
+        let span = ChunkSpan { index: self.next_index, samples: sample(start - self.lead_in())..sample(end) };
+        self.next_index += 1;
+        span
+    }
+}
+
+/// What a [`ChunkStream`] has found so far, shared with the code consuming
+/// its chunks
+#[derive(Debug, Default)]
+pub(crate) struct ChunkLog {
+    pub bounds: Vec<(f32, f32)>,  // Start and end of every chunk found, by index
+    pub speech: Vec<(f32, f32)>,  // Start and end of every speech region found, in file order
+    pub finished: bool,           // The whole file was read, so `bounds` is complete
+    pub vad: Duration,            // Time spent detecting speech
+    pub chunking: Duration,       // Time spent planning and copying out chunks
+}
+
+/// Chunks of a file, ready for inference, prepared as the file is decoded.
+///
+/// Only the samples a chunk still to come may need are kept: from the
+/// start of the chunk being gathered, or of speech the VAD hasn't settled,
+/// minus the overlap. Chunks in `skip`, such as those restored from a
+/// checkpoint, are planned and logged but not prepared.
+pub(crate) struct ChunkStream {
+    audio: AudioStream,
+    vad: Option<VadStream>,      // Taken at the end of the audio
+    planner: ChunkPlanner,
+    buffer: VecDeque<f32>,
+    buffer_start: usize,         // Position of `buffer[0]` in the audio
+    ready: VecDeque<ChunkSpan>,
+    skip: BTreeSet<usize>,
+    log: Arc<Mutex<ChunkLog>>,
+}
+
+impl ChunkStream {
+    pub fn new(audio: AudioStream, vad: VadStream, planner: ChunkPlanner, skip: BTreeSet<usize>) -> Self {
+        Self {
+            audio,
+            vad: Some(vad),
+            planner,
+            buffer: VecDeque::new(),
+            buffer_start: 0,
+            ready: VecDeque::new(),
+            skip,
+            log: Arc::default(),
+        }
+    }
+
+    pub fn log(&self) -> Arc<Mutex<ChunkLog>> {
+        Arc::clone(&self.log)
+    }
+
+    /// Decode the next block and plan the chunks it completes
+    fn advance(&mut self) -> Result<()> {
+        let Some(vad) = self.vad.as_mut() else { return Ok(()) };
+
+        // Nothing before this can end up in a chunk any more
+        let keep_from = self.planner.open_start().unwrap_or(f32::MAX).min(vad.settled_until() - self.planner.overlap);
+        let keep_from = ((keep_from.max(0.0) * SAMPLE_RATE as f32) as usize).saturating_sub(self.buffer_start);
+        let drop = keep_from.min(self.buffer.len());
+        self.buffer.drain(..drop);
+        self.buffer_start += drop;
+
+        let block = self.audio.next().transpose()?;
+        let started = Instant::now();
+        let segments = match &block {
+            Some(block) => {
+                self.buffer.extend(block);
+                vad.push(block)?
+            }
+            None => self.vad.take().map(VadStream::finish).transpose()?.unwrap_or_default(),
+        };
+        let vad_time = started.elapsed();
+
+        let started = Instant::now();
+        let mut spans: Vec<ChunkSpan> = segments.iter().flat_map(|segment| self.planner.push(segment)).collect();
+        match &self.vad {
+            Some(vad) => spans.extend(self.planner.settle(vad.settled_until())),
+            None => spans.extend(self.planner.finish()),
+        }
+
+        let mut log = self.log.lock().unwrap();
+        log.bounds.extend(spans.iter().map(|span| (span.start(), span.end())));
+        log.speech.extend(segments.iter().map(|segment| (segment.start, segment.end)));
+        log.finished = self.vad.is_none();
+        log.vad += vad_time;
+        log.chunking += started.elapsed();
+        self.ready.extend(spans);
+        Ok(())
+    }
+
+    fn prepare(&mut self, span: ChunkSpan) -> Result<AudioChunk> {
+        let started = Instant::now();
+        let len = self.buffer.len();
+        let local = |position: usize| position.saturating_sub(self.buffer_start).min(len);
+        let range = local(span.samples.start)..local(span.samples.end);
+        let chunk = prepare_chunk(&self.buffer.make_contiguous()[range], span);
+        self.log.lock().unwrap().chunking += started.elapsed();
+        chunk
+    }
+}
+
+impl Iterator for ChunkStream {
+    type Item = Result<AudioChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(span) = self.ready.pop_front() {
+                if self.skip.contains(&span.index) {
+                    continue;
+                }
+                return Some(self.prepare(span));
+            }
+            self.vad.as_ref()?;
+            if let Err(e) = self.advance() {
+                self.vad = None;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Get a chunk ready for inference from its samples
+#[tracing::instrument(skip_all, fields(index = span.index))]
+pub(crate) fn prepare_chunk(samples: &[f32], span: ChunkSpan) -> Result<AudioChunk> {
+    let mut chunk = AudioChunk {
+        index: span.index,
+        start: span.start(),
+        end: span.end(),
+        samples: samples.to_vec(),
+    };
+
+    if chunk.samples.is_empty() {
+        return Err(AudioTranscriptionError::Chunk {
+            index: chunk.index,
+            message: "contains no audio".to_string(),
+        });
+    }
+
+    // Pad short chunks with trailing silence so whisper accepts them
+    if chunk.samples.len() < MIN_CHUNK_SAMPLES {
+        chunk.samples.resize(MIN_CHUNK_SAMPLES, 0.0);
+    }
+
+    Ok(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::decoder::AudioDecoder;
+    use crate::core::vad::{SpeechDetector, Vad, VAD_WINDOW_SECS};
+
+    fn speech(start: f32, end: f32) -> VadSegment {
+        VadSegment { start, end, confidence: 0.9 }
+    }
+
+    fn plan(vad: &[VadSegment], duration: f32, overlap: f32) -> Vec<(f32, f32)> {
+        let mut planner = ChunkPlanner::new(duration, overlap);
+        let mut chunks: Vec<ChunkSpan> = vad.iter().flat_map(|segment| planner.push(segment)).collect();
+        chunks.extend(planner.finish());
+        assert!(chunks.iter().enumerate().all(|(index, chunk)| chunk.index == index));
+        chunks.iter().map(|chunk| (chunk.start(), chunk.end())).collect()
+    }
+
+    #[test]
+    fn test_chunks_end_in_pauses_between_speech() {
+        let vad = [speech(1.0, 4.0), speech(5.0, 8.0), speech(9.0, 13.0), speech(40.0, 42.0)];
+
+        assert_eq!(plan(&vad, 10.0, 0.0), vec![(1.0, 8.0), (9.0, 13.0), (40.0, 42.0)]);
+        assert!(plan(&[], 10.0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_chunks_overlap_and_split_long_speech() {
+        let vad = [speech(0.0, 25.0), speech(26.0, 29.0)];
+
+        assert_eq!(plan(&vad, 10.0, 2.0), vec![(0.0, 10.0), (8.0, 20.0), (18.0, 29.0)]);
+    }
+
+    #[test]
+    fn test_settled_chunks_close_early() {
+        let mut planner = ChunkPlanner::new(10.0, 0.0);
+        assert!(planner.push(&speech(1.0, 4.0)).is_empty());
+        assert!(planner.settle(11.0).is_none());
+
+        // Speech from 11.5s on could no longer join the chunk
+        let closed = planner.settle(11.5).unwrap();
+        assert_eq!((closed.index, closed.start(), closed.end()), (0, 1.0, 4.0));
+        assert!(planner.finish().is_none());
+    }
+
+    #[test]
+    fn test_prepared_chunk_copies_only_its_span() {
+        let audio: Vec<f32> = (0..SAMPLE_RATE as usize * 4).map(|i| i as f32).collect();
+
+        let chunk = prepare_chunk(&audio[16_000..40_000], ChunkSpan { index: 3, samples: 16_000..40_000 }).unwrap();
+        assert_eq!((chunk.index, chunk.start, chunk.end), (3, 1.0, 2.5));
+        assert_eq!(chunk.samples.len(), 24_000);
+        assert_eq!(chunk.samples[0], 16_000.0);
+
+        // Short chunks are padded for Whisper but keep their real end
+        let chunk = prepare_chunk(&audio[..8_000], ChunkSpan { index: 0, samples: 0..8_000 }).unwrap();
+        assert_eq!((chunk.samples.len(), chunk.end), (MIN_CHUNK_SAMPLES, 0.5));
+    }
+
+    #[test]
+    fn test_stream_holds_only_the_audio_it_still_needs() {
+        // Five minutes of 3s bursts of tone every 10s
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("long.wav");
+        let spec = hound::WavSpec { channels: 1, sample_rate: SAMPLE_RATE, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..SAMPLE_RATE as usize * 300 {
+            let loud = (i / SAMPLE_RATE as usize) % 10 >= 7;
+            let amplitude = if loud { 16_000.0 } else { 10.0 };
+            writer.write_sample((amplitude * (i as f32 * 0.17).sin()) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let audio = AudioDecoder::default().stream(&path).unwrap();
+        let vad = VadStream::new(SpeechDetector::Energy(Vad::default()));
+        let mut stream = ChunkStream::new(audio, vad, ChunkPlanner::new(10.0, 1.0), BTreeSet::from([0]));
+        let log = stream.log();
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = stream.next() {
+            chunks.push(chunk.unwrap());
+            let limit = (10.0 + 1.0 + 2.0 * VAD_WINDOW_SECS) * SAMPLE_RATE as f32;
+            assert!(stream.buffer.len() as f32 <= limit, "holding {} samples", stream.buffer.len());
+        }
+
+        // Each chunk holds one burst; the first was skipped but is logged
+        let log = log.lock().unwrap();
+        assert!(log.finished);
+        assert_eq!(log.bounds.len(), 30);
+        assert!((log.bounds[0].0 - 6.9).abs() < 0.05 && (log.bounds[0].1 - 10.1).abs() < 0.05, "{:?}", log.bounds[0]);
+        assert_eq!(chunks.len(), 29);
+        assert_eq!(chunks[0].index, 1);
+        assert!((chunks[0].start - 15.9).abs() < 0.05, "lead-in missing: {}", chunks[0].start);
+        assert_eq!(chunks[0].samples.len(), ((chunks[0].end - chunks[0].start) * SAMPLE_RATE as f32).round() as usize);
+    }
+}
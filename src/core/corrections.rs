@@ -0,0 +1,189 @@
+//! User-editable corrections for words Whisper keeps mishearing.
+//!
+//! A TOML file maps what Whisper wrote to what was meant: exact phrases,
+//! matched as whole words regardless of case, and regular expressions for
+//! anything more flexible.
+//!
+//! ```toml
+//! [exact]
+//! "cube control" = "kubectl"
+//! "graph ana" = "Grafana"
+//!
+//! [[regex]]
+//! pattern = '\b[Kk] ?8 ?[sS]\b'
+//! replacement = "k8s"
+//! ```
+//!
+//! Exact phrases are applied first, then the expressions in file order.
+//! `$1` and `${name}` in a replacement refer to capture groups.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use regex::Regex;
+use serde::Deserialize;
+use crate::core::audio_processor::TranscriptResult;
+use crate::error::{Result, AudioTranscriptionError};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CorrectionsConfig {
+    #[serde(default)]
+    exact: BTreeMap<String, String>,
+    #[serde(default)]
+    regex: Vec<RegexConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegexConfig {
+    pattern: String,
+    replacement: String,
+}
+
+/// One replacement, with exact phrases compiled to expressions too
+#[derive(Debug, Clone)]
+struct Correction {
+    pattern: Regex,
+    replacement: String,
+}
+
+/// Replacements applied to the text of every segment
+#[derive(Debug, Clone, Default)]
+pub struct Corrections {
+    rules: Vec<Correction>,
+}
+
+impl Corrections {
+    /// `<config dir>/audio-transcribe/corrections.toml`
+    pub fn default_config_path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir().ok_or_else(|| AudioTranscriptionError::Configuration(
+            "Unable to determine config directory".to_string()
+        ))?;
+        Ok(config_dir.join("audio-transcribe").join("corrections.toml"))
+    }
+
+    /// The corrections in the default config file, if there is one
+    pub fn load_default() -> Result<Option<Self>> {
+        let path = Self::default_config_path()?;
+        path.is_file().then(|| Self::from_config_file(&path)).transpose()
+    }
+
+    pub fn from_config_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|source| AudioTranscriptionError::File { path: path.to_path_buf(), source })?;
+        Self::from_toml(&contents).map_err(|message| AudioTranscriptionError::Configuration(
+            format!("Invalid corrections file {}: {}", path.display(), message)
+        ))
+    }
+
+    pub fn from_toml(contents: &str) -> std::result::Result<Self, String> {
+        let config: CorrectionsConfig = toml::from_str(contents).map_err(|e| e.to_string())?;
+        let mut rules = Vec::with_capacity(config.exact.len() + config.regex.len());
+        for (phrase, replacement) in config.exact {
+            if phrase.trim().is_empty() {
+                return Err("exact phrases must not be empty".to_string());
+            }
+            // Literal replacement: `$` in it means a dollar sign
+            rules.push(Correction { pattern: exact_pattern(&phrase)?, replacement: replacement.replace('$', "$$") });
+        }
+        for regex in config.regex {
+            let pattern = Regex::new(&regex.pattern).map_err(|e| format!("pattern '{}': {}", regex.pattern, e))?;
+            rules.push(Correction { pattern, replacement: regex.replacement });
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Correct the text of every segment. Word timings keep the words
+    /// Whisper heard, as a phrase may map to a different number of words.
+    pub fn apply(&self, result: &TranscriptResult) -> TranscriptResult {
+        let mut corrected = result.clone();
+        for segment in &mut corrected.segments {
+            for rule in &self.rules {
+                if let std::borrow::Cow::Owned(text) = rule.pattern.replace_all(&segment.text, rule.replacement.as_str()) {
+                    segment.text = text;
+                }
+            }
+        }
+        corrected
+    }
+}
+
+/// Whole-word, case-insensitive expression for a literal phrase. Word
+/// boundaries are only required where the phrase starts or ends with a
+/// word character.
+fn exact_pattern(phrase: &str) -> std::result::Result<Regex, String> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let start = if phrase.starts_with(is_word_char) { r"\b" } else { "" };
+    let end = if phrase.ends_with(is_word_char) { r"\b" } else { "" };
+    Regex::new(&format!("(?i){}{}{}", start, regex::escape(phrase), end)).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::core::audio_processor::{ModelInfo, SpeechSegment};
+
+    fn result_with(texts: &[&str]) -> TranscriptResult {
+        TranscriptResult {
+            segments: texts
+                .iter()
+                .map(|text| SpeechSegment { start: 0.0, end: 1.0, text: text.to_string(), ..Default::default() })
+                .collect(),
+            audio_duration: Duration::from_secs(1),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_exact_and_regex_corrections() {
+        let corrections = Corrections::from_toml(r#"
+            [exact]
+            "cube control" = "kubectl"
+            "c++" = "C++ ($)"
+
+            [[regex]]
+            pattern = '\b[Kk] ?8 ?[sS]\b'
+            replacement = "k8s"
+
+            [[regex]]
+            pattern = '(\d+) percent'
+            replacement = "$1%"
+        "#).unwrap();
+        assert_eq!(corrections.len(), 4);
+
+        let result = result_with(&["Run Cube Control on the K 8 S cluster.", "Cube controller at 40 percent, c++ too"]);
+        let corrected = corrections.apply(&result);
+        assert_eq!(corrected.segments[0].text, "Run kubectl on the k8s cluster.");
+        assert_eq!(corrected.segments[1].text, "Cube controller at 40%, C++ ($) too");
+        assert_eq!(result.segments[0].text, "Run Cube Control on the K 8 S cluster.");
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        assert!(Corrections::from_toml("[[regex]]\npattern = '('\nreplacement = ''").is_err());
+        assert!(Corrections::from_toml("[exact]\n\" \" = \"x\"").is_err());
+        assert!(Corrections::from_toml("[glossary]\na = \"b\"").is_err());
+        assert!(Corrections::from_toml("").unwrap().is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("corrections.toml");
+        std::fs::write(&path, "[[regex]]\npattern = '['\nreplacement = 'x'\n").unwrap();
+        assert_eq!(Corrections::from_config_file(&path).unwrap_err().code(), 80);
+    }
+}
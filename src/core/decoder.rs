@@ -0,0 +1,415 @@
+use std::fs::File;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::{Time, TimeBase};
+use crate::core::audio_processor::SAMPLE_RATE;
+use crate::core::resample::{ResampleStream, Resampler};
+use crate::core::selection::TimeRange;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Gaps between selected ranges shorter than this are decoded through
+/// rather than seeked over
+const MIN_SEEK_SECS: f32 = 10.0;
+
+/// Decodes WAV, MP3, M4A (AAC and ALAC), FLAC and OGG Vorbis files into
+/// mono samples at a fixed rate, 16 kHz by default.
+///
+/// Channels are averaged, unless one is picked with
+/// [`AudioStream::channel`], and resampled packet by packet as the file is
+/// decoded, so [`stream`](Self::stream) never holds more than a packet of
+/// the recording.
+#[derive(Debug, Clone)]
+pub struct AudioDecoder {
+    sample_rate: u32,
+}
+
+impl Default for AudioDecoder {
+    fn default() -> Self {
+        Self::new(SAMPLE_RATE)
+    }
+}
+
+impl AudioDecoder {
+    /// Decode to `sample_rate` Hz
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Decode the default audio track of a file
+    #[tracing::instrument(name = "decode", skip_all, fields(path = %path.display()))]
+    pub fn decode(&self, path: &Path) -> Result<Vec<f32>> {
+        let mut samples = Vec::new();
+        for block in self.stream(path)? {
+            samples.extend(block?);
+        }
+        Ok(samples)
+    }
+
+    /// Decode the default audio track of a file block by block, in order.
+    /// The file is opened and probed straight away; decoding errors end the
+    /// stream.
+    pub fn stream(&self, path: &Path) -> Result<AudioStream> {
+        let unsupported = || AudioTranscriptionError::UnsupportedFormat {
+            path: path.to_path_buf(),
+            extension: path.extension().map(|ext| ext.to_string_lossy().into_owned()),
+        };
+
+        let file = File::open(path)
+            .map_err(|source| AudioTranscriptionError::File { path: path.to_path_buf(), source })?;
+        let mut hint = Hint::new();
+        if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+            hint.with_extension(extension);
+        }
+
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+        let format = symphonia::default::get_probe()
+            .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(|_| unsupported())?
+            .format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| AudioTranscriptionError::Audio(format!("No audio track found in {}", path.display())))?;
+        let track_id = track.id;
+        let source_rate = track.codec_params.sample_rate;
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|_| unsupported())?;
+
+        Ok(AudioStream {
+            path: path.to_path_buf(),
+            format,
+            decoder,
+            track_id,
+            source_rate,
+            sample_rate: self.sample_rate,
+            buffer: None,
+            resampler: None,
+            position: 0,
+            channel: None,
+            selection: None,
+            done: false,
+        })
+    }
+}
+
+/// Mono blocks of a file being decoded, one per packet, from
+/// [`AudioDecoder::stream`]
+pub struct AudioStream {
+    path: PathBuf,
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    source_rate: Option<u32>,  // From the container, or else the first packet
+    sample_rate: u32,
+    buffer: Option<SampleBuffer<f32>>,
+    resampler: Option<ResampleStream>,  // Created with the first packet
+    position: usize,  // Sample of the file the next block starts at
+    channel: Option<usize>,  // The one channel yielded; None mixes them all
+    selection: Option<Selection>,
+    done: bool,
+}
+
+/// Parts of the file a stream yields, from [`AudioStream::select`]
+struct Selection {
+    ranges: Vec<Range<usize>>,           // In samples
+    next: usize,                         // Index of the range being read
+    pending: Option<(usize, Vec<f32>)>,  // Rest of a block reaching past the range before, with its position
+    seekable: bool,                      // Cleared once the format fails to seek
+}
+
+impl AudioStream {
+    /// Yield only `channel`, counting from 0, instead of the mix of all
+    pub fn channel(mut self, channel: usize) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Yield only `ranges` of the file, back to back, seeking past the
+    /// audio in between where the format allows. The ranges must be in
+    /// order and apart; the stream ends after the last.
+    pub fn select(mut self, ranges: &[TimeRange]) -> Self {
+        let sample = |seconds: f32| (seconds.max(0.0) as f64 * self.sample_rate as f64).round() as usize;
+        let ranges = ranges.iter().map(|range| sample(range.start)..range.end.map_or(usize::MAX, sample)).collect();
+        self.selection = Some(Selection { ranges, next: 0, pending: None, seekable: true });
+        self
+    }
+
+    /// Jump ahead to `position`, in samples, or to the packet before it.
+    /// Returns false if the format can't seek, leaving the stream as it was.
+    fn seek(&mut self, position: usize) -> bool {
+        let time_base = self.format
+            .tracks()
+            .iter()
+            .find(|track| track.id == self.track_id)
+            .and_then(|track| track.codec_params.time_base)
+            .or_else(|| self.source_rate.map(|rate| TimeBase::new(1, rate)));
+        let Some(time_base) = time_base else { return false };
+        let time = Time::from(position as f64 / self.sample_rate as f64);
+        match self.format.seek(SeekMode::Accurate, SeekTo::Time { time, track_id: Some(self.track_id) }) {
+            Ok(seeked) => {
+                let reached = time_base.calc_time(seeked.actual_ts);
+                self.position = ((reached.seconds as f64 + reached.frac) * self.sample_rate as f64).round() as usize;
+                self.decoder.reset();
+                // Its state belongs to the audio before the jump
+                self.resampler = None;
+                true
+            }
+            Err(e) => {
+                tracing::debug!(error = %e, "Format can't seek, decoding through");
+                false
+            }
+        }
+    }
+
+    /// The next block of the selection, cut to the range it falls in
+    fn next_selected(&mut self) -> Option<Result<Vec<f32>>> {
+        loop {
+            let selection = self.selection.as_mut()?;
+            let range = selection.ranges.get(selection.next)?.clone();
+            let (start, block) = match selection.pending.take() {
+                Some(pending) => pending,
+                None => {
+                    let gap = range.start.saturating_sub(self.position) as f32 / self.sample_rate as f32;
+                    if gap >= MIN_SEEK_SECS && selection.seekable && !self.seek(range.start) {
+                        self.selection.as_mut()?.seekable = false;
+                    }
+                    let start = self.position;
+                    match self.next_block()? {
+                        Ok(block) => (start, block),
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+            };
+
+            let selection = self.selection.as_mut()?;
+            let end = start + block.len();
+            if end <= range.start {
+                continue;
+            }
+            if start >= range.end {
+                selection.next += 1;
+                selection.pending = Some((start, block));
+                continue;
+            }
+            let from = range.start.saturating_sub(start);
+            let to = (range.end - start).min(block.len());
+            if to < block.len() {
+                selection.next += 1;
+                selection.pending = Some((start + to, block[to..].to_vec()));
+            }
+            return Some(Ok(block[from..to].to_vec()));
+        }
+    }
+
+    /// Read and decode packets until one yields audio. `None` at the end
+    /// of the track.
+    fn next_packet(&mut self) -> Result<Option<Vec<f32>>> {
+        let audio_error = |e: SymphoniaError| AudioTranscriptionError::Audio(
+            format!("Failed to decode {}: {}", self.path.display(), e)
+        );
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                // Chained streams (e.g. concatenated OGG) are not followed
+                Err(SymphoniaError::ResetRequired) => return Ok(None),
+                Err(e) => return Err(audio_error(e)),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // A corrupt frame costs a few milliseconds, not the file
+                Err(SymphoniaError::DecodeError(e)) => {
+                    tracing::warn!(error = e, "Skipping undecodable packet");
+                    continue;
+                }
+                Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(audio_error(e)),
+            };
+
+            let spec = *decoded.spec();
+            let source_rate = *self.source_rate.get_or_insert(spec.rate);
+            let channels = spec.channels.count().max(1);
+            let samples = match &mut self.buffer {
+                Some(samples) if samples.capacity() >= decoded.capacity() * channels => samples,
+                _ => self.buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+            };
+            samples.copy_interleaved_ref(decoded);
+
+            let frames = samples.samples().chunks_exact(channels);
+            let mono: Vec<f32> = match self.channel {
+                Some(channel) if channel >= channels => {
+                    return Err(AudioTranscriptionError::Audio(format!(
+                        "{} has no channel {}, only {}",
+                        self.path.display(),
+                        channel + 1,
+                        channels
+                    )));
+                }
+                Some(channel) => frames.map(|frame| frame[channel]).collect(),
+                None => {
+                    let scale = 1.0 / channels as f32;
+                    frames.map(|frame| frame.iter().sum::<f32>() * scale).collect()
+                }
+            };
+            let sample_rate = self.sample_rate;
+            let resampler = self.resampler.get_or_insert_with(|| Resampler::new(source_rate, sample_rate).into_stream());
+            return Ok(Some(resampler.push(&mono)));
+        }
+    }
+
+    /// The next decoded block of the whole file
+    fn next_block(&mut self) -> Option<Result<Vec<f32>>> {
+        while !self.done {
+            let block = match self.next_packet() {
+                Ok(Some(block)) => block,
+                Ok(None) => {
+                    self.done = true;
+                    let tail = self.resampler.take().map(ResampleStream::finish).unwrap_or_default();
+                    tracing::debug!(source_rate = self.source_rate, samples = self.position + tail.len(), "Decoded audio");
+                    tail
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            };
+            if !block.is_empty() {
+                self.position += block.len();
+                return Some(Ok(block));
+            }
+        }
+        None
+    }
+}
+
+impl Iterator for AudioStream {
+    type Item = Result<Vec<f32>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.selection {
+            Some(_) => self.next_selected(),
+            None => self.next_block(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_wav(path: &Path, sample_rate: u32, frames: usize, sample: impl Fn(usize, u16) -> i16) {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for frame in 0..frames {
+            for channel in 0..2 {
+                writer.write_sample(sample(frame, channel)).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_decodes_stereo_to_16khz_mono() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("call.wav");
+        // Left and right cancel out except for a constant offset
+        write_wav(&path, 48_000, 48_000 * 2, |frame, channel| {
+            let tone = (8_000.0 * (frame as f32 * 0.05).sin()) as i16;
+            if channel == 0 { 4_096 + tone } else { 4_096 - tone }
+        });
+
+        let samples = AudioDecoder::default().decode(&path).unwrap();
+
+        assert_eq!(samples.len(), SAMPLE_RATE as usize * 2);
+        let middle = &samples[1_000..samples.len() - 1_000];
+        assert!(middle.iter().all(|s| (s - 0.125).abs() < 0.01), "not an even mix");
+    }
+
+    #[test]
+    fn test_keeps_rate_when_it_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("short.wav");
+        write_wav(&path, 8_000, 800, |_, _| 0);
+
+        assert_eq!(AudioDecoder::new(8_000).decode(&path).unwrap().len(), 800);
+        assert_eq!(AudioDecoder::default().decode(&path).unwrap().len(), 1_600);
+        let blocks: Vec<Vec<f32>> = AudioDecoder::default().stream(&path).unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(blocks.iter().map(Vec::len).sum::<usize>(), 1_600);
+    }
+
+    #[test]
+    fn test_picks_one_channel() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("call.wav");
+        write_wav(&path, SAMPLE_RATE, 1_600, |_, channel| if channel == 0 { 8_192 } else { -4_096 });
+
+        let channel = |index: usize| AudioDecoder::default().stream(&path).unwrap().channel(index).collect::<Result<Vec<_>>>();
+        let left = channel(0).unwrap().concat();
+        let right = channel(1).unwrap().concat();
+        assert_eq!(left.len(), 1_600);
+        assert!(left.iter().all(|&s| s == 0.25) && right.iter().all(|&s| s == -0.125));
+        assert_eq!(channel(2).unwrap_err().code(), 20);
+    }
+
+    #[test]
+    fn test_selects_ranges_back_to_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ramp.wav");
+        // One level per second
+        write_wav(&path, SAMPLE_RATE, SAMPLE_RATE as usize * 40, |frame, _| (frame / SAMPLE_RATE as usize) as i16 * 500);
+
+        // The gap before 30s is seeked over, the one before 5s decoded through
+        let ranges = [
+            TimeRange::new(1.0, Some(3.0)),
+            TimeRange::new(5.0, Some(6.0)),
+            TimeRange::new(30.0, Some(31.5)),
+            TimeRange::new(39.0, None),
+        ];
+        let samples: Vec<f32> = AudioDecoder::default()
+            .stream(&path)
+            .unwrap()
+            .select(&ranges)
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .concat();
+
+        assert_eq!(samples.len(), 88_000);
+        let level = |at: usize| (samples[at] * 32_768.0 / 500.0).round();
+        assert_eq!((level(0), level(31_999), level(32_000)), (1.0, 2.0, 5.0));
+        assert_eq!((level(47_999), level(48_000), level(71_999)), (5.0, 30.0, 31.0));
+        assert_eq!((level(72_000), level(87_999)), (39.0, 39.0));
+    }
+
+    #[test]
+    fn test_rejects_unreadable_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.mp3");
+        std::fs::write(&path, "not audio").unwrap();
+
+        assert_eq!(AudioDecoder::default().decode(&path).unwrap_err().code(), 21);
+        assert_eq!(AudioDecoder::default().decode(&dir.path().join("missing.wav")).unwrap_err().code(), 11);
+    }
+}
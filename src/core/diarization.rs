@@ -0,0 +1,414 @@
+use std::path::{Path, PathBuf};
+use crate::core::audio_processor::{DiarizationSegment, SAMPLE_RATE};
+use crate::core::model::{ModelKind, ModelProvider};
+use crate::core::decoder::{AudioDecoder, AudioStream};
+use crate::core::speakers::{self, Voiceprint, DEFAULT_MATCH_THRESHOLD};
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Length of audio segmented and embedded at once. Only one window's
+/// embeddings are held in memory at a time.
+pub const WINDOW_SECS: f32 = 300.0;
+
+/// Cosine similarity above which an embedding joins an existing speaker
+pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.5;
+
+/// Upper bound on distinct speakers tracked by the clusterer
+pub const DEFAULT_MAX_SPEAKERS: usize = 16;
+
+/// Turns of the same speaker separated by less than this are joined
+const JOIN_GAP_SECS: f32 = 0.5;
+
+/// A speech turn inside one window, with its speaker embedding
+#[derive(Debug, Clone)]
+pub struct SpeakerTurn {
+    pub start: f32,          // Start time in seconds, relative to the window
+    pub end: f32,            // End time in seconds, relative to the window
+    pub embedding: Vec<f32>,
+}
+
+/// Running centroid of one speaker cluster
+#[derive(Debug, Clone)]
+struct Centroid {
+    sum: Vec<f32>,  // Sum of L2-normalised member embeddings
+    count: usize,
+}
+
+impl Centroid {
+    fn similarity(&self, normalized: &[f32]) -> f32 {
+        let norm = l2_norm(&self.sum);
+        if norm == 0.0 {
+            return 0.0;
+        }
+        dot(&self.sum, normalized) / norm
+    }
+
+    fn add(&mut self, normalized: &[f32]) {
+        for (acc, value) in self.sum.iter_mut().zip(normalized) {
+            *acc += value;
+        }
+        self.count += 1;
+    }
+}
+
+/// Incremental speaker clustering.
+///
+/// Each embedding is compared against one running centroid per speaker and
+/// then dropped, so memory stays constant no matter how long the recording
+/// is. Speaker IDs start at 1 and are stable for the whole file.
+#[derive(Debug, Clone)]
+pub struct OnlineClusterer {
+    centroids: Vec<Centroid>,
+    threshold: f32,
+    max_speakers: usize,
+}
+
+impl OnlineClusterer {
+    pub fn new(threshold: f32, max_speakers: usize) -> Self {
+        Self {
+            centroids: Vec::new(),
+            threshold,
+            max_speakers: max_speakers.clamp(1, u8::MAX as usize),
+        }
+    }
+
+    /// Assign an embedding to a speaker, creating a new speaker when none is
+    /// similar enough and the speaker limit has not been reached
+    pub fn assign(&mut self, embedding: &[f32]) -> u8 {
+        let normalized = normalize(embedding);
+
+        let best = self.centroids
+            .iter()
+            .enumerate()
+            .map(|(index, centroid)| (index, centroid.similarity(&normalized)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        let index = match best {
+            Some((index, similarity)) if similarity >= self.threshold => index,
+            Some((index, _)) if self.centroids.len() >= self.max_speakers => index,
+            _ => {
+                self.centroids.push(Centroid { sum: vec![0.0; normalized.len()], count: 0 });
+                self.centroids.len() - 1
+            }
+        };
+
+        self.centroids[index].add(&normalized);
+        (index + 1) as u8
+    }
+
+    pub fn speaker_count(&self) -> usize {
+        self.centroids.len()
+    }
+
+    /// Each speaker's normalised centroid
+    pub fn centroids(&self) -> Vec<(u8, Vec<f32>)> {
+        self.centroids
+            .iter()
+            .enumerate()
+            .map(|(index, centroid)| ((index + 1) as u8, normalize(&centroid.sum)))
+            .collect()
+    }
+}
+
+/// Split audio into consecutive windows, yielding each window's start offset
+/// in seconds together with its samples
+pub fn windows(audio: &[f32], window_secs: f32) -> impl Iterator<Item = (f32, &[f32])> {
+    let window_len = ((window_secs * SAMPLE_RATE as f32) as usize).max(1);
+    audio
+        .chunks(window_len)
+        .enumerate()
+        .map(move |(index, samples)| ((index * window_len) as f32 / SAMPLE_RATE as f32, samples))
+}
+
+/// Regroup decoded blocks into the consecutive windows [`windows`] would
+/// give, holding no more than one window at a time. A decoding error ends
+/// the windows.
+pub fn stream_windows(
+    mut blocks: impl Iterator<Item = Result<Vec<f32>>>,
+    window_secs: f32,
+) -> impl Iterator<Item = Result<(f32, Vec<f32>)>> {
+    let window_len = ((window_secs * SAMPLE_RATE as f32) as usize).max(1);
+    let mut pending: Vec<f32> = Vec::new();
+    let mut offset = 0;
+    let mut ended = false;
+    std::iter::from_fn(move || {
+        while !ended && pending.len() < window_len {
+            match blocks.next() {
+                Some(Ok(block)) => pending.extend(block),
+                Some(Err(e)) => {
+                    ended = true;
+                    return Some(Err(e));
+                }
+                None => ended = true,
+            }
+        }
+        if pending.is_empty() {
+            return None;
+        }
+        let rest = pending.split_off(window_len.min(pending.len()));
+        let window = std::mem::replace(&mut pending, rest);
+        let start = offset as f32 / SAMPLE_RATE as f32;
+        offset += window.len();
+        Some(Ok((start, window)))
+    })
+}
+
+/// Tuning for [`Diarizer`]
+#[derive(Debug, Clone)]
+pub struct DiarizationOptions {
+    pub similarity_threshold: f32,  // Cosine similarity needed to join an existing speaker
+    pub max_speakers: usize,
+    pub voiceprints: Vec<Voiceprint>,  // Enrolled speakers to recognize
+    pub match_threshold: f32,       // Cosine similarity needed to name a speaker after a voiceprint
+}
+
+impl Default for DiarizationOptions {
+    fn default() -> Self {
+        Self {
+            similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
+            max_speakers: DEFAULT_MAX_SPEAKERS,
+            voiceprints: Vec::new(),
+            match_threshold: DEFAULT_MATCH_THRESHOLD,
+        }
+    }
+}
+
+/// Finds who spoke when, without transcribing anything
+#[derive(Debug, Clone)]
+pub struct Diarizer {
+    segmentation_model: PathBuf,
+    embedding_model: PathBuf,
+}
+
+impl Diarizer {
+    pub fn new(segmentation_model: impl Into<PathBuf>, embedding_model: impl Into<PathBuf>) -> Self {
+        Self {
+            segmentation_model: segmentation_model.into(),
+            embedding_model: embedding_model.into(),
+        }
+    }
+
+    /// Create a diarizer from the models of a provider, fetching them if the
+    /// provider supports it
+    pub async fn from_provider(provider: &dyn ModelProvider) -> Result<Self> {
+        let segmentation_model = provider.ensure_available(&ModelKind::Segmentation).await?;
+        let embedding_model = provider.ensure_available(&ModelKind::SpeakerEmbedding).await?;
+        Ok(Self::new(segmentation_model, embedding_model))
+    }
+
+    /// Speaker turns of an audio file, decoded one window at a time
+    pub fn diarize(&self, path: &Path, options: &DiarizationOptions) -> Result<Vec<DiarizationSegment>> {
+        self.diarize_stream(AudioDecoder::default().stream(path)?, options)
+    }
+
+    /// Speaker turns of a file being decoded, e.g. only the selected parts
+    /// of it, one window at a time
+    pub fn diarize_stream(&self, audio: AudioStream, options: &DiarizationOptions) -> Result<Vec<DiarizationSegment>> {
+        self.diarize_windows(stream_windows(audio, WINDOW_SECS), options)
+    }
+
+    /// Speaker turns of 16 kHz mono samples
+    pub fn diarize_samples(&self, audio: &[f32], options: &DiarizationOptions) -> Result<Vec<DiarizationSegment>> {
+        self.diarize_windows(windows(audio, WINDOW_SECS).map(Ok), options)
+    }
+
+    /// Voiceprint of the speaker in an audio file: the mean embedding of
+    /// its speech turns, weighted by their length, and the seconds of
+    /// speech it was made from. The file should hold one voice only.
+    pub fn voiceprint(&self, path: &Path) -> Result<(Vec<f32>, f32)> {
+        let mut extractor = pyannote_rs::EmbeddingExtractor::new(&self.embedding_model)
+            .map_err(|e| model_error(&self.embedding_model, e))?;
+
+        let mut sum: Vec<f32> = Vec::new();
+        let mut speech_secs = 0.0;
+        for window in stream_windows(AudioDecoder::default().stream(path)?, WINDOW_SECS) {
+            let (_, window) = window?;
+            for turn in self.speaker_turns(&mut extractor, &window)? {
+                let secs = turn.end - turn.start;
+                sum.resize(turn.embedding.len(), 0.0);
+                for (acc, value) in sum.iter_mut().zip(normalize(&turn.embedding)) {
+                    *acc += value * secs;
+                }
+                speech_secs += secs;
+            }
+        }
+
+        if speech_secs <= 0.0 {
+            return Err(AudioTranscriptionError::Audio(format!("No speech found in {}", path.display())));
+        }
+        Ok((normalize(&sum), speech_secs))
+    }
+
+    /// Speaker turns of consecutive windows, each with its start in seconds
+    fn diarize_windows<W: AsRef<[f32]>>(
+        &self,
+        windows: impl Iterator<Item = Result<(f32, W)>>,
+        options: &DiarizationOptions,
+    ) -> Result<Vec<DiarizationSegment>> {
+        let mut extractor = pyannote_rs::EmbeddingExtractor::new(&self.embedding_model)
+            .map_err(|e| model_error(&self.embedding_model, e))?;
+
+        // Embeddings are clustered incrementally window by window and dropped
+        // straight away, so memory stays bounded for multi-hour recordings
+        let mut clusterer = OnlineClusterer::new(options.similarity_threshold, options.max_speakers);
+        let mut segments = Vec::new();
+
+        for window in windows {
+            let (offset, window) = window?;
+            for turn in self.speaker_turns(&mut extractor, window.as_ref())? {
+                let speaker = clusterer.assign(&turn.embedding);
+                push_segment(&mut segments, DiarizationSegment {
+                    start: offset + turn.start,
+                    end: offset + turn.end,
+                    speaker,
+                    name: None,
+                });
+            }
+        }
+
+        // Speakers are named once their centroids have seen the whole file
+        if !options.voiceprints.is_empty() {
+            let names = speakers::identify(&clusterer.centroids(), &options.voiceprints, options.match_threshold);
+            for segment in &mut segments {
+                segment.name = names.get(&segment.speaker).cloned();
+            }
+            tracing::debug!(recognized = names.len(), "Matched enrolled speakers");
+        }
+
+        tracing::debug!(speakers = clusterer.speaker_count(), "Diarization finished");
+        Ok(segments)
+    }
+
+    /// Segment one window and embed each speech turn
+    fn speaker_turns(
+        &self,
+        extractor: &mut pyannote_rs::EmbeddingExtractor,
+        window: &[f32],
+    ) -> Result<Vec<SpeakerTurn>> {
+        let pcm: Vec<i16> = window
+            .iter()
+            .map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .collect();
+
+        let segments = pyannote_rs::get_segments(&pcm, SAMPLE_RATE, &self.segmentation_model)
+            .map_err(|e| model_error(&self.segmentation_model, e))?;
+
+        let mut turns = Vec::new();
+        for segment in segments {
+            let segment = segment.map_err(|e| model_error(&self.segmentation_model, e))?;
+            let embedding = extractor
+                .compute(&segment.samples)
+                .map_err(|e| model_error(&self.embedding_model, e))?
+                .collect();
+
+            turns.push(SpeakerTurn {
+                start: segment.start as f32,
+                end: segment.end as f32,
+                embedding,
+            });
+        }
+        Ok(turns)
+    }
+}
+
+fn model_error(path: &Path, error: impl std::fmt::Display) -> AudioTranscriptionError {
+    AudioTranscriptionError::Model(format!("{}: {}", path.display(), error))
+}
+
+/// Append a segment, joining it with the previous one when the same speaker
+/// simply continues across a window boundary or a short pause
+pub fn push_segment(segments: &mut Vec<DiarizationSegment>, segment: DiarizationSegment) {
+    if let Some(last) = segments.last_mut() {
+        if last.speaker == segment.speaker && segment.start - last.end < JOIN_GAP_SECS {
+            last.end = last.end.max(segment.end);
+            return;
+        }
+    }
+    segments.push(segment);
+}
+
+pub(crate) fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn l2_norm(values: &[f32]) -> f32 {
+    dot(values, values).sqrt()
+}
+
+pub(crate) fn normalize(values: &[f32]) -> Vec<f32> {
+    let norm = l2_norm(values);
+    if norm == 0.0 {
+        return values.to_vec();
+    }
+    values.iter().map(|v| v / norm).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similar_embeddings_share_speaker() {
+        let mut clusterer = OnlineClusterer::new(0.8, 4);
+
+        let first = clusterer.assign(&[1.0, 0.0, 0.0]);
+        let second = clusterer.assign(&[0.95, 0.05, 0.0]);
+        let third = clusterer.assign(&[0.0, 1.0, 0.0]);
+
+        assert_eq!(first, 1);
+        assert_eq!(second, 1);
+        assert_eq!(third, 2);
+        assert_eq!(clusterer.speaker_count(), 2);
+
+        let centroids = clusterer.centroids();
+        assert_eq!(centroids.iter().map(|(speaker, _)| *speaker).collect::<Vec<_>>(), vec![1, 2]);
+        assert!((dot(&centroids[0].1, &centroids[0].1) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_speaker_limit_falls_back_to_closest() {
+        let mut clusterer = OnlineClusterer::new(0.9, 2);
+
+        clusterer.assign(&[1.0, 0.0, 0.0]);
+        clusterer.assign(&[0.0, 1.0, 0.0]);
+        let overflow = clusterer.assign(&[0.1, 0.0, 1.0]);
+
+        assert_eq!(overflow, 1);
+        assert_eq!(clusterer.speaker_count(), 2);
+    }
+
+    #[test]
+    fn test_windows_cover_all_samples() {
+        let audio = vec![0.0; SAMPLE_RATE as usize * 5 / 2];
+        let windows: Vec<_> = windows(&audio, 1.0).collect();
+
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[1].0, 1.0);
+        assert_eq!(windows[2].1.len(), SAMPLE_RATE as usize / 2);
+
+        let blocks = audio.chunks(3_000).map(|block| Ok(block.to_vec()));
+        let streamed: Vec<(f32, Vec<f32>)> = stream_windows(blocks, 1.0).collect::<Result<_>>().unwrap();
+        assert_eq!(streamed.len(), 3);
+        assert_eq!(streamed[1].0, 1.0);
+        assert_eq!(streamed[2].1.len(), SAMPLE_RATE as usize / 2);
+    }
+
+    #[tokio::test]
+    async fn test_diarizer_requires_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = crate::core::model::DirectoryModelProvider::new(dir.path());
+
+        let error = Diarizer::from_provider(&provider).await.unwrap_err();
+        assert_eq!(error.code(), 31);
+    }
+
+    #[test]
+    fn test_push_segment_joins_continuing_speaker() {
+        let mut segments = Vec::new();
+        push_segment(&mut segments, DiarizationSegment { start: 0.0, end: 299.8, speaker: 1, name: None });
+        push_segment(&mut segments, DiarizationSegment { start: 300.0, end: 310.0, speaker: 1, name: None });
+        push_segment(&mut segments, DiarizationSegment { start: 310.2, end: 320.0, speaker: 2, name: None });
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].end, 310.0);
+    }
+}
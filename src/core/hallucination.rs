@@ -0,0 +1,206 @@
+//! Dropping text Whisper made up rather than heard.
+//!
+//! Whisper fills silence with phrases common in its training subtitles,
+//! such as "Thanks for watching!", and now and then gets stuck repeating
+//! itself. Three checks catch the usual cases: segments that lie over
+//! audio the VAD found no speech in, runs of the same words or segments
+//! repeated over and over, and known stock phrases standing on their own.
+
+use crate::core::audio_processor::{SpeechSegment, GAP_MARKER};
+
+/// Share of a segment that must overlap detected speech for it to be kept
+const MIN_SPEECH_SHARE: f32 = 0.1;
+
+/// Repeats of a phrase beyond which it counts as a decoding loop
+const MAX_REPEATS: usize = 3;
+
+/// Longest phrase, in words, checked for repeats
+const MAX_LOOP_WORDS: usize = 8;
+
+/// Phrases Whisper invents over silence, compared after [`normalize`].
+/// Only whole segments matching one are dropped.
+const HALLUCINATED_PHRASES: &[&str] = &[
+    "thanks for watching",
+    "thank you for watching",
+    "thank you so much for watching",
+    "thank you very much for watching",
+    "please subscribe",
+    "please like and subscribe",
+    "like and subscribe",
+    "dont forget to like and subscribe",
+    "subscribe to my channel",
+    "see you in the next video",
+    "subtitles by the amaraorg community",
+    "untertitel im auftrag des zdf für funk 2017",
+    "untertitel der amaraorg community",
+    "soustitres réalisés par la communauté damaraorg",
+];
+
+/// How many segments each check dropped or shortened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct FilterStats {
+    pub silence: usize,
+    pub loops: usize,
+    pub phrases: usize,
+}
+
+/// Drop hallucinated segments and collapse repetition loops. `speech` is
+/// the speech the VAD found, as start and end seconds in file order; the
+/// silence check is skipped without it, e.g. when the file wasn't read to
+/// the end.
+pub(crate) fn filter(segments: Vec<SpeechSegment>, speech: Option<&[(f32, f32)]>) -> (Vec<SpeechSegment>, FilterStats) {
+    let mut stats = FilterStats::default();
+    let mut output: Vec<SpeechSegment> = Vec::with_capacity(segments.len());
+    let mut repeats = 0;
+
+    for mut segment in segments {
+        // Skipped chunks are speech that failed, not text to judge
+        if segment.text == GAP_MARKER {
+            output.push(segment);
+            repeats = 0;
+            continue;
+        }
+
+        if speech.is_some_and(|speech| speech_overlap(&segment, speech) < MIN_SPEECH_SHARE * (segment.end - segment.start)) {
+            stats.silence += 1;
+            continue;
+        }
+
+        let key = normalize(&segment.text);
+        if HALLUCINATED_PHRASES.contains(&key.as_str()) {
+            stats.phrases += 1;
+            continue;
+        }
+
+        // The same segment over and over is a loop across segments
+        repeats = match output.last() {
+            Some(previous) if normalize(&previous.text) == key => repeats + 1,
+            _ => 0,
+        };
+        if repeats >= MAX_REPEATS {
+            stats.loops += 1;
+            continue;
+        }
+
+        if collapse_loops(&mut segment) {
+            stats.loops += 1;
+        }
+        output.push(segment);
+    }
+
+    (output, stats)
+}
+
+/// Seconds of `segment` that overlap detected speech
+fn speech_overlap(segment: &SpeechSegment, speech: &[(f32, f32)]) -> f32 {
+    let first = speech.partition_point(|&(_, end)| end <= segment.start);
+    speech[first..]
+        .iter()
+        .take_while(|&&(start, _)| start < segment.end)
+        .map(|&(start, end)| end.min(segment.end) - start.max(segment.start))
+        .sum()
+}
+
+/// Shorten a phrase repeated more than [`MAX_REPEATS`] times in a row to
+/// one occurrence. Word timings are kept in step when there is one per
+/// word. Returns whether anything was removed.
+fn collapse_loops(segment: &mut SpeechSegment) -> bool {
+    let words: Vec<&str> = segment.text.split_whitespace().collect();
+    let keys: Vec<String> = words.iter().map(|word| normalize(word)).collect();
+    let mut keep = vec![true; words.len()];
+
+    let mut i = 0;
+    while i < words.len() {
+        let longest = MAX_LOOP_WORDS.min(words.len() - i);
+        let repeated = (1..=longest).find_map(|len| {
+            let phrase = &keys[i..i + len];
+            let count = keys[i..].chunks_exact(len).take_while(|chunk| *chunk == phrase).count();
+            (count > MAX_REPEATS).then_some((len, count))
+        });
+        match repeated {
+            Some((len, count)) => {
+                keep[i + len..i + len * count].fill(false);
+                i += len * count;
+            }
+            None => i += 1,
+        }
+    }
+
+    if keep.iter().all(|&kept| kept) {
+        return false;
+    }
+    let text = words.iter().zip(&keep).filter(|(_, &kept)| kept).map(|(word, _)| *word).collect::<Vec<_>>().join(" ");
+    if segment.words.len() == keep.len() {
+        let mut kept = keep.iter();
+        segment.words.retain(|_| *kept.next().unwrap_or(&true));
+    } else {
+        segment.words.clear();
+    }
+    segment.text = text;
+    true
+}
+
+/// Lowercase letters, digits and single spaces, for comparing text
+/// regardless of punctuation
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::audio_processor::WordTiming;
+
+    fn segment(start: f32, end: f32, text: &str) -> SpeechSegment {
+        SpeechSegment { start, end, text: text.to_string(), ..Default::default() }
+    }
+
+    fn texts(segments: &[SpeechSegment]) -> Vec<&str> {
+        segments.iter().map(|segment| segment.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_drops_silence_and_stock_phrases() {
+        let speech = [(0.0, 10.0), (40.0, 50.0)];
+        let segments = vec![
+            segment(0.0, 5.0, "Let's start."),
+            segment(12.0, 20.0, "Bye."),
+            segment(9.0, 14.0, "Trailing off"),
+            segment(20.0, 40.0, GAP_MARKER),
+            segment(42.0, 45.0, "Thanks for watching!"),
+            segment(45.0, 48.0, "Thanks for watching the demo."),
+        ];
+
+        let (kept, stats) = filter(segments.clone(), Some(&speech));
+        assert_eq!(texts(&kept), ["Let's start.", "Trailing off", GAP_MARKER, "Thanks for watching the demo."]);
+        assert_eq!(stats, FilterStats { silence: 1, loops: 0, phrases: 1 });
+
+        let (kept, _) = filter(segments, None);
+        assert_eq!(kept.len(), 5);
+    }
+
+    #[test]
+    fn test_collapses_repetition_loops() {
+        let speech = [(0.0, 60.0)];
+        let mut looping = segment(0.0, 5.0, "I think so. I think so. I think so. I think so. I think so. Right");
+        looping.words = looping.text.split(' ').map(|text| WordTiming { start: 0.0, end: 0.0, text: text.to_string(), confidence: 0.9 }).collect();
+        let segments = vec![
+            looping,
+            segment(5.0, 6.0, "Okay."),
+            segment(6.0, 7.0, "Okay."),
+            segment(7.0, 8.0, "okay"),
+            segment(8.0, 9.0, "Okay."),
+            segment(9.0, 10.0, "Okay!"),
+            segment(10.0, 11.0, "no no no"),
+        ];
+
+        let (kept, stats) = filter(segments, Some(&speech));
+        assert_eq!(texts(&kept), ["I think so. Right", "Okay.", "Okay.", "okay", "no no no"]);
+        assert_eq!(kept[0].words.len(), 4);
+        assert_eq!(stats.loops, 3);
+    }
+}
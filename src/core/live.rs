@@ -0,0 +1,312 @@
+//! Microphone input: live captions (`listen`) and recordings (`record`).
+//!
+//! For captions, audio from the default input device is split into utterances by a
+//! [`StreamingVad`]. While someone is speaking, the utterance so far is
+//! decoded again every [`PARTIAL_INTERVAL_SECS`] for a rolling hypothesis;
+//! once they pause, or the utterance reaches [`MAX_UTTERANCE_SECS`], it is
+//! decoded one last time into final segments.
+
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use tokio_util::sync::CancellationToken;
+use crate::core::audio_processor::{AudioChunk, ProcessingConfig, SpeechSegment, SAMPLE_RATE};
+use crate::core::resample::Resampler;
+use crate::core::transcription::{self, TranscriptionWorker};
+use crate::core::vad::{StreamingVad, VadOptions, VadTransition};
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Seconds of new speech between two partial hypotheses
+pub const PARTIAL_INTERVAL_SECS: f32 = 1.0;
+
+/// Pause that ends an utterance, by default
+pub const DEFAULT_PAUSE_SECS: f32 = 0.8;
+
+/// Longest utterance before it is finalized even without a pause
+pub const MAX_UTTERANCE_SECS: f32 = 20.0;
+
+/// Audio kept from before speech was detected, so its onset isn't cut off
+const PRE_ROLL_SECS: f32 = 0.5;
+
+/// How often the capture loop checks for cancellation when no audio comes
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// What [`listen`] reports as it goes
+#[derive(Debug, Clone)]
+pub enum LiveEvent {
+    /// Capture has started
+    Listening { device: String, sample_rate: u32 },
+    /// The current hypothesis for the utterance in progress; replaces the
+    /// previous one
+    Partial(String),
+    /// A finished segment, timed from when listening started
+    Final(SpeechSegment),
+}
+
+/// Caption the default input device until `cancel` fires, sending events
+/// to `on_event`. Blocks, so run it on a blocking thread.
+pub fn listen(
+    model: &Path,
+    config: &ProcessingConfig,
+    vad: VadOptions,
+    cancel: CancellationToken,
+    mut on_event: impl FnMut(LiveEvent),
+) -> Result<()> {
+    let device = transcription::worker_devices(config).first().copied().flatten();
+    let worker = transcription::create_workers(model, &[device], config)?
+        .pop()
+        .ok_or_else(|| AudioTranscriptionError::Model("No transcription worker was created".to_string()))?;
+
+    // The stream has to stay on this thread: it isn't `Send` everywhere
+    let (microphone, blocks) = open_input(None)?;
+    let mut captioner = LiveCaptioner::new(worker, microphone.sample_rate, vad);
+    on_event(LiveEvent::Listening { device: microphone.name.clone(), sample_rate: microphone.sample_rate });
+
+    while !cancel.is_cancelled() {
+        match blocks.recv_timeout(RECEIVE_TIMEOUT) {
+            Ok(block) => {
+                // Decoding can't keep up with every block; catch up first
+                let mut samples = block;
+                while let Ok(block) = blocks.try_recv() {
+                    samples.extend(block);
+                }
+                captioner.push(&samples, &mut on_event)?;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(AudioTranscriptionError::Audio(format!("Input device '{}' stopped", microphone.name)));
+            }
+        }
+    }
+
+    captioner.finish(&mut on_event)
+}
+
+/// An open input stream; capture stops when it is dropped
+struct Microphone {
+    name: String,
+    sample_rate: u32,
+    _stream: cpal::Stream,
+}
+
+/// What [`record`] captured
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub device: String,
+    pub sample_rate: u32,
+    pub duration: Duration,
+}
+
+/// Record the input device named `device`, or the default one, into a
+/// 16-bit mono WAV file at `path` until `cancel` fires. `on_started` gets
+/// the device name once capture is running. Blocks, so run it on a
+/// blocking thread.
+pub fn record(path: &Path, device: Option<&str>, cancel: CancellationToken, on_started: impl FnOnce(&str)) -> Result<Recording> {
+    let (microphone, blocks) = open_input(device)?;
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: microphone.sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let wav_error = |e: hound::Error| match e {
+        hound::Error::IoError(source) => AudioTranscriptionError::File { path: path.to_path_buf(), source },
+        e => AudioTranscriptionError::Audio(format!("Failed to write {}: {}", path.display(), e)),
+    };
+    let mut writer = hound::WavWriter::create(path, spec).map_err(wav_error)?;
+    on_started(&microphone.name);
+
+    let mut samples: u64 = 0;
+    while !cancel.is_cancelled() {
+        match blocks.recv_timeout(RECEIVE_TIMEOUT) {
+            Ok(block) => {
+                for sample in &block {
+                    writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).map_err(wav_error)?;
+                }
+                samples += block.len() as u64;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(AudioTranscriptionError::Audio(format!("Input device '{}' stopped", microphone.name)));
+            }
+        }
+    }
+
+    // The header only gets its final length here
+    writer.finalize().map_err(wav_error)?;
+    Ok(Recording {
+        duration: Duration::from_secs_f64(samples as f64 / microphone.sample_rate as f64),
+        device: microphone.name,
+        sample_rate: microphone.sample_rate,
+    })
+}
+
+/// Names of the input devices, the default one first
+pub fn input_devices() -> Result<Vec<String>> {
+    let host = cpal::default_host();
+    let default = host.default_input_device().and_then(|device| device.name().ok());
+    let mut names: Vec<String> = host
+        .input_devices()
+        .map_err(|e| AudioTranscriptionError::Audio(format!("Failed to list input devices: {}", e)))?
+        .filter_map(|device| device.name().ok())
+        .collect();
+    names.sort_by_key(|name| Some(name) != default.as_ref());
+    Ok(names)
+}
+
+/// Start capturing from the input device named `name`, or the default one,
+/// delivering mono blocks in the device's own sample rate
+fn open_input(name: Option<&str>) -> Result<(Microphone, mpsc::Receiver<Vec<f32>>)> {
+    let audio_error = |message: String| AudioTranscriptionError::Audio(message);
+    let host = cpal::default_host();
+    let device = match name {
+        Some(name) => host
+            .input_devices()
+            .map_err(|e| audio_error(format!("Failed to list input devices: {}", e)))?
+            .find(|device| device.name().is_ok_and(|device_name| device_name == name))
+            .ok_or_else(|| AudioTranscriptionError::Configuration(format!(
+                "No input device named '{}'; `record --list-devices` shows them",
+                name
+            )))?,
+        None => host
+            .default_input_device()
+            .ok_or_else(|| audio_error("No audio input device found".to_string()))?,
+    };
+    let name = device.name().unwrap_or_else(|_| "default".to_string());
+    let supported = device
+        .default_input_config()
+        .map_err(|e| audio_error(format!("Input device '{}' is unusable: {}", name, e)))?;
+
+    let (sender, receiver) = mpsc::channel();
+    let config = supported.config();
+    let stream = match supported.sample_format() {
+        cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config, sender),
+        cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config, sender),
+        cpal::SampleFormat::I32 => build_stream::<i32>(&device, &config, sender),
+        cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config, sender),
+        format => return Err(audio_error(format!("Input device '{}' uses unsupported samples ({})", name, format))),
+    }
+    .map_err(|e| audio_error(format!("Failed to open input device '{}': {}", name, e)))?;
+    stream
+        .play()
+        .map_err(|e| audio_error(format!("Failed to start input device '{}': {}", name, e)))?;
+
+    tracing::info!(device = %name, sample_rate = config.sample_rate.0, channels = config.channels, "Capturing audio");
+    Ok((Microphone { name, sample_rate: config.sample_rate.0, _stream: stream }, receiver))
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sender: mpsc::Sender<Vec<f32>>,
+) -> std::result::Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            let mono = data
+                .chunks(channels)
+                .map(|frame| frame.iter().map(|sample| sample.to_sample::<f32>()).sum::<f32>() / channels as f32)
+                .collect();
+            // A closed receiver means listening has stopped
+            let _ = sender.send(mono);
+        },
+        |e| tracing::warn!(error = %e, "Audio input error"),
+        None,
+    )
+}
+
+/// Turns a stream of captured audio into partial and final captions
+struct LiveCaptioner {
+    worker: TranscriptionWorker,
+    vad: StreamingVad,
+    resampler: Resampler,
+    sample_rate: u32,
+    pre_roll: usize,        // Samples kept between utterances
+    utterance: Vec<f32>,    // Native rate: the utterance, or pre-roll between utterances
+    utterance_start: u64,   // Sample position of `utterance[0]` since listening started
+    undecoded: usize,       // Samples added since the last partial hypothesis
+}
+
+impl LiveCaptioner {
+    fn new(worker: TranscriptionWorker, sample_rate: u32, vad: VadOptions) -> Self {
+        // Speech is only reported once it has lasted `min_speech_secs`, so
+        // that much audio before the report belongs to the utterance too
+        let pre_roll = ((PRE_ROLL_SECS + vad.min_speech_secs) * sample_rate as f32) as usize;
+        Self {
+            worker,
+            vad: StreamingVad::new(sample_rate, vad),
+            resampler: Resampler::new(sample_rate, SAMPLE_RATE),
+            sample_rate,
+            pre_roll,
+            utterance: Vec::new(),
+            utterance_start: 0,
+            undecoded: 0,
+        }
+    }
+
+    fn push(&mut self, samples: &[f32], on_event: &mut impl FnMut(LiveEvent)) -> Result<()> {
+        self.utterance.extend_from_slice(samples);
+        self.undecoded += samples.len();
+
+        let transitions = self.vad.push(samples);
+        let rate = self.sample_rate as f32;
+        if transitions.contains(&VadTransition::SpeechEnded) || self.utterance.len() as f32 >= MAX_UTTERANCE_SECS * rate {
+            return self.finalize(on_event);
+        }
+
+        if self.vad.is_speaking() {
+            if self.undecoded as f32 >= PARTIAL_INTERVAL_SECS * rate {
+                self.undecoded = 0;
+                let text = self.decode()?.iter().map(|segment| segment.text.trim()).collect::<Vec<_>>().join(" ");
+                if !text.is_empty() {
+                    on_event(LiveEvent::Partial(text));
+                }
+            }
+        } else {
+            // Between utterances only the pre-roll is kept
+            if self.utterance.len() > self.pre_roll {
+                let dropped = self.utterance.len() - self.pre_roll;
+                self.utterance.drain(..dropped);
+                self.utterance_start += dropped as u64;
+            }
+            self.undecoded = 0;
+        }
+        Ok(())
+    }
+
+    /// Decode whatever was said before listening stopped
+    fn finish(&mut self, on_event: &mut impl FnMut(LiveEvent)) -> Result<()> {
+        if self.vad.is_speaking() {
+            self.finalize(on_event)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(&mut self, on_event: &mut impl FnMut(LiveEvent)) -> Result<()> {
+        for segment in self.decode()? {
+            on_event(LiveEvent::Final(segment));
+        }
+        self.utterance_start += self.utterance.len() as u64;
+        self.utterance.clear();
+        self.undecoded = 0;
+        Ok(())
+    }
+
+    fn decode(&mut self) -> Result<Vec<SpeechSegment>> {
+        let start = self.utterance_start as f32 / self.sample_rate as f32;
+        let mut samples = self.resampler.process(&self.utterance);
+        let end = start + samples.len() as f32 / SAMPLE_RATE as f32;
+        // Whisper needs at least a second of audio
+        if samples.len() < SAMPLE_RATE as usize {
+            samples.resize(SAMPLE_RATE as usize, 0.0);
+        }
+        self.worker.transcribe(&AudioChunk { index: 0, start, end, samples })
+    }
+}
@@ -0,0 +1,220 @@
+//! Meeting details read from a sidecar file next to the recording: a TOML
+//! file or a calendar event (`.ics`) exported from the meeting invite.
+//!
+//! ```toml
+//! title = "Weekly sync"
+//! date = "2024-05-01"
+//! attendees = ["Alice Smith", "Bob Jones"]
+//! ```
+
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Title, date and attendees of the recorded meeting
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MeetingInfo {
+    pub title: Option<String>,
+    pub date: Option<String>,   // `YYYY-MM-DD`
+    #[serde(default)]
+    pub attendees: Vec<String>, // Candidate speaker names, in the order given
+}
+
+impl MeetingInfo {
+    /// Read a `.ics` event or a TOML file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|source| AudioTranscriptionError::File { path: path.to_path_buf(), source })?;
+        let is_calendar = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ics"));
+
+        let parsed = if is_calendar {
+            Self::from_ics(&contents)
+        } else {
+            toml::from_str(&contents).map_err(|e| e.to_string())
+        };
+        parsed.map_err(|e| AudioTranscriptionError::Configuration(
+            format!("Invalid meeting file {}: {}", path.display(), e)
+        ))
+    }
+
+    /// The sidecar for `input`, if one exists: `<name>.meeting.toml` or
+    /// `<name>.ics` in the same directory
+    pub fn find_sidecar(input: &Path) -> Option<PathBuf> {
+        ["meeting.toml", "ics"]
+            .iter()
+            .map(|extension| input.with_extension(extension))
+            .find(|candidate| candidate.is_file())
+    }
+
+    /// Read the first event of an iCalendar file. Attendees are taken from
+    /// their display name (`CN`), or their address when it has none; the
+    /// organizer is listed first.
+    pub fn from_ics(contents: &str) -> std::result::Result<Self, String> {
+        let mut info = MeetingInfo::default();
+        let mut in_event = false;
+        let mut found_event = false;
+        let mut organizer = None;
+
+        for line in unfold(contents) {
+            let Some((name_and_params, value)) = line.split_once(':') else {
+                continue;
+            };
+            let mut params = name_and_params.split(';');
+            let name = params.next().unwrap_or_default().to_ascii_uppercase();
+
+            match name.as_str() {
+                "BEGIN" if value.eq_ignore_ascii_case("VEVENT") => {
+                    in_event = true;
+                    found_event = true;
+                }
+                "END" if value.eq_ignore_ascii_case("VEVENT") => break,
+                _ if !in_event => {}
+                "SUMMARY" => info.title = Some(unescape(value)),
+                "DTSTART" => info.date = ics_date(value),
+                "ATTENDEE" | "ORGANIZER" => {
+                    let common_name = params
+                        .filter_map(|param| param.split_once('='))
+                        .find(|(key, _)| key.eq_ignore_ascii_case("CN"))
+                        .map(|(_, cn)| cn.trim_matches('"').to_string());
+                    let address = value.strip_prefix("mailto:").or_else(|| value.strip_prefix("MAILTO:")).unwrap_or(value);
+                    let attendee = common_name.unwrap_or_else(|| address.to_string());
+
+                    if name == "ORGANIZER" {
+                        organizer = Some(attendee);
+                    } else if !info.attendees.contains(&attendee) {
+                        info.attendees.push(attendee);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if !found_event {
+            return Err("no VEVENT found".to_string());
+        }
+        if let Some(organizer) = organizer {
+            info.attendees.retain(|attendee| *attendee != organizer);
+            info.attendees.insert(0, organizer);
+        }
+        Ok(info)
+    }
+
+    /// Lines shown above plain-text and Markdown transcripts, or `None` when
+    /// there is nothing to show
+    pub fn header(&self, markdown: bool) -> Option<String> {
+        if self.title.is_none() && self.date.is_none() && self.attendees.is_empty() {
+            return None;
+        }
+
+        let mut header = String::new();
+        match &self.title {
+            Some(title) if markdown => header.push_str(&format!("# {}\n\n", title)),
+            Some(title) => header.push_str(&format!("{}\n", title)),
+            None => {}
+        }
+
+        let mut fields = Vec::new();
+        if let Some(date) = &self.date {
+            fields.push(format!("Date: {}", date));
+        }
+        if !self.attendees.is_empty() {
+            fields.push(format!("Attendees: {}", self.attendees.join(", ")));
+        }
+
+        // Trailing spaces are a Markdown line break
+        header.push_str(&fields.join(if markdown { "  \n" } else { "\n" }));
+        if !fields.is_empty() {
+            header.push('\n');
+        }
+        header.push('\n');
+        Some(header)
+    }
+}
+
+/// Join folded lines (continuations start with a space or tab)
+fn unfold(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(continuation), Some(previous)) => previous.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// `20240501T100000Z` or `20240501` to `2024-05-01`
+fn ics_date(value: &str) -> Option<String> {
+    let digits = value.get(..8)?;
+    digits
+        .bytes()
+        .all(|b| b.is_ascii_digit())
+        .then(|| format!("{}-{}-{}", &digits[..4], &digits[4..6], &digits[6..8]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVITE: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Weekly sync\\, planning\r\n\
+        DTSTART;TZID=Europe/Berlin:20240501T100000\r\n\
+        ORGANIZER;CN=Carol White:mailto:carol@example.com\r\n\
+        ATTENDEE;ROLE=REQ-PARTICIPANT;CN=\"Alice Smith\":mailto:alice@example.com\r\n\
+        ATTENDEE;CN=Carol White:mailto:carol@example.com\r\n\
+        ATTENDEE;RSVP=TRUE:mailto:bob@exa\r\n mple.com\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_from_ics() {
+        let info = MeetingInfo::from_ics(INVITE).unwrap();
+
+        assert_eq!(info.title.as_deref(), Some("Weekly sync, planning"));
+        assert_eq!(info.date.as_deref(), Some("2024-05-01"));
+        assert_eq!(info.attendees, vec!["Carol White", "Alice Smith", "bob@example.com"]);
+        assert!(MeetingInfo::from_ics("BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n").is_err());
+    }
+
+    #[test]
+    fn test_sidecar_discovery_and_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("standup.wav");
+        assert_eq!(MeetingInfo::find_sidecar(&input), None);
+
+        let sidecar = dir.path().join("standup.meeting.toml");
+        std::fs::write(&sidecar, "title = \"Standup\"\nattendees = [\"Alice\", \"Bob\"]\n").unwrap();
+        assert_eq!(MeetingInfo::find_sidecar(&input), Some(sidecar.clone()));
+
+        let info = MeetingInfo::from_file(&sidecar).unwrap();
+        assert_eq!(info.title.as_deref(), Some("Standup"));
+        assert_eq!(info.attendees, vec!["Alice", "Bob"]);
+
+        std::fs::write(&sidecar, "attendees = \"Alice\"\n").unwrap();
+        assert_eq!(MeetingInfo::from_file(&sidecar).unwrap_err().code(), 80);
+    }
+
+    #[test]
+    fn test_header() {
+        let info = MeetingInfo {
+            title: Some("Standup".to_string()),
+            date: Some("2024-05-01".to_string()),
+            attendees: vec!["Alice".to_string(), "Bob".to_string()],
+        };
+
+        assert_eq!(info.header(false).unwrap(), "Standup\nDate: 2024-05-01\nAttendees: Alice, Bob\n\n");
+        assert_eq!(info.header(true).unwrap(), "# Standup\n\nDate: 2024-05-01  \nAttendees: Alice, Bob\n\n");
+        assert_eq!(MeetingInfo::default().header(false), None);
+    }
+}
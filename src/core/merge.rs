@@ -0,0 +1,334 @@
+//! Assigning diarization speakers to transcript segments.
+//!
+//! Each segment gets the speaker whose turns overlap it the longest. A
+//! segment that runs across a speaker change is split at the change, with
+//! its words divided between the two parts by their timing.
+
+use std::collections::BTreeMap;
+use crate::core::audio_processor::{DiarizationSegment, SpeechSegment, WordTiming, GAP_MARKER};
+
+/// A speaker change inside a segment shorter than this is not split off
+const MIN_TURN_SECS: f32 = 0.5;
+
+/// A segment no turn overlaps takes the nearest turn at most this far away
+const MAX_GAP_SECS: f32 = 1.0;
+
+/// Overlaps closer than this count as a tie
+const TIE_EPSILON: f32 = 0.01;
+
+/// Part of a segment attributed to one speaker
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Run {
+    speaker: u8,
+    start: f32,
+    end: f32,
+}
+
+/// Label every segment with a speaker, splitting segments that span a
+/// speaker change, and with the name of a recognized speaker. Segments far
+/// from any speech turn, and gap markers for skipped chunks, are left
+/// without a speaker.
+pub fn assign_speakers(transcript: Vec<SpeechSegment>, diarization: &[DiarizationSegment]) -> Vec<SpeechSegment> {
+    let mut turns = diarization.to_vec();
+    turns.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let mut merged: Vec<SpeechSegment> = Vec::with_capacity(transcript.len());
+    for segment in transcript {
+        if segment.text == GAP_MARKER {
+            merged.push(segment);
+            continue;
+        }
+
+        let previous = merged.last().and_then(|segment| segment.speaker);
+        let runs = speaker_runs(&segment, &turns, previous);
+        match runs.as_slice() {
+            [] => merged.push(SpeechSegment { speaker: nearest_speaker(&segment, &turns), ..segment }),
+            [run] => merged.push(SpeechSegment { speaker: Some(run.speaker), ..segment }),
+            _ => merged.extend(split_segment(segment, &runs)),
+        }
+    }
+
+    let names: BTreeMap<u8, &str> = turns
+        .iter()
+        .filter_map(|turn| turn.name.as_deref().map(|name| (turn.speaker, name)))
+        .collect();
+    if !names.is_empty() {
+        for segment in &mut merged {
+            segment.speaker_name = segment.speaker.and_then(|speaker| names.get(&speaker)).map(|name| name.to_string());
+        }
+    }
+    merged
+}
+
+/// Who speaks when within a segment, as consecutive runs covering it
+fn speaker_runs(segment: &SpeechSegment, turns: &[DiarizationSegment], previous: Option<u8>) -> Vec<Run> {
+    let overlapping: Vec<&DiarizationSegment> = turns
+        .iter()
+        .take_while(|turn| turn.start < segment.end)
+        .filter(|turn| turn.end > segment.start)
+        .collect();
+    if overlapping.is_empty() {
+        return Vec::new();
+    }
+
+    let mut totals: BTreeMap<u8, f32> = BTreeMap::new();
+    for turn in &overlapping {
+        *totals.entry(turn.speaker).or_default() += overlap(segment, turn);
+    }
+    let dominant = best_speaker(&totals, previous);
+    if segment.end - segment.start < 2.0 * MIN_TURN_SECS || totals.len() == 1 {
+        return vec![Run { speaker: dominant, start: segment.start, end: segment.end }];
+    }
+
+    // Elementary intervals between every turn boundary inside the segment
+    let mut bounds = vec![segment.start, segment.end];
+    for turn in &overlapping {
+        bounds.extend([turn.start, turn.end].into_iter().filter(|t| *t > segment.start && *t < segment.end));
+    }
+    bounds.sort_by(f32::total_cmp);
+    bounds.dedup();
+
+    let mut runs: Vec<Run> = Vec::new();
+    for pair in bounds.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let middle = (start + end) / 2.0;
+
+        // Where voices overlap, the one heard longer in the segment wins;
+        // silence between turns goes to the speaker before it
+        let mut active: BTreeMap<u8, f32> = BTreeMap::new();
+        for turn in overlapping.iter().filter(|turn| turn.start <= middle && turn.end > middle) {
+            active.insert(turn.speaker, totals[&turn.speaker]);
+        }
+        let speaker = match (active.is_empty(), runs.last()) {
+            (true, Some(last)) => last.speaker,
+            (true, None) => continue,
+            (false, last) => best_speaker(&active, last.map(|run| run.speaker).or(previous)),
+        };
+
+        match runs.last_mut() {
+            Some(last) if last.speaker == speaker || end - start < MIN_TURN_SECS => last.end = end,
+            _ => runs.push(Run { speaker, start, end }),
+        }
+    }
+
+    // A short first run is folded into the next one, which then starts the
+    // segment; absorbed runs can leave neighbours with the same speaker
+    if runs.len() > 1 && runs[0].end - runs[0].start < MIN_TURN_SECS {
+        let first = runs.remove(0);
+        runs[0].start = first.start;
+    }
+    if let Some(first) = runs.first_mut() {
+        first.start = segment.start;
+    }
+    runs.dedup_by(|next, run| {
+        let same = next.speaker == run.speaker;
+        if same {
+            run.end = next.end;
+        }
+        same
+    });
+    runs
+}
+
+/// Divide a segment's words between its runs, by the words' own timings
+/// when the model gave them and otherwise assuming an even pace, weighted
+/// by word length.
+fn split_segment(segment: SpeechSegment, runs: &[Run]) -> Vec<SpeechSegment> {
+    // Each word with the run it is spoken in
+    let timed: Vec<(usize, &str, Option<&WordTiming>)> = if segment.words.is_empty() {
+        let words: Vec<&str> = segment.text.split_whitespace().collect();
+        let total: usize = words.iter().map(|word| word.len() + 1).sum();
+        let duration = segment.end - segment.start;
+        let mut spoken = 0;
+        words
+            .into_iter()
+            .map(|word| {
+                let weight = word.len() + 1;
+                let time = segment.start + duration * (spoken as f32 + weight as f32 / 2.0) / total as f32;
+                spoken += weight;
+                (run_at(runs, time), word, None)
+            })
+            .collect()
+    } else {
+        segment
+            .words
+            .iter()
+            .map(|word| (run_at(runs, (word.start + word.end) / 2.0), word.text.as_str(), Some(word)))
+            .collect()
+    };
+
+    // Consecutive words of the same run become one segment
+    let groups: Vec<&[(usize, &str, Option<&WordTiming>)]> = timed.chunk_by(|a, b| a.0 == b.0).collect();
+    let last = groups.len().saturating_sub(1);
+    groups
+        .iter()
+        .enumerate()
+        .map(|(position, group)| {
+            let run = &runs[group[0].0];
+            let words: Vec<WordTiming> = group.iter().filter_map(|(_, _, word)| word.cloned()).collect();
+            let confidence = match words.len() {
+                0 => segment.confidence,
+                count => Some(words.iter().map(|word| word.confidence).sum::<f32>() / count as f32),
+            };
+            SpeechSegment {
+                start: if position == 0 { segment.start } else { run.start },
+                end: if position == last { segment.end } else { run.end },
+                text: group.iter().map(|(_, text, _)| *text).collect::<Vec<_>>().join(" "),
+                speaker: Some(run.speaker),
+                confidence,
+                words,
+                speaker_name: None,
+            }
+        })
+        .collect()
+}
+
+/// Index of the run a moment falls in; the last run for anything after
+fn run_at(runs: &[Run], time: f32) -> usize {
+    runs.iter().position(|run| time < run.end).unwrap_or(runs.len() - 1)
+}
+
+/// Speaker of the closest turn within [`MAX_GAP_SECS`]
+fn nearest_speaker(segment: &SpeechSegment, turns: &[DiarizationSegment]) -> Option<u8> {
+    turns
+        .iter()
+        .map(|turn| ((turn.start - segment.end).max(segment.start - turn.end), turn.speaker))
+        .filter(|(gap, _)| *gap <= MAX_GAP_SECS)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, speaker)| speaker)
+}
+
+/// The speaker with the most overlap. Ties go to `previous` when it is
+/// among them, so a conversation doesn't flip back and forth on noise, and
+/// otherwise to the lower speaker number.
+fn best_speaker(totals: &BTreeMap<u8, f32>, previous: Option<u8>) -> u8 {
+    let longest = totals.values().copied().fold(f32::MIN, f32::max);
+    let tied: Vec<u8> = totals
+        .iter()
+        .filter(|(_, total)| longest - **total <= TIE_EPSILON)
+        .map(|(speaker, _)| *speaker)
+        .collect();
+
+    match previous {
+        Some(previous) if tied.contains(&previous) => previous,
+        _ => tied[0],
+    }
+}
+
+fn overlap(segment: &SpeechSegment, turn: &DiarizationSegment) -> f32 {
+    (segment.end.min(turn.end) - segment.start.max(turn.start)).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f32, end: f32, text: &str) -> SpeechSegment {
+        SpeechSegment { start, end, text: text.to_string(), ..Default::default() }
+    }
+
+    fn turn(start: f32, end: f32, speaker: u8) -> DiarizationSegment {
+        DiarizationSegment { start, end, speaker, name: None }
+    }
+
+    fn labels(segments: &[SpeechSegment]) -> Vec<(f32, f32, &str, Option<u8>)> {
+        segments.iter().map(|s| (s.start, s.end, s.text.as_str(), s.speaker)).collect()
+    }
+
+    #[test]
+    fn test_speaker_with_most_overlap_wins() {
+        let merged = assign_speakers(
+            vec![segment(0.0, 4.0, "Good morning everyone."), segment(5.0, 7.0, "Hi.")],
+            &[turn(0.0, 0.4, 2), turn(0.4, 4.2, 1), turn(4.8, 7.0, 2)],
+        );
+
+        assert_eq!(merged[0].speaker, Some(1));
+        assert_eq!(merged[1].speaker, Some(2));
+    }
+
+    #[test]
+    fn test_recognized_speakers_are_named() {
+        let named = DiarizationSegment { name: Some("Alice".to_string()), ..turn(4.0, 9.0, 2) };
+        let merged = assign_speakers(
+            vec![segment(0.0, 4.0, "Good morning."), segment(4.0, 6.0, "Hi."), segment(6.0, 8.0, "Thanks for joining.")],
+            &[turn(0.0, 4.0, 1), named.clone(), DiarizationSegment { start: 9.0, end: 10.0, ..named }],
+        );
+
+        let names: Vec<Option<&str>> = merged.iter().map(|segment| segment.speaker_name.as_deref()).collect();
+        assert_eq!(names, vec![None, Some("Alice"), Some("Alice")]);
+    }
+
+    #[test]
+    fn test_segment_across_turn_change_is_split() {
+        let merged = assign_speakers(
+            vec![segment(10.0, 14.0, "Thanks Anna. Sure, happy to")],
+            &[turn(9.0, 11.5, 1), turn(11.5, 15.0, 2)],
+        );
+
+        assert_eq!(labels(&merged), vec![
+            (10.0, 11.5, "Thanks Anna.", Some(1)),
+            (11.5, 14.0, "Sure, happy to", Some(2)),
+        ]);
+    }
+
+    #[test]
+    fn test_split_follows_word_timings() {
+        let word = |start: f32, text: &str, confidence: f32| WordTiming { start, end: start + 0.3, text: text.to_string(), confidence };
+        let timed = SpeechSegment {
+            words: vec![
+                word(10.0, "Thanks.", 0.9),
+                word(10.5, "Anna.", 0.7),
+                word(12.6, "Sure,", 0.5),
+                word(13.0, "happy", 0.7),
+                word(13.5, "to", 0.9),
+            ],
+            confidence: Some(0.74),
+            ..segment(10.0, 14.0, "Thanks. Anna. Sure, happy to")
+        };
+
+        // Evenly paced, "Sure," would fall before the change at 12.5
+        let merged = assign_speakers(vec![timed], &[turn(9.0, 12.5, 1), turn(12.5, 15.0, 2)]);
+
+        assert_eq!(labels(&merged), vec![
+            (10.0, 12.5, "Thanks. Anna.", Some(1)),
+            (12.5, 14.0, "Sure, happy to", Some(2)),
+        ]);
+        assert_eq!(merged[1].words.len(), 3);
+        assert!((merged[0].confidence.unwrap() - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_short_interjection_does_not_split() {
+        let merged = assign_speakers(
+            vec![segment(0.0, 6.0, "So the plan is to ship on Friday")],
+            &[turn(0.0, 3.0, 1), turn(3.0, 3.3, 2), turn(3.3, 6.0, 1)],
+        );
+
+        assert_eq!(labels(&merged), vec![(0.0, 6.0, "So the plan is to ship on Friday", Some(1))]);
+    }
+
+    #[test]
+    fn test_tie_keeps_previous_speaker() {
+        let merged = assign_speakers(
+            vec![segment(0.0, 2.0, "Right."), segment(3.0, 3.8, "Okay.")],
+            &[turn(0.0, 2.0, 2), turn(3.0, 3.4, 1), turn(3.4, 3.8, 2)],
+        );
+
+        assert_eq!(merged[1].speaker, Some(2));
+        assert_eq!(best_speaker(&BTreeMap::from([(3, 1.0), (1, 1.0)]), None), 1);
+    }
+
+    #[test]
+    fn test_segments_without_turns() {
+        let gap = segment(0.0, 30.0, GAP_MARKER);
+        let merged = assign_speakers(
+            vec![gap, segment(40.5, 41.0, "Mm-hm."), segment(50.0, 51.0, "Bye.")],
+            &[turn(35.0, 40.0, 3)],
+        );
+
+        assert_eq!(merged[0].speaker, None);
+        assert_eq!(merged[1].speaker, Some(3));
+        assert_eq!(merged[2].speaker, None);
+        assert!(assign_speakers(vec![segment(0.0, 1.0, "Hello.")], &[])[0].speaker.is_none());
+    }
+}
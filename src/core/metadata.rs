@@ -0,0 +1,96 @@
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use crate::core::audio_processor::AudioMetadata;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Read duration, sample rate, channels and codec from the container
+/// headers without decoding any audio
+pub fn probe(path: &Path) -> Result<AudioMetadata> {
+    let file = File::open(path)
+        .map_err(|source| AudioTranscriptionError::File { path: path.to_path_buf(), source })?;
+    let file_size = file.metadata()?.len();
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|_| AudioTranscriptionError::UnsupportedFormat {
+            path: path.to_path_buf(),
+            extension: path.extension().map(|ext| ext.to_string_lossy().into_owned()),
+        })?;
+
+    let track = probed.format.default_track().ok_or_else(|| AudioTranscriptionError::Audio(
+        format!("No audio track found in {}", path.display())
+    ))?;
+    let params = &track.codec_params;
+
+    let duration = match (params.time_base, params.n_frames, params.sample_rate) {
+        (Some(time_base), Some(frames), _) => {
+            let time = time_base.calc_time(frames);
+            Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac)
+        }
+        (None, Some(frames), Some(rate)) if rate > 0 => Duration::from_secs_f64(frames as f64 / rate as f64),
+        _ => Duration::ZERO,
+    };
+
+    let codec = symphonia::default::get_codecs()
+        .get_codec(params.codec)
+        .map(|descriptor| descriptor.short_name.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    Ok(AudioMetadata {
+        duration,
+        sample_rate: params.sample_rate.unwrap_or(0),
+        channels: params.channels.map(|channels| channels.count() as u16).unwrap_or(0),
+        codec,
+        file_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_wav() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tone.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44_100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..44_100 * 2 * 3 / 2 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let metadata = probe(&path).unwrap();
+
+        assert_eq!(metadata.sample_rate, 44_100);
+        assert_eq!(metadata.channels, 2);
+        assert_eq!(metadata.codec, "pcm_s16le");
+        assert_eq!(metadata.duration, Duration::from_millis(1500));
+        assert_eq!(metadata.file_size, std::fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn test_probe_rejects_non_audio() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "not audio").unwrap();
+
+        assert_eq!(probe(&path).unwrap_err().code(), 21);
+    }
+}
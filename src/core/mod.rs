@@ -1,7 +1,54 @@
 pub mod audio_processor;
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod bench;
+#[cfg(any(feature = "summarize", feature = "translate"))]
+pub mod chat;
+pub mod checkpoint;
+pub mod chunking;
+pub mod corrections;
+pub mod decoder;
+pub mod hallucination;
+#[cfg(feature = "listen")]
+pub mod live;
+pub mod meeting;
+pub mod merge;
+pub mod metadata;
+pub mod diarization;
 pub mod model;
+#[cfg(feature = "notify")]
+pub mod notify;
+pub mod post_process;
+pub mod prefetch;
+pub mod profiles;
+pub mod progress;
+pub mod raw;
+pub mod refine;
+pub mod resample;
+pub mod scheduler;
+pub mod selection;
+#[cfg(feature = "search")]
+pub mod search;
+pub mod sources;
+pub mod speaker_names;
+pub mod speakers;
+#[cfg(feature = "cloud")]
+pub mod storage;
+#[cfg(feature = "summarize")]
+pub mod summarize;
+pub mod transcription;
 pub mod transcript_generator;
+#[cfg(feature = "translate")]
+pub mod translate;
+pub mod vad;
+#[cfg(feature = "watch")]
+pub mod watch;
+pub mod workdir;
 
 pub use audio_processor::AudioProcessor;
-pub use model::{ModelManager, ModelSize};
+pub use decoder::AudioDecoder;
+pub use diarization::{DiarizationOptions, Diarizer};
+pub use model::{DirectoryModelProvider, DownloadPolicy, DownloadRetry, ModelKind, ModelManager, ModelProvider, ModelSize};
+pub use post_process::{PostProcessor, PostProcessorChain, Retime};
 pub use transcript_generator::TranscriptGenerator;
+pub use vad::{SileroVad, Vad, VadOptions};
@@ -0,0 +1,131 @@
+//! Model selection by available memory, for `--model auto`.
+//!
+//! The largest Whisper size that is expected to fit in free GPU memory, or
+//! in free system memory for CPU runs and unified-memory Macs, is picked.
+//! Estimates can be off, so a model that still fails to load is replaced
+//! by the next smaller one (see [`ModelSize::smaller`]).
+
+use std::process::Command;
+use crate::core::model::ModelSize;
+
+/// Sizes `auto` picks from, largest first. Medium is left out: large-v3-turbo
+/// needs about as much memory and transcribes better.
+pub const AUTO_MODELS: [ModelSize; 5] = [
+    ModelSize::LargeV3,
+    ModelSize::LargeV3Turbo,
+    ModelSize::Small,
+    ModelSize::Base,
+    ModelSize::Tiny,
+];
+
+/// Picked when free memory can't be determined
+pub const FALLBACK_MODEL: ModelSize = ModelSize::Medium;
+
+/// Where the memory a model would be loaded into was measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemorySource {
+    Gpu(usize),  // Free memory on this CUDA device
+    System,      // Free system memory
+}
+
+/// Free memory a model on `gpu_device` would be loaded into, or system
+/// memory for CPU runs. `None` when it can't be determined.
+pub fn available_memory(gpu_device: Option<usize>) -> Option<(u64, MemorySource)> {
+    if let Some(device) = gpu_device {
+        if let Some(bytes) = free_gpu_memory(device) {
+            return Some((bytes, MemorySource::Gpu(device)));
+        }
+    }
+    free_system_memory().map(|bytes| (bytes, MemorySource::System))
+}
+
+/// The largest size expected to fit in `memory` bytes, with the smallest
+/// as the last resort
+pub fn select_model(memory: u64) -> ModelSize {
+    AUTO_MODELS
+        .into_iter()
+        .find(|size| size.memory_bytes() <= memory)
+        .unwrap_or(ModelSize::Tiny)
+}
+
+/// Probe free memory for a run on `gpu_device` (`None` for CPU) and pick a
+/// size, logging the decision
+pub fn select_model_for(gpu_device: Option<usize>) -> ModelSize {
+    match available_memory(gpu_device) {
+        Some((memory, source)) => {
+            let size = select_model(memory);
+            tracing::info!(model = %size, free_mb = memory / 1_000_000, ?source, "Picked the Whisper model by free memory");
+            size
+        }
+        None => {
+            tracing::warn!(model = %FALLBACK_MODEL, "Unable to determine free memory, using the default model");
+            FALLBACK_MODEL
+        }
+    }
+}
+
+/// Free memory on a CUDA device, as `nvidia-smi` reports it
+fn free_gpu_memory(device: usize) -> Option<u64> {
+    let output = Command::new("nvidia-smi")
+        .args(["--query-gpu=memory.free", "--format=csv,noheader,nounits", "-i", &device.to_string()])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let mebibytes: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(mebibytes * 1024 * 1024)
+}
+
+#[cfg(target_os = "linux")]
+fn free_system_memory() -> Option<u64> {
+    parse_meminfo(&std::fs::read_to_string("/proc/meminfo").ok()?)
+}
+
+/// macOS keeps most memory in caches it gives up on demand, so all of it
+/// counts
+#[cfg(target_os = "macos")]
+fn free_system_memory() -> Option<u64> {
+    let output = Command::new("sysctl").args(["-n", "hw.memsize"]).output().ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn free_system_memory() -> Option<u64> {
+    None
+}
+
+/// `MemAvailable` from `/proc/meminfo`, in bytes
+#[cfg(any(target_os = "linux", test))]
+fn parse_meminfo(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let kibibytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kibibytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_largest_model_that_fits() {
+        assert_eq!(select_model(64_000_000_000), ModelSize::LargeV3);
+        assert_eq!(select_model(3_000_000_000), ModelSize::LargeV3Turbo);
+        assert_eq!(select_model(1_000_000_000), ModelSize::Small);
+        assert_eq!(select_model(100_000_000), ModelSize::Tiny);
+    }
+
+    #[test]
+    fn test_fallback_ladder() {
+        let ladder: Vec<ModelSize> = std::iter::successors(Some(ModelSize::LargeV3), ModelSize::smaller).collect();
+        assert_eq!(ladder, AUTO_MODELS);
+        assert_eq!(ModelSize::Medium.smaller(), Some(ModelSize::Small));
+        assert_eq!(ModelSize::MediumEn.smaller(), Some(ModelSize::Small));
+        assert_eq!(ModelSize::Tiny.smaller(), None);
+    }
+
+    #[test]
+    fn test_meminfo() {
+        let meminfo = "MemTotal:       16318452 kB\nMemFree:         1021344 kB\nMemAvailable:    9388044 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some(9_388_044 * 1024));
+        assert_eq!(parse_meminfo("MemTotal: 1 kB\n"), None);
+    }
+}
@@ -0,0 +1,210 @@
+//! SHA-256 manifest of the model cache.
+//!
+//! Every downloaded model is recorded in `SHA256SUMS` in the cache
+//! directory, in the format `sha256sum -c` reads. A model file that gets
+//! corrupted later, by a full disk or a bad sector, is then found by
+//! `models verify` instead of showing up as a crash or garbage output.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use crate::core::model::provider::model_path;
+use crate::core::model::ModelKind;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// SHA-256 of the files the downloader fetches, by file name. A download
+/// that doesn't match is deleted, so a tampered or truncated file never
+/// reaches the cache. An entry changes only together with its URL, and is
+/// keyed by the URL's file name. The Silero VAD model and the sherpa-onnx
+/// files (`ggml-silero-v5.1.2.bin`,
+/// `sherpa-onnx-pyannote-segmentation-3-0.tar.bz2` and
+/// `3dspeaker_speech_eres2net_base_sv_zh-cn_3dspeaker_16k.onnx`) are not
+/// pinned yet and download with a warning.
+#[cfg(any(feature = "download", test))]
+const PINNED_SHA256: &[(&str, &str)] = &[
+    ("ggml-tiny.bin", "be07e048e1e599ad46341c8d2a135645097a538221678b7acdd1b1919c6e1b21"),
+    ("ggml-tiny.en.bin", "921e4cf8686fdd993dcd081a5da5b6c365bfde1162e72b08d75ac75289920b1f"),
+    ("ggml-base.bin", "60ed5bc3dd14eea856493d334349b405782ddcaf0028d4b5df4088345fba2efe"),
+    ("ggml-base.en.bin", "a03779c86df3323075f5e796cb2ce5029f00ec8869eee3fdfb897afe36c6d002"),
+    ("ggml-small.bin", "1be3a9b2063867b937e64e2ec7483364a79917e157fa98c5d94b5c1fffea987b"),
+    ("ggml-small.en.bin", "c6138d6d58ecc8322097e0f987c32f1be8bb0a18532a3f88f734d1bbf9c41e5d"),
+    ("ggml-medium.bin", "6c14d5adee5f86394037b4e4e8b59f1673b6cee10e3cf0b11bbdbee79c156208"),
+    ("ggml-medium.en.bin", "cc37e93478338ec7700281a7ac30a10128929eb8f427dda2e865faa8f6da4356"),
+    ("ggml-large-v2.bin", "9a423fe4d40c82774b6af34115b8b935f34152246eb19e80e376071d3f999487"),
+    ("ggml-large-v3.bin", "64d182b440b98d5203c4f9bd541544d84c605196c4f7b845dfa11fb23594d1e2"),
+    ("ggml-large-v3-turbo.bin", "1fc70f774d38eb169993ac391eea357ef47c88757ef72ee5943879b7e8e2bc69"),
+];
+
+/// The pinned SHA-256 of a file the downloader fetches, if it has one
+#[cfg(any(feature = "download", test))]
+pub(super) fn pinned_sha256(file_name: &str) -> Option<&'static str> {
+    PINNED_SHA256.iter().find(|(name, _)| *name == file_name).map(|(_, hash)| *hash)
+}
+
+/// Manifest file in the cache directory
+pub(super) const MANIFEST_FILE: &str = "SHA256SUMS";
+
+/// Expected hashes of the files in a model cache
+pub(super) struct ChecksumManifest {
+    root: PathBuf,
+    entries: BTreeMap<String, String>,  // Path relative to `root`, with `/` separators -> hex SHA-256
+}
+
+impl ChecksumManifest {
+    /// Load the manifest of `cache_dir`; a cache without one has no entries
+    pub(super) fn load(cache_dir: &Path) -> Result<Self> {
+        let path = cache_dir.join(MANIFEST_FILE);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(source) => return Err(AudioTranscriptionError::File { path, source }),
+        };
+
+        let entries = text
+            .lines()
+            .filter_map(|line| line.split_once("  "))
+            .map(|(hash, file)| (file.to_string(), hash.to_string()))
+            .collect();
+        Ok(Self { root: cache_dir.to_path_buf(), entries })
+    }
+
+    pub(super) fn expected(&self, file: &Path) -> Option<&str> {
+        self.entries.get(&self.relative(file)).map(String::as_str)
+    }
+
+    /// Record the hash of `file` and save the manifest
+    #[cfg(any(feature = "download", test))]
+    pub(super) fn record(&mut self, file: &Path, sha256: &str) -> Result<()> {
+        self.entries.insert(self.relative(file), sha256.to_string());
+        self.save()
+    }
+
+    /// Drop the entry of a deleted file and save the manifest
+    pub(super) fn forget(&mut self, file: &Path) -> Result<()> {
+        if self.entries.remove(&self.relative(file)).is_none() {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        let text: String = self.entries.iter().map(|(file, hash)| format!("{}  {}\n", hash, file)).collect();
+        let path = self.root.join(MANIFEST_FILE);
+        std::fs::write(&path, text).map_err(|source| AudioTranscriptionError::File { path, source })
+    }
+
+    fn relative(&self, file: &Path) -> String {
+        let relative = file.strip_prefix(&self.root).unwrap_or(file);
+        relative.iter().map(|part| part.to_string_lossy()).collect::<Vec<_>>().join("/")
+    }
+}
+
+/// SHA-256 of the file's contents, as lowercase hex
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let file_error = |source| AudioTranscriptionError::File { path: path.to_path_buf(), source };
+    let mut file = std::fs::File::open(path).map_err(file_error)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(file_error)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+pub(super) fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Outcome of checking one model file against the manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    Ok,
+    Mismatch { expected: String, actual: String },
+    Missing,     // Recorded, but the file is gone; it is downloaded again when needed
+    Unrecorded,  // Downloaded before hashes were recorded, so it can't be checked
+}
+
+/// A model file in the cache and how it compares to the manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumCheck {
+    pub path: PathBuf,
+    pub status: ChecksumStatus,
+}
+
+/// Check every recorded file in `cache_dir` against its hash, and list the
+/// model files that have none
+pub(super) fn verify_cache(cache_dir: &Path) -> Result<Vec<ChecksumCheck>> {
+    let manifest = ChecksumManifest::load(cache_dir)?;
+    let mut checks = Vec::new();
+    for (file, expected) in &manifest.entries {
+        let path = cache_dir.join(file);
+        let status = if !path.is_file() {
+            ChecksumStatus::Missing
+        } else {
+            let actual = sha256_file(&path)?;
+            if actual == *expected {
+                ChecksumStatus::Ok
+            } else {
+                ChecksumStatus::Mismatch { expected: expected.clone(), actual }
+            }
+        };
+        checks.push(ChecksumCheck { path, status });
+    }
+
+    for path in ModelKind::all().iter().map(|model| model_path(cache_dir, model)) {
+        if path.is_file() && manifest.expected(&path).is_none() {
+            checks.push(ChecksumCheck { path, status: ChecksumStatus::Unrecorded });
+        }
+    }
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::model::{download, ModelSize};
+
+    #[test]
+    fn test_manifest_round_trip_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let vad = download::get_vad_model_path(dir.path());
+        let whisper = download::get_whisper_model_path(dir.path(), &ModelSize::Tiny);
+        let embedding = download::get_speaker_embedding_model_path(dir.path());
+        for path in [&vad, &whisper, &embedding] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"weights").unwrap();
+        }
+
+        let mut manifest = ChecksumManifest::load(dir.path()).unwrap();
+        manifest.record(&vad, &sha256_file(&vad).unwrap()).unwrap();
+        manifest.record(&whisper, &sha256_file(&whisper).unwrap()).unwrap();
+        let text = std::fs::read_to_string(dir.path().join(MANIFEST_FILE)).unwrap();
+        assert!(text.contains("  vad/ggml-silero-v5.1.2.bin\n"));
+        let hash = sha256_file(&vad).unwrap();
+        assert_eq!(ChecksumManifest::load(dir.path()).unwrap().expected(&vad), Some(hash.as_str()));
+
+        std::fs::write(&whisper, b"weigh").unwrap();
+        let checks = verify_cache(dir.path()).unwrap();
+        let status = |path: &Path| checks.iter().find(|check| check.path == path).map(|check| check.status.clone());
+        assert_eq!(status(&vad), Some(ChecksumStatus::Ok));
+        assert!(matches!(status(&whisper), Some(ChecksumStatus::Mismatch { .. })));
+        assert_eq!(status(&embedding), Some(ChecksumStatus::Unrecorded));
+
+        std::fs::remove_file(&vad).unwrap();
+        assert_eq!(verify_cache(dir.path()).unwrap()[0].status, ChecksumStatus::Missing);
+    }
+
+    #[test]
+    fn test_every_whisper_model_is_pinned() {
+        for size in ModelSize::ALL {
+            let hash = pinned_sha256(&format!("ggml-{}.bin", size)).unwrap();
+            assert!(hash.len() == 64 && hash.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')));
+        }
+        assert_eq!(pinned_sha256("model.onnx"), None);
+    }
+}
@@ -1,28 +1,40 @@
-use std::path::PathBuf;
-use crate::error::{Result, AudioTranscriptionError};
+use std::path::{Path, PathBuf};
 use crate::core::model::ModelSize;
-use reqwest;
+#[cfg(feature = "download")]
+use crate::error::{Result, AudioTranscriptionError};
+#[cfg(feature = "download")]
 use futures_util::StreamExt;
-use std::io::Write;
-use chrono;
+#[cfg(feature = "download")]
+use std::io::{Seek, SeekFrom, Write};
+use std::time::Duration;
+#[cfg(feature = "download")]
+use indicatif::{ProgressBar, ProgressStyle};
+#[cfg(feature = "download")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "download")]
+use crate::core::model::checksum::{self, ChecksumManifest};
+#[cfg(feature = "download")]
+use crate::core::workdir;
+#[cfg(feature = "download")]
+use fs4::FileExt;
 
 /// Get the full path to a whisper model file
-fn get_whisper_model_path(cache_dir: &PathBuf, size: &ModelSize) -> PathBuf {
+pub(super) fn get_whisper_model_path(cache_dir: &Path, size: &ModelSize) -> PathBuf {
     cache_dir.join("whisper").join(size.to_string()).join(format!("ggml-{}.bin", size))
 }
 
 /// Get the pyannote model directory
-fn get_pyannote_model_dir(cache_dir: &PathBuf) -> PathBuf {
+pub(super) fn get_pyannote_model_dir(cache_dir: &Path) -> PathBuf {
     cache_dir.join("pyannote")
 }
 
 /// Get the full path to the pyannote setup marker file
-fn get_pyannote_model_path(cache_dir: &PathBuf) -> PathBuf {
+pub(super) fn get_pyannote_model_path(cache_dir: &Path) -> PathBuf {
     get_pyannote_model_dir(cache_dir).join("setup_complete.txt")
 }
 
 /// Get the full path to the pyannote segmentation model
-fn get_pyannote_segmentation_model_path(cache_dir: &PathBuf) -> PathBuf {
+pub(super) fn get_pyannote_segmentation_model_path(cache_dir: &Path) -> PathBuf {
     // The segmentation model extracts to a subdirectory with the same name as the archive
     get_pyannote_model_dir(cache_dir)
         .join("sherpa-onnx-pyannote-segmentation-3-0")
@@ -30,59 +42,283 @@ fn get_pyannote_segmentation_model_path(cache_dir: &PathBuf) -> PathBuf {
 }
 
 /// Get the full path to the speaker embedding model
-fn get_speaker_embedding_model_path(cache_dir: &PathBuf) -> PathBuf {
+pub(super) fn get_speaker_embedding_model_path(cache_dir: &Path) -> PathBuf {
     get_pyannote_model_dir(cache_dir).join("3dspeaker_speech_eres2net_base_sv_zh-cn_3dspeaker_16k.onnx")
 }
 
-/// Download a model file from the given URL to the specified path
-pub async fn download_model(url: &str, destination: &PathBuf) -> Result<()> {
+/// Get the full path to the Silero VAD model
+pub(super) fn get_vad_model_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join("vad").join("ggml-silero-v5.1.2.bin")
+}
+
+/// Room for the Silero VAD model, which is under a megabyte
+#[cfg(feature = "download")]
+pub(super) const VAD_DOWNLOAD_BYTES: u64 = 1_000_000;
+
+/// Room for the diarization models: the segmentation archive, what it
+/// extracts to, and the embedding model
+#[cfg(feature = "download")]
+pub(super) const DIARIZATION_DOWNLOAD_BYTES: u64 = 60_000_000;
+
+/// Fail with [`AudioTranscriptionError::InsufficientDiskSpace`] before
+/// downloading `bytes` into the cache, rather than partway through
+#[cfg(feature = "download")]
+pub(super) fn ensure_space_for_download(cache_dir: &Path, bytes: u64) -> Result<()> {
+    workdir::ensure_free_space(cache_dir, bytes)
+}
+
+/// Extra attempts for an interrupted model download
+pub const DEFAULT_DOWNLOAD_RETRIES: u32 = 4;
+
+/// How interrupted model downloads are retried. Each retry waits twice as
+/// long as the one before, and continues where the download stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadRetry {
+    pub retries: u32,               // Extra attempts after the first
+    pub initial_backoff: Duration,  // Pause before the first retry
+    pub max_backoff: Duration,      // Longest pause between attempts
+}
+
+impl Default for DownloadRetry {
+    fn default() -> Self {
+        Self {
+            retries: DEFAULT_DOWNLOAD_RETRIES,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl DownloadRetry {
+    /// Pause before retry number `retry`, counting from 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Lock file in the cache directory, held while models are downloaded
+pub(super) const LOCK_FILE: &str = ".download.lock";
+
+/// Exclusive hold on a model cache for downloading into it. Another process
+/// that wants to download waits until it is dropped, then finds the models
+/// already there instead of writing to the same files.
+#[cfg(feature = "download")]
+pub(super) struct CacheLock {
+    _file: std::fs::File,  // Unlocked when closed
+}
+
+#[cfg(feature = "download")]
+impl CacheLock {
+    /// Lock `cache_dir`, waiting for another process that holds it
+    pub(super) async fn acquire(cache_dir: &Path) -> Result<Self> {
+        let path = cache_dir.join(LOCK_FILE);
+        let file_error = |source| AudioTranscriptionError::File { path: path.clone(), source };
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(file_error)?;
+        if file.try_lock_exclusive().is_ok() {
+            return Ok(Self { _file: file });
+        }
+
+        eprintln!("⏳ Another process is downloading models, waiting for it to finish...");
+        tracing::info!(path = %path.display(), "Waiting for the model cache lock");
+        let file = tokio::task::spawn_blocking(move || file.lock_exclusive().map(|()| file))
+            .await
+            .map_err(|e| AudioTranscriptionError::Io(std::io::Error::other(e)))?
+            .map_err(file_error)?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Download a model file from the given URL to the specified path and
+/// return its SHA-256. Transient failures are retried as `retry` says,
+/// resuming from the bytes already received. The data goes to a `.part`
+/// file next to `destination`, which is renamed once it is complete, so an
+/// interrupted download never looks like a model. A file whose hash
+/// differs from the one pinned for the URL's file name is deleted again.
+#[cfg(feature = "download")]
+pub async fn download_model(url: &str, destination: &PathBuf, retry: &DownloadRetry) -> Result<String> {
+    // Archives are saved under another name, so look the pin up by the URL
+    let name = url.rsplit('/').next().unwrap_or(url).to_string();
+    let pinned = checksum::pinned_sha256(&name);
+    if pinned.is_none() {
+        eprintln!("⚠️  No pinned SHA-256 for {}; it is recorded but can't be verified", name);
+        tracing::warn!(url, file = %name, "Downloading a file without a pinned checksum");
+    }
+
     // Create parent directory if it doesn't exist
     if let Some(parent) = destination.parent() {
         std::fs::create_dir_all(parent)
-            .map_err(|e| AudioTranscriptionError::Io(e))?;
+            .map_err(AudioTranscriptionError::Io)?;
     }
 
-    // Create HTTP client and start the download
+    // Create HTTP client and the file being written
     let client = reqwest::Client::new();
-    let request = client.get(url);
-    let response = request.send().await?;
-    
-    if !response.status().is_success() {
-        return Err(AudioTranscriptionError::Network(
-            reqwest::Error::from(response.error_for_status().unwrap_err())
-        ));
-    }
-
-    // Create the destination file
-    let mut file = std::fs::File::create(destination)
-        .map_err(|e| AudioTranscriptionError::Io(e))?;
+    let part = part_path(destination);
+    let file = std::fs::File::create(&part)
+        .map_err(AudioTranscriptionError::Io)?;
 
-    // Stream the response body to the file
-    let mut stream = response.bytes_stream();
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk?;
-        file.write_all(&chunk)
-            .map_err(|e| AudioTranscriptionError::Io(e))?;
+    let mut download = PartialDownload { file, hasher: Sha256::new(), received: 0, bar: None };
+    let mut retries = 0;
+    loop {
+        match download.fetch(&client, url, destination).await {
+            Ok(()) => break,
+            Err(e) if retries < retry.retries && is_transient(&e) => {
+                retries += 1;
+                let pause = retry.backoff(retries);
+                tracing::warn!(url, retry = retries, received = download.received, error = %e, ?pause, "Download interrupted, retrying");
+                tokio::time::sleep(pause).await;
+            }
+            Err(e) => {
+                if let Some(bar) = &download.bar {
+                    bar.abandon();
+                }
+                return Err(e);
+            }
+        }
+    }
+    if let Some(bar) = &download.bar {
+        bar.finish_and_clear();
     }
 
     // Validate the downloaded file exists and has content
-    let metadata = std::fs::metadata(destination)
-        .map_err(|e| AudioTranscriptionError::Io(e))?;
+    drop(download.file);
+    let metadata = std::fs::metadata(&part)
+        .map_err(AudioTranscriptionError::Io)?;
     
     if metadata.len() == 0 {
-        return Err(AudioTranscriptionError::Model(
-            "Downloaded model file is empty".to_string()
-        ));
+        let _ = std::fs::remove_file(&part);
+        return Err(AudioTranscriptionError::ModelCorrupt {
+            path: destination.clone(),
+            message: "downloaded file is empty".to_string(),
+        });
     }
 
-    Ok(())
+    let sha256 = checksum::to_hex(&download.hasher.finalize());
+    if let Some(expected) = pinned {
+        check_pinned(&part, destination, &sha256, expected)?;
+    }
+    std::fs::rename(&part, destination).map_err(AudioTranscriptionError::Io)?;
+    Ok(sha256)
+}
+
+/// Delete the finished download at `part` unless its hash is `expected`
+#[cfg(feature = "download")]
+fn check_pinned(part: &Path, destination: &Path, sha256: &str, expected: &str) -> Result<()> {
+    if sha256 == expected {
+        return Ok(());
+    }
+    let _ = std::fs::remove_file(part);
+    Err(AudioTranscriptionError::ModelCorrupt {
+        path: destination.to_path_buf(),
+        message: format!("SHA-256 {} of the download does not match the pinned {}", sha256, expected),
+    })
+}
+
+/// Where a download to `destination` is written until it is complete
+#[cfg(feature = "download")]
+fn part_path(destination: &Path) -> PathBuf {
+    let mut name = destination.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    destination.with_file_name(name)
+}
+
+/// A download in progress, kept across retries so the next attempt can
+/// pick up where the last one stopped
+#[cfg(feature = "download")]
+struct PartialDownload {
+    file: std::fs::File,
+    hasher: Sha256,            // Of the bytes received so far
+    received: u64,
+    bar: Option<ProgressBar>,  // Created once the size is known
+}
+
+#[cfg(feature = "download")]
+impl PartialDownload {
+    /// Fetch the rest of the file, from `received` on
+    async fn fetch(&mut self, client: &reqwest::Client, url: &str, destination: &Path) -> Result<()> {
+        let mut request = client.get(url);
+        if self.received > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", self.received));
+        }
+        let response = request.send().await?.error_for_status()?;
+
+        // A server that ignores the range sends the whole file again
+        if self.received > 0 && response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            tracing::info!(url, "Server can't resume downloads, starting over");
+            self.file.set_len(0).map_err(AudioTranscriptionError::Io)?;
+            self.file.seek(SeekFrom::Start(0)).map_err(AudioTranscriptionError::Io)?;
+            self.hasher = Sha256::new();
+            self.received = 0;
+        }
+        let received = self.received;
+        let bar = self.bar.get_or_insert_with(|| {
+            download_bar(response.content_length().map(|length| length + received), destination)
+        });
+        bar.set_position(received);
+
+        // Stream the response body to the file
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            self.file.write_all(&chunk).map_err(AudioTranscriptionError::Io)?;
+            self.hasher.update(&chunk);
+            self.received += chunk.len() as u64;
+            bar.inc(chunk.len() as u64);
+        }
+        Ok(())
+    }
+}
+
+/// Whether trying again could help: dropped connections, timeouts and
+/// server errors, but not a missing file or a full disk
+#[cfg(feature = "download")]
+fn is_transient(error: &AudioTranscriptionError) -> bool {
+    match error {
+        AudioTranscriptionError::Network(e) => e.status().is_none_or(|status| {
+            status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        }),
+        _ => false,
+    }
+}
+
+/// Remember a downloaded model's hash for `models verify`
+#[cfg(feature = "download")]
+fn record_checksum(cache_dir: &Path, path: &Path, sha256: &str) -> Result<()> {
+    ChecksumManifest::load(cache_dir)?.record(path, sha256)
+}
+
+/// Progress bar for a download of `length` bytes, with throughput and time
+/// left, or a spinner when the server doesn't say how large the file is.
+/// Drawn on stderr, and hidden when that isn't a terminal.
+#[cfg(feature = "download")]
+fn download_bar(length: Option<u64>, destination: &Path) -> ProgressBar {
+    let (bar, template) = match length {
+        Some(length) => (
+            ProgressBar::new(length),
+            "  {msg} [{bar:30}] {bytes}/{total_bytes} {bytes_per_sec}, {eta} left",
+        ),
+        None => (ProgressBar::new_spinner(), "  {spinner} {msg} {bytes} {bytes_per_sec}"),
+    };
+    if let Ok(style) = ProgressStyle::with_template(template) {
+        bar.set_style(style.progress_chars("=> "));
+    }
+    bar.set_message(destination.file_name().unwrap_or_default().to_string_lossy().into_owned());
+    bar.enable_steady_tick(std::time::Duration::from_millis(200));
+    bar
 }
 
 /// Download the Whisper transcription model for the specified size
-pub async fn download_transcription_model(cache_dir: &PathBuf, model_size: &ModelSize) -> Result<()> {
+#[cfg(feature = "download")]
+pub async fn download_transcription_model(cache_dir: &Path, model_size: &ModelSize, retry: &DownloadRetry) -> Result<()> {
     let model_path = get_whisper_model_path(cache_dir, model_size);
-    
-    println!("Downloading Whisper {} model...", model_size);
+    ensure_space_for_download(cache_dir, model_size.download_bytes())?;
+
+    eprintln!("Downloading Whisper {} model...", model_size);
     
     // Construct the download URL for whisper model
     // Using the official whisper.cpp model repository
@@ -91,13 +327,38 @@ pub async fn download_transcription_model(cache_dir: &PathBuf, model_size: &Mode
         model_size
     );
     
-    match download_model(&whisper_url, &model_path).await {
-        Ok(_) => {
-            println!("✅ Whisper {} model downloaded successfully", model_size);
+    match download_model(&whisper_url, &model_path, retry).await {
+        Ok(sha256) => {
+            record_checksum(cache_dir, &model_path, &sha256)?;
+            eprintln!("✅ Whisper {} model downloaded successfully", model_size);
             Ok(())
         }
         Err(e) => {
-            println!("❌ Failed to download Whisper model: {}", e);
+            eprintln!("❌ Failed to download Whisper model: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Download the Silero voice activity detection model in whisper.cpp's
+/// ggml format
+#[cfg(feature = "download")]
+pub async fn download_vad_model(cache_dir: &Path, retry: &DownloadRetry) -> Result<()> {
+    let model_path = get_vad_model_path(cache_dir);
+    ensure_space_for_download(cache_dir, VAD_DOWNLOAD_BYTES)?;
+
+    eprintln!("Downloading Silero VAD model...");
+
+    let vad_url = "https://huggingface.co/ggml-org/whisper-vad/resolve/main/ggml-silero-v5.1.2.bin";
+
+    match download_model(vad_url, &model_path, retry).await {
+        Ok(sha256) => {
+            record_checksum(cache_dir, &model_path, &sha256)?;
+            eprintln!("✅ Silero VAD model downloaded successfully");
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to download Silero VAD model: {}", e);
             Err(e)
         }
     }
@@ -105,38 +366,37 @@ pub async fn download_transcription_model(cache_dir: &PathBuf, model_size: &Mode
 
 /// Download and setup the sherpa-onnx diarization models
 /// Downloads ONNX models for speaker segmentation and embedding extraction
-pub async fn download_diarization_model(cache_dir: &PathBuf, _unused_token: &str) -> Result<()> {
-    println!("Setting up sherpa-onnx speaker diarization models...");
+#[cfg(feature = "download")]
+pub async fn download_diarization_model(cache_dir: &Path, _unused_token: &str, retry: &DownloadRetry) -> Result<()> {
+    ensure_space_for_download(cache_dir, DIARIZATION_DOWNLOAD_BYTES)?;
+    eprintln!("Setting up sherpa-onnx speaker diarization models...");
     
     // Download pyannote segmentation model (sherpa-onnx format)
     let segmentation_model_path = get_pyannote_segmentation_model_path(cache_dir);
     let segmentation_url = "https://github.com/k2-fsa/sherpa-onnx/releases/download/speaker-segmentation-models/sherpa-onnx-pyannote-segmentation-3-0.tar.bz2";
     
-    println!("  📥 Downloading pyannote segmentation model...");
+    eprintln!("  📥 Downloading pyannote segmentation model...");
     
     // Create a temporary file for the compressed model
     let temp_dir = std::env::temp_dir();
     let temp_file = temp_dir.join("pyannote-segmentation.tar.bz2");
     
     // Download the compressed model
-    match download_model(segmentation_url, &temp_file).await {
+    match download_model(segmentation_url, &temp_file, retry).await {
         Ok(_) => {
-            println!("  ✅ Segmentation model downloaded");
+            eprintln!("  ✅ Segmentation model downloaded");
             
             // Extract the model
-            println!("  📦 Extracting segmentation model...");
-            if let Err(e) = extract_tar_bz2(&temp_file, &get_pyannote_model_dir(cache_dir)).await {
-                return Err(AudioTranscriptionError::Model(
-                    format!("Failed to extract segmentation model: {}", e)
-                ));
-            }
+            eprintln!("  📦 Extracting segmentation model...");
+            extract_tar_bz2(&temp_file, &get_pyannote_model_dir(cache_dir)).await?;
             
             // Clean up temp file
             let _ = std::fs::remove_file(&temp_file);
-            println!("  ✅ Segmentation model extracted successfully");
+            record_checksum(cache_dir, &segmentation_model_path, &checksum::sha256_file(&segmentation_model_path)?)?;
+            eprintln!("  ✅ Segmentation model extracted successfully");
         }
         Err(e) => {
-            println!("  ❌ Failed to download segmentation model: {}", e);
+            eprintln!("  ❌ Failed to download segmentation model: {}", e);
             return Err(e);
         }
     }
@@ -145,14 +405,15 @@ pub async fn download_diarization_model(cache_dir: &PathBuf, _unused_token: &str
     let embedding_model_path = get_speaker_embedding_model_path(cache_dir);
     let embedding_url = "https://github.com/k2-fsa/sherpa-onnx/releases/download/speaker-recongition-models/3dspeaker_speech_eres2net_base_sv_zh-cn_3dspeaker_16k.onnx";
     
-    println!("  📥 Downloading speaker embedding model...");
+    eprintln!("  📥 Downloading speaker embedding model...");
     
-    match download_model(embedding_url, &embedding_model_path).await {
-        Ok(_) => {
-            println!("  ✅ Speaker embedding model downloaded successfully");
+    match download_model(embedding_url, &embedding_model_path, retry).await {
+        Ok(sha256) => {
+            record_checksum(cache_dir, &embedding_model_path, &sha256)?;
+            eprintln!("  ✅ Speaker embedding model downloaded successfully");
         }
         Err(e) => {
-            println!("  ❌ Failed to download embedding model: {}", e);
+            eprintln!("  ❌ Failed to download embedding model: {}", e);
             return Err(e);
         }
     }
@@ -172,36 +433,39 @@ pub async fn download_diarization_model(cache_dir: &PathBuf, _unused_token: &str
         embedding_model_path.display()
     )).map_err(|e| AudioTranscriptionError::Io(e))?;
     
-    println!("✅ Sherpa-ONNX diarization models setup completed successfully");
+    eprintln!("✅ Sherpa-ONNX diarization models setup completed successfully");
     
     Ok(())
 }
 
-/// Extract a tar.bz2 file to the specified directory
+/// Extract a tar.bz2 file to the specified directory, in-process so no
+/// `tar` or `bzip2` binary is needed
+#[cfg(feature = "download")]
 async fn extract_tar_bz2(archive_path: &PathBuf, extract_to: &PathBuf) -> Result<()> {
-    use std::process::Command;
-    
     // Create the extraction directory
     std::fs::create_dir_all(extract_to)
-        .map_err(|e| AudioTranscriptionError::Io(e))?;
-    
-    // Use tar command to extract
-    let output = Command::new("tar")
-        .args(&["-xjf", &archive_path.to_string_lossy(), "-C", &extract_to.to_string_lossy()])
-        .output()
-        .map_err(|e| AudioTranscriptionError::Io(e))?;
-    
-    if !output.status.success() {
-        return Err(AudioTranscriptionError::Model(
-            format!("Failed to extract archive: {}", String::from_utf8_lossy(&output.stderr))
-        ));
-    }
-    
-    Ok(())
+        .map_err(AudioTranscriptionError::Io)?;
+
+    // Decompressing takes a few seconds; keep it off the async runtime
+    let (archive, destination) = (archive_path.clone(), extract_to.clone());
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(&archive).map_err(AudioTranscriptionError::Io)?;
+        tar::Archive::new(bzip2::read::BzDecoder::new(std::io::BufReader::new(file)))
+            .unpack(&destination)
+            .map_err(|e| AudioTranscriptionError::ModelCorrupt {
+                path: archive.clone(),
+                message: format!("failed to extract archive: {}", e),
+            })
+    })
+    .await
+    .map_err(|e| AudioTranscriptionError::ModelCorrupt {
+        path: archive_path.clone(),
+        message: format!("failed to extract archive: {}", e),
+    })?
 }
 
 /// Check if a transcription model is available
-pub fn is_transcription_model_available(cache_dir: &PathBuf, model_size: &ModelSize) -> bool {
+pub fn is_transcription_model_available(cache_dir: &Path, model_size: &ModelSize) -> bool {
     let model_path = get_whisper_model_path(cache_dir, model_size);
     model_path.exists() && 
     std::fs::metadata(&model_path)
@@ -209,8 +473,15 @@ pub fn is_transcription_model_available(cache_dir: &PathBuf, model_size: &ModelS
         .unwrap_or(false)
 }
 
+/// Check if the Silero VAD model is available
+pub fn is_vad_model_available(cache_dir: &Path) -> bool {
+    std::fs::metadata(get_vad_model_path(cache_dir))
+        .map(|m| m.is_file() && m.len() > 0)
+        .unwrap_or(false)
+}
+
 /// Check if diarization model setup is complete
-pub fn is_diarization_model_available(cache_dir: &PathBuf) -> bool {
+pub fn is_diarization_model_available(cache_dir: &Path) -> bool {
     // Check if both required ONNX model files exist
     let segmentation_model = get_pyannote_segmentation_model_path(cache_dir);
     let embedding_model = get_speaker_embedding_model_path(cache_dir);
@@ -222,4 +493,89 @@ pub fn is_diarization_model_available(cache_dir: &PathBuf) -> bool {
     std::fs::metadata(&embedding_model)
         .map(|m| m.len() > 0)
         .unwrap_or(false)
-}
\ No newline at end of file
+}
+#[cfg(all(test, feature = "download"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_bar_falls_back_to_a_spinner() {
+        let destination = Path::new("/cache/whisper/medium/ggml-medium.bin");
+        let bar = download_bar(Some(1_533_763_059), destination);
+        assert_eq!(bar.length(), Some(1_533_763_059));
+        assert_eq!(bar.message(), "ggml-medium.bin");
+        assert_eq!(download_bar(None, destination).length(), None);
+    }
+
+    #[tokio::test]
+    async fn test_extract_tar_bz2() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("models.tar.bz2");
+        let encoder = bzip2::write::BzEncoder::new(std::fs::File::create(&archive_path).unwrap(), bzip2::Compression::fast());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(7);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "sherpa-onnx-pyannote-segmentation-3-0/model.onnx", &b"weights"[..]).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        extract_tar_bz2(&archive_path, &get_pyannote_model_dir(dir.path())).await.unwrap();
+        assert_eq!(std::fs::read(get_pyannote_segmentation_model_path(dir.path())).unwrap(), b"weights");
+
+        std::fs::write(&archive_path, b"not an archive").unwrap();
+        let error = extract_tar_bz2(&archive_path, &dir.path().join("out")).await.unwrap_err();
+        assert_eq!(error.code(), 32);
+    }
+
+    #[tokio::test]
+    async fn test_cache_lock_waits_for_the_holder() {
+        let dir = tempfile::tempdir().unwrap();
+        let held = CacheLock::acquire(dir.path()).await.unwrap();
+        let waiting = tokio::spawn({
+            let dir = dir.path().to_path_buf();
+            async move { CacheLock::acquire(&dir).await.map(drop) }
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!waiting.is_finished());
+
+        drop(held);
+        tokio::time::timeout(Duration::from_secs(5), waiting).await.unwrap().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_wrong_archive_hash_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("pyannote-segmentation.tar.bz2");
+        let part = part_path(&archive);
+        std::fs::write(&part, b"truncated archive").unwrap();
+        let sha256 = checksum::sha256_file(&part).unwrap();
+
+        assert!(check_pinned(&part, &archive, &sha256, &sha256).is_ok());
+        let pinned = checksum::pinned_sha256("ggml-tiny.bin").unwrap();
+        let error = check_pinned(&part, &archive, &sha256, pinned).unwrap_err();
+        assert_eq!(error.code(), 32);
+        assert!(!part.exists());
+    }
+
+    #[test]
+    fn test_part_path() {
+        assert_eq!(part_path(Path::new("/cache/vad/ggml-silero-v5.1.2.bin")), Path::new("/cache/vad/ggml-silero-v5.1.2.bin.part"));
+    }
+
+    #[test]
+    fn test_space_is_checked_before_downloading() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(ensure_space_for_download(dir.path(), ModelSize::Tiny.download_bytes()).is_ok());
+        let error = ensure_space_for_download(dir.path(), u64::MAX - ModelSize::LargeV3.download_bytes()).unwrap_err();
+        assert!(matches!(error, AudioTranscriptionError::InsufficientDiskSpace { .. }));
+    }
+
+    #[test]
+    fn test_download_backoff_doubles_up_to_the_limit() {
+        let retry = DownloadRetry::default();
+        let pauses: Vec<u64> = (1..=8).map(|attempt| retry.backoff(attempt).as_secs()).collect();
+        assert_eq!(pauses, [1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(retry.backoff(u32::MAX), retry.max_backoff);
+    }
+}
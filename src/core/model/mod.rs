@@ -1,27 +1,125 @@
 mod model_manager;
 mod download;
+mod provider;
+mod checksum;
+pub mod auto;
 
-pub use model_manager::ModelManager;
+pub use model_manager::{CachedModel, DownloadPolicy, ModelManager, MODEL_DIR_VAR};
+pub use download::{DownloadRetry, DEFAULT_DOWNLOAD_RETRIES};
+pub use checksum::{sha256_file, ChecksumCheck, ChecksumStatus};
+pub use provider::{DirectoryModelProvider, ModelKind, ModelProvider};
 
-use clap::ValueEnum;
-
-#[derive(Clone, ValueEnum, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum ModelSize {
     Tiny,
+    /// English only: faster and more accurate on English speech
+    #[cfg_attr(feature = "cli", value(name = "tiny.en"))]
+    TinyEn,
     Base,
+    #[cfg_attr(feature = "cli", value(name = "base.en"))]
+    BaseEn,
     Small,
+    #[cfg_attr(feature = "cli", value(name = "small.en"))]
+    SmallEn,
     Medium,
-    Large,
+    #[cfg_attr(feature = "cli", value(name = "medium.en"))]
+    MediumEn,
+    LargeV2,
+    /// The newest large model; `large` is an alias
+    #[cfg_attr(feature = "cli", value(alias = "large"))]
+    LargeV3,
+    /// Large v3 with a pruned decoder: much faster, a little less accurate
+    LargeV3Turbo,
+}
+
+impl ModelSize {
+    /// Every size, smallest first
+    pub const ALL: [ModelSize; 11] = [
+        ModelSize::Tiny,
+        ModelSize::TinyEn,
+        ModelSize::Base,
+        ModelSize::BaseEn,
+        ModelSize::Small,
+        ModelSize::SmallEn,
+        ModelSize::Medium,
+        ModelSize::MediumEn,
+        ModelSize::LargeV2,
+        ModelSize::LargeV3,
+        ModelSize::LargeV3Turbo,
+    ];
+
+    /// Whether this is a `.en` model, which only transcribes English
+    pub fn is_english_only(&self) -> bool {
+        matches!(self, ModelSize::TinyEn | ModelSize::BaseEn | ModelSize::SmallEn | ModelSize::MediumEn)
+    }
+
+    /// Size of the ggml model file whisper.cpp publishes, in bytes
+    pub fn download_bytes(&self) -> u64 {
+        match self {
+            ModelSize::Tiny => 77_691_713,
+            ModelSize::TinyEn => 77_704_715,
+            ModelSize::Base => 147_951_465,
+            ModelSize::BaseEn => 147_964_211,
+            ModelSize::Small => 487_601_967,
+            ModelSize::SmallEn => 487_614_201,
+            ModelSize::Medium => 1_533_763_059,
+            ModelSize::MediumEn => 1_533_774_781,
+            ModelSize::LargeV2 => 3_094_623_691,
+            ModelSize::LargeV3 => 3_095_033_483,
+            ModelSize::LargeV3Turbo => 1_624_555_275,
+        }
+    }
+
+    /// Memory whisper.cpp needs to run the model, as its README lists it;
+    /// large-v3-turbo's is estimated from its file size
+    pub fn memory_bytes(&self) -> u64 {
+        match self {
+            ModelSize::Tiny | ModelSize::TinyEn => 273_000_000,
+            ModelSize::Base | ModelSize::BaseEn => 388_000_000,
+            ModelSize::Small | ModelSize::SmallEn => 852_000_000,
+            ModelSize::Medium | ModelSize::MediumEn => 2_100_000_000,
+            ModelSize::LargeV2 | ModelSize::LargeV3 => 3_900_000_000,
+            ModelSize::LargeV3Turbo => 2_200_000_000,
+        }
+    }
+
+    /// The next size down to try when this one doesn't fit in memory
+    pub fn smaller(&self) -> Option<ModelSize> {
+        auto::AUTO_MODELS.into_iter().find(|size| size.memory_bytes() < self.memory_bytes())
+    }
+}
+
+impl std::str::FromStr for ModelSize {
+    type Err = String;
+
+    /// A size by name, case-insensitively; `large` is the newest large model
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let name = name.to_lowercase();
+        if name == "large" {
+            return Ok(ModelSize::LargeV3);
+        }
+        ModelSize::ALL.into_iter().find(|size| size.to_string() == name).ok_or_else(|| {
+            let names: Vec<String> = ModelSize::ALL.iter().map(ModelSize::to_string).collect();
+            format!("Unknown model size: {} (expected one of: {}, large)", name, names.join(", "))
+        })
+    }
 }
 
 impl std::fmt::Display for ModelSize {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ModelSize::Tiny => write!(f, "tiny"),
+            ModelSize::TinyEn => write!(f, "tiny.en"),
             ModelSize::Base => write!(f, "base"),
+            ModelSize::BaseEn => write!(f, "base.en"),
             ModelSize::Small => write!(f, "small"),
+            ModelSize::SmallEn => write!(f, "small.en"),
             ModelSize::Medium => write!(f, "medium"),
-            ModelSize::Large => write!(f, "large"),
+            ModelSize::MediumEn => write!(f, "medium.en"),
+            ModelSize::LargeV2 => write!(f, "large-v2"),
+            ModelSize::LargeV3 => write!(f, "large-v3"),
+            ModelSize::LargeV3Turbo => write!(f, "large-v3-turbo"),
         }
     }
 }
\ No newline at end of file
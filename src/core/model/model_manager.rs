@@ -1,31 +1,170 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+#[cfg(feature = "download")]
 use std::io::Write;
 use crate::error::{Result, AudioTranscriptionError};
-use crate::core::model::ModelSize;
-use crate::core::model::download;
+use crate::core::model::{ModelKind, ModelProvider, ModelSize};
+use crate::core::model::download::{self, DownloadRetry};
+use crate::core::model::checksum::{self, ChecksumCheck, ChecksumManifest};
+use crate::core::speakers;
+
+/// Environment variable that moves the model cache, e.g. to a network share
+pub const MODEL_DIR_VAR: &str = "AUDIO_TRANSCRIBE_MODEL_DIR";
 
 pub struct ModelManager {
     cache_dir: PathBuf,
+    download_policy: DownloadPolicy,
+    download_retry: DownloadRetry,
+}
+
+/// What to do when a model is missing from the cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DownloadPolicy {
+    /// Ask on the terminal before downloading
+    #[default]
+    Ask,
+    /// Download without asking, e.g. in CI or cron jobs
+    Always,
+    /// Never download; a missing model is an error right away
+    Never,
+}
+
+/// A model file in the cache
+#[derive(Debug, Clone)]
+pub struct CachedModel {
+    pub kind: ModelKind,
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub downloaded: Option<SystemTime>,  // When the file was written
+    pub sha256: Option<String>,          // Recorded when it was downloaded
 }
 
 impl ModelManager {
+    /// Use the directory in [`MODEL_DIR_VAR`], or the platform cache
+    /// directory when it isn't set
     pub fn new() -> Result<Self> {
-        let cache_dir = Self::get_cache_directory()?;
-        
+        Self::with_cache_dir(Self::get_cache_directory()?)
+    }
+
+    /// Use a custom cache directory instead of the platform default
+    pub fn with_cache_dir(cache_dir: PathBuf) -> Result<Self> {
         // Create the complete directory structure for model storage
         Self::create_directory_structure(&cache_dir)?;
         
         Ok(Self {
             cache_dir,
+            download_policy: DownloadPolicy::default(),
+            download_retry: DownloadRetry::default(),
         })
     }
 
+    /// Decide whether missing models are downloaded, and whether to ask first
+    pub fn with_download_policy(mut self, policy: DownloadPolicy) -> Self {
+        self.download_policy = policy;
+        self
+    }
+
+    /// How often an interrupted download is retried, and how long to wait
+    pub fn with_download_retry(mut self, retry: DownloadRetry) -> Self {
+        self.download_retry = retry;
+        self
+    }
+
     pub fn cache_dir(&self) -> &PathBuf {
         &self.cache_dir
     }
 
+    pub fn download_policy(&self) -> DownloadPolicy {
+        self.download_policy
+    }
+
+    pub fn download_retry(&self) -> &DownloadRetry {
+        &self.download_retry
+    }
+
+    /// Hash the cached models again and compare them with the hashes
+    /// recorded when they were downloaded
+    pub fn verify_checksums(&self) -> Result<Vec<ChecksumCheck>> {
+        checksum::verify_cache(&self.cache_dir)
+    }
+
+    /// The models in the cache, in the order of [`ModelKind::all`]
+    pub fn cached_models(&self) -> Result<Vec<CachedModel>> {
+        let manifest = ChecksumManifest::load(&self.cache_dir)?;
+        let cached = ModelKind::all().into_iter().filter_map(|kind| {
+            let path = self.resolve(&kind);
+            let metadata = std::fs::metadata(&path).ok().filter(|metadata| metadata.is_file())?;
+            let sha256 = manifest.expected(&path).map(str::to_string);
+            Some(CachedModel { kind, path, bytes: metadata.len(), downloaded: metadata.modified().ok(), sha256 })
+        });
+        Ok(cached.collect())
+    }
+
+    /// Delete a cached model, which is downloaded again when it is next
+    /// needed. Returns the bytes freed.
+    pub fn remove(&self, model: &ModelKind) -> Result<u64> {
+        let path = self.resolve(model);
+        if !path.is_file() {
+            return Err(AudioTranscriptionError::ModelNotFound { model: model.to_string(), path });
+        }
+
+        // The segmentation model is one file of an unpacked archive; the
+        // rest of the archive goes with it
+        let freed = match model {
+            ModelKind::Segmentation => {
+                let dir = path.parent().unwrap_or(&path);
+                let freed = files_below(dir).iter().map(|(_, bytes)| bytes).sum();
+                std::fs::remove_dir_all(dir).map_err(|source| AudioTranscriptionError::File { path: dir.to_path_buf(), source })?;
+                freed
+            }
+            _ => remove_file(&path)?,
+        };
+        ChecksumManifest::load(&self.cache_dir)?.forget(&path)?;
+        tracing::info!(model = %model, path = %path.display(), freed, "Removed cached model");
+        Ok(freed)
+    }
+
+    /// Delete the Whisper models whose size isn't in `keep`, and files in
+    /// the cache that belong to no model, such as interrupted downloads.
+    /// Returns the deleted files with the bytes each freed.
+    pub fn prune(&self, keep: &[ModelSize]) -> Result<Vec<(PathBuf, u64)>> {
+        let mut removed = Vec::new();
+        for size in ModelSize::ALL.into_iter().filter(|size| !keep.contains(size)) {
+            let model = ModelKind::Whisper(size);
+            let path = self.resolve(&model);
+            if path.is_file() {
+                removed.push((path, self.remove(&model)?));
+            }
+        }
+
+        let segmentation_dir = download::get_pyannote_segmentation_model_path(&self.cache_dir)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let known: Vec<PathBuf> = ModelKind::all()
+            .iter()
+            .map(|model| self.resolve(model))
+            .chain([
+                self.cache_dir.join(checksum::MANIFEST_FILE),
+                self.cache_dir.join(download::LOCK_FILE),
+                self.cache_dir.join(speakers::REGISTRY_FILE),
+                download::get_pyannote_model_path(&self.cache_dir),
+            ])
+            .collect();
+        for (path, _) in files_below(&self.cache_dir) {
+            if !known.contains(&path) && !path.starts_with(&segmentation_dir) {
+                let freed = remove_file(&path)?;
+                removed.push((path, freed));
+            }
+        }
+        Ok(removed)
+    }
+
     /// Get platform-specific cache directory for model storage
     fn get_cache_directory() -> Result<PathBuf> {
+        if let Some(dir) = std::env::var_os(MODEL_DIR_VAR).filter(|dir| !dir.is_empty()) {
+            return Ok(PathBuf::from(dir));
+        }
         let cache_dir = dirs::cache_dir()
             .ok_or_else(|| AudioTranscriptionError::Configuration(
                 "Unable to determine cache directory".to_string()
@@ -36,7 +175,8 @@ impl ModelManager {
         Ok(cache_dir)
     }
 
-    /// Check if required models exist and prompt for download if needed
+    /// Check if required models exist and download them if needed, asking
+    /// first unless the download policy says otherwise
     /// Returns Ok(true) if models are available, Ok(false) if user cancelled, Err on error
     pub async fn ensure_models_available(&self, model_size: &ModelSize) -> Result<bool> {
         // Check if transcription model exists
@@ -44,55 +184,134 @@ impl ModelManager {
         
         // Check if diarization model exists
         let diarization_available = download::is_diarization_model_available(&self.cache_dir);
+
+        // Check if the voice activity detection model exists
+        let vad_available = download::is_vad_model_available(&self.cache_dir);
         
-        // If both models are available, we're good to go
-        if transcription_available && diarization_available {
-            log::info!("All required models are available");
+        // If all models are available, we're good to go
+        if transcription_available && diarization_available && vad_available {
+            tracing::info!(model = %model_size, cache_dir = %self.cache_dir.display(), "All required models are available");
             return Ok(true);
         }
-        
+        if self.download_policy == DownloadPolicy::Never {
+            return Err(self.missing_model_error(model_size, transcription_available, diarization_available));
+        }
+
+        self.download_missing_models(model_size, transcription_available, diarization_available, vad_available).await
+    }
+
+    /// Prompt for and download whichever models are missing
+    #[cfg(feature = "download")]
+    async fn download_missing_models(
+        &self,
+        model_size: &ModelSize,
+        transcription_available: bool,
+        diarization_available: bool,
+        vad_available: bool,
+    ) -> Result<bool> {
         // Display which models are missing
-        println!("\n⚠️  Required models are missing:");
+        eprintln!("\n⚠️  Required models are missing:");
         if !transcription_available {
-            println!("   - Whisper {} model", model_size);
+            eprintln!("   - Whisper {} model", model_size);
         }
         if !diarization_available {
-            println!("   - Sherpa-ONNX speaker diarization models (segmentation + embedding)");
+            eprintln!("   - Sherpa-ONNX speaker diarization models (segmentation + embedding)");
         }
-        println!();
+        if !vad_available {
+            eprintln!("   - Silero voice activity detection model");
+        }
+        eprintln!();
+
+        // Fail before asking rather than partway through a large download
+        let needed = [
+            (!transcription_available, model_size.download_bytes()),
+            (!diarization_available, download::DIARIZATION_DOWNLOAD_BYTES),
+            (!vad_available, download::VAD_DOWNLOAD_BYTES),
+        ];
+        download::ensure_space_for_download(&self.cache_dir, needed.iter().filter(|(missing, _)| *missing).map(|(_, bytes)| bytes).sum())?;
         
         // Prompt user for download confirmation
-        println!("Would you like to download the missing models now?");
-        println!("(This is a one-time download and models will be cached for future use)");
-        print!("Download models? [Y/n]: ");
-        std::io::stdout().flush().map_err(|e| AudioTranscriptionError::Io(e))?;
-        
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input).map_err(|e| AudioTranscriptionError::Io(e))?;
-        
-        let input = input.trim().to_lowercase();
-        if input == "n" || input == "no" {
-            return Ok(false);
+        if self.download_policy == DownloadPolicy::Ask {
+            eprintln!("Would you like to download the missing models now?");
+            eprintln!("(This is a one-time download and models will be cached for future use)");
+            eprint!("Download models? [Y/n]: ");
+            std::io::stderr().flush().map_err(|e| AudioTranscriptionError::Io(e))?;
+
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input).map_err(|e| AudioTranscriptionError::Io(e))?;
+
+            let input = input.trim().to_lowercase();
+            if input == "n" || input == "no" {
+                return Ok(false);
+            }
         }
         
+        // Another process may have downloaded them while we waited
+        let _lock = download::CacheLock::acquire(&self.cache_dir).await?;
+        let transcription_available = download::is_transcription_model_available(&self.cache_dir, model_size);
+        let diarization_available = download::is_diarization_model_available(&self.cache_dir);
+        let vad_available = download::is_vad_model_available(&self.cache_dir);
+
         // Download missing models
-        println!("\n📥 Downloading models...");
+        eprintln!("\n📥 Downloading models...");
         
         if !transcription_available {
-            download::download_transcription_model(&self.cache_dir, model_size).await?;
+            download::download_transcription_model(&self.cache_dir, model_size, &self.download_retry).await?;
         }
         
         if !diarization_available {
-            download::download_diarization_model(&self.cache_dir, "").await?;
+            download::download_diarization_model(&self.cache_dir, "", &self.download_retry).await?;
+        }
+
+        if !vad_available {
+            download::download_vad_model(&self.cache_dir, &self.download_retry).await?;
         }
         
-        println!("\n✅ All models downloaded successfully!");
-        println!("Models are cached at: {}", self.cache_dir().display());
+        eprintln!("\n✅ All models downloaded successfully!");
+        eprintln!("Models are cached at: {}", self.cache_dir().display());
         std::thread::sleep(std::time::Duration::from_millis(1500));
         
         Ok(true)
     }
 
+    /// Without the `download` feature missing models are an error; they
+    /// have to be placed in the cache directory beforehand
+    #[cfg(not(feature = "download"))]
+    async fn download_missing_models(
+        &self,
+        model_size: &ModelSize,
+        transcription_available: bool,
+        diarization_available: bool,
+        _vad_available: bool,
+    ) -> Result<bool> {
+        Err(self.missing_model_error(model_size, transcription_available, diarization_available))
+    }
+
+    /// Error naming the first missing model
+    fn missing_model_error(
+        &self,
+        model_size: &ModelSize,
+        transcription_available: bool,
+        diarization_available: bool,
+    ) -> AudioTranscriptionError {
+        if !transcription_available {
+            return AudioTranscriptionError::ModelNotFound {
+                model: format!("Whisper {}", model_size),
+                path: download::get_whisper_model_path(&self.cache_dir, model_size),
+            };
+        }
+        if diarization_available {
+            return AudioTranscriptionError::ModelNotFound {
+                model: "Silero VAD".to_string(),
+                path: download::get_vad_model_path(&self.cache_dir),
+            };
+        }
+        AudioTranscriptionError::ModelNotFound {
+            model: "Speaker diarization".to_string(),
+            path: download::get_pyannote_model_dir(&self.cache_dir),
+        }
+    }
+
     /// Create the complete directory structure for model storage
     fn create_directory_structure(cache_dir: &PathBuf) -> Result<()> {
         // Create main cache directory
@@ -109,7 +328,7 @@ impl ModelManager {
             ))?;
 
         // Create subdirectories for each whisper model size
-        for size in [ModelSize::Tiny, ModelSize::Base, ModelSize::Small, ModelSize::Medium, ModelSize::Large] {
+        for size in ModelSize::ALL {
             let size_dir = whisper_dir.join(size.to_string());
             std::fs::create_dir_all(&size_dir)
                 .map_err(|e| AudioTranscriptionError::Configuration(
@@ -124,6 +343,83 @@ impl ModelManager {
                 format!("Failed to create pyannote directory: {}", e)
             ))?;
 
+        // Create voice activity detection model directory
+        let vad_dir = cache_dir.join("vad");
+        std::fs::create_dir_all(&vad_dir)
+            .map_err(|e| AudioTranscriptionError::Configuration(
+                format!("Failed to create vad directory: {}", e)
+            ))?;
+
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Every file below `dir`, with its size
+fn files_below(dir: &Path) -> Vec<(PathBuf, u64)> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => files.extend(files_below(&path)),
+            Ok(metadata) => files.push((path, metadata.len())),
+            Err(_) => {}
+        }
+    }
+    files
+}
+
+/// Delete a file and return its size
+fn remove_file(path: &Path) -> Result<u64> {
+    let file_error = |source| AudioTranscriptionError::File { path: path.to_path_buf(), source };
+    let bytes = std::fs::metadata(path).map_err(file_error)?.len();
+    std::fs::remove_file(path).map_err(file_error)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_offline_fails_on_missing_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::with_cache_dir(dir.path().to_path_buf()).unwrap().with_download_policy(DownloadPolicy::Never);
+
+        let error = manager.ensure_models_available(&ModelSize::Tiny).await.unwrap_err();
+        assert_eq!(error.code(), 31);
+        assert!(error.to_string().contains("Whisper tiny"));
+        assert_eq!(manager.ensure_available(&ModelKind::Vad).await.unwrap_err().code(), 31);
+    }
+
+    #[test]
+    fn test_remove_and_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::with_cache_dir(dir.path().to_path_buf()).unwrap();
+        let write = |model: &ModelKind| {
+            let path = manager.resolve(model);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, b"weights").unwrap();
+        };
+        for model in [ModelKind::Whisper(ModelSize::Tiny), ModelKind::Whisper(ModelSize::Medium), ModelKind::Segmentation, ModelKind::Vad] {
+            write(&model);
+        }
+        let segmentation_dir = manager.resolve(&ModelKind::Segmentation).parent().unwrap().to_path_buf();
+        std::fs::write(segmentation_dir.join("README.md"), b"notes").unwrap();
+        std::fs::write(dir.path().join("whisper/small/ggml-small.bin.part"), b"half").unwrap();
+        std::fs::write(dir.path().join(speakers::REGISTRY_FILE), b"{}").unwrap();
+
+        let cached: Vec<String> = manager.cached_models().unwrap().iter().map(|model| model.kind.name()).collect();
+        assert_eq!(cached, ["tiny", "medium", "segmentation", "vad"]);
+
+        assert_eq!(manager.remove(&ModelKind::Segmentation).unwrap(), 12);
+        assert!(!segmentation_dir.exists());
+        assert_eq!(manager.remove(&ModelKind::SpeakerEmbedding).unwrap_err().code(), 31);
+
+        let mut pruned: Vec<PathBuf> = manager.prune(&[ModelSize::Medium]).unwrap().into_iter().map(|(path, _)| path).collect();
+        pruned.sort();
+        assert_eq!(pruned, [dir.path().join("whisper/small/ggml-small.bin.part"), dir.path().join("whisper/tiny/ggml-tiny.bin")]);
+        let cached: Vec<String> = manager.cached_models().unwrap().iter().map(|model| model.kind.name()).collect();
+        assert_eq!(cached, ["medium", "vad"]);
+    }
+}
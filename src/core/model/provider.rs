@@ -0,0 +1,210 @@
+use std::fmt;
+use std::str::FromStr;
+use std::path::{Path, PathBuf};
+use futures_util::future::BoxFuture;
+use crate::core::model::{download, ModelManager, ModelSize};
+#[cfg(feature = "download")]
+use crate::core::model::DownloadPolicy;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// A model file the pipeline needs
+#[derive(Debug, Clone)]
+pub enum ModelKind {
+    Whisper(ModelSize),
+    Segmentation,      // pyannote segmentation (sherpa-onnx export)
+    SpeakerEmbedding,  // 3D-Speaker embedding extractor
+    Vad,               // Silero voice activity detection (ggml export)
+}
+
+impl ModelKind {
+    /// Every model the pipeline can use
+    pub fn all() -> Vec<ModelKind> {
+        ModelSize::ALL
+            .into_iter()
+            .map(ModelKind::Whisper)
+            .chain([ModelKind::Segmentation, ModelKind::SpeakerEmbedding, ModelKind::Vad])
+            .collect()
+    }
+
+    /// Name on the command line: the Whisper size, or `segmentation`,
+    /// `embedding` or `vad`
+    pub fn name(&self) -> String {
+        match self {
+            ModelKind::Whisper(size) => size.to_string(),
+            ModelKind::Segmentation => "segmentation".to_string(),
+            ModelKind::SpeakerEmbedding => "embedding".to_string(),
+            ModelKind::Vad => "vad".to_string(),
+        }
+    }
+}
+
+impl FromStr for ModelKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        if let Ok(size) = name.parse() {
+            return Ok(ModelKind::Whisper(size));
+        }
+        ModelKind::all().into_iter().find(|model| model.name() == name).ok_or_else(|| {
+            let names: Vec<String> = ModelKind::all().iter().map(ModelKind::name).collect();
+            format!("unknown model '{}' (expected one of: {})", name, names.join(", "))
+        })
+    }
+}
+
+impl fmt::Display for ModelKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelKind::Whisper(size) => write!(f, "Whisper {}", size),
+            ModelKind::Segmentation => write!(f, "Speaker segmentation"),
+            ModelKind::SpeakerEmbedding => write!(f, "Speaker embedding"),
+            ModelKind::Vad => write!(f, "Silero VAD"),
+        }
+    }
+}
+
+/// Supplies model files to the pipeline.
+///
+/// [`ModelManager`] is the built-in provider that downloads into the user
+/// cache. Implement this to serve models from bundled resources, object
+/// storage or an internal registry instead.
+pub trait ModelProvider: Send + Sync {
+    /// Local path the model is (or will be) stored at
+    fn resolve(&self, model: &ModelKind) -> PathBuf;
+
+    /// Make the model available locally, fetching it if needed, and return
+    /// its path
+    fn ensure_available<'a>(&'a self, model: &'a ModelKind) -> BoxFuture<'a, Result<PathBuf>>;
+
+    /// Check that the local model file is usable
+    fn verify(&self, model: &ModelKind) -> Result<()> {
+        verify_file(model, &self.resolve(model))
+    }
+}
+
+/// Serves models from a directory laid out like the model cache, e.g. one
+/// shipped with an application bundle. Never downloads anything.
+#[derive(Debug, Clone)]
+pub struct DirectoryModelProvider {
+    root: PathBuf,
+}
+
+impl DirectoryModelProvider {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl ModelProvider for DirectoryModelProvider {
+    fn resolve(&self, model: &ModelKind) -> PathBuf {
+        model_path(&self.root, model)
+    }
+
+    fn ensure_available<'a>(&'a self, model: &'a ModelKind) -> BoxFuture<'a, Result<PathBuf>> {
+        Box::pin(async move {
+            self.verify(model)?;
+            Ok(self.resolve(model))
+        })
+    }
+}
+
+impl ModelProvider for ModelManager {
+    fn resolve(&self, model: &ModelKind) -> PathBuf {
+        model_path(self.cache_dir(), model)
+    }
+
+    fn ensure_available<'a>(&'a self, model: &'a ModelKind) -> BoxFuture<'a, Result<PathBuf>> {
+        Box::pin(async move {
+            if self.verify(model).is_err() {
+                self.fetch(model).await?;
+                self.verify(model)?;
+            }
+            Ok(self.resolve(model))
+        })
+    }
+}
+
+impl ModelManager {
+    #[cfg(feature = "download")]
+    async fn fetch(&self, model: &ModelKind) -> Result<()> {
+        if self.download_policy() == DownloadPolicy::Never {
+            return Err(AudioTranscriptionError::ModelNotFound {
+                model: model.to_string(),
+                path: self.resolve(model),
+            });
+        }
+
+        // Another process may have downloaded it while we waited
+        let _lock = download::CacheLock::acquire(self.cache_dir()).await?;
+        if self.verify(model).is_ok() {
+            return Ok(());
+        }
+        match model {
+            ModelKind::Whisper(size) => download::download_transcription_model(self.cache_dir(), size, self.download_retry()).await,
+            ModelKind::Segmentation | ModelKind::SpeakerEmbedding => {
+                download::download_diarization_model(self.cache_dir(), "", self.download_retry()).await
+            }
+            ModelKind::Vad => download::download_vad_model(self.cache_dir(), self.download_retry()).await,
+        }
+    }
+
+    #[cfg(not(feature = "download"))]
+    async fn fetch(&self, model: &ModelKind) -> Result<()> {
+        Err(AudioTranscriptionError::ModelNotFound {
+            model: model.to_string(),
+            path: self.resolve(model),
+        })
+    }
+}
+
+/// Location of a model inside a cache-layout directory
+pub(super) fn model_path(root: &Path, model: &ModelKind) -> PathBuf {
+    match model {
+        ModelKind::Whisper(size) => download::get_whisper_model_path(root, size),
+        ModelKind::Segmentation => download::get_pyannote_segmentation_model_path(root),
+        ModelKind::SpeakerEmbedding => download::get_speaker_embedding_model_path(root),
+        ModelKind::Vad => download::get_vad_model_path(root),
+    }
+}
+
+fn verify_file(model: &ModelKind, path: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(path).map_err(|_| AudioTranscriptionError::ModelNotFound {
+        model: model.to_string(),
+        path: path.to_path_buf(),
+    })?;
+
+    if !metadata.is_file() || metadata.len() == 0 {
+        return Err(AudioTranscriptionError::ModelCorrupt {
+            path: path.to_path_buf(),
+            message: "file is empty or not a regular file".to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_directory_provider_serves_existing_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = DirectoryModelProvider::new(dir.path());
+        let model = ModelKind::Whisper(ModelSize::Tiny);
+
+        let path = provider.resolve(&model);
+        assert_eq!(path, dir.path().join("whisper/tiny/ggml-tiny.bin"));
+        assert_eq!(provider.ensure_available(&model).await.unwrap_err().code(), 31);
+
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"").unwrap();
+        assert_eq!(provider.verify(&model).unwrap_err().code(), 32);
+
+        std::fs::write(&path, b"ggml").unwrap();
+        assert_eq!(provider.ensure_available(&model).await.unwrap(), path);
+    }
+}
@@ -0,0 +1,182 @@
+//! Notifications when a long run finishes: a desktop notification and/or a
+//! message posted to a Slack-compatible incoming webhook (Slack, Mattermost,
+//! Rocket.Chat and others accept the same `{"text": ..}` payload).
+//!
+//! Delivery problems are logged rather than returned, so a missed
+//! notification never fails a transcription that already succeeded.
+
+use std::path::Path;
+use std::time::Duration;
+use serde_json::json;
+use crate::core::audio_processor::TranscriptResult;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Application name shown by the desktop notification daemon
+const APP_NAME: &str = "audio-transcribe";
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A short message for a finished or failed run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+}
+
+impl Notification {
+    /// Audio length, processing time and where the transcripts were written
+    pub fn finished(input: &Path, result: &TranscriptResult, outputs: &[&Path]) -> Self {
+        let mut body = format!(
+            "{}: {} of audio transcribed in {}",
+            file_name(input),
+            format_duration(result.audio_duration),
+            format_duration(result.processing_time)
+        );
+        if !outputs.is_empty() {
+            let outputs: Vec<String> = outputs.iter().map(|path| path.display().to_string()).collect();
+            body.push_str(&format!("\n{}", outputs.join("\n")));
+        }
+
+        Self { title: "Transcription finished".to_string(), body }
+    }
+
+    pub fn failed(input: &Path, error: &AudioTranscriptionError) -> Self {
+        Self {
+            title: "Transcription failed".to_string(),
+            body: format!("{}: {} (code {})", file_name(input), error, error.code()),
+        }
+    }
+}
+
+/// Sends notifications to the configured destinations
+pub struct Notifier {
+    desktop: bool,
+    webhook: Option<String>,
+    client: reqwest::Client,
+}
+
+impl Notifier {
+    /// `webhook` is the full incoming-webhook URL
+    pub fn new(desktop: bool, webhook: Option<String>) -> Result<Self> {
+        if let Some(url) = &webhook {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(AudioTranscriptionError::Configuration(
+                    format!("Notification webhook must be an http(s) URL, got '{}'", url)
+                ));
+            }
+        }
+
+        let client = reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+        Ok(Self { desktop, webhook, client })
+    }
+
+    pub fn desktop(&self) -> bool {
+        self.desktop
+    }
+
+    pub fn webhook(&self) -> Option<&str> {
+        self.webhook.as_deref()
+    }
+
+    /// Deliver `notification` everywhere configured
+    pub async fn send(&self, notification: &Notification) {
+        if self.desktop {
+            let Notification { title, body } = notification.clone();
+            let shown = tokio::task::spawn_blocking(move || {
+                notify_rust::Notification::new().appname(APP_NAME).summary(&title).body(&body).show().map(|_| ())
+            })
+            .await;
+            match shown {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => tracing::warn!(error = %e, "Desktop notification failed"),
+                Err(e) => tracing::warn!(error = %e, "Desktop notification failed"),
+            }
+        }
+
+        if let Some(url) = &self.webhook {
+            let posted = self.client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(webhook_payload(notification).to_string())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = posted {
+                tracing::warn!(error = %e, "Webhook notification failed");
+            }
+        }
+    }
+}
+
+/// Slack's incoming-webhook message: bold title, then the body
+fn webhook_payload(notification: &Notification) -> serde_json::Value {
+    json!({ "text": format!("*{}*\n{}", notification.title, notification.body) })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned()
+}
+
+/// `1h 02m`, `12m 30s` or `45s`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::audio_processor::ModelInfo;
+
+    fn sample_result() -> TranscriptResult {
+        TranscriptResult {
+            segments: Vec::new(),
+            audio_duration: Duration::from_secs(3725),
+            processing_time: Duration::from_secs(750),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(750),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_finished_message() {
+        let notification = Notification::finished(
+            Path::new("/recordings/all-hands.wav"),
+            &sample_result(),
+            &[Path::new("/recordings/all-hands.txt")],
+        );
+
+        assert_eq!(notification.title, "Transcription finished");
+        assert_eq!(notification.body, "all-hands.wav: 1h 02m of audio transcribed in 12m 30s\n/recordings/all-hands.txt");
+        assert_eq!(webhook_payload(&notification)["text"], format!("*Transcription finished*\n{}", notification.body));
+    }
+
+    #[test]
+    fn test_failed_message_includes_code() {
+        let error = AudioTranscriptionError::Audio("decoder stopped".to_string());
+        let notification = Notification::failed(Path::new("talk.mp3"), &error);
+
+        assert_eq!(notification.body, "talk.mp3: Audio processing error: decoder stopped (code 20)");
+    }
+
+    #[test]
+    fn test_webhook_must_be_http() {
+        assert!(Notifier::new(false, Some("https://hooks.slack.com/services/T0/B0/x".to_string())).is_ok());
+        assert_eq!(Notifier::new(false, Some("hooks.slack.com".to_string())).err().unwrap().code(), 80);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_webhook_is_not_an_error() {
+        let notifier = Notifier::new(false, Some("http://127.0.0.1:9/hook".to_string())).unwrap();
+        notifier.send(&Notification { title: "t".to_string(), body: "b".to_string() }).await;
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use serde::Deserialize;
+use crate::core::audio_processor::TranscriptResult;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// A step applied to the finished transcript before it is written out,
+/// e.g. redaction or glossary substitution
+pub trait PostProcessor: Send + Sync {
+    /// Short name shown in logs and used in the config file
+    fn name(&self) -> &str;
+
+    fn process(&self, result: &mut TranscriptResult) -> Result<()>;
+}
+
+/// Post-processors applied in insertion order
+#[derive(Clone, Default)]
+pub struct PostProcessorChain {
+    processors: Vec<Arc<dyn PostProcessor>>,
+}
+
+impl PostProcessorChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the chain from a TOML file with one `[[processor]]` table per
+    /// step:
+    ///
+    /// ```toml
+    /// [[processor]]
+    /// type = "glossary"
+    /// terms = { "kubernetes" = "Kubernetes" }
+    ///
+    /// [[processor]]
+    /// type = "redact"
+    /// words = ["password"]
+    ///
+    /// [[processor]]
+    /// type = "casing"
+    ///
+    /// [[processor]]
+    /// type = "retime"
+    /// offset = 90.5
+    /// ```
+    pub fn from_config_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|source| AudioTranscriptionError::File { path: path.to_path_buf(), source })?;
+        Self::from_toml(&contents).map_err(|e| AudioTranscriptionError::Configuration(
+            format!("Invalid post-processing config {}: {}", path.display(), e)
+        ))
+    }
+
+    pub fn from_toml(contents: &str) -> std::result::Result<Self, toml::de::Error> {
+        let config: ChainConfig = toml::from_str(contents)?;
+        let mut chain = Self::new();
+        for processor in config.processor {
+            chain.push(processor.build().map_err(<toml::de::Error as serde::de::Error>::custom)?);
+        }
+        Ok(chain)
+    }
+
+    pub fn push(&mut self, processor: Arc<dyn PostProcessor>) {
+        self.processors.push(processor);
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.processors.iter().map(|processor| processor.name()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    /// Run every processor in order, stopping at the first failure
+    pub fn run(&self, result: &mut TranscriptResult) -> Result<()> {
+        for processor in &self.processors {
+            tracing::debug!(processor = processor.name(), "Running post-processor");
+            processor.process(result)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChainConfig {
+    #[serde(default)]
+    processor: Vec<ProcessorConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ProcessorConfig {
+    Redact {
+        words: Vec<String>,
+        #[serde(default = "default_replacement")]
+        replacement: String,
+    },
+    Glossary {
+        terms: BTreeMap<String, String>,
+    },
+    Casing,
+    Retime {
+        #[serde(default)]
+        offset: f32,
+        #[serde(default = "default_rate")]
+        rate: f32,
+    },
+}
+
+fn default_replacement() -> String {
+    "[REDACTED]".to_string()
+}
+
+fn default_rate() -> f32 {
+    1.0
+}
+
+impl ProcessorConfig {
+    fn build(self) -> std::result::Result<Arc<dyn PostProcessor>, String> {
+        Ok(match self {
+            Self::Redact { words, replacement } => Arc::new(Redact { words, replacement }),
+            Self::Glossary { terms } => Arc::new(Glossary { terms: terms.into_iter().collect() }),
+            Self::Casing => Arc::new(SentenceCase),
+            Self::Retime { offset, rate } => Arc::new(Retime::new(offset, rate).map_err(|e| e.to_string())?),
+        })
+    }
+}
+
+/// Replaces listed words (case-insensitive, whole words) with a marker
+#[derive(Debug, Clone)]
+pub struct Redact {
+    pub words: Vec<String>,
+    pub replacement: String,
+}
+
+impl PostProcessor for Redact {
+    fn name(&self) -> &str {
+        "redact"
+    }
+
+    fn process(&self, result: &mut TranscriptResult) -> Result<()> {
+        for segment in &mut result.segments {
+            for word in &self.words {
+                segment.text = replace_word(&segment.text, word, &self.replacement);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Substitutes terms (case-insensitive, whole words) with their preferred
+/// spelling, e.g. product names the model gets wrong
+#[derive(Debug, Clone)]
+pub struct Glossary {
+    pub terms: Vec<(String, String)>,
+}
+
+impl PostProcessor for Glossary {
+    fn name(&self) -> &str {
+        "glossary"
+    }
+
+    fn process(&self, result: &mut TranscriptResult) -> Result<()> {
+        for segment in &mut result.segments {
+            for (from, to) in &self.terms {
+                segment.text = replace_word(&segment.text, from, to);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Trims segments, collapses repeated spaces and capitalises the first
+/// letter of each segment
+#[derive(Debug, Clone, Copy)]
+pub struct SentenceCase;
+
+impl PostProcessor for SentenceCase {
+    fn name(&self) -> &str {
+        "casing"
+    }
+
+    fn process(&self, result: &mut TranscriptResult) -> Result<()> {
+        for segment in &mut result.segments {
+            let collapsed = segment.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let mut chars = collapsed.chars();
+            segment.text = match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => collapsed,
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Maps timestamps onto other media: each time becomes `time * rate +
+/// offset`. Use the offset when the audio was cut from a longer video, and
+/// the rate when the media was sped up or slowed down, e.g. `23.976 / 25`
+/// for a PAL release of a film. Segments moved entirely before zero are
+/// dropped; ones straddling it are clipped.
+#[derive(Debug, Clone, Copy)]
+pub struct Retime {
+    offset: f32,  // Seconds, may be negative
+    rate: f32,
+}
+
+impl Retime {
+    pub fn new(offset: f32, rate: f32) -> Result<Self> {
+        if !offset.is_finite() || !rate.is_finite() || rate <= 0.0 {
+            return Err(AudioTranscriptionError::Configuration(format!(
+                "Timestamp rate must be a positive number and offset finite, got rate {} and offset {}",
+                rate, offset
+            )));
+        }
+        Ok(Self { offset, rate })
+    }
+
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+}
+
+impl PostProcessor for Retime {
+    fn name(&self) -> &str {
+        "retime"
+    }
+
+    fn process(&self, result: &mut TranscriptResult) -> Result<()> {
+        let map = |time: f32| time * self.rate + self.offset;
+        result.segments.retain(|segment| map(segment.end) > 0.0);
+        for segment in &mut result.segments {
+            segment.start = map(segment.start).max(0.0);
+            segment.end = map(segment.end);
+            for word in &mut segment.words {
+                word.start = map(word.start).max(0.0);
+                word.end = map(word.end).max(0.0);
+            }
+        }
+        for range in &mut result.skipped {
+            range.start = map(range.start).max(0.0);
+            range.end = map(range.end).max(0.0);
+        }
+        result.audio_duration = result.audio_duration.mul_f32(self.rate);
+        Ok(())
+    }
+}
+
+/// Replace whole-word, ASCII case-insensitive occurrences of `word`
+fn replace_word(text: &str, word: &str, replacement: &str) -> String {
+    if word.is_empty() {
+        return text.to_string();
+    }
+
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '\'';
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut previous: Option<char> = None;
+
+    while !rest.is_empty() {
+        let candidate = rest.get(..word.len());
+        let matches = candidate.is_some_and(|c| c.eq_ignore_ascii_case(word))
+            && !previous.is_some_and(is_word_char)
+            && !rest[word.len()..].chars().next().is_some_and(is_word_char);
+
+        if matches {
+            output.push_str(replacement);
+            previous = word.chars().last();
+            rest = &rest[word.len()..];
+        } else {
+            let c = rest.chars().next().unwrap_or_default();
+            output.push(c);
+            previous = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::core::audio_processor::{ModelInfo, SkippedRange, SpeechSegment};
+
+    fn result_with(texts: &[&str]) -> TranscriptResult {
+        TranscriptResult {
+            segments: texts
+                .iter()
+                .map(|text| SpeechSegment { start: 0.0, end: 1.0, text: text.to_string(), ..Default::default() })
+                .collect(),
+            audio_duration: Duration::from_secs(1),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_replace_word_respects_boundaries() {
+        assert_eq!(replace_word("Cat, cats and CAT.", "cat", "dog"), "dog, cats and dog.");
+        assert_eq!(replace_word("concat", "cat", "dog"), "concat");
+        assert_eq!(replace_word("héllo cat", "cat", "dog"), "héllo dog");
+    }
+
+    #[test]
+    fn test_chain_from_toml_runs_in_order() {
+        let chain = PostProcessorChain::from_toml(r#"
+            [[processor]]
+            type = "glossary"
+            terms = { "nexus flow" = "nxsflow" }
+
+            [[processor]]
+            type = "redact"
+            words = ["secret"]
+
+            [[processor]]
+            type = "casing"
+        "#).unwrap();
+        let mut result = result_with(&["  the secret of   nexus flow "]);
+
+        chain.run(&mut result).unwrap();
+
+        assert_eq!(chain.names(), vec!["glossary", "redact", "casing"]);
+        assert_eq!(result.segments[0].text, "The [REDACTED] of nxsflow");
+    }
+
+    #[test]
+    fn test_retime_shifts_scales_and_clips() {
+        let mut result = result_with(&["a", "b", "c"]);
+        for (segment, start) in result.segments.iter_mut().zip([0.0, 4.0, 10.0]) {
+            segment.start = start;
+            segment.end = start + 2.0;
+        }
+        result.skipped = vec![SkippedRange { start: 3.0, end: 6.0, attempts: 2, error: "timeout".to_string() }];
+
+        Retime::new(-5.0, 1.0).unwrap().process(&mut result).unwrap();
+        let times: Vec<(f32, f32)> = result.segments.iter().map(|s| (s.start, s.end)).collect();
+        assert_eq!(times, vec![(0.0, 1.0), (5.0, 7.0)]);
+        assert_eq!((result.skipped[0].start, result.skipped[0].end), (0.0, 1.0));
+        assert_eq!(result.audio_duration, Duration::from_secs(1));
+
+        Retime::new(60.0, 2.0).unwrap().process(&mut result).unwrap();
+        assert_eq!(result.segments[1].start, 70.0);
+        assert_eq!(result.segments[1].end, 74.0);
+        assert_eq!((result.skipped[0].start, result.skipped[0].end), (60.0, 62.0));
+        assert_eq!(result.audio_duration, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_retime_config_is_validated() {
+        let chain = PostProcessorChain::from_toml("[[processor]]\ntype = \"retime\"\noffset = 90.5").unwrap();
+        assert_eq!(chain.names(), vec!["retime"]);
+
+        assert!(PostProcessorChain::from_toml("[[processor]]\ntype = \"retime\"\nrate = 0.0").is_err());
+        assert_eq!(Retime::new(0.0, -1.0).unwrap_err().code(), 80);
+    }
+
+    #[test]
+    fn test_unknown_processor_type_is_rejected() {
+        assert!(PostProcessorChain::from_toml("[[processor]]\ntype = \"translate\"").is_err());
+    }
+}
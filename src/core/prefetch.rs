@@ -0,0 +1,170 @@
+use tokio::sync::mpsc::{self, error::TrySendError};
+use crate::error::{AudioTranscriptionError, Result};
+
+/// Prepares upcoming work items on a blocking thread while the consumer is
+/// busy with the current one, so transcription never idles waiting for
+/// audio preparation.
+pub struct Prefetcher<T> {
+    receiver: mpsc::Receiver<Result<T>>,
+    peeked: Option<Result<T>>,
+}
+
+impl<T: Send + 'static> Prefetcher<T> {
+    /// Spawn the producer. `lookahead` is the number of prepared items that
+    /// may wait in the queue ahead of the consumer.
+    pub fn spawn<I, S, F>(items: I, lookahead: usize, prepare: F) -> Self
+    where
+        I: IntoIterator<Item = S> + Send + 'static,
+        I::IntoIter: Send,
+        S: Send + 'static,
+        F: FnMut(S) -> Result<T> + Send + 'static,
+    {
+        Self::spawn_scheduled(items, lookahead, prepare, |_: &[T]| 0)
+    }
+
+    /// Like [`spawn`](Self::spawn), handing out the prepared items in the
+    /// order `pick` chooses: given the items waiting, it returns the
+    /// position of the one the consumer gets next. Only the `lookahead`
+    /// items already prepared are reordered; an item is never held back
+    /// while the consumer is waiting for one.
+    pub fn spawn_scheduled<I, S, F, P>(items: I, lookahead: usize, mut prepare: F, mut pick: P) -> Self
+    where
+        I: IntoIterator<Item = S> + Send + 'static,
+        I::IntoIter: Send,
+        S: Send + 'static,
+        F: FnMut(S) -> Result<T> + Send + 'static,
+        P: FnMut(&[T]) -> usize + Send + 'static,
+    {
+        let lookahead = lookahead.max(1);
+        let (sender, receiver) = mpsc::channel(1);
+
+        tokio::task::spawn_blocking(move || {
+            let mut items = items.into_iter();
+            let mut window: Vec<T> = Vec::with_capacity(lookahead);
+            loop {
+                // Hand an item over as soon as the consumer has room for it
+                if !window.is_empty() {
+                    match sender.try_reserve() {
+                        Ok(permit) => {
+                            let next = pick(&window);
+                            permit.send(Ok(window.remove(next)));
+                            continue;
+                        }
+                        Err(TrySendError::Closed(())) => break,
+                        Err(TrySendError::Full(())) => {}
+                    }
+                }
+
+                if window.len() < lookahead {
+                    if let Some(item) = items.next() {
+                        match prepare(item) {
+                            Ok(prepared) => window.push(prepared),
+                            Err(e) => {
+                                // One bad chunk is skipped by the consumer; anything else ends the run
+                                let fatal = !matches!(e, AudioTranscriptionError::Chunk { .. });
+                                if sender.blocking_send(Err(e)).is_err() || fatal {
+                                    break;
+                                }
+                            }
+                        }
+                        continue;
+                    }
+                }
+
+                // The window is full or everything is prepared: wait for the consumer
+                if window.is_empty() {
+                    break;
+                }
+                let next = pick(&window);
+                if sender.blocking_send(Ok(window.remove(next))).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { receiver, peeked: None }
+    }
+
+    /// Wait for the next prepared item. Returns `None` once all items have
+    /// been consumed.
+    pub async fn next(&mut self) -> Option<Result<T>> {
+        match self.peeked.take() {
+            Some(item) => Some(item),
+            None => self.receiver.recv().await,
+        }
+    }
+
+    /// Wait for the next prepared item without taking it
+    pub async fn peek(&mut self) -> Option<&Result<T>> {
+        if self.peeked.is_none() {
+            self.peeked = self.receiver.recv().await;
+        }
+        self.peeked.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_items_arrive_in_order() {
+        let mut prefetcher = Prefetcher::spawn(0..5, 1, |i: u32| Ok(i * 2));
+
+        assert_eq!(*prefetcher.peek().await.unwrap().as_ref().unwrap(), 0);
+        let mut received = Vec::new();
+        while let Some(item) = prefetcher.next().await {
+            received.push(item.unwrap());
+        }
+
+        assert_eq!(received, vec![0, 2, 4, 6, 8]);
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_items_reorder_within_the_window() {
+        let mut prefetcher = Prefetcher::spawn_scheduled([5, 3, 9, 1, 7], 4, Ok, |window: &[u32]| {
+            (0..window.len()).min_by_key(|&i| window[i]).unwrap()
+        });
+        // Let the window fill before taking anything
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let mut received = Vec::new();
+        while let Some(item) = prefetcher.next().await {
+            received.push(item.unwrap());
+        }
+        // 5 is handed over before the rest are prepared, which then go smallest first
+        assert_eq!(received, vec![5, 1, 3, 7, 9]);
+    }
+
+    #[tokio::test]
+    async fn test_continues_after_chunk_error() {
+        let mut prefetcher = Prefetcher::spawn(0..4, 2, |i: u32| {
+            if i == 1 {
+                Err(AudioTranscriptionError::Chunk { index: 1, message: "empty".to_string() })
+            } else {
+                Ok(i)
+            }
+        });
+
+        assert_eq!(prefetcher.next().await.unwrap().unwrap(), 0);
+        assert!(prefetcher.next().await.unwrap().is_err());
+        assert_eq!(prefetcher.next().await.unwrap().unwrap(), 2);
+        assert_eq!(prefetcher.next().await.unwrap().unwrap(), 3);
+        assert!(prefetcher.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stops_after_fatal_error() {
+        let mut prefetcher = Prefetcher::spawn(0..5, 2, |i: u32| {
+            if i == 1 {
+                Err(AudioTranscriptionError::Audio("decode failed".to_string()))
+            } else {
+                Ok(i)
+            }
+        });
+
+        assert_eq!(prefetcher.next().await.unwrap().unwrap(), 0);
+        assert!(prefetcher.next().await.unwrap().is_err());
+        assert!(prefetcher.next().await.is_none());
+    }
+}
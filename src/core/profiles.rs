@@ -0,0 +1,238 @@
+//! Defaults for every run from `config.toml`, with named profiles for
+//! recurring kinds of recording, selected with `--profile`:
+//!
+//! ```toml
+//! model = "small"
+//! format = ["txt", "srt"]
+//!
+//! [profile.podcast]
+//! model = "large-v3"
+//! chunk_size = 300
+//! format = ["srt", "json"]
+//! max_speakers = 3
+//! output = "~/Podcasts/{parent}"
+//!
+//! [profile.meeting]
+//! format = ["notes"]
+//! max_speakers = 8
+//! speaker_stats = true
+//! output = "~/Notes/Meetings/{date}"
+//! ```
+//!
+//! Settings are named after the command-line options they stand in for.
+//! An option given on the command line wins over the profile, and the
+//! profile over the top-level settings.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::core::sources::expand_home;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Placeholders available in output directory templates
+pub const OUTPUT_PLACEHOLDERS: &[&str] = &["{stem}", "{parent}", "{date}"];
+
+/// Option values from the config file; unset ones keep their default
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    pub model: Option<String>,
+    pub chunk_size: Option<f32>,
+    pub chunk_overlap: Option<f32>,
+    pub jobs: Option<usize>,
+    pub vad_threshold: Option<f32>,
+    pub language: Option<String>,
+    pub word_timestamps: Option<bool>,
+    pub format: Option<Vec<String>>,
+    pub max_speakers: Option<usize>,
+    pub split_channels: Option<bool>,
+    pub speaker_stats: Option<bool>,
+    pub speaker_names: Option<PathBuf>,
+    pub output: Option<PathBuf>,      // Directory, may hold OUTPUT_PLACEHOLDERS
+}
+
+impl Settings {
+    /// These settings, with the ones `profile` sets taking their place
+    fn with(self, profile: &Settings) -> Settings {
+        let profile = profile.clone();
+        Settings {
+            model: profile.model.or(self.model),
+            chunk_size: profile.chunk_size.or(self.chunk_size),
+            chunk_overlap: profile.chunk_overlap.or(self.chunk_overlap),
+            jobs: profile.jobs.or(self.jobs),
+            vad_threshold: profile.vad_threshold.or(self.vad_threshold),
+            language: profile.language.or(self.language),
+            word_timestamps: profile.word_timestamps.or(self.word_timestamps),
+            format: profile.format.or(self.format),
+            max_speakers: profile.max_speakers.or(self.max_speakers),
+            split_channels: profile.split_channels.or(self.split_channels),
+            speaker_stats: profile.speaker_stats.or(self.speaker_stats),
+            speaker_names: profile.speaker_names.or(self.speaker_names),
+            output: profile.output.or(self.output),
+        }
+    }
+
+    /// Paths with a leading `~` point into the home directory
+    fn expand_home(mut self) -> Settings {
+        self.speaker_names = self.speaker_names.map(|path| expand_home(&path.to_string_lossy()));
+        self.output = self.output.map(|path| expand_home(&path.to_string_lossy()));
+        self
+    }
+}
+
+/// The top-level settings and the named profiles of a config file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserConfig {
+    defaults: Settings,
+    profiles: BTreeMap<String, Settings>,
+}
+
+impl UserConfig {
+    /// `<config dir>/audio-transcribe/config.toml`
+    pub fn default_config_path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir().ok_or_else(|| AudioTranscriptionError::Configuration(
+            "Unable to determine config directory".to_string()
+        ))?;
+        Ok(config_dir.join("audio-transcribe").join("config.toml"))
+    }
+
+    /// The default config file, or no settings when there is none
+    pub fn load_default() -> Result<Self> {
+        let path = Self::default_config_path()?;
+        if path.is_file() {
+            Self::from_config_file(&path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn from_config_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|source| AudioTranscriptionError::File { path: path.to_path_buf(), source })?;
+        Self::from_toml(&contents).map_err(|message| AudioTranscriptionError::Configuration(
+            format!("Invalid config file {}: {}", path.display(), message)
+        ))
+    }
+
+    pub fn from_toml(contents: &str) -> std::result::Result<Self, String> {
+        let mut table: toml::Table = toml::from_str(contents).map_err(|e| e.to_string())?;
+        let profiles = match table.remove("profile") {
+            Some(profiles) => profiles.try_into().map_err(|e: toml::de::Error| format!("in [profile]: {}", e))?,
+            None => BTreeMap::new(),
+        };
+        let defaults = toml::Value::Table(table).try_into().map_err(|e: toml::de::Error| e.to_string())?;
+        Ok(Self { defaults, profiles })
+    }
+
+    /// Names of the profiles, in alphabetical order
+    pub fn profiles(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
+    /// The settings of a run: the top-level ones, overridden by those of
+    /// `profile` when one is selected
+    pub fn settings(&self, profile: Option<&str>) -> Result<Settings> {
+        let settings = match profile {
+            Some(name) => {
+                let profile = self.profiles.get(name).ok_or_else(|| {
+                    let known: Vec<&str> = self.profiles().collect();
+                    AudioTranscriptionError::Configuration(if known.is_empty() {
+                        format!("No profile named {}; the config file defines none", name)
+                    } else {
+                        format!("No profile named {}; known profiles: {}", name, known.join(", "))
+                    })
+                })?;
+                self.defaults.clone().with(profile)
+            }
+            None => self.defaults.clone(),
+        };
+        Ok(settings.expand_home())
+    }
+}
+
+/// The output directory for one recording: `template` with its
+/// placeholders filled in from the input's name and folder and the
+/// recording date. `{date}` is left as is when the date is unknown.
+pub fn expand_output(template: &Path, input: &Path, date: Option<&str>) -> PathBuf {
+    let template = template.to_string_lossy();
+    if !OUTPUT_PLACEHOLDERS.iter().any(|placeholder| template.contains(placeholder)) {
+        return PathBuf::from(template.as_ref());
+    }
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let parent = input
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    let mut expanded = template
+        .replace("{stem}", &stem)
+        .replace("{parent}", &parent);
+    if let Some(date) = date {
+        expanded = expanded.replace("{date}", date);
+    }
+    PathBuf::from(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        model = "small"
+        format = ["txt", "srt"]
+        max_speakers = 4
+
+        [profile.podcast]
+        model = "large-v3"
+        chunk_size = 300
+        output = "/srv/podcasts/{parent}"
+
+        [profile.meeting]
+        format = ["notes"]
+        speaker_stats = true
+    "#;
+
+    #[test]
+    fn test_profile_overrides_defaults() {
+        let config = UserConfig::from_toml(CONFIG).unwrap();
+        assert_eq!(config.profiles().collect::<Vec<_>>(), ["meeting", "podcast"]);
+
+        let defaults = config.settings(None).unwrap();
+        assert_eq!((defaults.model.as_deref(), defaults.max_speakers, defaults.chunk_size), (Some("small"), Some(4), None));
+
+        let podcast = config.settings(Some("podcast")).unwrap();
+        assert_eq!((podcast.model.as_deref(), podcast.chunk_size), (Some("large-v3"), Some(300.0)));
+        assert_eq!(podcast.format, Some(vec!["txt".to_string(), "srt".to_string()]));
+        assert_eq!(podcast.output, Some(PathBuf::from("/srv/podcasts/{parent}")));
+
+        let meeting = config.settings(Some("meeting")).unwrap();
+        assert_eq!((meeting.format, meeting.speaker_stats, meeting.max_speakers), (Some(vec!["notes".to_string()]), Some(true), Some(4)));
+
+        assert_eq!(config.settings(Some("lecture")).unwrap_err().code(), 80);
+        assert_eq!(UserConfig::default().settings(None).unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        assert!(UserConfig::from_toml("modle = \"small\"").is_err());
+        assert!(UserConfig::from_toml("[profile.podcast]\nchunk_size = \"long\"").is_err());
+        assert!(UserConfig::from_toml("profile = 1").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[profile.podcast]\nspeakers = 2\n").unwrap();
+        assert_eq!(UserConfig::from_config_file(&path).unwrap_err().code(), 80);
+    }
+
+    #[test]
+    fn test_output_templates_are_expanded() {
+        let input = Path::new("/recordings/Weekly Sync/audio1.m4a");
+        assert_eq!(
+            expand_output(Path::new("/notes/{parent}/{date}-{stem}"), input, Some("2024-03-05")),
+            PathBuf::from("/notes/Weekly Sync/2024-03-05-audio1")
+        );
+        assert_eq!(expand_output(Path::new("out/{date}"), input, None), PathBuf::from("out/{date}"));
+        assert_eq!(expand_output(Path::new("out"), input, None), PathBuf::from("out"));
+    }
+}
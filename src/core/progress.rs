@@ -0,0 +1,92 @@
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessingStage {
+    VAD,
+    Chunking,
+    Transcription,
+    Refinement,
+    Diarization,
+    Merging,
+    Complete,
+}
+
+impl ProcessingStage {
+    /// Short lowercase name, as in [`StageTimings::stages`](crate::core::audio_processor::StageTimings::stages)
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProcessingStage::VAD => "vad",
+            ProcessingStage::Chunking => "chunking",
+            ProcessingStage::Transcription => "transcription",
+            ProcessingStage::Refinement => "refinement",
+            ProcessingStage::Diarization => "diarization",
+            ProcessingStage::Merging => "merging",
+            ProcessingStage::Complete => "complete",
+        }
+    }
+}
+
+impl std::fmt::Display for ProcessingStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessingStage::VAD => write!(f, "Voice Activity Detection"),
+            ProcessingStage::Chunking => write!(f, "Audio Chunking"),
+            ProcessingStage::Transcription => write!(f, "Transcription"),
+            ProcessingStage::Refinement => write!(f, "Refining Unclear Segments"),
+            ProcessingStage::Diarization => write!(f, "Speaker Diarization"),
+            ProcessingStage::Merging => write!(f, "Merging Results"),
+            ProcessingStage::Complete => write!(f, "Complete"),
+        }
+    }
+}
+
+/// Progress reported by the processing pipeline
+#[derive(Debug, Clone)]
+pub enum ProgressEvent {
+    /// A new pipeline stage has started
+    StageStarted(ProcessingStage),
+    /// Transcription worker `worker` picked up chunk `index`; `device` is
+    /// the GPU it runs on, or `None` on the CPU
+    ChunkStarted {
+        index: usize,
+        worker: usize,
+        device: Option<usize>,
+    },
+    /// A transcription chunk finished. Chunks are found while the file is
+    /// read, so `total` counts those found so far; audio seconds are the
+    /// furthest point in the file transcribed and the file's length.
+    ChunkCompleted {
+        index: usize,
+        completed: usize,
+        total: usize,
+        completed_audio_secs: f32,
+        total_audio_secs: f32,
+        eta: Option<Duration>,
+    },
+}
+
+/// Receiver of pipeline progress.
+///
+/// Called from worker tasks, so implementations must be cheap and must not
+/// block. The terminal [`ProgressDisplay`](crate::ui::ProgressDisplay) is one
+/// implementation; an `mpsc` sender forwards events to another task.
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, event: ProgressEvent);
+}
+
+impl ProgressSink for mpsc::UnboundedSender<ProgressEvent> {
+    fn report(&self, event: ProgressEvent) {
+        // A dropped receiver just means nobody is listening any more
+        let _ = self.send(event);
+    }
+}
+
+/// Estimate the time left from the share of audio completed so far
+pub fn estimate_remaining(elapsed: Duration, completed_audio_secs: f32, total_audio_secs: f32) -> Option<Duration> {
+    if completed_audio_secs <= 0.0 || total_audio_secs <= completed_audio_secs {
+        return None;
+    }
+    let secs_per_audio_sec = elapsed.as_secs_f32() / completed_audio_secs;
+    Some(Duration::from_secs_f32(secs_per_audio_sec * (total_audio_secs - completed_audio_secs)))
+}
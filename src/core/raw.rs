@@ -0,0 +1,154 @@
+//! Raw PCM input, such as audio piped from ffmpeg or an SDR tool.
+//!
+//! Raw samples carry no header, so their layout has to be given. They are
+//! wrapped in a WAV header as they are copied, which lets the rest of the
+//! pipeline treat the stream like any other recording.
+
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Input path that stands for standard input
+pub const STDIN: &str = "-";
+
+/// Size of the WAV header written in front of the samples
+const WAV_HEADER_BYTES: u32 = 44;
+
+/// Sample encoding of raw PCM, named as in ffmpeg's `-f` option
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum RawFormat {
+    U8,
+    S16le,
+    S32le,
+    F32le,
+}
+
+impl RawFormat {
+    fn bits(self) -> u16 {
+        match self {
+            Self::U8 => 8,
+            Self::S16le => 16,
+            Self::S32le | Self::F32le => 32,
+        }
+    }
+
+    /// `WAVE_FORMAT_PCM` or `WAVE_FORMAT_IEEE_FLOAT`
+    fn format_tag(self) -> u16 {
+        match self {
+            Self::F32le => 3,
+            _ => 1,
+        }
+    }
+}
+
+/// Layout of a raw PCM stream; multi-channel samples are interleaved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawPcm {
+    pub format: RawFormat,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl RawPcm {
+    /// Bytes per frame (one sample of every channel)
+    fn block_align(&self) -> u16 {
+        self.channels * self.format.bits() / 8
+    }
+
+    /// Copy raw samples from `reader` into a WAV file at `path` until the
+    /// stream ends. A trailing partial frame is dropped. Returns the
+    /// number of frames written.
+    pub fn write_wav(&self, mut reader: impl Read, path: &Path) -> Result<u64> {
+        if self.sample_rate == 0 || self.channels == 0 {
+            return Err(AudioTranscriptionError::Configuration(
+                "Raw PCM needs a sample rate and at least one channel".to_string()
+            ));
+        }
+        let file_error = |source| AudioTranscriptionError::File { path: path.to_path_buf(), source };
+
+        let mut file = File::create(path).map_err(file_error)?;
+        let mut writer = BufWriter::new(&mut file);
+        writer.write_all(&self.header(0)).map_err(file_error)?;
+        let copied = std::io::copy(&mut reader, &mut writer).map_err(file_error)?;
+        writer.flush().map_err(file_error)?;
+        drop(writer);
+
+        let block_align = u64::from(self.block_align());
+        let data_bytes = copied - copied % block_align;
+        let data_bytes = u32::try_from(data_bytes)
+            .ok()
+            .filter(|bytes| bytes.checked_add(WAV_HEADER_BYTES).is_some())
+            .ok_or_else(|| AudioTranscriptionError::Audio(format!(
+                "Raw audio is longer than a WAV file can hold ({} bytes)",
+                copied
+            )))?;
+        if data_bytes == 0 {
+            return Err(AudioTranscriptionError::Audio("The raw audio stream was empty".to_string()));
+        }
+
+        // Now that the length is known, fill it in and cut off a partial frame
+        file.set_len(u64::from(WAV_HEADER_BYTES + data_bytes)).map_err(file_error)?;
+        file.seek(SeekFrom::Start(0)).map_err(file_error)?;
+        file.write_all(&self.header(data_bytes)).map_err(file_error)?;
+        Ok(u64::from(data_bytes) / block_align)
+    }
+
+    fn header(&self, data_bytes: u32) -> Vec<u8> {
+        let block_align = self.block_align();
+        let mut header = Vec::with_capacity(WAV_HEADER_BYTES as usize);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(WAV_HEADER_BYTES - 8 + data_bytes).to_le_bytes());
+        header.extend_from_slice(b"WAVEfmt ");
+        header.extend_from_slice(&16u32.to_le_bytes());
+        header.extend_from_slice(&self.format.format_tag().to_le_bytes());
+        header.extend_from_slice(&self.channels.to_le_bytes());
+        header.extend_from_slice(&self.sample_rate.to_le_bytes());
+        header.extend_from_slice(&(self.sample_rate * u32::from(block_align)).to_le_bytes());
+        header.extend_from_slice(&block_align.to_le_bytes());
+        header.extend_from_slice(&self.format.bits().to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&data_bytes.to_le_bytes());
+        header
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::decoder::AudioDecoder;
+
+    #[test]
+    fn test_s16le_becomes_a_decodable_wav() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stdin.wav");
+        let samples: Vec<i16> = (0..8_000).map(|i| if i % 2 == 0 { 8_000 } else { -8_000 }).collect();
+        let mut bytes: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        bytes.push(0x7f);  // Half a frame, as when a pipe is cut mid-sample
+
+        let pcm = RawPcm { format: RawFormat::S16le, sample_rate: 8_000, channels: 1 };
+        assert_eq!(pcm.write_wav(bytes.as_slice(), &path).unwrap(), 8_000);
+
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_rate, 8_000);
+        assert_eq!(reader.duration(), 8_000);
+        // Resampled to 16 kHz by the usual decoder
+        assert_eq!(AudioDecoder::default().decode(&path).unwrap().len(), 16_000);
+    }
+
+    #[test]
+    fn test_f32le_stereo_and_empty_streams() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stdin.wav");
+        let bytes: Vec<u8> = [0.25f32, -0.25].repeat(1_600).iter().flat_map(|sample| sample.to_le_bytes()).collect();
+
+        let pcm = RawPcm { format: RawFormat::F32le, sample_rate: 16_000, channels: 2 };
+        assert_eq!(pcm.write_wav(bytes.as_slice(), &path).unwrap(), 1_600);
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_format, hound::SampleFormat::Float);
+        assert_eq!(reader.spec().channels, 2);
+
+        assert!(pcm.write_wav(std::io::empty(), &path).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Second pass over the hard parts of a recording.
+//!
+//! A small model transcribes the whole file quickly; only the segments it
+//! was unsure of, by mean token probability, are transcribed again with a
+//! larger model. On mostly clear audio that is a small share of the file,
+//! so the run costs little more than the small model alone.
+
+use std::collections::VecDeque;
+use std::ops::Range;
+use crate::core::audio_processor::{AudioChunk, SpeechSegment, SAMPLE_RATE};
+use crate::core::chunking::{prepare_chunk, ChunkSpan};
+use crate::core::decoder::AudioStream;
+use crate::error::Result;
+
+/// Consecutive low-confidence segments, transcribed again as one chunk
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RefineSpan {
+    pub segments: Range<usize>,  // Indices into the first-pass transcript
+    pub start: f32,
+    pub end: f32,
+}
+
+impl RefineSpan {
+    fn samples(&self) -> Range<usize> {
+        let sample = |seconds: f32| (seconds.max(0.0) * SAMPLE_RATE as f32) as usize;
+        sample(self.start)..sample(self.end)
+    }
+}
+
+/// Group the segments whose confidence is below `threshold` into spans of
+/// at most `max_secs`, in transcript order. Segments without a confidence,
+/// such as gap markers, are left alone.
+pub(crate) fn low_confidence_spans(segments: &[SpeechSegment], threshold: f32, max_secs: f32) -> Vec<RefineSpan> {
+    let mut spans: Vec<RefineSpan> = Vec::new();
+    for (index, segment) in segments.iter().enumerate() {
+        if !segment.confidence.is_some_and(|confidence| confidence < threshold) {
+            continue;
+        }
+        match spans.last_mut() {
+            Some(span) if span.segments.end == index && segment.end - span.start <= max_secs => {
+                span.segments.end = index + 1;
+                span.end = span.end.max(segment.end);
+            }
+            _ => spans.push(RefineSpan { segments: index..index + 1, start: segment.start, end: segment.end }),
+        }
+    }
+    spans
+}
+
+/// Replace the segments of each span with what the larger model heard
+/// there. A span it failed on or found no speech in keeps the first pass.
+pub(crate) fn apply(segments: Vec<SpeechSegment>, spans: &[RefineSpan], mut refined: Vec<Option<Vec<SpeechSegment>>>) -> Vec<SpeechSegment> {
+    let mut output = Vec::with_capacity(segments.len());
+    let mut spans = spans.iter().zip(refined.iter_mut()).peekable();
+    let mut skip_until = 0;
+    for (index, segment) in segments.into_iter().enumerate() {
+        if let Some((span, replacement)) = spans.next_if(|(span, _)| span.segments.start == index) {
+            if let Some(replacement) = replacement.take().filter(|replacement| !replacement.is_empty()) {
+                output.extend(replacement);
+                skip_until = span.segments.end;
+            }
+        }
+        if index >= skip_until {
+            output.push(segment);
+        }
+    }
+    output
+}
+
+/// Decodes the file once more and yields the audio of each span as a
+/// chunk, indexed by span. Audio before the next span is dropped as it is
+/// read, so only about a chunk is held at once.
+pub(crate) struct RefineChunks {
+    audio: AudioStream,
+    spans: Vec<RefineSpan>,
+    next: usize,
+    buffer: VecDeque<f32>,
+    buffer_start: usize,  // Sample position of the front of `buffer`
+    done: bool,           // The stream has no more audio
+}
+
+impl RefineChunks {
+    pub fn new(audio: AudioStream, spans: Vec<RefineSpan>) -> Self {
+        Self { audio, spans, next: 0, buffer: VecDeque::new(), buffer_start: 0, done: false }
+    }
+}
+
+impl Iterator for RefineChunks {
+    type Item = Result<AudioChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let range = self.spans.get(self.next)?.samples();
+        loop {
+            let before = range.start.saturating_sub(self.buffer_start).min(self.buffer.len());
+            self.buffer.drain(..before);
+            self.buffer_start += before;
+            if self.done || self.buffer_start + self.buffer.len() >= range.end {
+                break;
+            }
+            match self.audio.next() {
+                Some(Ok(block)) => self.buffer.extend(block),
+                Some(Err(e)) => return Some(Err(e)),
+                None => self.done = true,
+            }
+        }
+
+        let samples = self.buffer.make_contiguous();
+        let from = (range.start - self.buffer_start).min(samples.len());
+        let to = (range.end - self.buffer_start).min(samples.len());
+        let chunk = prepare_chunk(&samples[from..to], ChunkSpan { index: self.next, samples: range });
+        self.next += 1;
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::decoder::AudioDecoder;
+
+    fn segment(start: f32, end: f32, text: &str, confidence: Option<f32>) -> SpeechSegment {
+        SpeechSegment { start, end, text: text.to_string(), confidence, ..Default::default() }
+    }
+
+    #[test]
+    fn test_spans_group_consecutive_low_confidence_segments() {
+        let segments = [
+            segment(0.0, 4.0, "clear", Some(0.9)),
+            segment(4.0, 8.0, "mumbled", Some(0.3)),
+            segment(8.0, 14.0, "noisy", Some(0.5)),
+            segment(14.0, 20.0, "crosstalk", Some(0.4)),
+            segment(20.0, 22.0, "clear", Some(0.8)),
+            segment(22.0, 30.0, "[untranscribed audio]", None),
+            segment(30.0, 31.0, "quiet", Some(0.2)),
+        ];
+
+        let spans = low_confidence_spans(&segments, 0.6, 12.0);
+        assert_eq!(spans, [
+            RefineSpan { segments: 1..3, start: 4.0, end: 14.0 },
+            RefineSpan { segments: 3..4, start: 14.0, end: 20.0 },
+            RefineSpan { segments: 6..7, start: 30.0, end: 31.0 },
+        ]);
+        assert!(low_confidence_spans(&segments, 0.1, 12.0).is_empty());
+
+        // The second span found nothing, so its segment stays
+        let refined = vec![Some(vec![segment(4.0, 14.0, "spoken clearly", Some(0.9))]), Some(Vec::new()), None];
+        let texts: Vec<String> = apply(segments.to_vec(), &spans, refined).into_iter().map(|segment| segment.text).collect();
+        assert_eq!(texts, ["clear", "spoken clearly", "crosstalk", "clear", "[untranscribed audio]", "quiet"]);
+    }
+
+    #[test]
+    fn test_chunks_hold_the_audio_of_each_span() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ramp.wav");
+        let spec = hound::WavSpec { channels: 1, sample_rate: SAMPLE_RATE, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..SAMPLE_RATE * 10 {
+            writer.write_sample((i / SAMPLE_RATE) as i16 * 1_000).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let spans = vec![
+            RefineSpan { segments: 0..1, start: 1.0, end: 3.0 },
+            RefineSpan { segments: 1..2, start: 2.5, end: 2.75 },
+            RefineSpan { segments: 4..5, start: 9.0, end: 12.0 },
+        ];
+        let chunks: Vec<AudioChunk> = RefineChunks::new(AudioDecoder::default().stream(&path).unwrap(), spans)
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert_eq!(chunks.iter().map(|chunk| chunk.index).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!((chunks[0].start, chunks[0].end, chunks[0].samples.len()), (1.0, 3.0, 32_000));
+        let level = |chunk: &AudioChunk, at: usize| (chunk.samples[at] * 32_768.0 / 1_000.0).round();
+        assert_eq!((level(&chunks[0], 0), level(&chunks[0], 31_999)), (1.0, 2.0));
+        // Short spans are padded to a second, spans past the end cut short
+        assert_eq!((chunks[1].samples.len(), level(&chunks[1], 0)), (16_000, 2.0));
+        assert_eq!((chunks[2].samples.len(), level(&chunks[2], 0)), (16_000, 9.0));
+    }
+}
@@ -0,0 +1,293 @@
+use wide::f32x8;
+
+/// Taps per polyphase filter; a multiple of the SIMD lane count
+const FILTER_TAPS: usize = 32;
+
+const LANES: usize = 8;
+
+/// Average planar channels into a single mono channel.
+///
+/// Channels shorter than the first one are treated as padded with silence.
+pub fn downmix(channels: &[&[f32]]) -> Vec<f32> {
+    let Some(first) = channels.first() else {
+        return Vec::new();
+    };
+    if channels.len() == 1 {
+        return first.to_vec();
+    }
+
+    let mut mono = first.to_vec();
+    for channel in &channels[1..] {
+        add_assign(&mut mono, channel);
+    }
+    scale(&mut mono, 1.0 / channels.len() as f32);
+    mono
+}
+
+/// Polyphase windowed-sinc resampler for a fixed pair of sample rates.
+///
+/// The rate ratio is reduced to `up / down`; one low-pass kernel is
+/// precomputed per output phase so each output sample is a single
+/// contiguous dot product, which vectorises well.
+pub struct Resampler {
+    up: usize,
+    down: usize,
+    kernels: Vec<[f32; FILTER_TAPS]>,
+}
+
+impl Resampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        let divisor = gcd(from_rate as usize, to_rate as usize).max(1);
+        let up = (to_rate as usize / divisor).max(1);
+        let down = (from_rate as usize / divisor).max(1);
+
+        // Cut off at the lower of the two Nyquist frequencies
+        let cutoff = (up as f64 / down as f64).min(1.0) * 0.95;
+        let half = FILTER_TAPS as f64 / 2.0;
+
+        let kernels = (0..up)
+            .map(|phase| {
+                let frac = phase as f64 / up as f64;
+                let mut kernel = [0.0f32; FILTER_TAPS];
+                let mut sum = 0.0;
+                for (k, tap) in kernel.iter_mut().enumerate() {
+                    let x = frac + half - 1.0 - k as f64;
+                    let value = cutoff * sinc(cutoff * x) * blackman(x, half);
+                    *tap = value as f32;
+                    sum += value;
+                }
+                // Normalise for unity DC gain
+                if sum != 0.0 {
+                    kernel.iter_mut().for_each(|tap| *tap /= sum as f32);
+                }
+                kernel
+            })
+            .collect();
+
+        Self { up, down, kernels }
+    }
+
+    pub fn is_passthrough(&self) -> bool {
+        self.up == self.down
+    }
+
+    /// Resample audio that arrives in blocks, such as from a decoder
+    pub fn into_stream(self) -> ResampleStream {
+        ResampleStream { resampler: self, window: vec![0.0; FILTER_TAPS / 2], consumed: 0, received: 0, produced: 0 }
+    }
+
+    /// Resample a complete buffer
+    pub fn process(&self, input: &[f32]) -> Vec<f32> {
+        if self.is_passthrough() || input.is_empty() {
+            return input.to_vec();
+        }
+
+        // Zero-pad so every kernel window stays in bounds
+        let pad = FILTER_TAPS / 2;
+        let mut padded = vec![0.0f32; input.len() + FILTER_TAPS];
+        padded[pad..pad + input.len()].copy_from_slice(input);
+
+        let output_len = input.len() * self.up / self.down;
+        (0..output_len)
+            .map(|n| {
+                let position = n * self.down;
+                let index = position / self.up;
+                let phase = position % self.up;
+                // Window covers input[index - pad + 1 ..= index + pad]
+                let window = &padded[index + 1..index + 1 + FILTER_TAPS];
+                dot(window, &self.kernels[phase])
+            })
+            .collect()
+    }
+}
+
+/// A [`Resampler`] fed block by block. The output is the same as resampling
+/// the whole input at once; only the last filter window of input is kept
+/// between blocks.
+pub struct ResampleStream {
+    resampler: Resampler,
+    window: Vec<f32>,  // Zero-padded input from `consumed` on, not yet fully used
+    consumed: usize,   // Padded input samples dropped from the front of `window`
+    received: usize,   // Input samples pushed so far
+    produced: usize,   // Output samples returned so far
+}
+
+impl ResampleStream {
+    /// Resample the next block; returns the output it completed
+    pub fn push(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.resampler.is_passthrough() {
+            return input.to_vec();
+        }
+        self.window.extend_from_slice(input);
+        self.received += input.len();
+        self.drain()
+    }
+
+    /// Flush the output still waiting on the filter's look-ahead
+    pub fn finish(mut self) -> Vec<f32> {
+        if self.resampler.is_passthrough() {
+            return Vec::new();
+        }
+        self.window.extend_from_slice(&[0.0; FILTER_TAPS]);
+        self.drain()
+    }
+
+    fn drain(&mut self) -> Vec<f32> {
+        let Resampler { up, down, .. } = self.resampler;
+        let output_len = self.received * up / down;
+        let available = self.consumed + self.window.len();
+
+        let mut output = Vec::new();
+        while self.produced < output_len {
+            let position = self.produced * down;
+            let (index, phase) = (position / up, position % up);
+            let start = index + 1;
+            if start + FILTER_TAPS > available {
+                break;
+            }
+            let window = &self.window[start - self.consumed..start - self.consumed + FILTER_TAPS];
+            output.push(dot(window, &self.resampler.kernels[phase]));
+            self.produced += 1;
+        }
+
+        // Keep the input from the next output's window on
+        let next = (self.produced * down / up + 1).min(available);
+        self.window.drain(..next - self.consumed);
+        self.consumed = next;
+        output
+    }
+}
+
+fn add_assign(acc: &mut [f32], values: &[f32]) {
+    let len = acc.len().min(values.len());
+    let (acc, values) = (&mut acc[..len], &values[..len]);
+
+    let mut acc_chunks = acc.chunks_exact_mut(LANES);
+    let mut value_chunks = values.chunks_exact(LANES);
+    for (a, v) in (&mut acc_chunks).zip(&mut value_chunks) {
+        let sum = load(a) + load(v);
+        a.copy_from_slice(&sum.to_array());
+    }
+    for (a, v) in acc_chunks.into_remainder().iter_mut().zip(value_chunks.remainder()) {
+        *a += v;
+    }
+}
+
+fn scale(values: &mut [f32], factor: f32) {
+    let factor_lanes = f32x8::splat(factor);
+    let mut chunks = values.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        let scaled = load(chunk) * factor_lanes;
+        chunk.copy_from_slice(&scaled.to_array());
+    }
+    for value in chunks.into_remainder() {
+        *value *= factor;
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let mut acc = f32x8::ZERO;
+    let mut a_chunks = a.chunks_exact(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    for (x, y) in (&mut a_chunks).zip(&mut b_chunks) {
+        acc = load(x).mul_add(load(y), acc);
+    }
+    let tail: f32 = a_chunks.remainder().iter().zip(b_chunks.remainder()).map(|(x, y)| x * y).sum();
+    acc.reduce_add() + tail
+}
+
+fn load(values: &[f32]) -> f32x8 {
+    let mut lanes = [0.0f32; LANES];
+    lanes.copy_from_slice(values);
+    f32x8::from(lanes)
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        let px = std::f64::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+fn blackman(x: f64, half: f64) -> f64 {
+    if x.abs() > half {
+        return 0.0;
+    }
+    let t = (x + half) / (2.0 * half);
+    let tau = 2.0 * std::f64::consts::PI;
+    0.42 - 0.5 * (tau * t).cos() + 0.08 * (2.0 * tau * t).cos()
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_downmix_averages_channels() {
+        let left = [1.0; 11];
+        let right = [0.0; 11];
+
+        assert_eq!(downmix(&[&left, &right]), vec![0.5; 11]);
+        assert_eq!(downmix(&[&left]), left.to_vec());
+        assert!(downmix(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_resample_output_length() {
+        let input = vec![0.0; 48_000];
+
+        assert_eq!(Resampler::new(48_000, 16_000).process(&input).len(), 16_000);
+        assert_eq!(Resampler::new(44_100, 16_000).process(&input[..44_100]).len(), 16_000);
+        assert_eq!(Resampler::new(8_000, 16_000).process(&input[..8_000]).len(), 16_000);
+    }
+
+    #[test]
+    fn test_resample_preserves_dc_level() {
+        let input = vec![0.25; 4_800];
+        let output = Resampler::new(48_000, 16_000).process(&input);
+
+        // Ignore the filter's edge transients
+        for sample in &output[32..output.len() - 32] {
+            assert!((sample - 0.25).abs() < 1e-3, "sample {} drifted", sample);
+        }
+    }
+
+    #[test]
+    fn test_stream_matches_whole_buffer() {
+        let input: Vec<f32> = (0..10_000).map(|i| (i as f32 * 0.01).sin()).collect();
+        for (from, to) in [(44_100, 16_000), (8_000, 16_000), (16_000, 16_000)] {
+            let mut stream = Resampler::new(from, to).into_stream();
+            let mut output = Vec::new();
+            for block in input.chunks(777) {
+                output.extend(stream.push(block));
+            }
+            output.extend(stream.finish());
+            assert_eq!(output, Resampler::new(from, to).process(&input), "{} -> {}", from, to);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_simd_dot_matches_scalar(values in prop::collection::vec(-1.0f32..1.0, 0..100)) {
+            let weights: Vec<f32> = values.iter().rev().copied().collect();
+            let scalar: f32 = values.iter().zip(&weights).map(|(a, b)| a * b).sum();
+            prop_assert!((dot(&values, &weights) - scalar).abs() < 1e-4);
+        }
+
+        #[test]
+        fn prop_downmix_matches_scalar(left in prop::collection::vec(-1.0f32..1.0, 0..100)) {
+            let right: Vec<f32> = left.iter().map(|v| v * 0.5).collect();
+            let mono = downmix(&[&left, &right]);
+            for ((m, l), r) in mono.iter().zip(&left).zip(&right) {
+                prop_assert!((m - (l + r) / 2.0).abs() < 1e-6);
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+/// Picks which of the chunks prepared ahead a free worker gets next.
+///
+/// The first round gives every worker one of the shortest chunks waiting,
+/// so the first completions arrive quickly and the remaining-time estimate
+/// has real measurements early on. After that the longest waiting chunk
+/// goes first, which keeps long stragglers from all landing at the end of
+/// the run. Finished chunks are put back in file order by [`ReorderBuffer`].
+#[derive(Debug)]
+pub struct DispatchOrder {
+    workers: usize,
+    dispatched: usize,
+}
+
+impl DispatchOrder {
+    pub fn new(workers: usize) -> Self {
+        Self { workers: workers.max(1), dispatched: 0 }
+    }
+
+    /// Position in `durations`, the lengths of the waiting chunks, of the
+    /// chunk to hand out next
+    pub fn pick(&mut self, durations: &[f32]) -> usize {
+        // On a tie the chunk that comes first in the file wins
+        let positions = 0..durations.len();
+        let next = if self.dispatched < self.workers {
+            positions.min_by(|&a, &b| durations[a].total_cmp(&durations[b]))
+        } else {
+            positions.max_by(|&a, &b| durations[a].total_cmp(&durations[b]).then(b.cmp(&a)))
+        };
+        self.dispatched += 1;
+        next.unwrap_or(0)
+    }
+}
+
+/// Releases items in index order even though they complete out of order.
+/// Indices must be contiguous from zero.
+#[derive(Debug)]
+pub struct ReorderBuffer<T> {
+    next_index: usize,
+    pending: BTreeMap<usize, T>,
+}
+
+impl<T> ReorderBuffer<T> {
+    pub fn new() -> Self {
+        Self {
+            next_index: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Add a finished item and return every item that is now ready, in order
+    pub fn push(&mut self, index: usize, item: T) -> Vec<T> {
+        self.pending.insert(index, item);
+
+        let mut ready = Vec::new();
+        while let Some(item) = self.pending.remove(&self.next_index) {
+            ready.push(item);
+            self.next_index += 1;
+        }
+        ready
+    }
+
+    /// Take every held-back item in index order, skipping over gaps
+    pub fn drain(&mut self) -> Vec<T> {
+        std::mem::take(&mut self.pending).into_values().collect()
+    }
+
+    /// Number of items held back waiting for an earlier index
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+impl<T> Default for ReorderBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortest_first_then_longest() {
+        let mut order = DispatchOrder::new(2);
+        let mut waiting = vec![120.0, 30.0, 90.0, 10.0, 60.0];
+        let mut picked = Vec::new();
+        while !waiting.is_empty() {
+            picked.push(waiting.remove(order.pick(&waiting)));
+        }
+
+        assert_eq!(picked, vec![10.0, 30.0, 120.0, 90.0, 60.0]);
+    }
+
+    #[test]
+    fn test_ties_go_to_the_earlier_chunk() {
+        assert_eq!(DispatchOrder::new(1).pick(&[20.0, 20.0, 50.0]), 0);
+        let mut order = DispatchOrder::new(0);
+        order.pick(&[5.0]);
+        assert_eq!(order.pick(&[50.0, 20.0, 50.0]), 0);
+    }
+
+    #[test]
+    fn test_reorder_buffer_releases_in_order() {
+        let mut buffer = ReorderBuffer::new();
+
+        assert!(buffer.push(2, "c").is_empty());
+        assert!(buffer.push(1, "b").is_empty());
+        assert_eq!(buffer.pending(), 2);
+        assert_eq!(buffer.push(0, "a"), vec!["a", "b", "c"]);
+        assert_eq!(buffer.push(3, "d"), vec!["d"]);
+        assert_eq!(buffer.pending(), 0);
+    }
+
+    #[test]
+    fn test_reorder_buffer_drain_skips_gaps() {
+        let mut buffer = ReorderBuffer::new();
+        buffer.push(3, "d");
+        buffer.push(1, "b");
+
+        assert_eq!(buffer.drain(), vec!["b", "d"]);
+        assert_eq!(buffer.pending(), 0);
+    }
+}
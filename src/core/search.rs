@@ -0,0 +1,259 @@
+//! Full-text search over generated transcripts, backed by tantivy.
+//!
+//! Every segment is indexed as its own document, so a hit points at the
+//! transcript file, the speaker and the moment something was said.
+
+use std::path::{Path, PathBuf};
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, Value, INDEXED, STORED, STRING, TEXT};
+use tantivy::{doc, Index, IndexWriter, TantivyDocument, TantivyError, Term};
+use crate::core::audio_processor::TranscriptResult;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Memory the index writer may buffer before flushing a segment
+const WRITER_MEMORY_BYTES: usize = 50 * 1024 * 1024;
+
+/// Stored in place of a speaker for segments without one
+const NO_SPEAKER: i64 = -1;
+
+/// `<data dir>/audio-transcribe/index`
+pub fn default_index_dir() -> Result<PathBuf> {
+    let data_dir = dirs::data_dir().ok_or_else(|| AudioTranscriptionError::Configuration(
+        "Unable to determine data directory".to_string()
+    ))?;
+    Ok(data_dir.join("audio-transcribe").join("index"))
+}
+
+/// JSON transcripts among `paths`, searching directories recursively.
+/// JSON is the only output format that keeps timestamps and speakers
+/// exactly, so it is the one indexed.
+pub fn find_transcripts(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut pending: Vec<PathBuf> = paths.to_vec();
+
+    while let Some(path) = pending.pop() {
+        if !path.exists() {
+            return Err(AudioTranscriptionError::InputNotFound { path });
+        }
+        if path.is_dir() {
+            let entries = std::fs::read_dir(&path)
+                .map_err(|source| AudioTranscriptionError::File { path: path.clone(), source })?;
+            for entry in entries {
+                pending.push(entry?.path());
+            }
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            found.push(path);
+        }
+    }
+
+    found.sort();
+    Ok(found)
+}
+
+/// Read a transcript written in the JSON output format
+pub fn load_transcript(path: &Path) -> Result<TranscriptResult> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|source| AudioTranscriptionError::File { path: path.to_path_buf(), source })?;
+    Ok(audio_transcribe_format::json::parse(&json)?)
+}
+
+/// A segment matching a query
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub file: PathBuf,          // Transcript the segment was indexed from
+    pub speaker: Option<u8>,
+    pub start: f32,             // Seconds
+    pub end: f32,
+    pub text: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    file: Field,
+    speaker: Field,
+    start: Field,
+    end: Field,
+    text: Field,
+}
+
+/// On-disk index of transcript segments
+pub struct TranscriptIndex {
+    index: Index,
+    fields: Fields,
+}
+
+impl TranscriptIndex {
+    /// Open the index in `dir`, creating it if needed
+    pub fn open(dir: &Path) -> Result<Self> {
+        let (schema, fields) = schema();
+        std::fs::create_dir_all(dir)
+            .map_err(|source| AudioTranscriptionError::File { path: dir.to_path_buf(), source })?;
+        let directory = MmapDirectory::open(dir).map_err(TantivyError::from)?;
+
+        Ok(Self { index: Index::open_or_create(directory, schema)?, fields })
+    }
+
+    /// An index that is lost when dropped, for tests
+    pub fn in_memory() -> Self {
+        let (schema, fields) = schema();
+        Self { index: Index::create_in_ram(schema), fields }
+    }
+
+    /// Index the segments of each transcript. A file that was indexed
+    /// before is replaced, so re-running `index` after regenerating a
+    /// transcript doesn't leave stale segments. Returns the number of
+    /// segments indexed.
+    pub fn add<'a>(&self, transcripts: impl IntoIterator<Item = (&'a Path, &'a TranscriptResult)>) -> Result<usize> {
+        let mut writer: IndexWriter = self.index.writer(WRITER_MEMORY_BYTES)?;
+        let mut count = 0;
+
+        for (file, result) in transcripts {
+            let file = file.to_string_lossy();
+            writer.delete_term(Term::from_field_text(self.fields.file, &file));
+
+            for segment in &result.segments {
+                writer.add_document(doc!(
+                    self.fields.file => file.as_ref(),
+                    self.fields.speaker => segment.speaker.map_or(NO_SPEAKER, i64::from),
+                    self.fields.start => f64::from(segment.start),
+                    self.fields.end => f64::from(segment.end),
+                    self.fields.text => segment.text.as_str(),
+                ))?;
+                count += 1;
+            }
+        }
+
+        writer.commit()?;
+        Ok(count)
+    }
+
+    /// Best-matching segments, highest score first. All words must match
+    /// unless the query says otherwise (`budget OR cost`); quotes search
+    /// for a phrase.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let mut parser = QueryParser::for_index(&self.index, vec![self.fields.text]);
+        parser.set_conjunction_by_default();
+        let query = parser.parse_query(query).map_err(|e| AudioTranscriptionError::Configuration(
+            format!("Invalid search query '{}': {}", query, e)
+        ))?;
+
+        let searcher = self.index.reader()?.searcher();
+        let mut hits = Vec::new();
+        for (score, address) in searcher.search(&query, &TopDocs::with_limit(limit.max(1)))? {
+            let document: TantivyDocument = searcher.doc(address)?;
+            let text = |field| document.get_first(field).and_then(|value| value.as_str()).unwrap_or_default().to_string();
+            let number = |field| document.get_first(field).and_then(|value| value.as_f64()).unwrap_or_default() as f32;
+            let speaker = document.get_first(self.fields.speaker).and_then(|value| value.as_i64()).unwrap_or(NO_SPEAKER);
+
+            hits.push(SearchHit {
+                file: PathBuf::from(text(self.fields.file)),
+                speaker: u8::try_from(speaker).ok(),
+                start: number(self.fields.start),
+                end: number(self.fields.end),
+                text: text(self.fields.text),
+                score,
+            });
+        }
+        Ok(hits)
+    }
+}
+
+fn schema() -> (Schema, Fields) {
+    let mut builder = Schema::builder();
+    let fields = Fields {
+        file: builder.add_text_field("file", STRING | STORED),
+        speaker: builder.add_i64_field("speaker", INDEXED | STORED),
+        start: builder.add_f64_field("start", STORED),
+        end: builder.add_f64_field("end", STORED),
+        text: builder.add_text_field("text", TEXT | STORED),
+    };
+    (builder.build(), fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::core::audio_processor::{ModelInfo, SpeechSegment};
+
+    fn transcript(segments: &[(f32, Option<u8>, &str)]) -> TranscriptResult {
+        TranscriptResult {
+            segments: segments
+                .iter()
+                .map(|&(start, speaker, text)| SpeechSegment { start, end: start + 2.0, text: text.to_string(), speaker, ..Default::default() })
+                .collect(),
+            audio_duration: Duration::from_secs(60),
+            processing_time: Duration::from_secs(10),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_returns_file_speaker_and_time() {
+        let index = TranscriptIndex::in_memory();
+        let standup = transcript(&[(0.0, Some(1), "Morning everyone."), (754.0, Some(2), "We still need budget approval.")]);
+        let review = transcript(&[(12.0, None, "The budget looks fine.")]);
+
+        let count = index.add([(Path::new("standup.json"), &standup), (Path::new("review.json"), &review)]).unwrap();
+        assert_eq!(count, 3);
+
+        let hits = index.search("budget approval", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].file, PathBuf::from("standup.json"));
+        assert_eq!(hits[0].speaker, Some(2));
+        assert_eq!(hits[0].start, 754.0);
+
+        let hits = index.search("budget", 10).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().any(|hit| hit.speaker.is_none()));
+    }
+
+    #[test]
+    fn test_reindexing_replaces_file() {
+        let index = TranscriptIndex::in_memory();
+        index.add([(Path::new("a.json"), &transcript(&[(0.0, Some(1), "old wording")]))]).unwrap();
+        index.add([(Path::new("a.json"), &transcript(&[(0.0, Some(1), "new wording")]))]).unwrap();
+
+        assert!(index.search("old", 10).unwrap().is_empty());
+        assert_eq!(index.search("wording", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_index_persists_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        TranscriptIndex::open(dir.path()).unwrap()
+            .add([(Path::new("a.json"), &transcript(&[(0.0, Some(1), "quarterly numbers")]))])
+            .unwrap();
+
+        let hits = TranscriptIndex::open(dir.path()).unwrap().search("quarterly", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn test_find_transcripts_walks_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("2024")).unwrap();
+        std::fs::write(dir.path().join("2024").join("standup.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("standup.txt"), "").unwrap();
+        std::fs::write(dir.path().join("review.JSON"), "{}").unwrap();
+
+        let found = find_transcripts(&[dir.path().to_path_buf()]).unwrap();
+        assert_eq!(found, vec![dir.path().join("2024").join("standup.json"), dir.path().join("review.JSON")]);
+        assert_eq!(find_transcripts(&[dir.path().join("missing.json")]).unwrap_err().code(), 12);
+    }
+
+    #[test]
+    fn test_invalid_query_is_rejected() {
+        let index = TranscriptIndex::in_memory();
+        assert_eq!(index.search("speaker:", 10).unwrap_err().code(), 80);
+    }
+}
@@ -0,0 +1,182 @@
+//! Transcribing only parts of a recording.
+//!
+//! The selected ranges are decoded back to back, seeking past the audio in
+//! between where the format allows, and every stage runs on them as if they
+//! were one shorter recording. Times on that selection are placed back in
+//! the file at the end, unless the transcript is to start at zero.
+
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::core::audio_processor::SpeechSegment;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// A part of a recording, in seconds from its start
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: f32,
+    pub end: Option<f32>,  // None runs to the end of the file
+}
+
+impl TimeRange {
+    pub fn new(start: f32, end: Option<f32>) -> Self {
+        Self { start, end }
+    }
+
+    /// Seconds of the range within a file `duration` seconds long
+    fn secs(&self, duration: f32) -> f32 {
+        (self.end.unwrap_or(duration).min(duration) - self.start.min(duration)).max(0.0)
+    }
+}
+
+/// `START-END` with timestamps as for [`parse_time`]; either side may be
+/// left out, e.g. `13:20-` runs to the end of the file
+impl FromStr for TimeRange {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        let (start, end) = text
+            .split_once('-')
+            .ok_or_else(|| format!("'{}' is not a range such as 00:13:20-00:47:00", text))?;
+        let start = if start.trim().is_empty() { 0.0 } else { parse_time(start)? };
+        let end = if end.trim().is_empty() { None } else { Some(parse_time(end)?) };
+        Ok(Self { start, end })
+    }
+}
+
+/// Seconds from `HH:MM:SS`, `MM:SS` or plain seconds, each with an
+/// optional fraction, e.g. `1:02:03.5`
+pub fn parse_time(text: &str) -> std::result::Result<f32, String> {
+    let invalid = || format!("'{}' is not a time such as 00:13:20, 13:20 or 800", text);
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+    let (seconds, units) = parts.split_last().ok_or_else(invalid)?;
+    let seconds: f64 = seconds.parse().map_err(|_| invalid())?;
+    if !seconds.is_finite() || seconds < 0.0 || (!units.is_empty() && seconds >= 60.0) {
+        return Err(invalid());
+    }
+    let mut total = 0.0;
+    for (index, unit) in units.iter().enumerate() {
+        let value: u32 = unit.parse().map_err(|_| invalid())?;
+        // Minutes after hours stay below an hour
+        if index > 0 && value >= 60 {
+            return Err(invalid());
+        }
+        total = total * 60.0 + value as f64;
+    }
+    Ok((total * 60.0 + seconds) as f32)
+}
+
+/// Check that `ranges` are in order, apart and not empty. Only the last
+/// may run to the end of the file.
+pub(crate) fn validate(ranges: &[TimeRange]) -> Result<()> {
+    let invalid = |message: String| Err(AudioTranscriptionError::Configuration(message));
+    let mut previous_end = 0.0;
+    for (index, range) in ranges.iter().enumerate() {
+        if !(range.start.is_finite() && range.start >= previous_end) {
+            return invalid(format!("Time ranges must be in order and must not overlap, got one starting at {}s", range.start));
+        }
+        match range.end {
+            Some(end) if end.is_finite() && end > range.start => previous_end = end,
+            Some(end) => return invalid(format!("A time range must end after it starts, got {}s to {}s", range.start, end)),
+            None if index + 1 < ranges.len() => {
+                return invalid(format!("Only the last time range may run to the end, not the one from {}s", range.start));
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+/// Seconds of audio `ranges` select in a file `duration` seconds long; all
+/// of it when there are none
+pub(crate) fn selected_secs(ranges: &[TimeRange], duration: f32) -> f32 {
+    if ranges.is_empty() {
+        return duration;
+    }
+    ranges.iter().map(|range| range.secs(duration)).sum()
+}
+
+/// Where the span from `start` to `end` on the selection, its ranges back to
+/// back, lies in the file. A span crossing from one range into the next
+/// starts in the first and ends in the second.
+pub(crate) fn place(ranges: &[TimeRange], start: f32, end: f32) -> (f32, f32) {
+    (file_time(ranges, start, false), file_time(ranges, end, true))
+}
+
+/// [`place`] for a segment and its words
+pub(crate) fn place_segment(ranges: &[TimeRange], segment: &mut SpeechSegment) {
+    (segment.start, segment.end) = place(ranges, segment.start, segment.end);
+    for word in &mut segment.words {
+        (word.start, word.end) = place(ranges, word.start, word.end);
+    }
+}
+
+/// A time on the selection in the file. Times on the edge between two
+/// ranges are placed at the end of the first if `end` is set, else at the
+/// start of the second.
+fn file_time(ranges: &[TimeRange], time: f32, end: bool) -> f32 {
+    let mut offset = 0.0;  // Where the current range starts on the selection
+    for (index, range) in ranges.iter().enumerate() {
+        let length = range.end.map_or(f32::INFINITY, |range_end| range_end - range.start);
+        let within = if end { time <= offset + length } else { time < offset + length };
+        if within || index + 1 == ranges.len() {
+            return range.start + time - offset;
+        }
+        offset += length;
+    }
+    time
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_times_and_ranges() {
+        assert_eq!(parse_time("00:13:20"), Ok(800.0));
+        assert_eq!(parse_time("13:20"), Ok(800.0));
+        assert_eq!(parse_time("1:02:03.5"), Ok(3723.5));
+        assert_eq!(parse_time("90"), Ok(90.0));
+        assert_eq!(parse_time("120:00"), Ok(7200.0));
+        for invalid in ["", "1:60", "1:75:00", "-5", "1:2:3:4", "ten", "1:00:NaN", "inf"] {
+            assert!(parse_time(invalid).is_err(), "{} parsed", invalid);
+        }
+
+        assert_eq!("00:13:20-00:47:00".parse(), Ok(TimeRange::new(800.0, Some(2820.0))));
+        assert_eq!("13:20-".parse(), Ok(TimeRange::new(800.0, None)));
+        assert_eq!("-1:00".parse(), Ok(TimeRange::new(0.0, Some(60.0))));
+        assert!("13:20".parse::<TimeRange>().is_err());
+    }
+
+    #[test]
+    fn test_validates_ranges() {
+        assert!(validate(&[TimeRange::new(10.0, Some(20.0)), TimeRange::new(20.0, Some(30.0)), TimeRange::new(60.0, None)]).is_ok());
+        assert!(validate(&[]).is_ok());
+        assert!(validate(&[TimeRange::new(10.0, Some(5.0))]).is_err());
+        assert!(validate(&[TimeRange::new(10.0, Some(20.0)), TimeRange::new(15.0, Some(30.0))]).is_err());
+        assert!(validate(&[TimeRange::new(30.0, Some(40.0)), TimeRange::new(10.0, Some(20.0))]).is_err());
+        assert!(validate(&[TimeRange::new(10.0, None), TimeRange::new(30.0, Some(40.0))]).is_err());
+        assert!(validate(&[TimeRange::new(f32::NAN, None)]).is_err());
+    }
+
+    #[test]
+    fn test_places_selection_times_in_the_file() {
+        let ranges = [TimeRange::new(100.0, Some(110.0)), TimeRange::new(200.0, Some(205.0)), TimeRange::new(300.0, None)];
+        assert_eq!(selected_secs(&ranges, 320.0), 35.0);
+        assert_eq!(selected_secs(&ranges, 202.0), 12.0);
+        assert_eq!(selected_secs(&[], 320.0), 320.0);
+
+        assert_eq!(place(&ranges, 2.0, 4.0), (102.0, 104.0));
+        assert_eq!(place(&ranges, 8.0, 10.0), (108.0, 110.0));
+        assert_eq!(place(&ranges, 10.0, 12.0), (200.0, 202.0));
+        assert_eq!(place(&ranges, 14.0, 16.0), (204.0, 301.0));
+        assert_eq!(place(&ranges, 40.0, 45.0), (325.0, 330.0));
+        assert_eq!(place(&[], 40.0, 45.0), (40.0, 45.0));
+
+        let mut segment = SpeechSegment { start: 9.0, end: 11.0, text: "across".to_string(), ..Default::default() };
+        place_segment(&ranges, &mut segment);
+        assert_eq!((segment.start, segment.end), (109.0, 201.0));
+    }
+}
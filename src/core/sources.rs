@@ -0,0 +1,331 @@
+//! Well-known folders that recording apps save into (Zoom, OBS, Voice
+//! Memos), with per-source rules for naming transcripts and where to write
+//! them. Built-in sources can be adjusted, disabled or extended in
+//! `sources.toml`:
+//!
+//! ```toml
+//! [[source]]
+//! name = "zoom"
+//! output = "~/Notes/Meetings"
+//!
+//! [[source]]
+//! name = "obs"
+//! enabled = false
+//!
+//! [[source]]
+//! name = "dictaphone"
+//! dir = "/media/recorder/VOICE"
+//! extensions = ["mp3"]
+//! naming = "{source}-{stem}"
+//! ```
+
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::core::audio_processor::SUPPORTED_EXTENSIONS;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Placeholders available in naming templates
+pub const NAMING_PLACEHOLDERS: &[&str] = &["{source}", "{stem}", "{parent}"];
+
+/// A folder recordings appear in
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingSource {
+    pub name: String,
+    pub dir: PathBuf,               // Searched recursively
+    pub extensions: Vec<String>,    // Lowercase, without the dot
+    pub naming: Option<String>,     // Transcript name template; the input's stem when unset
+    pub output: Option<PathBuf>,    // Transcript directory; next to the recording when unset
+}
+
+/// Where a recording's transcripts go
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub source: String,
+    pub name: String,               // File name without extension
+    pub output_dir: Option<PathBuf>,
+}
+
+impl RecordingSource {
+    fn new(name: &str, dir: PathBuf) -> Self {
+        Self {
+            name: name.to_string(),
+            dir,
+            extensions: SUPPORTED_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+            naming: None,
+            output: None,
+        }
+    }
+
+    /// Whether `path` is a recording from this source
+    pub fn contains(&self, path: &Path) -> bool {
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+        path.starts_with(&self.dir) && extension.is_some_and(|ext| self.extensions.contains(&ext))
+    }
+
+    /// Recordings currently in the folder, sorted by path; empty when the
+    /// folder doesn't exist
+    pub fn recordings(&self) -> Result<Vec<PathBuf>> {
+        let mut found = Vec::new();
+        let mut pending = vec![self.dir.clone()];
+
+        while let Some(dir) = pending.pop() {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(source) => return Err(AudioTranscriptionError::File { path: dir, source }),
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if self.contains(&path) {
+                    found.push(path);
+                }
+            }
+        }
+
+        found.sort();
+        Ok(found)
+    }
+
+    /// Transcript name and directory for one of this source's recordings
+    pub fn route(&self, input: &Path) -> Route {
+        let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+        let parent = input
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .unwrap_or_default()
+            .to_string_lossy();
+
+        let name = match &self.naming {
+            Some(template) => template
+                .replace("{source}", &self.name)
+                .replace("{stem}", &stem)
+                .replace("{parent}", &parent)
+                .replace(['/', '\\'], "-"),
+            None => stem.into_owned(),
+        };
+
+        Route { source: self.name.clone(), name, output_dir: self.output.clone() }
+    }
+}
+
+/// The configured recording sources
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordingSources {
+    sources: Vec<RecordingSource>,
+}
+
+impl RecordingSources {
+    /// Default folders of common recording apps on this platform. Zoom keeps
+    /// each meeting in its own folder named after the date and topic, so
+    /// Zoom transcripts are named after that folder.
+    pub fn well_known() -> Self {
+        let mut sources = Vec::new();
+
+        if let Some(documents) = dirs::document_dir() {
+            let mut zoom = RecordingSource::new("zoom", documents.join("Zoom"));
+            zoom.naming = Some("{parent}".to_string());
+            sources.push(zoom);
+        }
+        // ~/Videos on Linux and Windows, ~/Movies on macOS
+        if let Some(videos) = dirs::video_dir() {
+            sources.push(RecordingSource::new("obs", videos));
+        }
+        if cfg!(target_os = "macos") {
+            if let Some(home) = dirs::home_dir() {
+                let recordings = home.join("Library/Group Containers/group.com.apple.VoiceMemos.shared/Recordings");
+                sources.push(RecordingSource::new("voice-memos", recordings));
+            }
+        }
+
+        Self { sources }
+    }
+
+    /// `<config dir>/audio-transcribe/sources.toml`
+    pub fn default_config_path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir().ok_or_else(|| AudioTranscriptionError::Configuration(
+            "Unable to determine config directory".to_string()
+        ))?;
+        Ok(config_dir.join("audio-transcribe").join("sources.toml"))
+    }
+
+    /// The built-in sources, adjusted by the default config file if there
+    /// is one
+    pub fn load_default() -> Result<Self> {
+        let path = Self::default_config_path()?;
+        if path.is_file() {
+            Self::from_config_file(&path)
+        } else {
+            Ok(Self::well_known())
+        }
+    }
+
+    pub fn from_config_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|source| AudioTranscriptionError::File { path: path.to_path_buf(), source })?;
+        Self::well_known().with_toml(&contents).map_err(|e| AudioTranscriptionError::Configuration(
+            format!("Invalid sources config {}: {}", path.display(), e)
+        ))
+    }
+
+    /// Apply `[[source]]` entries: entries naming an existing source change
+    /// only the fields they set, others add a source
+    pub fn with_toml(mut self, contents: &str) -> std::result::Result<Self, String> {
+        let config: SourcesConfig = toml::from_str(contents).map_err(|e| e.to_string())?;
+
+        for entry in config.source {
+            if !entry.enabled {
+                self.sources.retain(|source| source.name != entry.name);
+                continue;
+            }
+
+            let index = match self.sources.iter().position(|source| source.name == entry.name) {
+                Some(index) => index,
+                None => {
+                    let dir = entry.dir.as_deref().ok_or_else(|| format!("source '{}' needs a dir", entry.name))?;
+                    self.sources.push(RecordingSource::new(&entry.name, expand_home(dir)));
+                    self.sources.len() - 1
+                }
+            };
+
+            let source = &mut self.sources[index];
+            if let Some(dir) = &entry.dir {
+                source.dir = expand_home(dir);
+            }
+            if let Some(extensions) = entry.extensions {
+                source.extensions = extensions.iter().map(|ext| ext.trim_start_matches('.').to_lowercase()).collect();
+            }
+            if let Some(naming) = entry.naming {
+                if !NAMING_PLACEHOLDERS.iter().any(|placeholder| naming.contains(placeholder)) {
+                    return Err(format!(
+                        "naming for source '{}' must use at least one of {}",
+                        entry.name,
+                        NAMING_PLACEHOLDERS.join(", ")
+                    ));
+                }
+                source.naming = Some(naming);
+            }
+            if let Some(output) = &entry.output {
+                source.output = Some(expand_home(output));
+            }
+        }
+
+        Ok(self)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RecordingSource> {
+        self.sources.iter()
+    }
+
+    /// Route `input` by the first source containing it
+    pub fn route(&self, input: &Path) -> Option<Route> {
+        self.sources.iter().find(|source| source.contains(input)).map(|source| source.route(input))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcesConfig {
+    #[serde(default)]
+    source: Vec<SourceEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SourceEntry {
+    name: String,
+    dir: Option<String>,
+    extensions: Option<Vec<String>>,
+    naming: Option<String>,
+    output: Option<String>,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Replace a leading `~` with the home directory
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(toml: &str) -> RecordingSources {
+        let zoom = RecordingSource { naming: Some("{parent}".to_string()), ..RecordingSource::new("zoom", PathBuf::from("/home/a/Documents/Zoom")) };
+        let obs = RecordingSource::new("obs", PathBuf::from("/home/a/Videos"));
+        RecordingSources { sources: vec![zoom, obs] }.with_toml(toml).unwrap()
+    }
+
+    #[test]
+    fn test_zoom_recordings_are_named_after_their_folder() {
+        let sources = sources("");
+        let route = sources.route(Path::new("/home/a/Documents/Zoom/2024-05-01 10.00.00 Weekly sync/audio_only.m4a")).unwrap();
+
+        assert_eq!(route.source, "zoom");
+        assert_eq!(route.name, "2024-05-01 10.00.00 Weekly sync");
+        assert_eq!(route.output_dir, None);
+
+        assert!(sources.route(Path::new("/home/a/Documents/Zoom/notes.txt")).is_none());
+        assert!(sources.route(Path::new("/tmp/audio_only.m4a")).is_none());
+    }
+
+    #[test]
+    fn test_config_overrides_disables_and_adds_sources() {
+        let sources = sources(r#"
+            [[source]]
+            name = "zoom"
+            output = "/notes/meetings"
+
+            [[source]]
+            name = "obs"
+            enabled = false
+
+            [[source]]
+            name = "dictaphone"
+            dir = "/media/recorder"
+            extensions = [".MP3"]
+            naming = "{source}-{stem}"
+        "#);
+
+        let names: Vec<&str> = sources.iter().map(|source| source.name.as_str()).collect();
+        assert_eq!(names, vec!["zoom", "dictaphone"]);
+
+        let zoom = sources.route(Path::new("/home/a/Documents/Zoom/standup/audio_only.m4a")).unwrap();
+        assert_eq!(zoom.name, "standup");
+        assert_eq!(zoom.output_dir, Some(PathBuf::from("/notes/meetings")));
+
+        let memo = sources.route(Path::new("/media/recorder/VOICE/REC001.mp3")).unwrap();
+        assert_eq!(memo.name, "dictaphone-REC001");
+        assert!(sources.route(Path::new("/home/a/Videos/stream.mp3")).is_none());
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        let base = RecordingSources::default();
+        assert!(base.clone().with_toml("[[source]]\nname = \"new\"").unwrap_err().contains("needs a dir"));
+        assert!(base.clone().with_toml("[[source]]\nname = \"x\"\ndir = \"/x\"\nnaming = \"fixed\"").is_err());
+        assert!(base.with_toml("[[source]]\nname = \"x\"\ndirectory = \"/x\"").is_err());
+    }
+
+    #[test]
+    fn test_recordings_are_found_recursively() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("2024-05-01 Standup")).unwrap();
+        std::fs::write(dir.path().join("2024-05-01 Standup").join("audio_only.m4a"), b"").unwrap();
+        std::fs::write(dir.path().join("2024-05-01 Standup").join("chat.txt"), b"").unwrap();
+
+        let source = RecordingSource::new("zoom", dir.path().to_path_buf());
+        assert_eq!(source.recordings().unwrap(), vec![dir.path().join("2024-05-01 Standup").join("audio_only.m4a")]);
+        assert!(RecordingSource::new("gone", dir.path().join("missing")).recordings().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+//! Display names for speakers, from a file, so scripted pipelines can
+//! rename them without being asked.
+//!
+//! A TOML file maps a speaker's label to the name to show instead. Labels
+//! are matched regardless of case, and a recognized speaker's name can be
+//! mapped like any other label.
+//!
+//! ```toml
+//! SPEAKER_01 = "Moderator"
+//! SPEAKER_02 = "Guest"
+//! alice = "Alice Smith"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::core::audio_processor::TranscriptResult;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Names that replace speaker labels in every transcript written
+#[derive(Debug, Clone, Default)]
+pub struct SpeakerNames {
+    names: BTreeMap<String, String>,  // Lowercase label to display name
+}
+
+impl SpeakerNames {
+    pub fn from_config_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|source| AudioTranscriptionError::File { path: path.to_path_buf(), source })?;
+        Self::from_toml(&contents).map_err(|message| AudioTranscriptionError::Configuration(
+            format!("Invalid speaker names file {}: {}", path.display(), message)
+        ))
+    }
+
+    pub fn from_toml(contents: &str) -> std::result::Result<Self, String> {
+        let table: BTreeMap<String, String> = toml::from_str(contents).map_err(|e| e.to_string())?;
+        let mut names = BTreeMap::new();
+        for (label, name) in table {
+            if name.trim().is_empty() {
+                return Err(format!("the name for {} must not be empty", label));
+            }
+            if names.insert(label.to_lowercase(), name.trim().to_string()).is_some() {
+                return Err(format!("{} is named more than once", label));
+            }
+        }
+        Ok(Self { names })
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The display name for a speaker label, if it is mapped
+    pub fn get(&self, label: &str) -> Option<&str> {
+        self.names.get(&label.to_lowercase()).map(String::as_str)
+    }
+
+    /// Rename the speakers of every segment whose label is mapped
+    pub fn apply(&self, result: &TranscriptResult) -> TranscriptResult {
+        let mut renamed = result.clone();
+        for segment in renamed.segments.iter_mut().filter(|segment| segment.speaker.is_some()) {
+            if let Some(name) = self.get(&segment.speaker_label()) {
+                segment.speaker_name = Some(name.to_string());
+            }
+        }
+        renamed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::core::audio_processor::{ModelInfo, SpeechSegment};
+
+    fn result_with(segments: Vec<SpeechSegment>) -> TranscriptResult {
+        TranscriptResult {
+            segments,
+            audio_duration: Duration::from_secs(1),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_labels_are_renamed() {
+        let names = SpeakerNames::from_toml("SPEAKER_01 = \"Moderator\"\nalice = \"Alice Smith\"\n").unwrap();
+        assert_eq!(names.len(), 2);
+
+        let segment = |speaker: Option<u8>, name: Option<&str>| SpeechSegment {
+            text: "Hi.".to_string(),
+            speaker,
+            speaker_name: name.map(str::to_string),
+            ..Default::default()
+        };
+        let result = result_with(vec![segment(Some(1), None), segment(Some(2), None), segment(Some(3), Some("Alice")), segment(None, None)]);
+
+        let renamed = names.apply(&result);
+        let labels: Vec<Option<&str>> = renamed.segments.iter().map(|segment| segment.speaker_name.as_deref()).collect();
+        assert_eq!(labels, vec![Some("Moderator"), None, Some("Alice Smith"), None]);
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        assert!(SpeakerNames::from_toml("SPEAKER_01 = 1").is_err());
+        assert!(SpeakerNames::from_toml("SPEAKER_01 = \" \"").is_err());
+        assert!(SpeakerNames::from_toml("SPEAKER_01 = \"A\"\nspeaker_01 = \"B\"").is_err());
+        assert!(SpeakerNames::from_toml("").unwrap().is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("names.toml");
+        std::fs::write(&path, "[SPEAKER_01]\nname = \"x\"\n").unwrap();
+        assert_eq!(SpeakerNames::from_config_file(&path).unwrap_err().code(), 80);
+    }
+}
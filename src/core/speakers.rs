@@ -0,0 +1,206 @@
+//! Enrolled speakers: named voiceprints that diarization recognizes
+//! across recordings.
+//!
+//! A voiceprint is the mean speaker embedding of a sample of someone
+//! talking. After a recording is diarized, each speaker's centroid is
+//! compared with the enrolled voiceprints, and speakers close enough to one
+//! are labelled with its name instead of `SPEAKER_NN`. The registry is a
+//! JSON file kept in the model cache, next to the embedding model the
+//! voiceprints were made with.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::core::diarization::{dot, normalize};
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Name of the registry file in the model cache
+pub const REGISTRY_FILE: &str = "speakers.json";
+
+/// Cosine similarity a speaker needs with a voiceprint to be named after it
+pub const DEFAULT_MATCH_THRESHOLD: f32 = 0.6;
+
+/// The voice of one enrolled speaker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Voiceprint {
+    pub name: String,
+    pub embedding: Vec<f32>,  // L2-normalised mean embedding
+    pub speech_secs: f32,     // Speech the voiceprint was made from, to weigh further samples
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RegistryFile {
+    speakers: Vec<Voiceprint>,
+}
+
+/// The enrolled speakers, loaded from and saved to one file
+#[derive(Debug, Clone)]
+pub struct SpeakerRegistry {
+    path: PathBuf,
+    voiceprints: Vec<Voiceprint>,
+}
+
+impl SpeakerRegistry {
+    /// The registry kept in a model cache directory
+    pub fn in_cache_dir(cache_dir: &Path) -> Result<Self> {
+        Self::load(cache_dir.join(REGISTRY_FILE))
+    }
+
+    /// Load the registry at `path`; empty when there is no file yet
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self { path, voiceprints: Vec::new() }),
+            Err(source) => return Err(AudioTranscriptionError::File { path, source }),
+        };
+        let file: RegistryFile = serde_json::from_str(&json).map_err(|e| AudioTranscriptionError::Configuration(
+            format!("Invalid speaker registry {}: {}", path.display(), e)
+        ))?;
+        Ok(Self { path, voiceprints: file.speakers })
+    }
+
+    /// Where the registry is saved
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Enrolled speakers in the order they were first enrolled
+    pub fn voiceprints(&self) -> &[Voiceprint] {
+        &self.voiceprints
+    }
+
+    pub fn into_voiceprints(self) -> Vec<Voiceprint> {
+        self.voiceprints
+    }
+
+    /// Add a speaker from the mean embedding of `speech_secs` of their
+    /// speech. Enrolling a name again, in any case, refines the existing
+    /// voiceprint with the new sample, weighted by speech length.
+    pub fn enroll(&mut self, name: &str, embedding: &[f32], speech_secs: f32) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AudioTranscriptionError::Configuration("A speaker name must not be empty".to_string()));
+        }
+        let embedding = normalize(embedding);
+
+        match self.voiceprints.iter_mut().find(|voiceprint| voiceprint.name.eq_ignore_ascii_case(name)) {
+            Some(voiceprint) if voiceprint.embedding.len() != embedding.len() => {
+                return Err(AudioTranscriptionError::Configuration(format!(
+                    "{} was enrolled with a different embedding model; remove and enroll them again",
+                    voiceprint.name
+                )));
+            }
+            Some(voiceprint) => {
+                let total = voiceprint.speech_secs + speech_secs;
+                let mean: Vec<f32> = voiceprint.embedding
+                    .iter()
+                    .zip(&embedding)
+                    .map(|(old, new)| (old * voiceprint.speech_secs + new * speech_secs) / total.max(f32::EPSILON))
+                    .collect();
+                voiceprint.embedding = normalize(&mean);
+                voiceprint.speech_secs = total;
+            }
+            None => self.voiceprints.push(Voiceprint { name: name.to_string(), embedding, speech_secs }),
+        }
+        Ok(())
+    }
+
+    /// Forget a speaker, by name in any case; false if none was enrolled
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.voiceprints.len();
+        self.voiceprints.retain(|voiceprint| !voiceprint.name.eq_ignore_ascii_case(name.trim()));
+        self.voiceprints.len() < before
+    }
+
+    /// Write the registry. The file is replaced atomically, so an interrupt
+    /// while saving leaves the previous one intact.
+    pub fn save(&self) -> Result<()> {
+        let dir = self.path.parent().unwrap_or_else(|| Path::new("."));
+        std::fs::create_dir_all(dir)
+            .map_err(|source| AudioTranscriptionError::File { path: dir.to_path_buf(), source })?;
+        let file = RegistryFile { speakers: self.voiceprints.clone() };
+        let temporary = self.path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_vec_pretty(&file)?)
+            .map_err(|source| AudioTranscriptionError::File { path: temporary.clone(), source })?;
+        std::fs::rename(&temporary, &self.path)
+            .map_err(|source| AudioTranscriptionError::File { path: self.path.clone(), source })?;
+        Ok(())
+    }
+}
+
+/// Names for diarized speakers, from their normalised centroids. The
+/// closest speaker and voiceprint are paired first, so each enrolled voice
+/// names at most one speaker and speakers below `threshold` stay unnamed.
+pub fn identify(speakers: &[(u8, Vec<f32>)], voiceprints: &[Voiceprint], threshold: f32) -> BTreeMap<u8, String> {
+    let mut pairs: Vec<(f32, u8, usize)> = Vec::new();
+    for (speaker, centroid) in speakers {
+        for (index, voiceprint) in voiceprints.iter().enumerate() {
+            if voiceprint.embedding.len() == centroid.len() {
+                let similarity = dot(centroid, &voiceprint.embedding);
+                if similarity >= threshold {
+                    pairs.push((similarity, *speaker, index));
+                }
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut names = BTreeMap::new();
+    let mut used = vec![false; voiceprints.len()];
+    for (_, speaker, index) in pairs {
+        if !used[index] && !names.contains_key(&speaker) {
+            used[index] = true;
+            names.insert(speaker, voiceprints[index].name.clone());
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enroll_save_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registry = SpeakerRegistry::in_cache_dir(dir.path()).unwrap();
+        assert!(registry.voiceprints().is_empty());
+
+        registry.enroll("Alice", &[2.0, 0.0], 10.0).unwrap();
+        registry.enroll("bob", &[0.0, 1.0], 5.0).unwrap();
+        // A second sample of Alice is averaged in by speech length
+        registry.enroll("alice", &[0.0, 1.0], 10.0).unwrap();
+        assert!(registry.enroll(" ", &[1.0, 0.0], 1.0).is_err());
+        assert!(registry.enroll("Bob", &[1.0, 0.0, 0.0], 1.0).is_err());
+        registry.save().unwrap();
+
+        let mut reloaded = SpeakerRegistry::in_cache_dir(dir.path()).unwrap();
+        let alice = &reloaded.voiceprints()[0];
+        assert_eq!((alice.name.as_str(), alice.speech_secs), ("Alice", 20.0));
+        assert!((alice.embedding[0] - alice.embedding[1]).abs() < 1e-6);
+        assert!((dot(&alice.embedding, &alice.embedding) - 1.0).abs() < 1e-6);
+
+        assert!(reloaded.remove("BOB"));
+        assert!(!reloaded.remove("carol"));
+        assert_eq!(reloaded.voiceprints().len(), 1);
+
+        std::fs::write(dir.path().join(REGISTRY_FILE), "not json").unwrap();
+        assert_eq!(SpeakerRegistry::in_cache_dir(dir.path()).unwrap_err().code(), 80);
+    }
+
+    #[test]
+    fn test_identify_pairs_closest_voices_once() {
+        let voiceprint = |name: &str, embedding: Vec<f32>| Voiceprint { name: name.to_string(), embedding, speech_secs: 10.0 };
+        let voiceprints = [voiceprint("Alice", vec![1.0, 0.0, 0.0]), voiceprint("Bob", vec![0.0, 1.0, 0.0])];
+        let speakers = [
+            (1, normalize(&[0.9, 0.1, 0.0])),
+            (2, normalize(&[1.0, 0.05, 0.0])),  // Closer to Alice than speaker 1
+            (3, normalize(&[0.1, 1.0, 0.0])),
+            (4, vec![0.0, 0.0, 1.0]),
+        ];
+
+        let names = identify(&speakers, &voiceprints, DEFAULT_MATCH_THRESHOLD);
+        assert_eq!(names, BTreeMap::from([(2, "Alice".to_string()), (3, "Bob".to_string())]));
+        assert!(identify(&speakers, &[], DEFAULT_MATCH_THRESHOLD).is_empty());
+    }
+}
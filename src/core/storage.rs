@@ -0,0 +1,171 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use futures_util::StreamExt;
+use object_store::aws::AmazonS3Builder;
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStore, WriteMultipart};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Upload part size; S3 needs at least 5 MiB per part
+const PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Parts uploaded at the same time
+const MAX_CONCURRENT_PARTS: usize = 4;
+
+/// Whether `location` is an object storage URI rather than a local path
+pub fn is_remote(location: &str) -> bool {
+    location.starts_with("s3://") || location.starts_with("gs://")
+}
+
+/// An object (or prefix) in S3 or Google Cloud Storage
+#[derive(Debug, Clone)]
+pub struct ObjectLocation {
+    store: Arc<dyn ObjectStore>,
+    path: ObjectPath,
+    uri: String,
+}
+
+impl ObjectLocation {
+    /// Parse an `s3://bucket/key` or `gs://bucket/key` URI. Credentials and
+    /// region come from the usual `AWS_*` and `GOOGLE_*` environment variables.
+    pub fn parse(uri: &str) -> Result<Self> {
+        let (scheme, _) = uri.split_once("://").unwrap_or_default();
+        let store: Arc<dyn ObjectStore> = match scheme {
+            "s3" => Arc::new(AmazonS3Builder::from_env().with_url(uri).build().map_err(|e| storage_error(uri, e))?),
+            "gs" => Arc::new(GoogleCloudStorageBuilder::from_env().with_url(uri).build().map_err(|e| storage_error(uri, e))?),
+            _ => return Err(AudioTranscriptionError::Configuration(
+                format!("Unsupported storage URI '{}' (expected s3:// or gs://)", uri)
+            )),
+        };
+        Self::with_store(store, uri)
+    }
+
+    /// Address `uri` in an already configured store, e.g. one with custom
+    /// credentials or an S3-compatible endpoint
+    pub fn with_store(store: Arc<dyn ObjectStore>, uri: &str) -> Result<Self> {
+        let (_, rest) = uri.split_once("://").ok_or_else(|| AudioTranscriptionError::Configuration(
+            format!("Invalid storage URI '{}'", uri)
+        ))?;
+        let key = rest.split_once('/').map(|(_, key)| key).unwrap_or_default();
+
+        Ok(Self { store, path: ObjectPath::from(key), uri: uri.to_string() })
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Last segment of the key, if any
+    pub fn file_name(&self) -> Option<&str> {
+        self.path.filename()
+    }
+
+    /// Location of `name` under this prefix
+    pub fn join(&self, name: &str) -> Self {
+        Self {
+            store: Arc::clone(&self.store),
+            path: self.path.child(name),
+            uri: format!("{}/{}", self.uri.trim_end_matches('/'), name),
+        }
+    }
+
+    /// Stream the object into `dir`, keeping its file name, and return the
+    /// local path
+    pub async fn download_to(&self, dir: &Path) -> Result<PathBuf> {
+        let name = self.file_name().ok_or_else(|| AudioTranscriptionError::Configuration(
+            format!("Storage URI '{}' does not name an object", self.uri)
+        ))?;
+        let local = dir.join(name);
+        let file_error = |source| AudioTranscriptionError::File { path: local.clone(), source };
+
+        let mut stream = self.store
+            .get(&self.path)
+            .await
+            .map_err(|e| storage_error(&self.uri, e))?
+            .into_stream();
+        let mut file = tokio::fs::File::create(&local).await.map_err(file_error)?;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| storage_error(&self.uri, e))?;
+            file.write_all(&chunk).await.map_err(file_error)?;
+        }
+        file.flush().await.map_err(file_error)?;
+
+        tracing::debug!(uri = %self.uri, path = %local.display(), "Downloaded object");
+        Ok(local)
+    }
+
+    /// Stream a local file to this location as a multipart upload
+    pub async fn upload_from(&self, local: &Path) -> Result<()> {
+        let file_error = |source| AudioTranscriptionError::File { path: local.to_path_buf(), source };
+        let mut file = tokio::fs::File::open(local).await.map_err(file_error)?;
+
+        let upload = self.store.put_multipart(&self.path).await.map_err(|e| storage_error(&self.uri, e))?;
+        let mut writer = WriteMultipart::new_with_chunk_size(upload, PART_SIZE);
+        let mut buffer = vec![0; PART_SIZE];
+
+        loop {
+            let read = match file.read(&mut buffer).await {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(source) => {
+                    // Don't leave an incomplete upload behind
+                    let _ = writer.abort().await;
+                    return Err(file_error(source));
+                }
+            };
+            writer.wait_for_capacity(MAX_CONCURRENT_PARTS).await.map_err(|e| storage_error(&self.uri, e))?;
+            writer.write(&buffer[..read]);
+        }
+        writer.finish().await.map_err(|e| storage_error(&self.uri, e))?;
+
+        tracing::debug!(uri = %self.uri, path = %local.display(), "Uploaded object");
+        Ok(())
+    }
+}
+
+fn storage_error(uri: &str, error: object_store::Error) -> AudioTranscriptionError {
+    AudioTranscriptionError::ObjectStore { uri: uri.to_string(), message: error.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    #[tokio::test]
+    async fn test_upload_then_download_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("meeting.srt");
+        std::fs::write(&source, "1\n00:00:00,000 --> 00:00:01,000\nHello\n").unwrap();
+
+        let prefix = ObjectLocation::with_store(Arc::new(InMemory::new()), "s3://bucket/transcripts/").unwrap();
+        let object = prefix.join("meeting.srt");
+        assert_eq!(object.uri(), "s3://bucket/transcripts/meeting.srt");
+
+        object.upload_from(&source).await.unwrap();
+        let downloaded_dir = dir.path().join("downloaded");
+        std::fs::create_dir(&downloaded_dir).unwrap();
+        let downloaded = object.download_to(&downloaded_dir).await.unwrap();
+
+        assert_eq!(downloaded, downloaded_dir.join("meeting.srt"));
+        assert_eq!(std::fs::read(downloaded).unwrap(), std::fs::read(source).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_missing_object_reports_storage_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let object = ObjectLocation::with_store(Arc::new(InMemory::new()), "gs://bucket/missing.wav").unwrap();
+
+        assert_eq!(object.download_to(dir.path()).await.unwrap_err().code(), 42);
+    }
+
+    #[test]
+    fn test_remote_detection() {
+        assert!(is_remote("s3://bucket/a.wav"));
+        assert!(is_remote("gs://bucket/a.wav"));
+        assert!(!is_remote("/data/a.wav"));
+        assert!(ObjectLocation::parse("ftp://host/a.wav").is_err());
+    }
+}
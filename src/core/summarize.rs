@@ -0,0 +1,196 @@
+//! Optional summary of a finished transcript, produced by a chat model
+//! behind an OpenAI-compatible API such as a local llama.cpp server.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use audio_transcribe_format::OutputFormat;
+use serde::Deserialize;
+use crate::core::audio_processor::TranscriptResult;
+use crate::core::chat::{ChatClient, ChatConfig};
+use crate::error::{Result, AudioTranscriptionError};
+
+const SYSTEM_PROMPT: &str = "You summarize meeting transcripts. Reply with a JSON object with the keys \
+    \"summary\" (a short executive summary as one paragraph) and \"action_items\" (an array of strings, \
+    each naming the owner if the transcript does). Use only information from the transcript.";
+
+/// Executive summary and action items of a transcript
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Summary {
+    pub summary: String,
+    #[serde(default)]
+    pub action_items: Vec<String>,
+}
+
+impl Summary {
+    /// Read the model's reply. Models that ignore the JSON instruction
+    /// still produce a usable summary, just without separate action items.
+    fn parse(reply: &str) -> Self {
+        let reply = reply.trim();
+        serde_json::from_str(reply).unwrap_or_else(|_| Summary {
+            summary: reply.to_string(),
+            action_items: Vec::new(),
+        })
+    }
+
+    /// The section appended to Markdown transcripts
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("\n## Executive Summary\n\n{}\n", self.summary);
+        if !self.action_items.is_empty() {
+            out.push_str("\n## Action Items\n\n");
+            for item in &self.action_items {
+                out.push_str(&format!("- [ ] {}\n", item));
+            }
+        }
+        out
+    }
+
+    /// The section appended to plain-text transcripts
+    pub fn to_text(&self) -> String {
+        let mut out = format!("\n[EXECUTIVE SUMMARY]\n{}\n", self.summary);
+        if !self.action_items.is_empty() {
+            out.push_str("\n[ACTION ITEMS]\n");
+            for item in &self.action_items {
+                out.push_str(&format!("- {}\n", item));
+            }
+        }
+        out
+    }
+
+    /// Append the summary to a transcript written to `path`. Text and
+    /// Markdown files get a section at the end; other formats would no
+    /// longer parse, so the summary goes to `<name>.summary.md` next to them.
+    /// Returns the file the summary was written to.
+    pub fn append_to(&self, path: &Path) -> Result<PathBuf> {
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        let (target, section) = match OutputFormat::from_name(extension) {
+            Some(OutputFormat::Text) => (path.to_path_buf(), self.to_text()),
+            Some(OutputFormat::Markdown) => (path.to_path_buf(), self.to_markdown()),
+            _ => (path.with_extension("summary.md"), self.to_markdown()),
+        };
+
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&target)
+            .and_then(|mut file| file.write_all(section.as_bytes()))
+            .map_err(|source| AudioTranscriptionError::File { path: target.clone(), source })?;
+        Ok(target)
+    }
+
+    /// Append the summary to each of a run's transcripts. Every text and
+    /// Markdown transcript gets it; `<name>.summary.md` is only written when
+    /// none of them can hold it. Returns the files written to.
+    pub fn append_to_all(&self, paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let holds_summary = |path: &&PathBuf| {
+            let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+            matches!(OutputFormat::from_name(extension), Some(OutputFormat::Text | OutputFormat::Markdown))
+        };
+        let mut targets: Vec<&PathBuf> = paths.iter().filter(holds_summary).collect();
+        if targets.is_empty() {
+            targets.extend(paths.first());
+        }
+        targets.into_iter().map(|path| self.append_to(path)).collect()
+    }
+}
+
+/// Produces a [`Summary`] with a chat model
+pub struct Summarizer {
+    chat: ChatClient,
+}
+
+impl Summarizer {
+    pub fn new(config: ChatConfig) -> Result<Self> {
+        Ok(Self { chat: ChatClient::new(config)? })
+    }
+
+    pub fn config(&self) -> &ChatConfig {
+        self.chat.config()
+    }
+
+    /// Send the transcript, with speaker labels, to the model and return
+    /// its summary
+    pub async fn summarize(&self, result: &TranscriptResult) -> Result<Summary> {
+        tracing::info!(endpoint = %self.config().endpoint, model = %self.config().model, "Requesting transcript summary");
+        let reply = self.chat.complete_json(SYSTEM_PROMPT, &OutputFormat::Text.render(result)).await?;
+        Ok(Summary::parse(&reply))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::core::chat;
+    use crate::core::audio_processor::{ModelInfo, SpeechSegment};
+
+    fn sample_result() -> TranscriptResult {
+        TranscriptResult {
+            segments: vec![SpeechSegment { start: 0.0, end: 1.5, text: "Ship it Friday.".to_string(), speaker: Some(1), ..Default::default() }],
+            audio_duration: Duration::from_secs(2),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_json_and_plain_replies() {
+        let summary = Summary::parse("{\"summary\": \"Release planned.\", \"action_items\": [\"Ship Friday\"]}");
+        assert_eq!(summary.summary, "Release planned.");
+        assert_eq!(summary.action_items, vec!["Ship Friday"]);
+
+        let summary = Summary::parse("The team plans a release.");
+        assert_eq!(summary.summary, "The team plans a release.");
+        assert!(summary.action_items.is_empty());
+    }
+
+    #[test]
+    fn test_append_to_text_and_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let summary = Summary { summary: "Release planned.".to_string(), action_items: vec!["Ship Friday".to_string()] };
+
+        let text = dir.path().join("standup.txt");
+        std::fs::write(&text, "[SPEAKER_01]\nShip it Friday.\n").unwrap();
+        assert_eq!(summary.append_to(&text).unwrap(), text);
+        assert_eq!(
+            std::fs::read_to_string(&text).unwrap(),
+            "[SPEAKER_01]\nShip it Friday.\n\n[EXECUTIVE SUMMARY]\nRelease planned.\n\n[ACTION ITEMS]\n- Ship Friday\n"
+        );
+
+        let srt = dir.path().join("standup.srt");
+        let sidecar = summary.append_to(&srt).unwrap();
+        assert_eq!(sidecar, dir.path().join("standup.summary.md"));
+        assert!(std::fs::read_to_string(sidecar).unwrap().contains("- [ ] Ship Friday"));
+        assert!(!srt.exists());
+    }
+
+    #[test]
+    fn test_append_to_several_transcripts() {
+        let dir = tempfile::tempdir().unwrap();
+        let summary = Summary { summary: "Release planned.".to_string(), action_items: Vec::new() };
+        let path = |name: &str| dir.path().join(name);
+
+        let written = summary.append_to_all(&[path("a.srt"), path("a.txt"), path("a.md")]).unwrap();
+        assert_eq!(written, vec![path("a.txt"), path("a.md")]);
+        assert!(!path("a.summary.md").exists());
+
+        let written = summary.append_to_all(&[path("b.srt"), path("b.json")]).unwrap();
+        assert_eq!(written, vec![path("b.summary.md")]);
+        assert_eq!(std::fs::read_to_string(path("b.summary.md")).unwrap().matches("Release planned.").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_sends_transcript() {
+        let (endpoint, server) = chat::mock_endpoint("{\"summary\": \"Release planned.\", \"action_items\": [\"Ship Friday\"]}").await;
+        let summarizer = Summarizer::new(ChatConfig { endpoint, ..ChatConfig::default() }).unwrap();
+
+        let summary = summarizer.summarize(&sample_result()).await.unwrap();
+        assert_eq!(summary.action_items, vec!["Ship Friday"]);
+        assert!(server.await.unwrap().contains("Ship it Friday."));
+    }
+}
@@ -1,73 +1,223 @@
 use std::path::{Path, PathBuf};
-use crate::core::audio_processor::{SpeechSegment, TranscriptResult};
+use audio_transcribe_format::{OutputFormat, OutputFormatter};
+use crate::core::audio_processor::TranscriptResult;
+use crate::core::corrections::Corrections;
+use crate::core::meeting::MeetingInfo;
+use crate::core::speaker_names::SpeakerNames;
 use crate::error::{Result, AudioTranscriptionError};
 
+/// Format used when none is requested
+pub const DEFAULT_FORMAT: &str = "txt";
+
 pub struct TranscriptGenerator {
     output_dir: Option<PathBuf>,
+    formatters: Vec<Box<dyn OutputFormatter>>,
+    meeting: Option<MeetingInfo>,   // Header for plain-text and Markdown transcripts
+    output_name: Option<String>,    // File name without extension; the input's stem when unset
+    corrections: Option<Corrections>,  // Applied to the text of every format written
+    speaker_names: Option<SpeakerNames>,  // Display names for speaker labels in every format
 }
 
 impl TranscriptGenerator {
     pub fn new(output_dir: Option<PathBuf>) -> Self {
-        Self { output_dir }
+        let formatters = OutputFormat::ALL
+            .iter()
+            .map(|format| Box::new(*format) as Box<dyn OutputFormatter>)
+            .collect();
+
+        Self { output_dir, formatters, meeting: None, output_name: None, corrections: None, speaker_names: None }
+    }
+
+    /// Add a custom output format. A formatter with the same name as an
+    /// existing one replaces it.
+    pub fn register_formatter(&mut self, formatter: Box<dyn OutputFormatter>) {
+        self.formatters.retain(|existing| existing.name() != formatter.name());
+        self.formatters.push(formatter);
+    }
+
+    /// Look up a registered formatter by name
+    pub fn formatter(&self, name: &str) -> Option<&dyn OutputFormatter> {
+        self.formatters
+            .iter()
+            .find(|formatter| formatter.name().eq_ignore_ascii_case(name))
+            .map(|formatter| formatter.as_ref())
+    }
+
+    /// Like [`formatter`](Self::formatter), but an unknown name is a
+    /// configuration error listing the available formats
+    pub fn require_formatter(&self, name: &str) -> Result<&dyn OutputFormatter> {
+        self.formatter(name).ok_or_else(|| AudioTranscriptionError::Configuration(
+            format!("Unknown output format '{}' (available: {})", name, self.format_names().join(", "))
+        ))
+    }
+
+    /// Look up several formats at once. Formats that would write the same
+    /// file, such as `md` and `notes`, cannot be combined.
+    pub fn require_formatters<S: AsRef<str>>(&self, names: &[S]) -> Result<Vec<&dyn OutputFormatter>> {
+        let mut formatters: Vec<&dyn OutputFormatter> = Vec::with_capacity(names.len());
+        for name in names {
+            let formatter = self.require_formatter(name.as_ref())?;
+            match formatters.iter().find(|other| other.extension() == formatter.extension()) {
+                // The same format listed twice is written once
+                Some(other) if other.name() == formatter.name() => {}
+                Some(other) => {
+                    return Err(AudioTranscriptionError::Configuration(format!(
+                        "Formats '{}' and '{}' both write .{} files",
+                        other.name(),
+                        formatter.name(),
+                        formatter.extension()
+                    )));
+                }
+                None => formatters.push(formatter),
+            }
+        }
+        Ok(formatters)
+    }
+
+    /// Names of all registered formats
+    pub fn format_names(&self) -> Vec<&str> {
+        self.formatters.iter().map(|formatter| formatter.name()).collect()
     }
 
     pub fn generate_transcript(&self, input_path: &Path, result: &TranscriptResult) -> Result<PathBuf> {
-        let output_path = self.determine_output_path(input_path)?;
-        let formatted_transcript = self.format_transcript(&result.segments)?;
-        
-        // TODO: Write transcript to file
-        // This will be implemented in task 11
-        log::info!("Generated transcript: {}", output_path.display());
-        
-        // Placeholder - write to file
-        std::fs::write(&output_path, formatted_transcript)?;
-        
+        self.generate(input_path, result, DEFAULT_FORMAT)
+    }
+
+    /// Write the transcript in the named format next to the input file (or
+    /// into the output directory)
+    pub fn generate(&self, input_path: &Path, result: &TranscriptResult, format: &str) -> Result<PathBuf> {
+        self.write(input_path, result, format, None)
+    }
+
+    /// Write the transcript in each of the named formats, checking all of
+    /// them before anything is written
+    pub fn generate_all<S: AsRef<str>>(&self, input_path: &Path, result: &TranscriptResult, formats: &[S]) -> Result<Vec<PathBuf>> {
+        self.write_all(input_path, result, formats, None)
+    }
+
+    /// Write a translated transcript as `<name>.<language>.<ext>`, so it sits
+    /// next to the original
+    pub fn generate_translation(
+        &self,
+        input_path: &Path,
+        result: &TranscriptResult,
+        format: &str,
+        language: &str,
+    ) -> Result<PathBuf> {
+        self.write(input_path, result, format, Some(language))
+    }
+
+    /// [`generate_translation`](Self::generate_translation) in each of the
+    /// named formats
+    pub fn generate_translations<S: AsRef<str>>(
+        &self,
+        input_path: &Path,
+        result: &TranscriptResult,
+        formats: &[S],
+        language: &str,
+    ) -> Result<Vec<PathBuf>> {
+        self.write_all(input_path, result, formats, Some(language))
+    }
+
+    /// The transcript in the named format as it would be written, with
+    /// speaker names, corrections and the meeting header applied
+    pub fn render(&self, result: &TranscriptResult, format: &str) -> Result<Vec<u8>> {
+        let formatter = self.require_formatter(format)?;
+        let renamed = self.speaker_names.as_ref().map(|names| names.apply(result));
+        let result = renamed.as_ref().unwrap_or(result);
+        let corrected = self.corrections.as_ref().map(|corrections| corrections.apply(result));
+        let mut contents = formatter.format(corrected.as_ref().unwrap_or(result));
+        let header = match formatter.name() {
+            "txt" => self.meeting.as_ref().and_then(|meeting| meeting.header(false)),
+            "md" => self.meeting.as_ref().and_then(|meeting| meeting.header(true)),
+            _ => None,
+        };
+        if let Some(header) = header {
+            contents.splice(0..0, header.into_bytes());
+        }
+        Ok(contents)
+    }
+
+    fn write_all<S: AsRef<str>>(
+        &self,
+        input_path: &Path,
+        result: &TranscriptResult,
+        formats: &[S],
+        suffix: Option<&str>,
+    ) -> Result<Vec<PathBuf>> {
+        self.require_formatters(formats)?
+            .into_iter()
+            .map(|formatter| self.write(input_path, result, formatter.name(), suffix))
+            .collect()
+    }
+
+    fn write(&self, input_path: &Path, result: &TranscriptResult, format: &str, suffix: Option<&str>) -> Result<PathBuf> {
+        let formatter = self.require_formatter(format)?;
+
+        // An interrupted run is marked in the name, so it never passes for
+        // (or overwrites) a complete transcript
+        let mut extension = formatter.extension().to_string();
+        if result.partial {
+            extension = format!("partial.{}", extension);
+        }
+        if let Some(suffix) = suffix {
+            extension = format!("{}.{}", suffix, extension);
+        }
+        let output_path = self.determine_output_path(input_path, &extension)?;
+        let contents = self.render(result, format)?;
+
+        std::fs::write(&output_path, contents)
+            .map_err(|source| AudioTranscriptionError::File { path: output_path.clone(), source })?;
+
+        tracing::info!(path = %output_path.display(), format = formatter.name(), "Generated transcript");
         Ok(output_path)
     }
 
-    fn determine_output_path(&self, input_path: &Path) -> Result<PathBuf> {
-        let base_name = input_path
-            .file_stem()
-            .ok_or_else(|| AudioTranscriptionError::Configuration("Invalid input file path".to_string()))?
-            .to_string_lossy();
+    /// Where a file with `extension` (without the dot) belonging to the
+    /// transcript of `input_path` goes, e.g. `log.jsonl` for the run log
+    pub fn output_path(&self, input_path: &Path, extension: &str) -> Result<PathBuf> {
+        self.determine_output_path(input_path, extension)
+    }
+
+    fn determine_output_path(&self, input_path: &Path, extension: &str) -> Result<PathBuf> {
+        let base_name = match &self.output_name {
+            Some(name) => name.clone(),
+            None => input_path
+                .file_stem()
+                .ok_or_else(|| AudioTranscriptionError::Configuration("Invalid input file path".to_string()))?
+                .to_string_lossy()
+                .into_owned(),
+        };
 
         let output_dir = self.output_dir
             .as_ref()
             .map(|p| p.as_path())
             .unwrap_or_else(|| input_path.parent().unwrap_or_else(|| Path::new(".")));
 
-        let output_path = output_dir.join(format!("{}.txt", base_name));
+        let output_path = output_dir.join(format!("{}.{}", base_name, extension));
         Ok(output_path)
     }
 
-    fn format_transcript(&self, segments: &[SpeechSegment]) -> Result<String> {
-        // TODO: Implement proper transcript formatting with speaker labels
-        // This will be implemented in task 11
-        let mut output = String::new();
-        let mut current_speaker: Option<u8> = None;
+    /// Show the meeting's title, date and attendees above plain-text and
+    /// Markdown transcripts
+    pub fn set_meeting(&mut self, meeting: Option<MeetingInfo>) {
+        self.meeting = meeting;
+    }
 
-        for segment in segments {
-            // Check if speaker changed
-            if segment.speaker != current_speaker {
-                if current_speaker.is_some() {
-                    output.push('\n'); // Empty line between speakers
-                }
-                
-                if let Some(speaker_id) = segment.speaker {
-                    output.push_str(&format!("[SPEAKER_{:02}]\n", speaker_id));
-                } else {
-                    output.push_str("[SPEAKER_00]\n");
-                }
-                
-                current_speaker = segment.speaker;
-            }
+    /// Fix recurring mishearings in every transcript written from now on
+    pub fn set_corrections(&mut self, corrections: Option<Corrections>) {
+        self.corrections = corrections;
+    }
 
-            // Add the transcribed text
-            output.push_str(&segment.text);
-            output.push('\n');
-        }
+    /// Show speakers under these names in every transcript written from
+    /// now on
+    pub fn set_speaker_names(&mut self, speaker_names: Option<SpeakerNames>) {
+        self.speaker_names = speaker_names;
+    }
 
-        Ok(output)
+    /// Name transcripts `<name>.<ext>` instead of after the input file
+    pub fn set_output_name(&mut self, name: Option<String>) {
+        self.output_name = name;
     }
 
     pub fn set_output_dir(&mut self, output_dir: Option<PathBuf>) {
@@ -77,4 +227,168 @@ impl TranscriptGenerator {
     pub fn output_dir(&self) -> Option<&PathBuf> {
         self.output_dir.as_ref()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::core::audio_processor::{ModelInfo, SpeechSegment};
+
+    struct CsvFormatter;
+
+    impl OutputFormatter for CsvFormatter {
+        fn name(&self) -> &str {
+            "csv"
+        }
+
+        fn extension(&self) -> &str {
+            "csv"
+        }
+
+        fn format(&self, result: &TranscriptResult) -> Vec<u8> {
+            result.segments
+                .iter()
+                .map(|s| format!("{},{},{}\n", s.start, s.end, s.text))
+                .collect::<String>()
+                .into_bytes()
+        }
+    }
+
+    fn sample_result() -> TranscriptResult {
+        TranscriptResult {
+            segments: vec![SpeechSegment { start: 0.0, end: 1.5, text: "Hello.".to_string(), speaker: Some(1), ..Default::default() }],
+            audio_duration: Duration::from_secs(2),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_with_builtin_and_custom_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut generator = TranscriptGenerator::new(Some(dir.path().to_path_buf()));
+        generator.register_formatter(Box::new(CsvFormatter));
+
+        let input = Path::new("/recordings/meeting.wav");
+        let text = generator.generate_transcript(input, &sample_result()).unwrap();
+        let csv = generator.generate(input, &sample_result(), "CSV").unwrap();
+
+        assert_eq!(text, dir.path().join("meeting.txt"));
+        assert_eq!(std::fs::read_to_string(text).unwrap(), "[SPEAKER_01]\nHello.\n");
+        assert_eq!(std::fs::read_to_string(csv).unwrap(), "0,1.5,Hello.\n");
+        assert_eq!(generator.render(&sample_result(), "txt").unwrap(), b"[SPEAKER_01]\nHello.\n");
+    }
+
+    #[test]
+    fn test_generate_several_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let generator = TranscriptGenerator::new(Some(dir.path().to_path_buf()));
+        let input = Path::new("meeting.wav");
+
+        let paths = generator.generate_all(input, &sample_result(), &["txt", "srt", "json", "srt"]).unwrap();
+        assert_eq!(paths, vec![
+            dir.path().join("meeting.txt"),
+            dir.path().join("meeting.srt"),
+            dir.path().join("meeting.json"),
+        ]);
+
+        let paths = generator.generate_translations(input, &sample_result(), &["txt", "vtt"], "de").unwrap();
+        assert_eq!(paths, vec![dir.path().join("meeting.de.txt"), dir.path().join("meeting.de.vtt")]);
+
+        // Nothing is written when any format is unusable
+        for formats in [["vtt", "pdf"], ["md", "notes"]] {
+            assert_eq!(generator.generate_all(Path::new("other.wav"), &sample_result(), &formats).unwrap_err().code(), 80);
+        }
+        assert!(!dir.path().join("other.vtt").exists());
+        assert!(!dir.path().join("other.md").exists());
+    }
+
+    #[test]
+    fn test_translation_is_written_alongside() {
+        let dir = tempfile::tempdir().unwrap();
+        let generator = TranscriptGenerator::new(Some(dir.path().to_path_buf()));
+
+        let path = generator.generate_translation(Path::new("meeting.wav"), &sample_result(), "srt", "de").unwrap();
+        assert_eq!(path, dir.path().join("meeting.de.srt"));
+    }
+
+    #[test]
+    fn test_partial_transcript_is_marked() {
+        let dir = tempfile::tempdir().unwrap();
+        let generator = TranscriptGenerator::new(Some(dir.path().to_path_buf()));
+        let result = TranscriptResult { partial: true, ..sample_result() };
+
+        let path = generator.generate(Path::new("meeting.wav"), &result, "srt").unwrap();
+        assert_eq!(path, dir.path().join("meeting.partial.srt"));
+
+        let path = generator.generate_translation(Path::new("meeting.wav"), &result, "txt", "de").unwrap();
+        assert_eq!(path, dir.path().join("meeting.de.partial.txt"));
+    }
+
+    #[test]
+    fn test_output_name_replaces_input_stem() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut generator = TranscriptGenerator::new(Some(dir.path().to_path_buf()));
+        generator.set_output_name(Some("2024-05-01 Standup".to_string()));
+
+        let path = generator.generate_translation(Path::new("audio_only.m4a"), &sample_result(), "txt", "de").unwrap();
+        assert_eq!(path, dir.path().join("2024-05-01 Standup.de.txt"));
+    }
+
+    #[test]
+    fn test_meeting_header_on_text_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut generator = TranscriptGenerator::new(Some(dir.path().to_path_buf()));
+        generator.set_meeting(Some(MeetingInfo {
+            title: Some("Standup".to_string()),
+            attendees: vec!["Alice".to_string()],
+            ..MeetingInfo::default()
+        }));
+
+        let text = generator.generate_transcript(Path::new("meeting.wav"), &sample_result()).unwrap();
+        assert_eq!(std::fs::read_to_string(text).unwrap(), "Standup\nAttendees: Alice\n\n[SPEAKER_01]\nHello.\n");
+
+        let srt = generator.generate(Path::new("meeting.wav"), &sample_result(), "srt").unwrap();
+        assert!(std::fs::read_to_string(srt).unwrap().starts_with("1\n"));
+    }
+
+    #[test]
+    fn test_corrections_apply_to_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut generator = TranscriptGenerator::new(Some(dir.path().to_path_buf()));
+        generator.set_corrections(Some(Corrections::from_toml("[exact]\nhello = \"Hi\"").unwrap()));
+
+        for path in generator.generate_all(Path::new("meeting.wav"), &sample_result(), &["txt", "srt"]).unwrap() {
+            let contents = std::fs::read_to_string(path).unwrap();
+            assert!(contents.contains("Hi.") && !contents.contains("Hello"));
+        }
+    }
+
+    #[test]
+    fn test_speaker_names_apply_to_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut generator = TranscriptGenerator::new(Some(dir.path().to_path_buf()));
+        generator.set_speaker_names(Some(SpeakerNames::from_toml("SPEAKER_01 = \"Moderator\"").unwrap()));
+
+        let paths = generator.generate_all(Path::new("meeting.wav"), &sample_result(), &["txt", "vtt", "json"]).unwrap();
+        assert_eq!(std::fs::read_to_string(&paths[0]).unwrap(), "[Moderator]\nHello.\n");
+        assert!(std::fs::read_to_string(&paths[1]).unwrap().contains("<v Moderator>Hello.</v>"));
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&paths[2]).unwrap()).unwrap();
+        assert_eq!(json["segments"][0]["speaker_name"], "Moderator");
+    }
+
+    #[test]
+    fn test_unknown_format_is_rejected() {
+        let generator = TranscriptGenerator::new(None);
+
+        let error = generator.generate(Path::new("a.wav"), &sample_result(), "docx").unwrap_err();
+        assert_eq!(error.code(), 80);
+    }
+}
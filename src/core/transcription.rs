@@ -0,0 +1,473 @@
+use std::ffi::c_int;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState, WhisperTokenId};
+use crate::core::audio_processor::{AudioChunk, ProcessingConfig, SpeechSegment, WordTiming};
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Whisper reports segment times in centiseconds
+const CENTISECONDS: f32 = 0.01;
+
+/// Language codes Whisper can transcribe, as accepted by `--language`
+pub const LANGUAGES: &[&str] = &[
+    "en", "zh", "de", "es", "ru", "ko", "fr", "ja", "pt", "tr", "pl", "ca", "nl", "ar", "sv", "it", "id", "hi",
+    "fi", "vi", "he", "uk", "el", "ms", "cs", "ro", "da", "hu", "ta", "no", "th", "ur", "hr", "bg", "lt", "la",
+    "mi", "ml", "cy", "sk", "te", "fa", "lv", "bn", "sr", "az", "sl", "kn", "et", "mk", "br", "eu", "is", "hy",
+    "ne", "mn", "bs", "kk", "sq", "sw", "gl", "mr", "pa", "si", "km", "sn", "yo", "so", "af", "oc", "ka", "be",
+    "tg", "sd", "gu", "am", "yi", "lo", "uz", "fo", "ht", "ps", "tk", "nn", "mt", "sa", "lb", "my", "bo", "tl",
+    "mg", "as", "tt", "haw", "ln", "ha", "ba", "jw", "su", "yue",
+];
+
+/// Audio Whisper looks at to detect the language
+pub const LANGUAGE_DETECTION_SECS: f32 = 30.0;
+
+/// Most decoders whisper.cpp runs at once, which bounds the beam size and
+/// best-of
+pub const MAX_DECODERS: usize = 8;
+
+/// How Whisper picks tokens. The defaults decode greedily, falling back to
+/// sampling at higher temperatures when a decode looks like it failed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodingOptions {
+    pub beam_size: Option<usize>,    // Beam search with this many beams; None decodes greedily
+    pub best_of: usize,              // Candidates sampled at each fallback temperature, greedy only
+    pub temperature: f32,            // Temperature of the first attempt; 0 always takes the likeliest token
+    pub temperature_increment: f32,  // Added on each fallback; 0 turns fallback off
+    pub no_speech_threshold: f32,    // Probability of no speech above which a window is left empty
+}
+
+impl Default for DecodingOptions {
+    fn default() -> Self {
+        Self {
+            beam_size: None,
+            best_of: 1,
+            temperature: 0.0,
+            temperature_increment: 0.2,
+            no_speech_threshold: 0.6,
+        }
+    }
+}
+
+impl DecodingOptions {
+    /// Check that whisper.cpp can decode with these options
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(AudioTranscriptionError::Configuration(message));
+
+        if let Some(beam_size) = self.beam_size.filter(|beam_size| !(1..=MAX_DECODERS).contains(beam_size)) {
+            return invalid(format!("Beam size must be between 1 and {}, got {}", MAX_DECODERS, beam_size));
+        }
+        if !(1..=MAX_DECODERS).contains(&self.best_of) {
+            return invalid(format!("Best-of must be between 1 and {}, got {}", MAX_DECODERS, self.best_of));
+        }
+        if self.beam_size.is_some() && self.best_of > 1 {
+            return invalid("Best-of only applies to greedy decoding, not beam search".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.temperature) {
+            return invalid(format!("Temperature must be between 0 and 1, got {}", self.temperature));
+        }
+        if !(0.0..=1.0).contains(&self.temperature_increment) {
+            return invalid(format!("Temperature increment must be between 0 and 1, got {}", self.temperature_increment));
+        }
+        if !(0.0..=1.0).contains(&self.no_speech_threshold) {
+            return invalid(format!("No-speech threshold must be between 0 and 1, got {}", self.no_speech_threshold));
+        }
+        Ok(())
+    }
+
+    fn params<'a, 'b>(&self) -> FullParams<'a, 'b> {
+        let strategy = match self.beam_size {
+            Some(beam_size) => SamplingStrategy::BeamSearch { beam_size: beam_size as c_int, patience: -1.0 },
+            None => SamplingStrategy::Greedy { best_of: self.best_of as c_int },
+        };
+        let mut params = FullParams::new(strategy);
+        params.set_temperature(self.temperature);
+        params.set_temperature_inc(self.temperature_increment);
+        params.set_no_speech_thold(self.no_speech_threshold);
+        params
+    }
+}
+
+/// GPU backend whisper.cpp runs on. Backends are compiled in with the
+/// `metal`, `cuda` and `vulkan` features; `Auto` uses whichever is there.
+/// whisper.cpp picks the backend at build time, so a configured backend is
+/// only checked up front with [`ensure_compiled`](Self::ensure_compiled)
+/// and never passed to the model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum GpuBackend {
+    #[default]
+    Auto,
+    Metal,
+    Cuda,
+    Vulkan,
+}
+
+impl GpuBackend {
+    /// The backends this build can run on
+    pub fn compiled() -> Vec<GpuBackend> {
+        [
+            (GpuBackend::Metal, cfg!(feature = "metal")),
+            (GpuBackend::Cuda, cfg!(feature = "cuda")),
+            (GpuBackend::Vulkan, cfg!(feature = "vulkan")),
+        ]
+        .into_iter()
+        .filter_map(|(backend, compiled)| compiled.then_some(backend))
+        .collect()
+    }
+
+    /// Fail with [`AudioTranscriptionError::GpuUnavailable`] unless this
+    /// backend is compiled in
+    pub fn ensure_compiled(self) -> Result<()> {
+        let compiled = GpuBackend::compiled();
+        if self == GpuBackend::Auto || compiled.contains(&self) {
+            return Ok(());
+        }
+        let available = match compiled.as_slice() {
+            [] => "none".to_string(),
+            backends => backends.iter().map(GpuBackend::to_string).collect::<Vec<_>>().join(", "),
+        };
+        Err(AudioTranscriptionError::GpuUnavailable {
+            device: None,
+            message: format!("the {} backend is not compiled in; rebuild with `--features {}` (compiled in: {})", self, self, available),
+        })
+    }
+}
+
+impl std::fmt::Display for GpuBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuBackend::Auto => write!(f, "auto"),
+            GpuBackend::Metal => write!(f, "metal"),
+            GpuBackend::Cuda => write!(f, "cuda"),
+            GpuBackend::Vulkan => write!(f, "vulkan"),
+        }
+    }
+}
+
+/// One transcription worker bound to a single device.
+///
+/// Each worker owns its own inference state, so workers never contend for
+/// the same decoder; workers on the same device share the loaded model.
+pub struct TranscriptionWorker {
+    device: Option<usize>,  // GPU index, or None for CPU
+    state: WhisperState,    // Keeps the model it was created from alive
+    threads: usize,         // CPU threads per inference
+    overlap: f32,           // Seconds each chunk after the first repeats
+    word_timestamps: bool,
+    language: Option<String>,   // None lets Whisper detect it per chunk
+    translate: bool,            // Emit English text whatever the spoken language
+    decoding: DecodingOptions,
+    initial_prompt: Option<String>,  // Terms Whisper is primed with before every chunk
+    token_eot: WhisperTokenId,  // Token IDs from here on are control and timestamp tokens
+    tokens: u64,                // Decoded so far, for throughput
+}
+
+/// A decoded text token, with times in seconds from the start of the file
+#[derive(Debug, Clone)]
+struct Token {
+    bytes: Vec<u8>,  // Multi-byte characters can be split across tokens
+    start: f32,
+    end: f32,
+    probability: f32,
+}
+
+impl TranscriptionWorker {
+    pub fn new(device: Option<usize>, model: &WhisperContext, config: &ProcessingConfig) -> Result<Self> {
+        let state = model.create_state().map_err(|e| AudioTranscriptionError::Model(
+            format!("Failed to create Whisper state: {}", e)
+        ))?;
+
+        // CPU cores are split evenly between the workers running at once
+        let threads = (num_cpus::get() / worker_devices(config).len().max(1)).max(1);
+        Ok(Self {
+            device,
+            state,
+            threads,
+            overlap: config.chunk_overlap,
+            word_timestamps: config.word_timestamps,
+            // English-only models can't detect a language
+            language: config.language.clone().or_else(|| config.model_size.is_english_only().then(|| "en".to_string())),
+            translate: config.translate,
+            decoding: config.decoding.clone(),
+            initial_prompt: config.initial_prompt.clone(),
+            token_eot: model.token_eot(),
+            tokens: 0,
+        })
+    }
+
+    pub fn device(&self) -> Option<usize> {
+        self.device
+    }
+
+    /// Tokens decoded by this worker so far, timestamp tokens included
+    pub fn tokens(&self) -> u64 {
+        self.tokens
+    }
+
+    /// Transcribe in `language` from now on, e.g. after detecting it
+    pub fn set_language(&mut self, language: Option<String>) {
+        self.language = language;
+    }
+
+    /// Detect the spoken language from the start of `samples`. Returns its
+    /// code and Whisper's probability for it.
+    pub fn detect_language(&mut self, samples: &[f32]) -> Result<(String, f32)> {
+        let model_error = |message: String| AudioTranscriptionError::Model(format!("Language detection failed: {}", message));
+
+        self.state.pcm_to_mel(samples, self.threads).map_err(|e| model_error(e.to_string()))?;
+        let (id, probabilities) = self.state.lang_detect(0, self.threads).map_err(|e| model_error(e.to_string()))?;
+        let code = whisper_rs::get_lang_str(id).ok_or_else(|| model_error(format!("unknown language id {}", id)))?;
+        Ok((code.to_string(), probabilities.get(id as usize).copied().unwrap_or_default()))
+    }
+
+    /// Transcribe a prepared chunk. Timestamps are relative to the start of
+    /// the file.
+    pub fn transcribe(&mut self, chunk: &AudioChunk) -> Result<Vec<SpeechSegment>> {
+        let chunk_error = |message: String| AudioTranscriptionError::Chunk { index: chunk.index, message };
+
+        let mut params = self.decoding.params();
+        params.set_n_threads(self.threads as c_int);
+        params.set_language(Some(self.language.as_deref().unwrap_or("auto")));
+        params.set_translate(self.translate);
+        if let Some(prompt) = &self.initial_prompt {
+            params.set_initial_prompt(prompt);
+        }
+        // Chunks finish out of order, so there is no earlier text to condition on
+        params.set_no_context(true);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_special(false);
+        params.set_print_timestamps(false);
+        params.set_token_timestamps(self.word_timestamps);
+
+        self.state.full(params, &chunk.samples).map_err(|e| chunk_error(e.to_string()))?;
+
+        // Speech in the overlap belongs to the end of the previous chunk
+        let lead_in = if chunk.index > 0 { self.overlap } else { 0.0 };
+        let mut segments = Vec::new();
+        for segment in self.state.as_iter() {
+            self.tokens += segment.n_tokens() as u64;
+            let start = segment.start_timestamp() as f32 * CENTISECONDS;
+            let end = segment.end_timestamp() as f32 * CENTISECONDS;
+            if (start + end) / 2.0 < lead_in {
+                continue;
+            }
+
+            let text = segment.to_str_lossy().map_err(|e| chunk_error(e.to_string()))?;
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+
+            // Padding added to short chunks is not part of the file
+            let time = |centiseconds: i64| (chunk.start + centiseconds as f32 * CENTISECONDS).min(chunk.end);
+            let mut tokens = Vec::new();
+            for token in (0..segment.n_tokens()).filter_map(|index| segment.get_token(index)) {
+                if token.token_id() >= self.token_eot {
+                    continue;
+                }
+                let data = token.token_data();
+                tokens.push(Token {
+                    bytes: token.to_bytes().map_err(|e| chunk_error(e.to_string()))?.to_vec(),
+                    start: time(data.t0),
+                    end: time(data.t1),
+                    probability: data.p,
+                });
+            }
+
+            segments.push(SpeechSegment {
+                start: time(segment.start_timestamp()),
+                end: time(segment.end_timestamp()),
+                text: text.to_string(),
+                speaker: None,
+                confidence: mean_probability(&tokens),
+                words: if self.word_timestamps { group_words(&tokens) } else { Vec::new() },
+                speaker_name: None,
+            });
+        }
+        Ok(segments)
+    }
+}
+
+/// Join tokens into words: a token starting with a space begins a new word,
+/// any other token (punctuation, the rest of a long word) continues one
+fn group_words(tokens: &[Token]) -> Vec<WordTiming> {
+    let mut groups: Vec<&[Token]> = Vec::new();
+    let mut first = 0;
+    for (index, token) in tokens.iter().enumerate().skip(1) {
+        if token.bytes.starts_with(b" ") {
+            groups.push(&tokens[first..index]);
+            first = index;
+        }
+    }
+    groups.push(&tokens[first..]);
+
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            let bytes: Vec<u8> = group.iter().flat_map(|token| token.bytes.iter().copied()).collect();
+            let text = String::from_utf8_lossy(&bytes).trim().to_string();
+            (!text.is_empty()).then(|| WordTiming {
+                start: group[0].start,
+                end: group[group.len() - 1].end,
+                text,
+                confidence: mean_probability(group).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn mean_probability(tokens: &[Token]) -> Option<f32> {
+    (!tokens.is_empty()).then(|| tokens.iter().map(|token| token.probability).sum::<f32>() / tokens.len() as f32)
+}
+
+/// Terms from a vocabulary file, one per line. Blank lines and lines
+/// starting with `#` are skipped.
+pub fn read_vocabulary(path: &Path) -> Result<Vec<String>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|source| AudioTranscriptionError::File { path: path.to_path_buf(), source })?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Initial prompt made of `prompt` followed by the `vocabulary` terms, or
+/// None when both are empty
+pub fn build_prompt(prompt: Option<&str>, vocabulary: &[String]) -> Option<String> {
+    let terms = vocabulary.join(", ");
+    let parts: Vec<&str> = prompt.map(str::trim).into_iter().chain([terms.as_str()]).filter(|part| !part.is_empty()).collect();
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Load the ggml model at `path` once per device and create a worker for
+/// each entry of `devices`
+#[tracing::instrument(skip_all, fields(model = %path.display()))]
+pub fn create_workers(path: &Path, devices: &[Option<usize>], config: &ProcessingConfig) -> Result<Vec<TranscriptionWorker>> {
+    whisper_rs::install_logging_hooks();
+
+    let mut models: Vec<(Option<usize>, WhisperContext)> = Vec::new();
+    let mut workers = Vec::with_capacity(devices.len());
+    for &device in devices {
+        if !models.iter().any(|(loaded, _)| *loaded == device) {
+            models.push((device, load_model(path, device)?));
+        }
+        let (_, model) = models.iter().find(|(loaded, _)| *loaded == device).expect("model loaded above");
+        workers.push(TranscriptionWorker::new(device, model, config)?);
+    }
+    Ok(workers)
+}
+
+fn load_model(path: &Path, device: Option<usize>) -> Result<WhisperContext> {
+    let unusable = |message: String| AudioTranscriptionError::ModelCorrupt { path: path.to_path_buf(), message };
+    let path_str = path.to_str().ok_or_else(|| unusable("path is not valid UTF-8".to_string()))?;
+
+    let params = WhisperContextParameters {
+        use_gpu: device.is_some(),
+        gpu_device: device.unwrap_or_default() as c_int,
+        ..WhisperContextParameters::default()
+    };
+    tracing::debug!(?device, "Loading Whisper model");
+    WhisperContext::new_with_params(path_str, params).map_err(|e| unusable(e.to_string()))
+}
+
+/// Devices to start one worker on each. GPU runs get one worker per
+/// configured device (the default device when none is given), but never
+/// more than the parallel job count; CPU runs get one worker per parallel
+/// job.
+pub fn worker_devices(config: &ProcessingConfig) -> Vec<Option<usize>> {
+    let jobs = config.parallel_jobs.max(1);
+    if !config.use_gpu {
+        return vec![None; jobs];
+    }
+
+    if config.gpu_devices.is_empty() {
+        vec![Some(0)]
+    } else {
+        config.gpu_devices.iter().copied().take(jobs).map(Some).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_worker_per_gpu() {
+        let config = ProcessingConfig {
+            gpu_devices: vec![0, 1],
+            parallel_jobs: 4,
+            ..ProcessingConfig::default()
+        };
+
+        assert_eq!(worker_devices(&config), vec![Some(0), Some(1)]);
+        assert_eq!(worker_devices(&ProcessingConfig { parallel_jobs: 1, ..config }), vec![Some(0)]);
+    }
+
+    #[test]
+    fn test_vocabulary_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("words.txt");
+        std::fs::write(&path, "# Observability stack\nGrafana\n\n  Tempo  \nOpenTelemetry\n").unwrap();
+        let vocabulary = read_vocabulary(&path).unwrap();
+        assert_eq!(vocabulary, ["Grafana", "Tempo", "OpenTelemetry"]);
+
+        assert_eq!(build_prompt(None, &vocabulary).as_deref(), Some("Grafana, Tempo, OpenTelemetry"));
+        assert_eq!(
+            build_prompt(Some("Kubernetes migration review."), &vocabulary).as_deref(),
+            Some("Kubernetes migration review. Grafana, Tempo, OpenTelemetry")
+        );
+        assert_eq!(build_prompt(Some("Kubernetes"), &[]).as_deref(), Some("Kubernetes"));
+        assert_eq!(build_prompt(Some(" "), &[]), None);
+        assert!(read_vocabulary(&dir.path().join("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn test_tokens_grouped_into_words() {
+        let token = |text: &str, start: f32, probability: f32| Token {
+            bytes: text.as_bytes().to_vec(),
+            start,
+            end: start + 0.2,
+            probability,
+        };
+        // "Zürich" split inside the two-byte "ü"
+        let mut tokens = vec![token(" Hello", 0.0, 0.9), token(",", 0.2, 0.7), token(" Z", 0.5, 0.8)];
+        tokens.push(Token { bytes: vec![0xC3], ..token("", 0.7, 0.6) });
+        tokens.push(Token { bytes: b"\xBCrich".to_vec(), ..token("", 0.9, 1.0) });
+
+        let words = group_words(&tokens);
+        assert_eq!(words.iter().map(|word| word.text.as_str()).collect::<Vec<_>>(), vec!["Hello,", "Zürich"]);
+        assert_eq!((words[1].start, words[1].end), (0.5, 1.1));
+        assert!((words[0].confidence - 0.8).abs() < 1e-6);
+        assert!((mean_probability(&tokens).unwrap() - 0.8).abs() < 1e-6);
+        assert!(group_words(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_backend_must_be_compiled_in() {
+        assert!(GpuBackend::Auto.ensure_compiled().is_ok());
+        for backend in [GpuBackend::Metal, GpuBackend::Cuda, GpuBackend::Vulkan] {
+            match backend.ensure_compiled() {
+                Ok(()) => assert!(GpuBackend::compiled().contains(&backend)),
+                Err(e) => {
+                    assert_eq!(e.code(), 71);
+                    assert!(e.to_string().contains(&format!("--features {}", backend)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cpu_workers_follow_job_count() {
+        let config = ProcessingConfig {
+            use_gpu: false,
+            parallel_jobs: 3,
+            gpu_devices: vec![0, 1],
+            ..ProcessingConfig::default()
+        };
+
+        assert_eq!(worker_devices(&config), vec![None, None, None]);
+    }
+}
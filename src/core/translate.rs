@@ -0,0 +1,153 @@
+//! Machine translation of a finished transcript with a chat model behind an
+//! OpenAI-compatible API, e.g. a local llama.cpp server.
+//!
+//! Segments are translated one by one in batches, so the translated
+//! transcript keeps the original timestamps and speakers and can be written
+//! in any output format, including subtitles.
+
+use serde::Deserialize;
+use serde_json::json;
+use crate::core::audio_processor::TranscriptResult;
+use crate::core::chat::{ChatClient, ChatConfig};
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Segments sent per request; enough context for consistent wording while
+/// keeping replies well inside small models' context windows
+const BATCH_SEGMENTS: usize = 40;
+
+#[derive(Deserialize)]
+struct TranslationReply {
+    translations: Vec<String>,
+}
+
+/// Translates transcripts into one target language
+pub struct Translator {
+    chat: ChatClient,
+    target_language: String,
+}
+
+impl Translator {
+    /// `target_language` is a name or code such as `de` or `Japanese`; it
+    /// is also used in the translated file's name
+    pub fn new(config: ChatConfig, target_language: impl Into<String>) -> Result<Self> {
+        let target_language = target_language.into();
+        let valid = !target_language.is_empty()
+            && target_language.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(AudioTranscriptionError::Configuration(format!(
+                "Target language must be a name or code like 'de' or 'pt-BR', got '{}'",
+                target_language
+            )));
+        }
+
+        Ok(Self { chat: ChatClient::new(config)?, target_language })
+    }
+
+    pub fn config(&self) -> &ChatConfig {
+        self.chat.config()
+    }
+
+    pub fn target_language(&self) -> &str {
+        &self.target_language
+    }
+
+    /// A copy of `result` with every segment's text translated
+    pub async fn translate(&self, result: &TranscriptResult) -> Result<TranscriptResult> {
+        let system = format!(
+            "You translate transcript segments into {}. The user sends a JSON object whose \"segments\" \
+             array holds consecutive lines of one conversation. Reply with a JSON object with the key \
+             \"translations\": an array with exactly one translated string per segment, in the same order. \
+             Keep names, numbers and technical terms.",
+            self.target_language
+        );
+
+        let mut translated = result.clone();
+        let batches = translated.segments.chunks_mut(BATCH_SEGMENTS);
+        let batch_count = batches.len();
+        for (index, batch) in batches.enumerate() {
+            tracing::info!(batch = index + 1, of = batch_count, language = %self.target_language, "Translating segments");
+
+            let texts: Vec<&str> = batch.iter().map(|segment| segment.text.as_str()).collect();
+            let reply = self.chat.complete_json(&system, &json!({ "segments": texts }).to_string()).await?;
+            let reply: TranslationReply = serde_json::from_str(&reply).map_err(|e| AudioTranscriptionError::RemoteModel(
+                format!("translation is not the requested JSON: {}", e)
+            ))?;
+
+            if reply.translations.len() != batch.len() {
+                return Err(AudioTranscriptionError::RemoteModel(format!(
+                    "expected {} translated segments, got {}",
+                    batch.len(),
+                    reply.translations.len()
+                )));
+            }
+            // Word timings belong to the original wording
+            for (segment, text) in batch.iter_mut().zip(reply.translations) {
+                segment.text = text;
+                segment.words.clear();
+            }
+        }
+
+        // The text is now in the target language, not Whisper's English
+        translated.model_info.language = Some(self.target_language.clone());
+        translated.model_info.translated = false;
+        Ok(translated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::core::chat;
+    use crate::core::audio_processor::{ModelInfo, SpeechSegment};
+
+    fn sample_result() -> TranscriptResult {
+        TranscriptResult {
+            segments: vec![
+                SpeechSegment { start: 0.0, end: 1.5, text: "Good morning.".to_string(), speaker: Some(1), ..Default::default() },
+                SpeechSegment { start: 1.5, end: 3.0, text: "Let's start.".to_string(), speaker: Some(2), ..Default::default() },
+            ],
+            audio_duration: Duration::from_secs(3),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                language: Some("en".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_translate_keeps_timing_and_speakers() {
+        let (endpoint, server) = chat::mock_endpoint("{\"translations\": [\"Guten Morgen.\", \"Fangen wir an.\"]}").await;
+        let translator = Translator::new(ChatConfig { endpoint, ..ChatConfig::default() }, "de").unwrap();
+
+        let translated = translator.translate(&sample_result()).await.unwrap();
+        assert_eq!(translated.segments[0].text, "Guten Morgen.");
+        assert_eq!(translated.segments[1].text, "Fangen wir an.");
+        assert_eq!(translated.segments[1].start, 1.5);
+        assert_eq!(translated.segments[1].speaker, Some(2));
+        assert_eq!(translated.model_info.language.as_deref(), Some("de"));
+
+        let request = server.await.unwrap();
+        assert!(request.contains("into de"));
+        assert!(request.contains("Let's start."));
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_reply_is_rejected() {
+        let (endpoint, _server) = chat::mock_endpoint("{\"translations\": [\"Guten Morgen.\"]}").await;
+        let translator = Translator::new(ChatConfig { endpoint, ..ChatConfig::default() }, "de").unwrap();
+
+        assert_eq!(translator.translate(&sample_result()).await.unwrap_err().code(), 43);
+    }
+
+    #[test]
+    fn test_language_is_validated() {
+        assert!(Translator::new(ChatConfig::default(), "pt-BR").is_ok());
+        assert_eq!(Translator::new(ChatConfig::default(), "../de").err().unwrap().code(), 80);
+    }
+}
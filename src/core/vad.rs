@@ -0,0 +1,565 @@
+use std::path::{Path, PathBuf};
+use whisper_rs::{WhisperVadContext, WhisperVadContextParams, WhisperVadParams};
+use crate::core::audio_processor::{VadSegment, SAMPLE_RATE};
+use crate::core::model::{ModelKind, ModelProvider};
+use crate::core::decoder::AudioDecoder;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Analysis frame length (20 ms at 16 kHz)
+const FRAME_SAMPLES: usize = 320;
+
+/// Frames quieter than this are never speech, however quiet the recording
+const ABSOLUTE_FLOOR_DB: f32 = -60.0;
+
+/// How fast [`StreamingVad`] lets the noise floor rise (0.5 dB a second)
+const FLOOR_RISE_DB_PER_FRAME: f32 = 0.01;
+
+/// Silero scores one probability per window of this many samples
+const SILERO_WINDOW_SAMPLES: usize = 512;
+
+/// Default speech probability for [`SileroVad`]
+pub const DEFAULT_VAD_THRESHOLD: f32 = 0.5;
+
+/// Audio [`VadStream`] hands to the detector at once. The energy detector
+/// uses the lowest noise floor of the windows so far.
+pub const VAD_WINDOW_SECS: f32 = 60.0;
+
+/// Tuning for [`Vad`] and [`SileroVad`]
+#[derive(Debug, Clone)]
+pub struct VadOptions {
+    pub threshold: f32,         // Speech probability at which Silero counts a window as speech
+    pub threshold_db: f32,      // Level above the noise floor that counts as speech
+    pub min_speech_secs: f32,   // Shorter bursts are dropped as noise
+    pub min_silence_secs: f32,  // Shorter pauses don't split a region
+    pub padding_secs: f32,      // Added on both sides of each region
+}
+
+impl Default for VadOptions {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_VAD_THRESHOLD,
+            threshold_db: 12.0,
+            min_speech_secs: 0.25,
+            min_silence_secs: 0.3,
+            padding_secs: 0.1,
+        }
+    }
+}
+
+/// Finds the regions of a recording that contain speech.
+///
+/// Frames are compared against the recording's own noise floor, so the
+/// detector adapts to quiet and noisy sources without calibration.
+#[derive(Debug, Clone, Default)]
+pub struct Vad {
+    options: VadOptions,
+}
+
+impl Vad {
+    pub fn new(options: VadOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn options(&self) -> &VadOptions {
+        &self.options
+    }
+
+    /// Detect speech regions in an audio file, decoded as it goes
+    pub fn detect(&self, path: &Path) -> Result<Vec<VadSegment>> {
+        SpeechDetector::Energy(self.clone()).detect(path)
+    }
+
+    /// Detect speech regions in 16 kHz mono samples
+    pub fn detect_samples(&self, samples: &[f32]) -> Vec<VadSegment> {
+        self.detect_with_floor(samples, &mut None)
+    }
+
+    /// Like [`detect_samples`](Self::detect_samples), but against the lower
+    /// of this audio's noise floor and `floor_db`, which is then updated.
+    /// Lets a stream keep a floor measured earlier through loud stretches.
+    fn detect_with_floor(&self, samples: &[f32], floor_db: &mut Option<f32>) -> Vec<VadSegment> {
+        let energies: Vec<f32> = samples.chunks(FRAME_SAMPLES).map(frame_energy_db).collect();
+        if energies.is_empty() {
+            return Vec::new();
+        }
+
+        let floor = noise_floor_db(&energies).min(floor_db.unwrap_or(f32::INFINITY));
+        *floor_db = Some(floor);
+        let threshold = (floor + self.options.threshold_db).max(ABSOLUTE_FLOOR_DB);
+        let frame_secs = FRAME_SAMPLES as f32 / SAMPLE_RATE as f32;
+        let duration = samples.len() as f32 / SAMPLE_RATE as f32;
+
+        // Runs of loud frames as (first frame, end frame)
+        let mut regions: Vec<(usize, usize)> = Vec::new();
+        for (index, &energy) in energies.iter().enumerate() {
+            if energy < threshold {
+                continue;
+            }
+            match regions.last_mut() {
+                Some(last) if last.1 == index => last.1 = index + 1,
+                _ => regions.push((index, index + 1)),
+            }
+        }
+
+        // Bridge short pauses, then drop short bursts
+        let max_gap = (self.options.min_silence_secs / frame_secs).ceil() as usize;
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for region in regions {
+            match merged.last_mut() {
+                Some(last) if region.0 - last.1 < max_gap => last.1 = region.1,
+                _ => merged.push(region),
+            }
+        }
+        let min_frames = (self.options.min_speech_secs / frame_secs).ceil() as usize;
+
+        let mut segments: Vec<VadSegment> = Vec::new();
+        for (first, end) in merged.into_iter().filter(|(first, end)| end - first >= min_frames) {
+            let mean_db = energies[first..end].iter().sum::<f32>() / (end - first) as f32;
+            let start = (first as f32 * frame_secs - self.options.padding_secs).max(0.0);
+            let end = (end as f32 * frame_secs + self.options.padding_secs).min(duration);
+
+            // Padding can make neighbours touch
+            if let Some(last) = segments.last_mut() {
+                if start <= last.end {
+                    last.end = end;
+                    continue;
+                }
+            }
+            segments.push(VadSegment {
+                start,
+                end,
+                confidence: ((mean_db - threshold) / 20.0 + 0.5).clamp(0.0, 1.0),
+            });
+        }
+
+        segments
+    }
+}
+
+/// Speech starting or stopping in a stream, as found by [`StreamingVad`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VadTransition {
+    SpeechStarted,
+    SpeechEnded,
+}
+
+/// Energy-based voice activity detection for audio that arrives as it is
+/// recorded, such as from a microphone.
+///
+/// Like [`Vad`] it compares frames against the noise floor, but it follows
+/// the floor as the stream goes on instead of measuring it up front. Speech
+/// is reported once it has lasted `min_speech_secs` and ends after
+/// `min_silence_secs` of quiet; `padding_secs` is not applied.
+#[derive(Debug, Clone)]
+pub struct StreamingVad {
+    options: VadOptions,
+    frame_samples: usize,   // 20 ms at the stream's sample rate
+    pending: Vec<f32>,      // Samples short of a whole frame
+    floor_db: Option<f32>,  // None until the first frame
+    speaking: bool,
+    run: usize,             // Consecutive frames that disagree with `speaking`
+}
+
+impl StreamingVad {
+    pub fn new(sample_rate: u32, options: VadOptions) -> Self {
+        Self {
+            options,
+            frame_samples: (sample_rate as usize / 50).max(1),
+            pending: Vec::new(),
+            floor_db: None,
+            speaking: false,
+            run: 0,
+        }
+    }
+
+    pub fn is_speaking(&self) -> bool {
+        self.speaking
+    }
+
+    /// Feed the next mono samples; returns the transitions they caused
+    pub fn push(&mut self, samples: &[f32]) -> Vec<VadTransition> {
+        self.pending.extend_from_slice(samples);
+        let frames = self.pending.len() / self.frame_samples;
+        let energies: Vec<f32> = self.pending[..frames * self.frame_samples]
+            .chunks(self.frame_samples)
+            .map(frame_energy_db)
+            .collect();
+        self.pending.drain(..frames * self.frame_samples);
+
+        let frame_secs = FRAME_SAMPLES as f32 / SAMPLE_RATE as f32;
+        let min_speech = (self.options.min_speech_secs / frame_secs).ceil() as usize;
+        let min_silence = (self.options.min_silence_secs / frame_secs).ceil() as usize;
+
+        let mut transitions = Vec::new();
+        for energy in energies {
+            // Quieter frames lower the floor at once; louder ones only raise
+            // it slowly, so speech doesn't become the new floor
+            let floor = match self.floor_db {
+                Some(floor) if energy >= floor => (floor + FLOOR_RISE_DB_PER_FRAME).min(energy),
+                _ => energy,
+            };
+            self.floor_db = Some(floor);
+
+            let loud = energy >= (floor + self.options.threshold_db).max(ABSOLUTE_FLOOR_DB);
+            self.run = if loud != self.speaking { self.run + 1 } else { 0 };
+            if self.run >= if self.speaking { min_silence } else { min_speech }.max(1) {
+                self.speaking = !self.speaking;
+                self.run = 0;
+                transitions.push(if self.speaking { VadTransition::SpeechStarted } else { VadTransition::SpeechEnded });
+            }
+        }
+        transitions
+    }
+}
+
+/// Finds speech with the Silero neural voice activity detector, run through
+/// whisper.cpp.
+///
+/// Unlike [`Vad`] it tells speech from music, typing and other steady noise,
+/// so those stretches aren't sent to Whisper.
+#[derive(Debug, Clone)]
+pub struct SileroVad {
+    model: PathBuf,
+    options: VadOptions,
+}
+
+impl SileroVad {
+    pub fn new(model: PathBuf, options: VadOptions) -> Self {
+        Self { model, options }
+    }
+
+    /// Use the Silero model from `provider`, fetching it when missing
+    pub async fn from_provider(provider: &dyn ModelProvider, options: VadOptions) -> Result<Self> {
+        let model = provider.ensure_available(&ModelKind::Vad).await?;
+        Ok(Self::new(model, options))
+    }
+
+    pub fn options(&self) -> &VadOptions {
+        &self.options
+    }
+
+    /// Detect speech regions in an audio file, decoded as it goes
+    pub fn detect(&self, path: &Path) -> Result<Vec<VadSegment>> {
+        SpeechDetector::Silero(self.clone()).detect(path)
+    }
+
+    /// Detect speech regions in 16 kHz mono samples. A region's confidence
+    /// is the mean speech probability of its windows.
+    pub fn detect_samples(&self, samples: &[f32]) -> Result<Vec<VadSegment>> {
+        if samples.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut context_params = WhisperVadContextParams::new();
+        context_params.set_n_threads(num_cpus::get().min(4) as i32);
+        context_params.set_use_gpu(false);
+        let mut context = WhisperVadContext::new(&self.model.to_string_lossy(), context_params)
+            .map_err(|e| AudioTranscriptionError::ModelCorrupt {
+                path: self.model.clone(),
+                message: format!("Failed to load the Silero VAD model: {}", e),
+            })?;
+
+        let vad_error = |e| AudioTranscriptionError::Audio(format!("Voice activity detection failed: {}", e));
+        context.detect_speech(samples).map_err(vad_error)?;
+        let probabilities = context.probabilities().to_vec();
+
+        let mut params = WhisperVadParams::new();
+        params.set_threshold(self.options.threshold);
+        params.set_min_speech_duration(millis(self.options.min_speech_secs));
+        params.set_min_silence_duration(millis(self.options.min_silence_secs));
+        params.set_speech_pad(millis(self.options.padding_secs));
+
+        let duration = samples.len() as f32 / SAMPLE_RATE as f32;
+        let window_secs = SILERO_WINDOW_SAMPLES as f32 / SAMPLE_RATE as f32;
+        let segments = context
+            .segments_from_probabilities(params)
+            .map_err(vad_error)?
+            .map(|segment| {
+                // whisper.cpp reports centiseconds
+                let start = (segment.start / 100.0).clamp(0.0, duration);
+                let end = (segment.end / 100.0).clamp(start, duration);
+                let first = ((start / window_secs) as usize).min(probabilities.len());
+                let last = ((end / window_secs).ceil() as usize).clamp(first, probabilities.len());
+                let confidence = match &probabilities[first..last] {
+                    [] => self.options.threshold,
+                    windows => windows.iter().sum::<f32>() / windows.len() as f32,
+                };
+                VadSegment { start, end, confidence }
+            })
+            .collect();
+
+        Ok(segments)
+    }
+}
+
+/// [`SileroVad`], or [`Vad`] when the Silero model isn't available
+#[derive(Debug, Clone)]
+pub enum SpeechDetector {
+    Silero(SileroVad),
+    Energy(Vad),
+}
+
+impl SpeechDetector {
+    pub fn options(&self) -> &VadOptions {
+        match self {
+            Self::Silero(vad) => vad.options(),
+            Self::Energy(vad) => vad.options(),
+        }
+    }
+
+    /// Detect speech regions in 16 kHz mono samples
+    pub fn detect_samples(&self, samples: &[f32]) -> Result<Vec<VadSegment>> {
+        match self {
+            Self::Silero(vad) => vad.detect_samples(samples),
+            Self::Energy(vad) => Ok(vad.detect_samples(samples)),
+        }
+    }
+
+    /// Detect speech regions in an audio file, decoded as it goes
+    pub fn detect(self, path: &Path) -> Result<Vec<VadSegment>> {
+        let mut stream = VadStream::new(self);
+        let mut segments = Vec::new();
+        for block in AudioDecoder::default().stream(path)? {
+            segments.extend(stream.push(&block?)?);
+        }
+        segments.extend(stream.finish()?);
+        Ok(segments)
+    }
+}
+
+/// Speech detection over audio that arrives in blocks, such as from a file
+/// being decoded.
+///
+/// Blocks are gathered into [`VAD_WINDOW_SECS`] windows for the detector.
+/// Regions that meet at a window edge are joined, so a region is only
+/// reported once the audio after it has been seen; a region running past a
+/// whole window is reported in parts instead of being held back.
+#[derive(Debug)]
+pub struct VadStream {
+    detector: SpeechDetector,
+    window: Vec<f32>,
+    window_samples: usize,
+    offset: f32,                // Start of `window` in seconds
+    join_gap: f32,              // Regions closer than this across a window edge are one
+    held: Option<VadSegment>,   // The last region, which the next window may continue
+    floor_db: Option<f32>,      // Noise floor of the energy detector
+}
+
+impl VadStream {
+    pub fn new(detector: SpeechDetector) -> Self {
+        let options = detector.options();
+        let join_gap = options.min_silence_secs + 2.0 * options.padding_secs;
+        let window_samples = (VAD_WINDOW_SECS * SAMPLE_RATE as f32) as usize;
+        Self { detector, window: Vec::with_capacity(window_samples), window_samples, offset: 0.0, join_gap, held: None, floor_db: None }
+    }
+
+    /// Feed the next 16 kHz mono samples; returns the regions they settled
+    pub fn push(&mut self, mut samples: &[f32]) -> Result<Vec<VadSegment>> {
+        let mut settled = Vec::new();
+        while !samples.is_empty() {
+            let take = (self.window_samples - self.window.len()).min(samples.len());
+            self.window.extend_from_slice(&samples[..take]);
+            samples = &samples[take..];
+            if self.window.len() == self.window_samples {
+                self.detect_window(&mut settled)?;
+            }
+        }
+        Ok(settled)
+    }
+
+    /// The regions left once the stream has ended
+    pub fn finish(mut self) -> Result<Vec<VadSegment>> {
+        let mut settled = Vec::new();
+        if !self.window.is_empty() {
+            self.detect_window(&mut settled)?;
+        }
+        settled.extend(self.held.take());
+        Ok(settled)
+    }
+
+    /// Time in seconds before which no more speech will be reported
+    pub fn settled_until(&self) -> f32 {
+        self.held.as_ref().map_or(self.offset, |held| held.start.min(self.offset))
+    }
+
+    fn detect_window(&mut self, settled: &mut Vec<VadSegment>) -> Result<()> {
+        let window_secs = self.window.len() as f32 / SAMPLE_RATE as f32;
+        let window_end = self.offset + window_secs;
+        let segments = match &self.detector {
+            SpeechDetector::Energy(vad) => vad.detect_with_floor(&self.window, &mut self.floor_db),
+            detector => detector.detect_samples(&self.window)?,
+        };
+        for mut segment in segments {
+            segment.start += self.offset;
+            segment.end += self.offset;
+            match &mut self.held {
+                Some(held) if segment.start - held.end <= self.join_gap => {
+                    let (held_secs, segment_secs) = (held.end - held.start, segment.end - segment.start);
+                    held.confidence = (held.confidence * held_secs + segment.confidence * segment_secs) / (held_secs + segment_secs);
+                    held.end = segment.end;
+                }
+                _ => settled.extend(self.held.replace(segment)),
+            }
+        }
+
+        let cut = window_end - self.join_gap;
+        if let Some(held) = &mut self.held {
+            if held.end < cut {
+                settled.extend(self.held.take());
+            } else if held.end - held.start > VAD_WINDOW_SECS {
+                settled.push(VadSegment { end: cut, ..held.clone() });
+                held.start = cut;
+            }
+        }
+
+        self.offset = window_end;
+        self.window.clear();
+        Ok(())
+    }
+}
+
+fn millis(secs: f32) -> i32 {
+    (secs * 1000.0).round() as i32
+}
+
+fn frame_energy_db(frame: &[f32]) -> f32 {
+    let power = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+    10.0 * (power + 1e-10).log10()
+}
+
+/// Energy of the quietest tenth of the recording
+fn noise_floor_db(energies: &[f32]) -> f32 {
+    let mut sorted = energies.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    sorted[sorted.len() / 10]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(secs: f32, amplitude: f32) -> Vec<f32> {
+        (0..(secs * SAMPLE_RATE as f32) as usize)
+            .map(|i| amplitude * (i as f32 * 440.0 * std::f32::consts::TAU / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_speech_between_silence() {
+        let mut audio = tone(1.0, 0.001);
+        audio.extend(tone(1.0, 0.5));
+        audio.extend(tone(1.0, 0.001));
+
+        let segments = Vad::default().detect_samples(&audio);
+
+        assert_eq!(segments.len(), 1);
+        assert!((segments[0].start - 0.9).abs() < 0.05, "start {}", segments[0].start);
+        assert!((segments[0].end - 2.1).abs() < 0.05, "end {}", segments[0].end);
+        assert!(segments[0].confidence > 0.5);
+    }
+
+    #[test]
+    fn test_short_pause_does_not_split_region() {
+        let mut audio = tone(1.0, 0.001);
+        audio.extend(tone(0.5, 0.5));
+        audio.extend(tone(0.1, 0.001));
+        audio.extend(tone(0.5, 0.5));
+        audio.extend(tone(1.0, 0.001));
+
+        assert_eq!(Vad::default().detect_samples(&audio).len(), 1);
+    }
+
+    #[test]
+    fn test_silence_has_no_speech() {
+        assert!(Vad::default().detect_samples(&vec![0.0; SAMPLE_RATE as usize]).is_empty());
+        assert!(Vad::default().detect_samples(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_streaming_reports_start_and_end_of_speech() {
+        let mut vad = StreamingVad::new(SAMPLE_RATE, VadOptions::default());
+        let mut transitions = Vec::new();
+        let mut audio = tone(1.0, 0.001);
+        audio.extend(tone(1.0, 0.5));
+        audio.extend(tone(0.1, 0.001));  // A pause too short to end speech
+        audio.extend(tone(0.5, 0.5));
+        audio.extend(tone(1.0, 0.001));
+
+        // In uneven blocks, as a sound card delivers them
+        for (index, block) in audio.chunks(441).enumerate() {
+            for transition in vad.push(block) {
+                transitions.push((index * 441) as f32 / SAMPLE_RATE as f32);
+                assert_eq!(transition == VadTransition::SpeechStarted, transitions.len() == 1);
+            }
+        }
+
+        assert_eq!(transitions.len(), 2, "{:?}", transitions);
+        assert!((transitions[0] - 1.25).abs() < 0.05, "start {}", transitions[0]);
+        assert!((transitions[1] - 2.9).abs() < 0.05, "end {}", transitions[1]);
+        assert!(!vad.is_speaking());
+    }
+
+    #[test]
+    fn test_stream_joins_regions_across_windows() {
+        let window = VAD_WINDOW_SECS as usize;
+        let mut audio = tone(window as f32 - 1.0, 0.001);
+        audio.extend(tone(2.0, 0.5));  // Across the first window's edge
+        audio.extend(tone(window as f32, 0.001));
+        audio.extend(tone(1.0, 0.5));
+
+        let mut stream = VadStream::new(SpeechDetector::Energy(Vad::default()));
+        let mut segments = Vec::new();
+        for block in audio.chunks(4_000) {
+            segments.extend(stream.push(block).unwrap());
+            if segments.is_empty() {
+                assert!(stream.settled_until() < window as f32);
+            }
+        }
+        segments.extend(stream.finish().unwrap());
+
+        assert_eq!(segments.len(), 2, "{:?}", segments);
+        assert!((segments[0].start - (window as f32 - 1.1)).abs() < 0.05, "start {}", segments[0].start);
+        assert!((segments[0].end - (window as f32 + 1.1)).abs() < 0.05, "end {}", segments[0].end);
+        assert!((segments[1].start - (window as f32 * 2.0 + 0.9)).abs() < 0.05, "start {}", segments[1].start);
+    }
+
+    #[test]
+    fn test_stream_reports_long_speech_in_parts() {
+        // Loud from 10s on, with no pause in later windows to measure the floor in
+        let mut audio = tone(10.0, 0.001);
+        audio.extend(tone(VAD_WINDOW_SECS * 3.0, 0.5));
+        let mut stream = VadStream::new(SpeechDetector::Energy(Vad::default()));
+        let mut segments = stream.push(&audio).unwrap();
+        assert!(!segments.is_empty());
+        segments.extend(stream.finish().unwrap());
+
+        assert!((segments.first().unwrap().start - 9.9).abs() < 0.05);
+        assert!(segments.windows(2).all(|pair| pair[0].end == pair[1].start));
+        assert!((segments.last().unwrap().end - (VAD_WINDOW_SECS * 3.0 + 10.0)).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_detect_reads_stereo_wav() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("speech.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..48_000 * 3 {
+            let amplitude = if (48_000..96_000).contains(&i) { 16_000.0 } else { 10.0 };
+            let sample = (amplitude * (i as f32 * 440.0 * std::f32::consts::TAU / 48_000.0).sin()) as i16;
+            writer.write_sample(sample).unwrap();
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let segments = Vad::default().detect(&path).unwrap();
+
+        assert_eq!(segments.len(), 1);
+        assert!((segments[0].start - 0.9).abs() < 0.05);
+    }
+}
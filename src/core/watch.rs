@@ -0,0 +1,261 @@
+//! Drop folders (`--watch`): recordings copied or saved into a folder are
+//! transcribed as soon as they have finished arriving.
+//!
+//! A recording counts as arrived once its size hasn't changed for the
+//! settle time, so files still being written or copied are left alone.
+//! Transcribed recordings go on a skip-list kept outside the folder, so a
+//! restarted watcher only picks up the ones it hasn't done yet, including
+//! any that arrived while it wasn't running.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use crate::core::audio_processor::SUPPORTED_EXTENSIONS;
+use crate::core::checkpoint;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// How long a recording's size must stay the same before it is transcribed
+pub const DEFAULT_SETTLE: Duration = Duration::from_secs(5);
+
+/// How often the sizes of arriving recordings are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// `<data dir>/audio-transcribe/watched`
+pub fn default_state_dir() -> Result<PathBuf> {
+    let data_dir = dirs::data_dir().ok_or_else(|| AudioTranscriptionError::Configuration(
+        "Unable to determine data directory".to_string()
+    ))?;
+    Ok(data_dir.join("audio-transcribe").join("watched"))
+}
+
+/// Recordings of one folder that were already transcribed
+#[derive(Debug, Serialize, Deserialize)]
+struct SkipList {
+    dir: PathBuf,
+    processed: BTreeSet<String>,  // File names
+}
+
+/// A recording that is still arriving
+#[derive(Debug)]
+struct Arrival {
+    size: u64,        // When last checked
+    since: Instant,   // Last change in size
+}
+
+/// A watched folder that hands out each new recording once
+pub struct DropFolder {
+    dir: PathBuf,
+    settle: Duration,
+    skip_list_path: PathBuf,
+    skip_list: SkipList,
+    arriving: HashMap<PathBuf, Arrival>,
+    events: mpsc::UnboundedReceiver<PathBuf>,
+    _watcher: RecommendedWatcher,  // Events stop when it is dropped
+}
+
+impl DropFolder {
+    /// Watch `dir`, keeping its skip-list in `state_dir`. Recordings
+    /// already in the folder and not on the skip-list are queued first.
+    pub fn watch(dir: &Path, state_dir: &Path, settle: Duration) -> Result<Self> {
+        let dir_error = |source| AudioTranscriptionError::File { path: dir.to_path_buf(), source };
+        let dir = dir.canonicalize().map_err(dir_error)?;
+        if !dir.is_dir() {
+            return Err(AudioTranscriptionError::Configuration(format!("Not a folder: {}", dir.display())));
+        }
+
+        let (sender, events) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            // Renames cover recordings moved in and temporary files renamed
+            // once a download completes
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!(error = %e, "File watcher error"),
+        })
+        .map_err(|e| dir_error(std::io::Error::other(e)))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| dir_error(std::io::Error::other(e)))?;
+
+        let skip_list_path = state_dir.join(checkpoint::file_name(&dir));
+        let skip_list = std::fs::read_to_string(&skip_list_path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<SkipList>(&json).ok())
+            .unwrap_or_else(|| SkipList { dir: dir.clone(), processed: BTreeSet::new() });
+
+        let mut existing: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .map_err(dir_error)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        existing.sort();
+
+        let mut folder = Self { dir, settle, skip_list_path, skip_list, arriving: HashMap::new(), events, _watcher: watcher };
+        for path in existing {
+            folder.arrived(path);
+        }
+        tracing::info!(
+            dir = %folder.dir.display(),
+            queued = folder.arriving.len(),
+            processed = folder.skip_list.processed.len(),
+            "Watching folder"
+        );
+        Ok(folder)
+    }
+
+    /// The watched folder, as an absolute path
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Wait for the next recording to finish arriving, oldest first.
+    /// Returns `None` if the watcher stopped.
+    pub async fn next(&mut self) -> Option<PathBuf> {
+        loop {
+            while let Ok(path) = self.events.try_recv() {
+                self.arrived(path);
+            }
+            if let Some(path) = self.settled() {
+                return Some(path);
+            }
+
+            if self.arriving.is_empty() {
+                let path = self.events.recv().await?;
+                self.arrived(path);
+            } else {
+                tokio::select! {
+                    path = self.events.recv() => self.arrived(path?),
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+            }
+        }
+    }
+
+    /// Put a recording on the skip-list so it is never transcribed again,
+    /// also after a restart. The file is replaced atomically.
+    pub fn mark_processed(&mut self, path: &Path) -> Result<()> {
+        self.skip_list.processed.insert(file_name(path));
+
+        let dir = self.skip_list_path.parent().unwrap_or_else(|| Path::new("."));
+        std::fs::create_dir_all(dir)
+            .map_err(|source| AudioTranscriptionError::File { path: dir.to_path_buf(), source })?;
+        let temporary = self.skip_list_path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_vec_pretty(&self.skip_list)?)
+            .map_err(|source| AudioTranscriptionError::File { path: temporary.clone(), source })?;
+        std::fs::rename(&temporary, &self.skip_list_path)
+            .map_err(|source| AudioTranscriptionError::File { path: self.skip_list_path.clone(), source })?;
+        Ok(())
+    }
+
+    /// Note a change to `path`; every change restarts its settle time
+    fn arrived(&mut self, path: PathBuf) {
+        if !is_recording(&path) || self.skip_list.processed.contains(&file_name(&path)) {
+            return;
+        }
+        match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => {
+                self.arriving.insert(path, Arrival { size: metadata.len(), since: Instant::now() });
+            }
+            _ => {
+                self.arriving.remove(&path);
+            }
+        }
+    }
+
+    /// Take the longest-settled recording whose size stayed the same for
+    /// the settle time. Recordings that disappeared are forgotten.
+    fn settled(&mut self) -> Option<PathBuf> {
+        self.arriving.retain(|path, arrival| match std::fs::metadata(path) {
+            Ok(metadata) => {
+                if metadata.len() != arrival.size {
+                    *arrival = Arrival { size: metadata.len(), since: Instant::now() };
+                }
+                true
+            }
+            Err(_) => false,
+        });
+
+        let path = self
+            .arriving
+            .iter()
+            .filter(|(_, arrival)| arrival.since.elapsed() >= self.settle)
+            .min_by_key(|(path, arrival)| (arrival.since, path.to_path_buf()))
+            .map(|(path, _)| path.clone())?;
+        self.arriving.remove(&path);
+        Some(path)
+    }
+}
+
+/// An audio file, other than hidden ones such as macOS `._` resource files
+/// and the temporary files of sync tools
+fn is_recording(path: &Path) -> bool {
+    let hidden = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+    let supported = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+    supported && !hidden
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const SETTLE: Duration = Duration::from_millis(300);
+
+    async fn next_within(folder: &mut DropFolder, limit: Duration) -> Option<String> {
+        let path = tokio::time::timeout(limit, folder.next()).await.ok().flatten()?;
+        Some(file_name(&path))
+    }
+
+    #[tokio::test]
+    async fn test_recordings_are_handed_out_once_settled() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("earlier.wav"), b"audio").unwrap();
+        std::fs::write(dir.path().join("earlier.txt"), b"transcript").unwrap();
+        std::fs::write(dir.path().join("._earlier.wav"), b"resource fork").unwrap();
+
+        let mut folder = DropFolder::watch(dir.path(), state.path(), SETTLE).unwrap();
+        assert_eq!(next_within(&mut folder, Duration::from_secs(5)).await.as_deref(), Some("earlier.wav"));
+
+        // Still being written: nothing until it stops growing
+        let mut file = std::fs::File::create(dir.path().join("call.MP3")).unwrap();
+        file.write_all(b"first half").unwrap();
+        tokio::time::sleep(SETTLE / 2).await;
+        file.write_all(b"second half").unwrap();
+        file.flush().unwrap();
+        let written = Instant::now();
+
+        assert_eq!(next_within(&mut folder, Duration::from_secs(5)).await.as_deref(), Some("call.MP3"));
+        assert!(written.elapsed() >= SETTLE);
+        assert_eq!(next_within(&mut folder, SETTLE * 3).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_skip_list_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("done.wav"), b"audio").unwrap();
+
+        let mut folder = DropFolder::watch(dir.path(), state.path(), SETTLE).unwrap();
+        let path = folder.next().await.unwrap();
+        folder.mark_processed(&path).unwrap();
+        drop(folder);
+
+        std::fs::write(dir.path().join("new.flac"), b"audio").unwrap();
+        let mut folder = DropFolder::watch(dir.path(), state.path(), SETTLE).unwrap();
+        assert_eq!(next_within(&mut folder, Duration::from_secs(5)).await.as_deref(), Some("new.flac"));
+        assert_eq!(next_within(&mut folder, SETTLE * 3).await, None);
+    }
+}
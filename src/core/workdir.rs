@@ -0,0 +1,142 @@
+//! Scratch space for a run and up-front disk space checks.
+//!
+//! Each run gets its own directory for downloads and other intermediates,
+//! under the system temp directory or a configured one (e.g. on a larger
+//! disk). It is deleted when the run ends, whether it succeeded, failed or
+//! was cancelled.
+
+use std::path::Path;
+use std::time::Duration;
+use crate::core::audio_processor::SAMPLE_RATE;
+use crate::error::{Result, AudioTranscriptionError};
+
+/// Intermediates are budgeted as the decoded audio: 16 kHz mono `f32`
+const WORK_BYTES_PER_SEC: u64 = SAMPLE_RATE as u64 * 4;
+
+/// Generous allowance for transcripts in several formats, the run log and
+/// the resume checkpoint
+const OUTPUT_BYTES_PER_SEC: u64 = 1_000;
+
+/// Left free on top of the estimate, so a run never fills a disk completely
+const RESERVED_BYTES: u64 = 100_000_000;
+
+/// Disk space a run is expected to need
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpaceEstimate {
+    pub work: u64,      // Bytes in the work directory
+    pub output: u64,    // Bytes next to the transcripts
+}
+
+impl SpaceEstimate {
+    pub fn for_audio(duration: Duration) -> Self {
+        let seconds = duration.as_secs().saturating_add(1);
+        Self {
+            work: seconds.saturating_mul(WORK_BYTES_PER_SEC),
+            output: seconds.saturating_mul(OUTPUT_BYTES_PER_SEC),
+        }
+    }
+}
+
+/// Fail with [`AudioTranscriptionError::InsufficientDiskSpace`] unless the
+/// disk holding `dir` has `needed` bytes free plus a reserve. `dir` doesn't
+/// have to exist yet; its nearest existing parent is checked. When free
+/// space can't be determined the check is skipped.
+pub fn ensure_free_space(dir: &Path, needed: u64) -> Result<()> {
+    let existing = dir
+        .ancestors()
+        .find(|ancestor| !ancestor.as_os_str().is_empty() && ancestor.exists())
+        .unwrap_or_else(|| Path::new("."));
+
+    let available = match fs4::available_space(existing) {
+        Ok(available) => available,
+        Err(e) => {
+            tracing::warn!(path = %existing.display(), error = %e, "Unable to determine free disk space");
+            return Ok(());
+        }
+    };
+    tracing::debug!(path = %existing.display(), needed, available, "Checked free disk space");
+
+    let needed = needed.saturating_add(RESERVED_BYTES);
+    if available < needed {
+        return Err(AudioTranscriptionError::InsufficientDiskSpace {
+            path: existing.to_path_buf(),
+            needed,
+            available,
+        });
+    }
+    Ok(())
+}
+
+/// A run's own scratch directory, deleted with everything in it when
+/// dropped
+#[derive(Debug)]
+pub struct WorkDir {
+    dir: tempfile::TempDir,
+}
+
+impl WorkDir {
+    /// Create a fresh directory in `base`, or in the system temp directory
+    /// when `base` is `None`
+    pub fn create(base: Option<&Path>) -> Result<Self> {
+        let base = base.map(Path::to_path_buf).unwrap_or_else(std::env::temp_dir);
+        let file_error = |source| AudioTranscriptionError::File { path: base.clone(), source };
+
+        std::fs::create_dir_all(&base).map_err(file_error)?;
+        let dir = tempfile::Builder::new()
+            .prefix("audio-transcribe-")
+            .tempdir_in(&base)
+            .map_err(file_error)?;
+
+        tracing::debug!(path = %dir.path().display(), "Created work directory");
+        Ok(Self { dir })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Delete the directory now, reporting what dropping would ignore
+    pub fn remove(self) -> Result<()> {
+        let path = self.dir.path().to_path_buf();
+        self.dir.close().map_err(|source| AudioTranscriptionError::File { path, source })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_work_dir_is_removed_on_drop_and_remove() {
+        let base = tempfile::tempdir().unwrap();
+
+        let work_dir = WorkDir::create(Some(&base.path().join("scratch"))).unwrap();
+        let dropped = work_dir.path().to_path_buf();
+        std::fs::write(dropped.join("download.mp3"), b"ID3").unwrap();
+        assert!(dropped.starts_with(base.path().join("scratch")));
+        drop(work_dir);
+        assert!(!dropped.exists());
+
+        let work_dir = WorkDir::create(Some(base.path())).unwrap();
+        let removed = work_dir.path().to_path_buf();
+        work_dir.remove().unwrap();
+        assert!(!removed.exists());
+    }
+
+    #[test]
+    fn test_free_space_check() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(ensure_free_space(&dir.path().join("not").join("created"), 0).is_ok());
+        let error = ensure_free_space(dir.path(), u64::MAX).unwrap_err();
+        assert_eq!(error.code(), 72);
+        assert_eq!(error.path(), Some(dir.path()));
+    }
+
+    #[test]
+    fn test_estimate_grows_with_duration() {
+        let hour = SpaceEstimate::for_audio(Duration::from_secs(3600));
+        assert_eq!(hour.work, 3601 * 64_000);
+        assert!(hour.output < hour.work);
+    }
+}
@@ -1,39 +1,191 @@
+use std::path::PathBuf;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, AudioTranscriptionError>;
 
+/// Errors from the transcription pipeline.
+///
+/// Every variant has a stable numeric [`code`](Self::code) that is never
+/// reused, so callers can branch on failures without matching messages.
+/// New variants may be added in minor releases.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum AudioTranscriptionError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("Cannot access {}: {source}", path.display())]
+    File {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("File does not exist: {}", path.display())]
+    InputNotFound { path: PathBuf },
+
+    #[error("Path is not a file: {}", path.display())]
+    InputNotAFile { path: PathBuf },
+
     #[error("Audio processing error: {0}")]
     Audio(String),
 
+    #[error("Unsupported audio format: {}", extension.as_deref().map(|e| format!(".{}", e)).unwrap_or_else(|| "no file extension".to_string()))]
+    UnsupportedFormat {
+        path: PathBuf,
+        extension: Option<String>,
+    },
+
+    #[error("Chunk {index} failed: {message}")]
+    Chunk { index: usize, message: String },
+
     #[error("Model error: {0}")]
     Model(String),
 
+    #[error("{model} model not found at {}", path.display())]
+    ModelNotFound { model: String, path: PathBuf },
+
+    #[error("Model file {} is unusable: {message}", path.display())]
+    ModelCorrupt { path: PathBuf, message: String },
+
+    #[cfg(any(feature = "download", feature = "summarize", feature = "translate", feature = "notify"))]
     #[error("Network error: {0}")]
     Network(#[from] reqwest::Error),
 
+    #[cfg(feature = "download")]
     #[error("Hugging Face Hub error: {0}")]
     HuggingFaceHub(#[from] hf_hub::api::tokio::ApiError),
 
+    #[cfg(any(feature = "summarize", feature = "translate"))]
+    #[error("Unusable reply from the chat model: {0}")]
+    RemoteModel(String),
+
+    #[cfg(feature = "cloud")]
+    #[error("Object storage error for {uri}: {message}")]
+    ObjectStore { uri: String, message: String },
+
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
     #[error("File browser error: {0}")]
     FileBrowser(String),
 
-    #[error("Unsupported audio format: {0}")]
-    UnsupportedFormat(String),
-
     #[error("Insufficient memory: {0}")]
     InsufficientMemory(String),
 
-    #[error("GPU acceleration unavailable: {0}")]
-    GpuUnavailable(String),
+    #[error("GPU acceleration unavailable{}: {message}", device.map(|d| format!(" on device {}", d)).unwrap_or_default())]
+    GpuUnavailable { device: Option<usize>, message: String },
+
+    #[error("Not enough disk space in {}: {} MB needed, {} MB free", path.display(), needed / 1_000_000, available / 1_000_000)]
+    InsufficientDiskSpace { path: PathBuf, needed: u64, available: u64 },
 
     #[error("Configuration error: {0}")]
     Configuration(String),
-}
\ No newline at end of file
+
+    #[cfg(any(feature = "server", feature = "archive"))]
+    #[error("Database error: {0}")]
+    JobDatabase(#[from] rusqlite::Error),
+
+    #[cfg(feature = "search")]
+    #[error("Search index error: {0}")]
+    SearchIndex(#[from] tantivy::TantivyError),
+}
+
+impl AudioTranscriptionError {
+    /// Stable numeric code identifying the kind of failure. Codes are grouped
+    /// by area (1x input, 2x audio, 3x models, 4x network, 5x serialization,
+    /// 6x UI, 7x resources, 8x configuration, 9x server, 10x search) and fit
+    /// in a process exit status, which is how the CLI reports them.
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Io(_) => 10,
+            Self::File { .. } => 11,
+            Self::InputNotFound { .. } => 12,
+            Self::InputNotAFile { .. } => 13,
+            Self::Audio(_) => 20,
+            Self::UnsupportedFormat { .. } => 21,
+            Self::Chunk { .. } => 22,
+            Self::Model(_) => 30,
+            Self::ModelNotFound { .. } => 31,
+            Self::ModelCorrupt { .. } => 32,
+            #[cfg(any(feature = "download", feature = "summarize", feature = "translate", feature = "notify"))]
+            Self::Network(_) => 40,
+            #[cfg(feature = "download")]
+            Self::HuggingFaceHub(_) => 41,
+            #[cfg(feature = "cloud")]
+            Self::ObjectStore { .. } => 42,
+            #[cfg(any(feature = "summarize", feature = "translate"))]
+            Self::RemoteModel(_) => 43,
+            Self::Serialization(_) => 50,
+            Self::FileBrowser(_) => 60,
+            Self::InsufficientMemory(_) => 70,
+            Self::GpuUnavailable { .. } => 71,
+            Self::InsufficientDiskSpace { .. } => 72,
+            Self::Configuration(_) => 80,
+            #[cfg(any(feature = "server", feature = "archive"))]
+            Self::JobDatabase(_) => 90,
+            #[cfg(feature = "search")]
+            Self::SearchIndex(_) => 100,
+        }
+    }
+
+    /// Whether trying the same work again can't help: the input is missing
+    /// or not audio, or the configuration is wrong
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            Self::InputNotFound { .. } | Self::InputNotAFile { .. } | Self::UnsupportedFormat { .. } | Self::Configuration(_)
+        )
+    }
+
+    /// Path of the file involved, when the failure concerns one
+    pub fn path(&self) -> Option<&std::path::Path> {
+        match self {
+            Self::File { path, .. }
+            | Self::InputNotFound { path }
+            | Self::InputNotAFile { path }
+            | Self::UnsupportedFormat { path, .. }
+            | Self::ModelNotFound { path, .. }
+            | Self::ModelCorrupt { path, .. }
+            | Self::InsufficientDiskSpace { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_stable() {
+        let chunk = AudioTranscriptionError::Chunk { index: 3, message: "empty".to_string() };
+        let missing = AudioTranscriptionError::InputNotFound { path: PathBuf::from("a.wav") };
+
+        assert_eq!(chunk.code(), 22);
+        assert_eq!(chunk.to_string(), "Chunk 3 failed: empty");
+        assert_eq!(missing.code(), 12);
+        assert_eq!(missing.path(), Some(std::path::Path::new("a.wav")));
+        assert_eq!(AudioTranscriptionError::Configuration(String::new()).code(), 80);
+    }
+
+    #[test]
+    fn test_permanent_errors() {
+        assert!(AudioTranscriptionError::InputNotFound { path: PathBuf::from("a.wav") }.is_permanent());
+        assert!(AudioTranscriptionError::Configuration(String::new()).is_permanent());
+        assert!(!AudioTranscriptionError::Audio("decoder stalled".to_string()).is_permanent());
+        assert!(!AudioTranscriptionError::InsufficientMemory(String::new()).is_permanent());
+    }
+
+    #[test]
+    fn test_context_in_messages() {
+        let format = AudioTranscriptionError::UnsupportedFormat {
+            path: PathBuf::from("notes.txt"),
+            extension: Some("txt".to_string()),
+        };
+        let gpu = AudioTranscriptionError::GpuUnavailable { device: Some(1), message: "out of memory".to_string() };
+
+        assert_eq!(format.to_string(), "Unsupported audio format: .txt");
+        assert_eq!(gpu.to_string(), "GPU acceleration unavailable on device 1: out of memory");
+    }
+}
@@ -1,29 +1,32 @@
+pub mod blocking;
+#[cfg(feature = "cli")]
 pub mod cli;
 pub mod core;
 pub mod ui;
 pub mod error;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod transcriber;
 
-pub use crate::error::Result;
+#[cfg(feature = "python")]
+mod python;
 
-// Re-export the ModelSize enum for use in tests
-// This is a duplicate of the one in main.rs but needed for library tests
-#[derive(Clone, Debug)]
-pub enum ModelSize {
-    Tiny,
-    Base,
-    Small,
-    Medium,
-    Large,
-}
-
-impl std::fmt::Display for ModelSize {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ModelSize::Tiny => write!(f, "tiny"),
-            ModelSize::Base => write!(f, "base"),
-            ModelSize::Small => write!(f, "small"),
-            ModelSize::Medium => write!(f, "medium"),
-            ModelSize::Large => write!(f, "large"),
-        }
-    }
-}
\ No newline at end of file
+pub use crate::core::audio_processor::{
+    DiarizationSegment, ProcessingConfig, ProcessingConfigBuilder, SpeechSegment, TranscriptResult, VadSegment, WordTiming,
+};
+pub use crate::core::decoder::AudioDecoder;
+pub use crate::core::diarization::{DiarizationOptions, Diarizer};
+pub use crate::core::meeting::MeetingInfo;
+pub use crate::core::transcription::{DecodingOptions, GpuBackend};
+pub use crate::core::model::{DirectoryModelProvider, DownloadPolicy, DownloadRetry, ModelKind, ModelManager, ModelProvider, ModelSize};
+pub use crate::core::post_process::{PostProcessor, PostProcessorChain, Retime};
+pub use crate::core::progress::{ProcessingStage, ProgressEvent, ProgressSink};
+pub use crate::core::selection::TimeRange;
+pub use crate::core::speakers::{SpeakerRegistry, Voiceprint};
+pub use crate::core::transcript_generator::TranscriptGenerator;
+pub use crate::core::vad::{SileroVad, Vad, VadOptions};
+pub use audio_transcribe_format::{OutputFormat, OutputFormatter};
+pub use audio_transcribe_format::notes::{NoteOptions, NotesFormatter};
+pub use audio_transcribe_format::text::{TextFormatter, TextOptions};
+pub use crate::error::{AudioTranscriptionError, Result};
+pub use crate::transcriber::{Transcriber, TranscriberBuilder};
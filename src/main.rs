@@ -1,15 +1,63 @@
-use clap::Parser;
-use std::path::PathBuf;
-use std::io::{self, Write};
+use clap::builder::{PossibleValue, PossibleValuesParser, TypedValueParser};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::io::{self, IsTerminal, Write};
+use std::sync::Arc;
+#[cfg(feature = "server")]
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use indicatif::MultiProgress;
 
-mod cli;
-mod core;
-mod ui;
-mod error;
+use audio_transcription_cli::cli::{format_file_size, init_tracing, speaker_names, terminal, FileBrowser, JsonProgress, RunLog, Verbosity, RUN_LOG_TARGET};
+use audio_transcription_cli::core::bench::{self, BenchRun, ReportFormat, ResourceSampler};
+use audio_transcription_cli::core::checkpoint::{self, Checkpoint};
+use audio_transcription_cli::core::corrections::Corrections;
+use audio_transcription_cli::core::speaker_names::SpeakerNames;
+#[cfg(feature = "cloud")]
+use audio_transcription_cli::core::storage::{self, ObjectLocation};
+#[cfg(feature = "summarize")]
+use audio_transcription_cli::core::summarize::Summarizer;
+#[cfg(feature = "translate")]
+use audio_transcription_cli::core::translate::Translator;
+#[cfg(feature = "notify")]
+use audio_transcription_cli::core::notify::{Notification, Notifier};
+#[cfg(feature = "archive")]
+use audio_transcription_cli::core::archive::{self, RunArchive};
+#[cfg(feature = "watch")]
+use audio_transcription_cli::core::watch::{self, DropFolder};
+#[cfg(feature = "listen")]
+use audio_transcription_cli::core::live::{self, LiveEvent};
+use audio_transcription_cli::{ModelKind, ModelProvider};
+use audio_transcription_cli::core::model::{auto, CachedModel, ChecksumStatus};
+#[cfg(any(feature = "summarize", feature = "translate"))]
+use audio_transcription_cli::core::chat::{self, ChatConfig};
+#[cfg(feature = "search")]
+use audio_transcription_cli::core::search::{self, TranscriptIndex};
+use audio_transcribe_format::format_timestamp;
+#[cfg(feature = "server")]
+use audio_transcription_cli::server;
+use audio_transcription_cli::core::transcript_generator::DEFAULT_FORMAT;
+use audio_transcription_cli::core::audio_processor::{DEFAULT_CHUNK_OVERLAP, DEFAULT_CHUNK_RETRIES, DEFAULT_REFINE_THRESHOLD, GAP_MARKER, SUPPORTED_EXTENSIONS};
+use audio_transcription_cli::core::profiles::{self, Settings, UserConfig};
+use audio_transcription_cli::core::sources::RecordingSources;
+use audio_transcription_cli::core::metadata;
+use audio_transcription_cli::core::raw::{self, RawFormat, RawPcm};
+use audio_transcription_cli::core::selection;
+use audio_transcription_cli::core::transcription;
+use audio_transcription_cli::core::workdir::{self, SpaceEstimate, WorkDir};
+use audio_transcription_cli::{
+    AudioTranscriptionError, DecodingOptions, DiarizationOptions, Diarizer, DownloadPolicy, DownloadRetry, GpuBackend, MeetingInfo, ModelManager, ModelSize, NoteOptions, NotesFormatter,
+    PostProcessorChain, ProcessingConfig, ProgressSink, Result, Retime, SileroVad, SpeakerRegistry, SpeechSegment, TextFormatter, TextOptions, TimeRange, TranscriptGenerator, TranscriptResult, Transcriber, Vad,
+    VadOptions,
+};
+use audio_transcription_cli::ui::{LiveProgress, ProgressDisplay};
 
-use crate::error::Result;
-use crate::cli::FileBrowser;
-use crate::core::{ModelManager, ModelSize};
+/// Exit status after an interrupt, following the shell convention of
+/// 128 + SIGINT
+const INTERRUPTED_EXIT_CODE: i32 = 130;
 
 #[derive(Parser)]
 #[command(name = "audio-transcribe")]
@@ -17,78 +65,979 @@ use crate::core::{ModelManager, ModelSize};
 #[command(version = "0.1.0")]
 #[derive(Debug)]
 pub struct Cli {
-    /// Input audio file path (optional - if not provided, opens file browser)
+    /// Transcription options without a subcommand; they are also the
+    /// settings `bench`, `serve`, `listen` and `record` run with
+    #[command(flatten)]
+    pub transcribe: TranscribeArgs,
+
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// What to transcribe, how, and where the transcripts go
+#[derive(Args, Debug)]
+pub struct TranscribeArgs {
+    /// Input audio file path, or - for raw PCM on standard input
+    /// (optional - if not provided, opens file browser)
     pub input: Option<PathBuf>,
 
-    /// Model size to use for transcription
-    #[arg(long, value_enum, default_value_t = ModelSize::Medium)]
-    pub model: ModelSize,
+    /// Config file with defaults and named profiles [default: config.toml
+    /// in the user config directory, if present]
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Profile of the config file whose settings to use, e.g. podcast
+    #[arg(long, value_name = "NAME", value_parser = profile_name)]
+    pub profile: Option<String>,
+
+    /// Model size to use for transcription, or auto for the largest that
+    /// fits in free GPU or system memory
+    #[arg(long, default_value = "medium", value_parser = model_choices())]
+    pub model: ModelChoice,
+
+    /// Larger model that transcribes the segments the first model was
+    /// unsure of again, e.g. --model base --refine-model large-v3
+    #[arg(long, value_enum, value_name = "MODEL")]
+    pub refine_model: Option<ModelSize>,
+
+    /// Mean token probability (0-1) below which a segment is transcribed
+    /// again by --refine-model
+    #[arg(long, value_name = "P", default_value_t = DEFAULT_REFINE_THRESHOLD)]
+    pub refine_threshold: f32,
 
-    /// Output directory for transcript files
+    /// Keep segments Whisper emitted over silence, repetition loops and
+    /// stock phrases such as "Thanks for watching!"
+    #[arg(long)]
+    pub no_hallucination_filter: bool,
+
+    /// Decode with beam search over N beams instead of greedily; slower,
+    /// usually a little more accurate
+    #[arg(long, value_name = "N")]
+    pub beam_size: Option<usize>,
+
+    /// Candidates sampled at each fallback temperature when decoding greedily
+    #[arg(long, value_name = "N", default_value_t = DecodingOptions::default().best_of, conflicts_with = "beam_size")]
+    pub best_of: usize,
+
+    /// Sampling temperature of the first decoding attempt; 0 always takes
+    /// the likeliest token
+    #[arg(long, value_name = "T", default_value_t = DecodingOptions::default().temperature)]
+    pub temperature: f32,
+
+    /// Temperature added each time a decode looks failed and is retried;
+    /// 0 turns the retries off, which is fastest
+    #[arg(long, value_name = "T", default_value_t = DecodingOptions::default().temperature_increment)]
+    pub temperature_increment: f32,
+
+    /// Probability of no speech (0-1) above which Whisper leaves a window
+    /// empty
+    #[arg(long, value_name = "P", default_value_t = DecodingOptions::default().no_speech_threshold)]
+    pub no_speech_threshold: f32,
+
+    /// Output directory for transcript files, or a transcript file whose
+    /// extension selects the format (e.g. talk.vtt)
     #[arg(long)]
     pub output: Option<PathBuf>,
 
-    /// Target chunk duration in seconds
+    /// Longest chunk in seconds; chunks end in the last pause before it
     #[arg(long, default_value_t = 120.0)]
     pub chunk_size: f32,
 
+    /// Seconds of audio each chunk repeats from before its start, so words
+    /// at a chunk boundary aren't lost
+    #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_CHUNK_OVERLAP)]
+    pub chunk_overlap: f32,
+
     /// Number of parallel transcription jobs
     #[arg(long)]
     pub jobs: Option<usize>,
 
+    /// Cap in MB on the audio held in memory at once; decoding runs fewer
+    /// chunks ahead to stay under it. The models come on top.
+    #[arg(long, value_name = "MB")]
+    pub max_memory: Option<u64>,
+
+    /// Extra attempts for a chunk whose transcription fails; after the last
+    /// one its audio is left as a gap marker and reported at the end
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_CHUNK_RETRIES)]
+    pub chunk_retries: u32,
+
+    /// Speech probability (0-1) above which the voice activity detector
+    /// treats audio as speech; chunks without speech are skipped
+    #[arg(long, value_name = "P", default_value_t = VadOptions::default().threshold)]
+    pub vad_threshold: f32,
+
+    /// Upper bound on distinct speakers; further voices are attributed to
+    /// the closest known speaker
+    #[arg(long, value_name = "N", default_value_t = DiarizationOptions::default().max_speakers)]
+    pub max_speakers: usize,
+
+    /// Transcribe each channel on its own as one speaker, channel 1 as
+    /// speaker 1, instead of diarizing; for calls with a caller per channel
+    #[arg(long)]
+    pub split_channels: bool,
+
+    /// Once transcribed, show a line from each speaker, ask for their name
+    /// and write the transcripts again with the names
+    #[arg(long)]
+    pub name_speakers: bool,
+
+    /// Spoken language, e.g. en or de [default: detected per chunk]
+    #[arg(long, value_name = "CODE", conflicts_with = "detect_language")]
+    pub language: Option<String>,
+
+    /// Detect the language once from the first 30 seconds of speech and
+    /// transcribe everything in it
+    #[arg(long)]
+    pub detect_language: bool,
+
+    /// Translate the speech into English with Whisper's translate task,
+    /// instead of transcribing it in the language spoken
+    #[arg(long)]
+    pub translate: bool,
+
+    /// Text Whisper is primed with, e.g. "Kubernetes, Grafana, Tempo", so
+    /// jargon and names are spelled right
+    #[arg(long, value_name = "TEXT")]
+    pub initial_prompt: Option<String>,
+
+    /// File of domain terms and names, one per line, added to the prompt
+    #[arg(long, value_name = "FILE")]
+    pub vocab_file: Option<PathBuf>,
+
+    /// Time every word (start, end and confidence), for JSON and
+    /// karaoke-style VTT output
+    #[arg(long)]
+    pub word_timestamps: bool,
+
+    /// Mean token probability (0-1) below which a segment counts as
+    /// unreliable; text output marks it with [?]
+    #[arg(long, value_name = "P")]
+    pub min_confidence: Option<f32>,
+
+    /// Leave unreliable segments out of text output instead of marking them
+    #[arg(long, requires = "min_confidence")]
+    pub drop_low_confidence: bool,
+
+    /// End text output with each speaker's talk time, word count,
+    /// interruptions and longest monologue; JSON output always has them
+    #[arg(long)]
+    pub speaker_stats: bool,
+
+    /// Sample encoding of raw PCM read from standard input (input -)
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub input_format: Option<RawFormat>,
+
+    /// Sample rate in Hz of raw PCM read from standard input
+    #[arg(long, value_name = "HZ")]
+    pub sample_rate: Option<u32>,
+
+    /// Interleaved channels of raw PCM read from standard input
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub channels: u16,
+
+    /// Watch a folder and transcribe each recording that appears in it once
+    /// it has finished copying; recordings already transcribed are skipped
+    #[cfg(feature = "watch")]
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["input", "stdout"])]
+    pub watch: Option<PathBuf>,
+
     /// Disable GPU acceleration (force CPU-only processing)
     #[arg(long)]
     pub no_gpu: bool,
 
+    /// GPU device indices to distribute chunks across (e.g. 0,1)
+    #[arg(long, value_delimiter = ',')]
+    pub gpu_device: Vec<usize>,
+
+    /// GPU backend to run on; fails if this build doesn't include it
+    #[arg(long, value_enum, default_value_t = GpuBackend::Auto)]
+    pub gpu_backend: GpuBackend,
+
+    /// Output formats, comma-separated: txt, srt, vtt, md, json, or notes
+    /// (Markdown with frontmatter for Obsidian/Notion) [default: txt, or the
+    /// extension of an --output file]
+    #[arg(long, value_delimiter = ',')]
+    pub format: Vec<String>,
+
+    /// How progress is reported: text for people, or json for one event
+    /// per line on stderr (stages, chunks with ETA, summary, errors) in
+    /// place of the log
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = ProgressFormat::Text)]
+    pub progress: ProgressFormat,
+
+    /// Print each segment to stdout as soon as its chunk is transcribed, in
+    /// timestamp order, before the transcripts are written. Speakers are
+    /// only known at the end, so streamed segments have none.
+    #[arg(long)]
+    pub stream_output: bool,
+
+    /// Write the transcript to stdout instead of a file, in the one format
+    /// given with --format, e.g. for piping into jq. Status lines are left
+    /// out; the log and progress stay on stderr.
+    #[arg(long, conflicts_with_all = ["output", "name_speakers", "stream_output"])]
+    pub stdout: bool,
+
+    /// Directory for downloads and other intermediates, e.g. on a larger
+    /// disk [default: the system temp directory]
+    #[arg(long, value_name = "DIR")]
+    pub tmp_dir: Option<PathBuf>,
+
+    /// Start over instead of resuming an interrupted run of the same file
+    #[arg(long)]
+    pub no_resume: bool,
+
+    /// Transcribe from this point of the recording on, e.g. 00:13:20, 13:20
+    /// or 800 (seconds)
+    #[arg(long, value_name = "TIME", value_parser = selection::parse_time, conflicts_with = "range")]
+    pub start: Option<f32>,
+
+    /// Stop transcribing at this point of the recording, e.g. 00:47:00
+    #[arg(long, value_name = "TIME", value_parser = selection::parse_time, conflicts_with = "range")]
+    pub end: Option<f32>,
+
+    /// Transcribe only this part of the recording, e.g. 00:13:20-00:47:00;
+    /// repeat for several parts, in order
+    #[arg(long, value_name = "START-END", allow_hyphen_values = true)]
+    pub range: Vec<TimeRange>,
+
+    /// Time the transcript from zero, with the selected parts back to back,
+    /// instead of keeping the times of the recording
+    #[arg(long)]
+    pub rebase_timestamps: bool,
+
+    /// Seconds added to every output timestamp, e.g. where the audio starts
+    /// in the original video; may be negative
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub offset: f32,
+
+    /// Factor output timestamps are multiplied by (before the offset), for
+    /// media played back at a different speed
+    #[arg(long, default_value_t = 1.0)]
+    pub rate: f32,
+
+    /// Meeting invite (.ics) or TOML file with the title, date and attendees
+    /// [default: <name>.meeting.toml or <name>.ics next to the input]
+    #[arg(long, value_name = "FILE")]
+    pub meeting: Option<PathBuf>,
+
+    /// TOML file listing post-processing steps applied before output
+    #[arg(long, value_name = "CONFIG")]
+    pub post_process: Option<PathBuf>,
+
+    /// TOML file of corrections for recurring mishearings, applied to
+    /// every output format [default: corrections.toml in the config
+    /// directory, if present]
+    #[arg(long, value_name = "FILE")]
+    pub corrections: Option<PathBuf>,
+
+    /// TOML file of display names for speaker labels, e.g.
+    /// SPEAKER_01 = "Moderator", applied to every output format
+    #[arg(long, value_name = "FILE")]
+    pub speaker_names: Option<PathBuf>,
+
+    /// Append an executive summary and action items, written by a chat model
+    #[cfg(feature = "summarize")]
+    #[arg(long, conflicts_with = "stdout")]
+    pub summarize: bool,
+
+    /// OpenAI-compatible API used by --summarize (key from OPENAI_API_KEY)
+    #[cfg(feature = "summarize")]
+    #[arg(long, value_name = "URL", default_value = chat::DEFAULT_ENDPOINT)]
+    pub summarize_endpoint: String,
+
+    /// Model name sent to the summarization endpoint
+    #[cfg(feature = "summarize")]
+    #[arg(long, value_name = "NAME", default_value = chat::DEFAULT_MODEL)]
+    pub summarize_model: String,
+
+    /// Also write a translated transcript, e.g. `de` or `Japanese`
+    #[cfg(feature = "translate")]
+    #[arg(long, value_name = "LANG", conflicts_with = "stdout")]
+    pub translate_to: Option<String>,
+
+    /// OpenAI-compatible API used by --translate-to (key from OPENAI_API_KEY)
+    #[cfg(feature = "translate")]
+    #[arg(long, value_name = "URL", default_value = chat::DEFAULT_ENDPOINT)]
+    pub translate_endpoint: String,
+
+    /// Model name sent to the translation endpoint
+    #[cfg(feature = "translate")]
+    #[arg(long, value_name = "NAME", default_value = chat::DEFAULT_MODEL)]
+    pub translate_model: String,
+
+    /// Show a desktop notification when the run finishes
+    #[cfg(feature = "notify")]
+    #[arg(long)]
+    pub notify: bool,
+
+    /// Also post the notification to this Slack-compatible webhook URL
+    #[cfg(feature = "notify")]
+    #[arg(long, value_name = "URL")]
+    pub notify_webhook: Option<String>,
+
+    /// Record the run (input hash, settings, transcript, statistics) in the
+    /// local archive; see the `history` subcommand
+    #[cfg(feature = "archive")]
+    #[arg(long)]
+    pub archive: bool,
+
+    /// Archive database [default: user data directory]
+    #[cfg(feature = "archive")]
+    #[arg(long, value_name = "FILE")]
+    pub archive_db: Option<PathBuf>,
+}
+
+/// Options every subcommand takes: model downloads and logging
+#[derive(Args, Debug)]
+#[command(next_help_heading = "Global Options")]
+pub struct GlobalArgs {
+    /// Download missing models without asking, e.g. in CI or cron jobs
+    #[arg(short, long, global = true, visible_alias = "download-models", conflicts_with = "offline")]
+    pub yes: bool,
+
+    /// Never download models; fail right away if one is missing
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Directory models are kept in, e.g. on a network share or a larger
+    /// drive [default: $AUDIO_TRANSCRIBE_MODEL_DIR, or the user cache]
+    #[arg(long, global = true, value_name = "DIR")]
+    pub model_dir: Option<PathBuf>,
+
+    /// Extra attempts for an interrupted model download, which resumes
+    /// where it stopped after a growing pause
+    #[arg(long, global = true, value_name = "N", default_value_t = audio_transcription_cli::core::model::DEFAULT_DOWNLOAD_RETRIES)]
+    pub download_retries: u32,
+
     /// Enable verbose logging
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub verbose: bool,
+
+    /// Leave out the configuration, status lines and progress display; the
+    /// log on stderr only shows warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Record per-stage timing spans to a chrome-trace file
+    #[arg(long, global = true, value_name = "TRACE_FILE")]
+    pub trace: Option<PathBuf>,
+
+    /// Write a JSON-lines log of the run (stages, chunk timings, model
+    /// paths, warnings, skipped chunks) [default: <name>.log.jsonl next to
+    /// the transcript]
+    #[arg(long, global = true, value_name = "FILE", num_args = 0..=1, require_equals = true)]
+    pub log_file: Option<Option<PathBuf>>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Transcribe a recording; the default, so `audio-transcribe talk.mp3`
+    /// is short for `audio-transcribe transcribe talk.mp3`
+    Transcribe(Box<TranscribeArgs>),
+
+    /// Print the speech regions of a recording without transcribing it
+    Vad(VadArgs),
+
+    /// Print who spoke when without transcribing (diarize-only mode)
+    Diarize(DiarizeArgs),
+
+    /// Run a local REST API that transcribes submitted files
+    #[cfg(feature = "server")]
+    Serve(ServeArgs),
+
+    /// Add JSON transcripts to the search index
+    #[cfg(feature = "search")]
+    Index(IndexArgs),
+
+    /// Find what was said, and by whom, across indexed transcripts
+    #[cfg(feature = "search")]
+    Search(SearchArgs),
+
+    /// List archived runs, optionally only those of one recording
+    #[cfg(feature = "archive")]
+    History(HistoryArgs),
+
+    /// Show the recording folders (Zoom, OBS, ...) and their naming and
+    /// output rules
+    Sources(SourcesArgs),
+
+    /// Manage the model cache
+    #[command(subcommand)]
+    Models(ModelsCommand),
+
+    /// Check the config files, models, GPU support, memory and disk space
+    /// a transcription needs
+    Doctor,
+
+    /// Enroll the voices of known speakers, so transcripts name them
+    #[command(subcommand)]
+    Speakers(SpeakersCommand),
+
+    /// Transcribe one recording with each of several models and compare
+    /// their speed, memory and GPU load
+    Bench(BenchArgs),
+
+    /// Caption speech from the default microphone live in the terminal
+    #[cfg(feature = "listen")]
+    Listen(ListenArgs),
+
+    /// Record from a microphone until Ctrl-C, then transcribe the recording
+    #[cfg(feature = "listen")]
+    Record(RecordArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct VadArgs {
+    /// Input audio file path
+    pub input: PathBuf,
+
+    /// Speech probability (0-1) above which Silero treats audio as speech
+    #[arg(long, default_value_t = VadOptions::default().threshold)]
+    pub threshold: f32,
+
+    /// Use the energy-based detector instead of the Silero model
+    #[arg(long)]
+    pub energy: bool,
+
+    /// Level in dB above the noise floor that counts as speech, with --energy
+    #[arg(long, default_value_t = VadOptions::default().threshold_db)]
+    pub threshold_db: f32,
+
+    /// Drop speech regions shorter than this many seconds
+    #[arg(long, default_value_t = VadOptions::default().min_speech_secs)]
+    pub min_speech: f32,
+
+    /// Merge regions separated by pauses shorter than this many seconds
+    #[arg(long, default_value_t = VadOptions::default().min_silence_secs)]
+    pub min_silence: f32,
+}
+
+#[derive(Args, Debug)]
+pub struct DiarizeArgs {
+    /// Input audio file path
+    pub input: PathBuf,
+
+    /// Upper bound on the number of distinct speakers
+    #[arg(long, default_value_t = DiarizationOptions::default().max_speakers)]
+    pub max_speakers: usize,
+
+    /// Cosine similarity needed for a turn to join an existing speaker
+    #[arg(long, default_value_t = DiarizationOptions::default().similarity_threshold)]
+    pub similarity_threshold: f32,
+}
+
+#[derive(Args, Debug)]
+pub struct SourcesArgs {
+    /// Sources config [default: sources.toml in the user config directory]
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Also list the recordings found in each folder
+    #[arg(long)]
+    pub list: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// Recording to transcribe with each model; the other settings are
+    /// the ones given before the subcommand
+    #[arg(long)]
+    pub input: PathBuf,
+
+    /// Whisper sizes to compare, comma-separated
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [ModelSize::Tiny, ModelSize::Base, ModelSize::Small])]
+    pub model: Vec<ModelSize>,
+
+    /// Also write the results to this file, as JSON or CSV by its extension
+    #[arg(long, value_name = "FILE")]
+    pub report: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ModelsCommand {
+    /// List the models and which of them are cached
+    List,
+
+    /// Show where a model is stored, its size, download date and checksum
+    Info {
+        /// A Whisper size, segmentation, embedding or vad
+        model: ModelKind,
+    },
+
+    /// Delete cached models; they are downloaded again when needed
+    Remove {
+        /// Whisper sizes, segmentation, embedding or vad
+        #[arg(required = true)]
+        models: Vec<ModelKind>,
+    },
+
+    /// Delete the Whisper sizes not kept and leftover files such as
+    /// interrupted downloads
+    Prune {
+        /// Whisper sizes to keep
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = [ModelSize::Medium])]
+        keep: Vec<ModelSize>,
+    },
+
+    /// Check the cached models against the SHA-256 hashes recorded when
+    /// they were downloaded
+    Verify,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SpeakersCommand {
+    /// Record a speaker's voiceprint from a recording of them alone;
+    /// enrolling a name again adds the sample to their voiceprint
+    Enroll {
+        /// Name the transcripts will use, e.g. alice
+        name: String,
+
+        /// Recording with only this speaker talking, ideally a minute or more
+        sample: PathBuf,
+    },
+
+    /// List the enrolled speakers
+    List,
+
+    /// Forget enrolled speakers
+    Remove {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+}
+
+#[cfg(feature = "server")]
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    pub host: IpAddr,
+
+    /// Port to listen on
+    #[arg(long, default_value_t = server::DEFAULT_PORT)]
+    pub port: u16,
+
+    /// SQLite file the job queue is kept in [default: user data directory]
+    #[arg(long)]
+    pub database: Option<PathBuf>,
+
+    /// Number of files transcribed at the same time
+    #[arg(long, default_value_t = 1)]
+    pub workers: usize,
+
+    /// Attempts per job before it is marked failed
+    #[arg(long, default_value_t = server::jobs::DEFAULT_MAX_ATTEMPTS)]
+    pub max_attempts: u32,
+
+    /// Also serve the gRPC API on this port
+    #[cfg(feature = "grpc")]
+    #[arg(long)]
+    pub grpc_port: Option<u16>,
+}
+
+#[cfg(feature = "listen")]
+#[derive(Args, Debug)]
+pub struct ListenArgs {
+    /// Seconds of quiet that end an utterance and finalize its caption
+    #[arg(long, value_name = "SECONDS", default_value_t = live::DEFAULT_PAUSE_SECS)]
+    pub pause: f32,
+
+    /// Level in dB above the background noise that counts as speech
+    #[arg(long, default_value_t = VadOptions::default().threshold_db)]
+    pub threshold_db: f32,
+}
+
+#[cfg(feature = "listen")]
+#[derive(Args, Debug)]
+pub struct RecordArgs {
+    /// WAV file to record into; transcripts are written next to it unless
+    /// --output is given before the subcommand
+    #[arg(long, value_name = "FILE", required_unless_present = "list_devices")]
+    pub output: Option<PathBuf>,
+
+    /// Input device to record from, as shown by --list-devices [default:
+    /// the system's default input]
+    #[arg(long, value_name = "NAME")]
+    pub device: Option<String>,
+
+    /// List the input devices and exit
+    #[arg(long)]
+    pub list_devices: bool,
+}
+
+#[cfg(feature = "search")]
+#[derive(Args, Debug)]
+pub struct IndexArgs {
+    /// JSON transcripts, or directories to search for them
+    #[arg(required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// Index location [default: user data directory]
+    #[arg(long)]
+    pub index_dir: Option<PathBuf>,
+}
+
+#[cfg(feature = "search")]
+#[derive(Args, Debug)]
+pub struct SearchArgs {
+    /// Words that must all appear; quote a phrase to match it exactly
+    pub query: String,
+
+    /// Maximum number of results
+    #[arg(long, default_value_t = 10)]
+    pub limit: usize,
+
+    /// Index location [default: user data directory]
+    #[arg(long)]
+    pub index_dir: Option<PathBuf>,
+}
+
+#[cfg(feature = "archive")]
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Only show runs of this recording, matched by content so renamed or
+    /// moved copies are found too
+    pub input: Option<PathBuf>,
+
+    /// Maximum number of runs
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Archive database [default: user data directory]
+    #[arg(long, value_name = "FILE")]
+    pub archive_db: Option<PathBuf>,
+}
+
+/// What every transcribed file shares: settings, endpoints and scratch
+/// space, set up once per invocation
+struct Session {
+    config: ProcessingConfig,
+    model_manager: Arc<ModelManager>,
+    post_processors: PostProcessorChain,
+    corrections: Option<Corrections>,
+    speaker_names: Option<SpeakerNames>,
+    meeting: Option<MeetingInfo>,  // From --meeting; otherwise looked up per file
+    run_log: Option<RunLog>,
+    work_dir: WorkDir,
+    bars: MultiProgress,           // Live progress on stderr; log events print above it
+    quiet: bool,                   // --quiet: no status lines or progress display
+    #[cfg(feature = "summarize")]
+    summarizer: Option<Summarizer>,
+    #[cfg(feature = "translate")]
+    translator: Option<Translator>,
+    #[cfg(feature = "notify")]
+    notifier: Option<Notifier>,
+    #[cfg(feature = "archive")]
+    archive_path: Option<PathBuf>,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-
-    // Initialize logging
-    if cli.verbose {
-        env_logger::Builder::from_default_env()
-            .filter_level(log::LevelFilter::Debug)
-            .init();
+async fn main() {
+    // The matches tell options given on the command line from defaults,
+    // which the config file may replace
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    // Exit with the error's stable code so scripts can branch on failures
+    if let Err(e) = run(cli, &matches).await {
+        tracing::error!(target: RUN_LOG_TARGET, code = e.code(), error = %e, "Run failed");
+        print_error(&e, json_progress(&matches));
+        std::process::exit(e.code().into());
+    }
+}
+
+async fn run(cli: Cli, matches: &ArgMatches) -> Result<()> {
+    let Cli { transcribe, global, command } = cli;
+    // Without a subcommand the top-level options are those of `transcribe`
+    let (mut cli, command, matches) = match command {
+        Some(Command::Transcribe(args)) => (*args, None, matches.subcommand_matches("transcribe").unwrap_or(matches)),
+        command => (transcribe, command, matches),
+    };
+
+    // Keep the guard alive so the trace is flushed on exit. The run log
+    // collects events from the start, even before it knows its file.
+    let run_log = global.log_file.is_some().then(RunLog::new);
+    let bars = MultiProgress::new();
+    let log_bars = (cli.progress == ProgressFormat::Text).then(|| bars.clone());
+    let verbosity = match (global.quiet, global.verbose) {
+        (true, _) => Verbosity::Quiet,
+        (false, true) => Verbosity::Verbose,
+        (false, false) => Verbosity::Normal,
+    };
+    let _profiling_guard = init_tracing(verbosity, log_bars, global.trace.as_deref(), run_log.clone())?;
+    if let (Some(run_log), Some(Some(path))) = (&run_log, &global.log_file) {
+        run_log.open(path)?;
+    } else if run_log.is_some() && command.is_some() {
+        return Err(AudioTranscriptionError::Configuration(
+            "--log-file needs a file name with a subcommand, e.g. --log-file=run.log.jsonl".to_string()
+        ));
+    }
+
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "Audio Transcription CLI");
+    let models = ModelOptions::new(&global);
+    let user_config = match &cli.config {
+        Some(path) => UserConfig::from_config_file(path),
+        None => UserConfig::load_default(),
+    };
+    // The doctor reports a broken config file rather than failing on it
+    if let Some(Command::Doctor) = &command {
+        return run_doctor(cli, matches, user_config, &models);
+    }
+    let settings = user_config?.settings(cli.profile.as_deref())?;
+    apply_settings(&mut cli, settings, matches)?;
+    tracing::debug!(?global, ?cli, "CLI arguments");
+
+    // Standalone stages don't need the transcription models
+    match &command {
+        Some(Command::Vad(args)) => return run_vad(args, &models).await,
+        Some(Command::Diarize(args)) => return run_diarize(args, &models).await,
+        Some(Command::Sources(args)) => return run_sources(args),
+        Some(Command::Models(command)) => return run_models(command, &models),
+        Some(Command::Speakers(command)) => return run_speakers(command, &models).await,
+        #[cfg(feature = "search")]
+        Some(Command::Index(args)) => return run_index(args),
+        #[cfg(feature = "search")]
+        Some(Command::Search(args)) => return run_search(args),
+        #[cfg(feature = "archive")]
+        Some(Command::History(args)) => return run_history(args),
+        #[cfg(feature = "listen")]
+        Some(Command::Record(args)) if args.list_devices => return list_input_devices(),
+        _ => {}
+    }
+
+    // Load post-processing steps up front so config mistakes fail fast
+    let mut post_processors = match &cli.post_process {
+        Some(config_path) => PostProcessorChain::from_config_file(config_path)?,
+        None => PostProcessorChain::new(),
+    };
+    // Retiming runs last so configured steps see the original timestamps
+    if cli.offset != 0.0 || cli.rate != 1.0 {
+        post_processors.push(Arc::new(Retime::new(cli.offset, cli.rate)?));
+    }
+    let corrections = match &cli.corrections {
+        Some(path) => Some(Corrections::from_config_file(path)?),
+        None => Corrections::load_default()?,
+    };
+    let speaker_names = cli.speaker_names.as_deref().map(SpeakerNames::from_config_file).transpose()?;
+
+    let model_size = match &cli.model {
+        ModelChoice::Size(size) => size.clone(),
+        ModelChoice::Auto => auto::select_model_for((!cli.no_gpu).then(|| cli.gpu_device.first().copied().unwrap_or(0))),
+    };
+    let mut config = ProcessingConfig::builder()
+        .model(model_size)
+        .model_fallback(cli.model == ModelChoice::Auto)
+        .chunk_duration(cli.chunk_size)
+        .chunk_overlap(cli.chunk_overlap)
+        .chunk_retries(cli.chunk_retries)
+        .vad_threshold(cli.vad_threshold)
+        .max_speakers(cli.max_speakers)
+        .split_channels(cli.split_channels)
+        .voiceprints(SpeakerRegistry::in_cache_dir(models.manager()?.cache_dir())?.into_voiceprints())
+        .word_timestamps(cli.word_timestamps)
+        .detect_language(cli.detect_language)
+        .translate(cli.translate)
+        .hallucination_filter(!cli.no_hallucination_filter)
+        .ranges(time_ranges(&cli))
+        .rebase_timestamps(cli.rebase_timestamps)
+        .decoding(DecodingOptions {
+            beam_size: cli.beam_size,
+            best_of: cli.best_of,
+            temperature: cli.temperature,
+            temperature_increment: cli.temperature_increment,
+            no_speech_threshold: cli.no_speech_threshold,
+        })
+        .gpu(!cli.no_gpu)
+        .gpu_devices(cli.gpu_device.iter().copied())
+        .gpu_backend(cli.gpu_backend);
+    if let Some(jobs) = cli.jobs {
+        config = config.jobs(jobs);
+    }
+    if let Some(megabytes) = cli.max_memory {
+        config = config.max_memory(megabytes.saturating_mul(1_000_000));
+    }
+    if let Some(language) = &cli.language {
+        config = config.language(language.to_ascii_lowercase());
+    }
+    if let Some(model) = &cli.refine_model {
+        config = config.refine_model(model.clone()).refine_threshold(cli.refine_threshold);
+    }
+    let vocabulary = cli.vocab_file.as_deref().map(transcription::read_vocabulary).transpose()?.unwrap_or_default();
+    if let Some(prompt) = transcription::build_prompt(cli.initial_prompt.as_deref(), &vocabulary) {
+        config = config.initial_prompt(prompt);
+    }
+    let config = config.build()?;
+
+    // A benchmark runs the same settings with each of its models
+    if let Some(Command::Bench(args)) = &command {
+        return run_bench(args, config, &models).await;
+    }
+
+    TranscriptGenerator::new(None).require_formatters(&cli.format)?;
+    let raw_pcm = raw_input(&cli)?;
+    if cli.name_speakers && (raw_pcm.is_some() || !io::stdin().is_terminal()) {
+        return Err(AudioTranscriptionError::Configuration(
+            "--name-speakers asks for names on the terminal, so standard input must be one".to_string()
+        ));
+    }
+    if cli.stdout && cli.format.len() > 1 {
+        return Err(AudioTranscriptionError::Configuration(format!(
+            "--stdout writes a single format, not {}",
+            cli.format.join(",")
+        )));
+    }
+    // Standard input is spooled into the work directory, which is deleted
+    // afterwards, so its transcripts go to the current directory
+    if raw_pcm.is_some() && cli.output.is_none() {
+        cli.output = Some(PathBuf::from("."));
+    }
+    let meeting = cli.meeting.as_deref().map(MeetingInfo::from_file).transpose()?;
+
+    // Check the summarization and translation endpoints before any work too
+    #[cfg(feature = "summarize")]
+    let summarizer = if cli.summarize {
+        Some(Summarizer::new(ChatConfig {
+            endpoint: cli.summarize_endpoint.clone(),
+            model: cli.summarize_model.clone(),
+            ..ChatConfig::default()
+        })?)
+    } else {
+        None
+    };
+    #[cfg(feature = "translate")]
+    let translator = match &cli.translate_to {
+        Some(language) => Some(Translator::new(
+            ChatConfig {
+                endpoint: cli.translate_endpoint.clone(),
+                model: cli.translate_model.clone(),
+                ..ChatConfig::default()
+            },
+            language.clone(),
+        )?),
+        None => None,
+    };
+
+    #[cfg(feature = "notify")]
+    let notifier = if cli.notify || cli.notify_webhook.is_some() {
+        Some(Notifier::new(cli.notify, cli.notify_webhook.clone())?)
+    } else {
+        None
+    };
+
+    #[cfg(feature = "archive")]
+    let archive_path = if cli.archive {
+        let path = match &cli.archive_db {
+            Some(path) => path.clone(),
+            None => archive::default_archive_path()?,
+        };
+        RunArchive::open(&path)?;
+        Some(path)
     } else {
-        env_logger::Builder::from_default_env()
-            .filter_level(log::LevelFilter::Info)
-            .init();
+        None
+    };
+
+    // Parse a remote output location now so a bad URI fails before any work
+    #[cfg(feature = "cloud")]
+    if let Some(uri) = cli.output.as_ref().and_then(|output| output.to_str()).filter(|uri| storage::is_remote(uri)) {
+        ObjectLocation::parse(uri)?;
     }
 
-    log::info!("Audio Transcription CLI v{}", env!("CARGO_PKG_VERSION"));
-    log::debug!("CLI arguments: {:?}", cli);
+    // Live captions only need the Whisper model
+    #[cfg(feature = "listen")]
+    if let Some(Command::Listen(args)) = &command {
+        return run_listen(args, config, &models).await;
+    }
 
     // Check and ensure models are available before proceeding
-    log::info!("Checking required models...");
-    let model_manager = ModelManager::new()?;
-    match model_manager.ensure_models_available(&cli.model).await {
+    tracing::info!("Checking required models...");
+    let model_manager = Arc::new(models.manager()?);
+    match model_manager.ensure_models_available(&config.model_size).await {
         Ok(true) => {
-            log::info!("All required models are available");
+            tracing::info!("All required models are available");
         }
         Ok(false) => {
-            println!("Model download cancelled. Cannot proceed without required models.");
+            eprintln!("Model download cancelled. Cannot proceed without required models.");
             return Ok(());
         }
         Err(e) => {
-            println!("Error with model setup: {}", e);
-            println!("Cannot proceed without required models.");
+            eprintln!("Error with model setup: {}", e);
+            eprintln!("Cannot proceed without required models.");
             return Err(e);
         }
     }
 
+    #[cfg(feature = "server")]
+    if let Some(Command::Serve(args)) = &command {
+        let transcriber = Transcriber::builder()
+            .config(config)
+            .post_processors(post_processors)
+            .model_provider(model_manager)
+            .build()?;
+        let mut server_config = server::ServerConfig::new(SocketAddr::new(args.host, args.port))?;
+        server_config.workers = args.workers;
+        server_config.max_attempts = args.max_attempts;
+        if let Some(database) = &args.database {
+            server_config.database = database.clone();
+        }
+        #[cfg(feature = "grpc")]
+        {
+            server_config.grpc_addr = args.grpc_port.map(|port| SocketAddr::new(args.host, port));
+        }
+        return server::serve(transcriber, server_config).await;
+    }
+
+    // Scratch space for this run, deleted however the run ends
+    let work_dir = WorkDir::create(cli.tmp_dir.as_deref())?;
+    let session = Session {
+        config,
+        model_manager,
+        post_processors,
+        corrections,
+        speaker_names,
+        meeting,
+        run_log,
+        work_dir,
+        bars,
+        quiet: global.quiet,
+        #[cfg(feature = "summarize")]
+        summarizer,
+        #[cfg(feature = "translate")]
+        translator,
+        #[cfg(feature = "notify")]
+        notifier,
+        #[cfg(feature = "archive")]
+        archive_path,
+    };
+
+    #[cfg(feature = "watch")]
+    if let Some(dir) = &cli.watch {
+        return watch_folder(&cli, session, dir).await;
+    }
+
+    #[cfg(feature = "listen")]
+    if let Some(Command::Record(args)) = &command {
+        let input_file = record(&cli, args).await?;
+        let cancel = install_interrupt_handler(session.work_dir.path().to_path_buf());
+        transcribe_file(&cli, &session, input_file, cancel).await?;
+        return session.work_dir.remove();
+    }
+
     // Determine input file path
-    let input_file = if let Some(input) = cli.input {
+    let input_file = if let Some(pcm) = raw_pcm {
+        read_stdin(pcm, session.work_dir.path()).await?
+    } else if let Some(input) = cli.input.clone() {
         // Direct file input provided
-        log::info!("Processing file: {}", input.display());
+        tracing::info!(path = %input.display(), "Processing file");
+        #[cfg(feature = "cloud")]
+        let input = fetch_remote_input(input, session.work_dir.path()).await?;
         input
     } else {
         // No input file provided, launch file browser
-        log::info!("No input file provided, launching interactive file browser...");
+        tracing::info!("No input file provided, launching interactive file browser...");
         
         // Wait a moment for any pending output to finish, then clear screen completely
         std::thread::sleep(std::time::Duration::from_millis(100));
@@ -101,7 +1050,7 @@ async fn main() -> Result<()> {
         println!(); // Add blank line
         
         let current_dir = std::env::current_dir()
-            .map_err(|e| crate::error::AudioTranscriptionError::FileBrowser(
+            .map_err(|e| AudioTranscriptionError::FileBrowser(
                 format!("Failed to get current directory: {}", e)
             ))?;
         
@@ -112,7 +1061,7 @@ async fn main() -> Result<()> {
                 // Clear screen after selection
                 print!("\x1b[2J\x1b[H");
                 io::stdout().flush().unwrap();
-                log::info!("Selected file: {}", selected_file.display());
+                tracing::info!(path = %selected_file.display(), "Selected file");
                 selected_file
             }
             None => {
@@ -125,106 +1074,1401 @@ async fn main() -> Result<()> {
         }
     };
 
+    let cancel = install_interrupt_handler(session.work_dir.path().to_path_buf());
+    transcribe_file(&cli, &session, input_file, cancel).await?;
+    session.work_dir.remove()
+}
+
+/// `println!` for the running commentary on a transcription, which
+/// `--quiet` and `--stdout` leave out
+macro_rules! say {
+    ($quiet:expr) => {
+        if !$quiet {
+            println!()
+        }
+    };
+    ($quiet:expr, $($arg:tt)*) => {
+        if !$quiet {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// `println!` for what needs attention even then, moved to stderr
+macro_rules! notice {
+    ($quiet:expr, $($arg:tt)*) => {
+        if $quiet {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// Transcribe one recording and write its transcripts, summary,
+/// translation and notifications as configured
+async fn transcribe_file(cli: &TranscribeArgs, session: &Session, input_file: PathBuf, cancel: CancellationToken) -> Result<TranscriptResult> {
+    let config = &session.config;
+    // Nothing but the transcript may go to stdout with --stdout
+    let quiet = session.quiet || cli.stdout;
+
     // Validate that the selected file exists and is supported
     if !input_file.exists() {
-        return Err(crate::error::AudioTranscriptionError::FileBrowser(
-            format!("File does not exist: {}", input_file.display())
-        ));
+        return Err(AudioTranscriptionError::InputNotFound { path: input_file });
     }
 
     if !input_file.is_file() {
-        return Err(crate::error::AudioTranscriptionError::FileBrowser(
-            format!("Path is not a file: {}", input_file.display())
-        ));
+        return Err(AudioTranscriptionError::InputNotAFile { path: input_file });
     }
 
     // Check if it's a supported audio format
     if let Some(extension) = input_file.extension().and_then(|ext| ext.to_str()) {
-        let supported_formats = ["wav", "mp3", "m4a", "flac", "ogg", "webm"];
-        if !supported_formats.contains(&extension.to_lowercase().as_str()) {
-            return Err(crate::error::AudioTranscriptionError::UnsupportedFormat(
-                format!("Unsupported audio format: .{}", extension)
-            ));
+        if !SUPPORTED_EXTENSIONS.contains(&extension.to_lowercase().as_str()) {
+            return Err(AudioTranscriptionError::UnsupportedFormat {
+                extension: Some(extension.to_string()),
+                path: input_file,
+            });
         }
     } else {
-        return Err(crate::error::AudioTranscriptionError::UnsupportedFormat(
-            "File has no extension or unsupported format".to_string()
-        ));
-    }
-
-    println!("\n✅ Selected audio file: {}", input_file.display());
-    println!("📊 Configuration:");
-    println!("   Model: {}", cli.model);
-    if let Some(output) = &cli.output {
-        println!("   Output directory: {}", output.display());
-    } else {
-        println!("   Output directory: Same as input file");
-    }
-    println!("   Chunk size: {} seconds", cli.chunk_size);
-    if let Some(jobs) = cli.jobs {
-        println!("   Parallel jobs: {}", jobs);
-    } else {
-        println!("   Parallel jobs: auto-detect ({})", num_cpus::get());
+        return Err(AudioTranscriptionError::UnsupportedFormat {
+            path: input_file,
+            extension: None,
+        });
     }
-    println!("   GPU acceleration: {}", !cli.no_gpu);
 
-    // TODO: Implement actual audio processing
-    // This will be implemented in subsequent tasks
-    println!("\n🚧 Audio processing pipeline not yet implemented.");
-    println!("This will be added in upcoming tasks (Task 5-15).");
-    println!("For now, the file browser integration is complete!");
+    let meeting = match session.meeting.clone() {
+        Some(meeting) => Some(meeting),
+        None => MeetingInfo::find_sidecar(&input_file).map(|path| MeetingInfo::from_file(&path)).transpose()?,
+    };
 
-    Ok(())
-}
+    let notes = note_options(&input_file, meeting.as_ref());
+    // An output directory template names a directory for each recording
+    let output = cli.output.as_deref().map(|output| profiles::expand_output(output, &input_file, notes.date.as_deref()));
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use clap::Parser;
-    use std::path::PathBuf;
+    // Recordings from a known app folder follow that source's naming and
+    // output rules; an explicit --output still wins
+    let route = RecordingSources::load_default()?.route(&input_file);
 
-    #[test]
-    fn test_default_cli_arguments() {
-        // Test default values when no arguments are provided
-        let cli = Cli::try_parse_from(&["audio-transcribe"]).unwrap();
-        
-        assert!(cli.input.is_none());
-        assert!(matches!(cli.model, ModelSize::Medium));
-        assert!(cli.output.is_none());
-        assert_eq!(cli.chunk_size, 120.0);
-        assert!(cli.jobs.is_none());
-        assert!(!cli.no_gpu);
-        assert!(!cli.verbose);
+    // Notes get the recording's name and date in their frontmatter
+    let mut generator = TranscriptGenerator::new(None);
+    if let Some(route) = &route {
+        if output.is_none() {
+            generator.set_output_dir(route.output_dir.clone());
+        }
+        generator.set_output_name(Some(route.name.clone()));
     }
+    generator.register_formatter(Box::new(NotesFormatter { options: notes }));
+    generator.register_formatter(Box::new(TextFormatter { options: text_options(cli)? }));
+    generator.set_meeting(meeting.clone());
+    generator.set_corrections(session.corrections.clone());
+    generator.set_speaker_names(session.speaker_names.clone());
 
-    #[test]
+    // `--output talk.vtt` names the transcript itself and picks its format
+    let output_file = output.as_deref().filter(|output| output_file_format(output, &generator).is_some());
+    if let Some(output) = output.as_ref().filter(|output| !is_remote_output(output)) {
+        match output_file {
+            Some(file) => {
+                let parent = file.parent().filter(|parent| !parent.as_os_str().is_empty());
+                generator.set_output_dir(Some(parent.unwrap_or(Path::new(".")).to_path_buf()));
+                generator.set_output_name(file.file_stem().map(|stem| stem.to_string_lossy().into_owned()));
+            }
+            None => generator.set_output_dir(Some(output.clone())),
+        }
+    }
+    let formats = match (output_file, output_file.and_then(|file| output_file_format(file, &generator))) {
+        (_, Some(file_format)) if cli.format.is_empty() => vec![file_format],
+        (Some(file), Some(file_format)) => {
+            let extension = generator.require_formatter(&file_format)?.extension();
+            if !generator.require_formatters(&cli.format)?.iter().any(|formatter| formatter.extension() == extension) {
+                return Err(AudioTranscriptionError::Configuration(format!(
+                    "--format {} writes no .{} file, but --output names {}",
+                    cli.format.join(","), extension, file.display()
+                )));
+            }
+            cli.format.clone()
+        }
+        _ if cli.format.is_empty() => vec![DEFAULT_FORMAT.to_string()],
+        _ => cli.format.clone(),
+    };
+    let formats: Vec<String> = generator
+        .require_formatters(&formats)?
+        .iter()
+        .map(|formatter| formatter.name().to_string())
+        .collect();
+
+    let output_dir = local_output_dir(output.as_deref().filter(|_| output_file.is_none()), &generator, &input_file)?;
+    if output != cli.output {
+        std::fs::create_dir_all(&output_dir)
+            .map_err(|source| AudioTranscriptionError::File { path: output_dir.clone(), source })?;
+    }
+    if let Some(run_log) = session.run_log.as_ref().filter(|run_log| run_log.path().is_none()) {
+        let path = generator.output_path(&input_file, "log.jsonl")?;
+        run_log.open(&output_dir.join(path.file_name().unwrap_or_default()))?;
+    }
+
+    // Chunks finished before a crash or interrupt are reused unless asked
+    // otherwise; the checkpoint sits next to the transcript
+    let checkpoint_file = output_dir.join(generator.output_path(&input_file, checkpoint::EXTENSION)?.file_name().unwrap_or_default());
+    let resume = Checkpoint::open_file(checkpoint_file.clone(), &input_file, config)?;
+    let resumed_chunks = if cli.no_resume {
+        resume.remove()?;
+        0
+    } else {
+        resume.len()
+    };
+
+    // Fail now rather than hours in when a disk fills up
+    let audio_duration = metadata::probe(&input_file)?.duration;
+    let space = SpaceEstimate::for_audio(audio_duration);
+    workdir::ensure_free_space(session.work_dir.path(), space.work)?;
+    workdir::ensure_free_space(&output_dir, space.output)?;
+
+    let json_progress = (cli.progress == ProgressFormat::Json).then(|| Arc::new(JsonProgress::stderr()));
+    if let Some(progress) = &json_progress {
+        progress.start(&input_file, &config.model_size.to_string(), audio_duration.as_secs_f32());
+    }
+    // The live display is hidden when stderr isn't a terminal anyway
+    let live_progress = (cli.progress == ProgressFormat::Text && !session.quiet && io::stderr().is_terminal())
+        .then(|| Arc::new(LiveProgress::new(&session.bars)));
+    let progress: Option<Arc<dyn ProgressSink>> = match (&json_progress, &live_progress) {
+        (Some(progress), _) => Some(progress.clone()),
+        (None, Some(progress)) => Some(progress.clone()),
+        (None, None) => None,
+    };
+
+    say!(quiet, "\n✅ Selected audio file: {}", input_file.display());
+    say!(quiet, "📊 Configuration:");
+    if let Some(profile) = &cli.profile {
+        say!(quiet, "   Profile: {}", profile);
+    }
+    say!(quiet, "   Model: {}", config.model_size);
+    if let Some(model) = &config.refine_model {
+        say!(quiet, "   Refine model: {} (segments below {} confidence)", model, config.refine_threshold);
+    }
+    if !config.hallucination_filter {
+        say!(quiet, "   Hallucination filter: off");
+    }
+    if !config.ranges.is_empty() {
+        let ranges: Vec<String> = config
+            .ranges
+            .iter()
+            .map(|range| {
+                let end = range.end.map_or("end".to_string(), |end| format_timestamp(end, '.'));
+                format!("{}-{}", format_timestamp(range.start, '.'), end)
+            })
+            .collect();
+        let timestamps = if config.rebase_timestamps { ", timestamps from zero" } else { "" };
+        say!(quiet, "   Time ranges: {}{}", ranges.join(", "), timestamps);
+    }
+    if let Some(prompt) = &config.initial_prompt {
+        say!(quiet, "   Initial prompt: {}", prompt);
+    }
+    if config.decoding != DecodingOptions::default() {
+        let decoding = &config.decoding;
+        let strategy = match decoding.beam_size {
+            Some(beams) => format!("beam search ({} beams)", beams),
+            None => format!("greedy (best of {})", decoding.best_of),
+        };
+        say!(
+            quiet,
+            "   Decoding: {}, temperature {} + {}, no-speech threshold {}",
+            strategy, decoding.temperature, decoding.temperature_increment, decoding.no_speech_threshold
+        );
+    }
+    if let Some(file) = output_file {
+        say!(quiet, "   Output file: {}", file.display());
+    } else if let Some(output) = &output {
+        say!(quiet, "   Output directory: {}", output.display());
+    } else if let Some(output) = generator.output_dir() {
+        say!(quiet, "   Output directory: {}", output.display());
+    } else {
+        say!(quiet, "   Output directory: Same as input file");
+    }
+    say!(quiet, "   Output format: {}", formats.join(", "));
+    if let Some(min) = cli.min_confidence {
+        let action = if cli.drop_low_confidence { "dropped" } else { "marked" };
+        say!(quiet, "   Min confidence: {} (unreliable text segments {})", min, action);
+    }
+    if let Some(route) = &route {
+        say!(quiet, "   Source: {} (transcript name: {})", route.source, route.name);
+    }
+    if let Some(path) = session.run_log.as_ref().and_then(|run_log| run_log.path()) {
+        say!(quiet, "   Log file: {}", path.display());
+    }
+    say!(quiet, "   Work directory: {}", session.work_dir.path().display());
+    if let Some(meeting) = &meeting {
+        say!(quiet, "   Meeting: {}", meeting.title.as_deref().unwrap_or("(untitled)"));
+        if !meeting.attendees.is_empty() {
+            say!(quiet, "   Attendees: {}", meeting.attendees.join(", "));
+        }
+    }
+    say!(quiet, "   Chunk size: {} seconds ({}s overlap)", config.chunk_duration, config.chunk_overlap);
+    if let Some(jobs) = cli.jobs {
+        say!(quiet, "   Parallel jobs: {}", jobs);
+    } else {
+        say!(quiet, "   Parallel jobs: auto-detect ({})", num_cpus::get());
+    }
+    if let Some(bytes) = config.max_memory {
+        say!(quiet, "   Max memory: {} MB for audio", bytes / 1_000_000);
+    }
+    say!(quiet, "   Chunk retries: {}", config.chunk_retries);
+    say!(quiet, "   VAD threshold: {}", config.vad_threshold);
+    if config.split_channels {
+        say!(quiet, "   Speakers: one per channel");
+    } else {
+        say!(quiet, "   Max speakers: {}", config.max_speakers);
+        if !config.voiceprints.is_empty() {
+            let names: Vec<&str> = config.voiceprints.iter().map(|voiceprint| voiceprint.name.as_str()).collect();
+            say!(quiet, "   Enrolled speakers: {}", names.join(", "));
+        }
+    }
+    match &config.language {
+        Some(language) => say!(quiet, "   Language: {}", language),
+        None if config.detect_language => say!(quiet, "   Language: detect from the first 30 seconds"),
+        None => say!(quiet, "   Language: auto-detect per chunk"),
+    }
+    if config.translate {
+        say!(quiet, "   Task: translate to English");
+    }
+    if config.word_timestamps {
+        say!(quiet, "   Word timestamps: on");
+    }
+    say!(quiet, "   GPU acceleration: {}", config.use_gpu);
+    if config.use_gpu && config.gpu_backend != GpuBackend::Auto {
+        say!(quiet, "   GPU backend: {}", config.gpu_backend);
+    }
+    if !config.gpu_devices.is_empty() {
+        say!(quiet, "   GPU devices: {:?}", config.gpu_devices);
+    }
+    if !session.post_processors.is_empty() {
+        say!(quiet, "   Post-processing: {}", session.post_processors.names().join(" → "));
+    }
+    if let Some(corrections) = &session.corrections {
+        say!(quiet, "   Corrections: {}", corrections.len());
+    }
+    if let Some(names) = &session.speaker_names {
+        say!(quiet, "   Speaker names: {}", names.len());
+    }
+    if resumed_chunks > 0 {
+        say!(quiet, "   Resuming: {} chunks already transcribed (--no-resume to start over)", resumed_chunks);
+    }
+    if cli.offset != 0.0 || cli.rate != 1.0 {
+        say!(quiet, "   Timestamps: ×{} {:+} seconds", cli.rate, cli.offset);
+    }
+    #[cfg(feature = "summarize")]
+    if let Some(summarizer) = &session.summarizer {
+        say!(quiet, "   Summary: {} ({})", summarizer.config().endpoint, summarizer.config().model);
+    }
+    #[cfg(feature = "translate")]
+    if let Some(translator) = &session.translator {
+        say!(
+            quiet,
+            "   Translation: {} via {} ({})",
+            translator.target_language(),
+            translator.config().endpoint,
+            translator.config().model
+        );
+    }
+    #[cfg(feature = "notify")]
+    if let Some(notifier) = &session.notifier {
+        let mut targets = Vec::new();
+        if notifier.desktop() {
+            targets.push("desktop");
+        }
+        if notifier.webhook().is_some() {
+            targets.push("webhook");
+        }
+        say!(quiet, "   Notify: {}", targets.join(", "));
+    }
+    #[cfg(feature = "archive")]
+    if let Some(path) = &session.archive_path {
+        say!(quiet, "   Archive: {}", path.display());
+    }
+
+    // A remote --output is written to the work directory, then uploaded
+    #[cfg(feature = "cloud")]
+    let remote_output = match output.as_ref().and_then(|output| output.to_str()).filter(|uri| storage::is_remote(uri)) {
+        Some(uri) => {
+            generator.set_output_dir(Some(session.work_dir.path().to_path_buf()));
+            Some(ObjectLocation::parse(uri)?)
+        }
+        None => None,
+    };
+
+    let mut transcriber = Transcriber::builder()
+        .config(config.clone())
+        .post_processors(session.post_processors.clone())
+        .model_provider(session.model_manager.clone())
+        .cancellation_token(cancel)
+        .checkpoint_file(checkpoint_file);
+    if let Some(progress) = progress {
+        transcriber = transcriber.progress(progress);
+    }
+    let transcriber = transcriber.build()?;
+
+    say!(quiet, "\n🎙️  Transcribing...");
+    let outcome: Result<(TranscriptResult, Vec<PathBuf>)> = async {
+        let mut result = if cli.stream_output {
+            let (segments, mut receiver) = mpsc::unbounded_channel();
+            let bars = session.bars.clone();
+            let printing = tokio::spawn(async move {
+                while let Some(segment) = receiver.recv().await {
+                    bars.suspend(|| print_segment(&segment));
+                }
+            });
+            let result = transcriber.transcribe_with_segments(&input_file, None, segments).await;
+            let _ = printing.await;
+            say!(quiet);
+            result?
+        } else {
+            transcriber.transcribe(&input_file).await?
+        };
+        let mut transcripts = if cli.stdout {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(&generator.render(&result, &formats[0])?)
+                .and_then(|()| stdout.flush())
+                .map_err(AudioTranscriptionError::Io)?;
+            Vec::new()
+        } else {
+            generator.generate_all(&input_file, &result, &formats)?
+        };
+
+        // Transcripts are written first, so nothing is lost if nobody answers
+        if cli.name_speakers && !result.partial {
+            let names = speaker_names::ask(&result, &mut io::stdin().lock(), &mut io::stdout())?;
+            if !names.is_empty() {
+                result = speaker_names::apply(&result, &names);
+                transcripts = generator.generate_all(&input_file, &result, &formats)?;
+                say!(quiet, "✏️  Named {} speaker(s) in the transcripts", names.len());
+            }
+        }
+        let mut outputs = transcripts.clone();
+
+        // An interrupted run only gets its partial transcript
+        if !result.partial {
+            #[cfg(feature = "summarize")]
+            if let Some(summarizer) = &session.summarizer {
+                say!(quiet, "📝 Summarizing...");
+                let written = summarizer.summarize(&result).await?.append_to_all(&transcripts)?;
+                outputs.extend(written.into_iter().filter(|path| !transcripts.contains(path)));
+            }
+
+            #[cfg(feature = "translate")]
+            if let Some(translator) = &session.translator {
+                say!(quiet, "🌐 Translating to {}...", translator.target_language());
+                let translated = translator.translate(&result).await?;
+                outputs.extend(generator.generate_translations(&input_file, &translated, &formats, translator.target_language())?);
+            }
+
+            #[cfg(feature = "archive")]
+            if let Some(path) = &session.archive_path {
+                let settings = serde_json::json!({
+                    "model": config.model_size.to_string(),
+                    "chunk_duration": config.chunk_duration,
+                    "chunk_overlap": config.chunk_overlap,
+                    "vad_threshold": config.vad_threshold,
+                    "word_timestamps": config.word_timestamps,
+                    "language": result.model_info.language,
+                    "task": if config.translate { "translate" } else { "transcribe" },
+                    "gpu": config.use_gpu,
+                    "format": formats.join(","),
+                });
+                RunArchive::open(path)?.record(&input_file, &archive::hash_file(&input_file)?, &settings, &result)?;
+            }
+        }
+
+        #[cfg(feature = "cloud")]
+        if let Some(location) = &remote_output {
+            for output in &mut outputs {
+                let target = location.join(&output.file_name().unwrap_or_default().to_string_lossy());
+                target.upload_from(output).await?;
+                *output = PathBuf::from(target.uri());
+            }
+        }
+
+        Ok((result, outputs))
+    }
+    .await;
+
+    #[cfg(feature = "notify")]
+    if let Some(notifier) = &session.notifier {
+        match &outcome {
+            Ok((result, _)) if result.partial => {}
+            Ok((result, outputs)) => {
+                let outputs: Vec<&Path> = outputs.iter().map(PathBuf::as_path).collect();
+                notifier.send(&Notification::finished(&input_file, result, &outputs)).await;
+            }
+            Err(e) => notifier.send(&Notification::failed(&input_file, e)).await,
+        }
+    }
+    if let Some(progress) = &live_progress {
+        progress.finish();
+    }
+    let (result, outputs) = outcome?;
+    if let Some(progress) = &json_progress {
+        progress.summary(&input_file, &result, &outputs);
+    }
+
+    say!(quiet);
+    if !quiet {
+        print!("{}", ProgressDisplay::render_summary(&result));
+    }
+    for output in &outputs {
+        say!(quiet, "📄 {}", output.display());
+    }
+    if !result.skipped.is_empty() {
+        notice!(quiet, "\n⚠️  {} chunk(s) could not be transcribed and are marked {}:", result.skipped.len(), GAP_MARKER);
+        for range in &result.skipped {
+            notice!(quiet, "   {}", range);
+        }
+        notice!(quiet, "Run the same command again to retry them.");
+    }
+    if result.partial {
+        notice!(quiet, "\n⏸️  Interrupted: the transcript is partial. Run the same command again to resume.");
+    }
+
+
+    Ok(result)
+}
+
+/// Transcribe every recording dropped into `dir`, each once its copy has
+/// finished, until Ctrl-C
+#[cfg(feature = "watch")]
+async fn watch_folder(cli: &TranscribeArgs, session: Session, dir: &Path) -> Result<()> {
+    if let Some(output) = cli.output.as_deref().filter(|output| output_file_format(output, &TranscriptGenerator::new(None)).is_some()) {
+        return Err(AudioTranscriptionError::Configuration(format!(
+            "--watch writes a transcript per recording, so --output must be a folder, not {}",
+            output.display()
+        )));
+    }
+    let mut folder = DropFolder::watch(dir, &watch::default_state_dir()?, watch::DEFAULT_SETTLE)?;
+    let cancel = install_interrupt_handler(session.work_dir.path().to_path_buf());
+
+    loop {
+        say!(session.quiet, "\n👀 Watching {} for new recordings (Ctrl-C to stop)", folder.dir().display());
+        let input_file = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => break,
+            input_file = folder.next() => match input_file {
+                Some(input_file) => input_file,
+                None => break,
+            },
+        };
+
+        // A recording that fails doesn't stop the watch; it is tried again
+        // the next time the folder is watched
+        match transcribe_file(cli, &session, input_file.clone(), cancel.clone()).await {
+            Ok(result) if result.partial => break,
+            Ok(_) => folder.mark_processed(&input_file)?,
+            Err(e) => {
+                tracing::error!(target: RUN_LOG_TARGET, code = e.code(), error = %e, path = %input_file.display(), "Transcription failed");
+                print_error(&e, cli.progress == ProgressFormat::Json);
+            }
+        }
+    }
+
+    session.work_dir.remove()
+}
+
+/// One streamed segment as a line of the transcript so far
+fn print_segment(segment: &SpeechSegment) {
+    let text = segment.text.trim();
+    if !text.is_empty() {
+        println!("[{}] {}", format_timestamp(segment.start, '.'), text);
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Print why a run or recording failed on stderr, as an event with
+/// `--progress json`
+fn print_error(error: &AudioTranscriptionError, json: bool) {
+    if json {
+        JsonProgress::stderr().error(error);
+    } else {
+        eprintln!("Error: {}", error);
+    }
+}
+
+/// Whether `--progress json` was given, without a subcommand or to
+/// `transcribe`
+fn json_progress(matches: &ArgMatches) -> bool {
+    let json = |matches: &ArgMatches| matches.get_one::<ProgressFormat>("progress") == Some(&ProgressFormat::Json);
+    json(matches) || matches.subcommand_matches("transcribe").is_some_and(json)
+}
+
+/// Whether `--output` names an object storage location rather than a directory
+fn is_remote_output(output: &Path) -> bool {
+    output.to_string_lossy().contains("://")
+}
+
+/// Name of the format an `--output` path selects when it is a file rather
+/// than a directory: its extension names a registered format
+fn output_file_format(output: &Path, generator: &TranscriptGenerator) -> Option<String> {
+    if is_remote_output(output) || output.is_dir() {
+        return None;
+    }
+    let extension = output.extension()?.to_str()?;
+    generator.formatter(extension).map(|formatter| formatter.name().to_string())
+}
+
+/// Local directory the run's files go to: `--output` unless it is a remote
+/// URI, otherwise wherever the generator puts transcripts
+fn local_output_dir(output: Option<&Path>, generator: &TranscriptGenerator, input: &Path) -> Result<PathBuf> {
+    if let Some(output) = output.filter(|output| !is_remote_output(output)) {
+        return Ok(output.to_path_buf());
+    }
+    let transcript = generator.output_path(input, DEFAULT_FORMAT)?;
+    Ok(transcript.parent().map(Path::to_path_buf).unwrap_or_default())
+}
+
+/// On the first Ctrl-C, cancel the returned token: the pipeline stops
+/// after the chunks in flight, and the finished segments are written as a
+/// partial transcript and kept in the resume checkpoint. A second Ctrl-C
+/// deletes the work directory, restores the terminal and exits right away.
+fn install_interrupt_handler(work_dir: PathBuf) -> CancellationToken {
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
+
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        token.cancel();
+        eprintln!("\nStopping after the chunks in progress; finished segments are saved. Press Ctrl-C again to quit now.");
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            // Exiting skips destructors, so nothing else would clean it up
+            let _ = std::fs::remove_dir_all(&work_dir);
+            terminal::restore();
+            std::process::exit(INTERRUPTED_EXIT_CODE);
+        }
+    });
+
+    cancel
+}
+
+/// Take the config file's settings for the options that weren't given on
+/// the command line
+fn apply_settings(cli: &mut TranscribeArgs, settings: Settings, matches: &ArgMatches) -> Result<()> {
+    let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+
+    if let Some(model) = settings.model.filter(|_| unset("model")) {
+        cli.model = model.parse().map_err(|e: String| AudioTranscriptionError::Configuration(
+            format!("Invalid model {} in the config file: {}", model, e)
+        ))?;
+    }
+    if let Some(seconds) = settings.chunk_size.filter(|_| unset("chunk_size")) {
+        cli.chunk_size = seconds;
+    }
+    if let Some(seconds) = settings.chunk_overlap.filter(|_| unset("chunk_overlap")) {
+        cli.chunk_overlap = seconds;
+    }
+    if let Some(jobs) = settings.jobs.filter(|_| unset("jobs")) {
+        cli.jobs = Some(jobs);
+    }
+    if let Some(threshold) = settings.vad_threshold.filter(|_| unset("vad_threshold")) {
+        cli.vad_threshold = threshold;
+    }
+    if let Some(language) = settings.language.filter(|_| unset("language")) {
+        cli.language = Some(language);
+    }
+    if let Some(enabled) = settings.word_timestamps.filter(|_| unset("word_timestamps")) {
+        cli.word_timestamps = enabled;
+    }
+    if let Some(formats) = settings.format.filter(|_| unset("format")) {
+        cli.format = formats;
+    }
+    if let Some(speakers) = settings.max_speakers.filter(|_| unset("max_speakers")) {
+        cli.max_speakers = speakers;
+    }
+    if let Some(enabled) = settings.split_channels.filter(|_| unset("split_channels")) {
+        cli.split_channels = enabled;
+    }
+    if let Some(enabled) = settings.speaker_stats.filter(|_| unset("speaker_stats")) {
+        cli.speaker_stats = enabled;
+    }
+    if let Some(path) = settings.speaker_names.filter(|_| unset("speaker_names")) {
+        cli.speaker_names = Some(path);
+    }
+    if let Some(output) = settings.output.filter(|_| unset("output")) {
+        cli.output = Some(output);
+    }
+    Ok(())
+}
+
+/// The parts of the recording to transcribe, from --start and --end or
+/// --range
+fn time_ranges(cli: &TranscribeArgs) -> Vec<TimeRange> {
+    if cli.start.is_some() || cli.end.is_some() {
+        vec![TimeRange::new(cli.start.unwrap_or(0.0), cli.end)]
+    } else {
+        cli.range.clone()
+    }
+}
+
+/// Confidence floor for text output, checked here as the config builder
+/// never sees it
+fn text_options(cli: &TranscribeArgs) -> Result<TextOptions> {
+    if let Some(min) = cli.min_confidence.filter(|min| !(0.0..=1.0).contains(min)) {
+        return Err(AudioTranscriptionError::Configuration(format!(
+            "--min-confidence is a probability between 0 and 1, not {}",
+            min
+        )));
+    }
+    Ok(TextOptions {
+        min_confidence: cli.min_confidence,
+        drop_unreliable: cli.drop_low_confidence,
+        speaker_stats: cli.speaker_stats,
+    })
+}
+
+/// Title and date from the meeting details, falling back to the file name
+/// and the file's modification time, which is when the recording was saved
+fn note_options(input: &Path, meeting: Option<&MeetingInfo>) -> NoteOptions {
+    let meeting = meeting.cloned().unwrap_or_default();
+    let date = meeting.date.or_else(|| {
+        std::fs::metadata(input)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(|modified| chrono::DateTime::<chrono::Local>::from(modified).format("%Y-%m-%d").to_string())
+    });
+
+    NoteOptions {
+        title: meeting.title.or_else(|| input.file_stem().map(|stem| stem.to_string_lossy().into_owned())),
+        date,
+        attendees: meeting.attendees,
+        ..NoteOptions::default()
+    }
+}
+
+fn run_sources(args: &SourcesArgs) -> Result<()> {
+    let sources = match &args.config {
+        Some(path) => RecordingSources::from_config_file(path)?,
+        None => RecordingSources::load_default()?,
+    };
+
+    for source in sources.iter() {
+        let recordings = source.recordings()?;
+        let status = if source.dir.is_dir() { format!("{} recordings", recordings.len()) } else { "not found".to_string() };
+        println!("{}: {} ({})", source.name, source.dir.display(), status);
+        println!("    extensions: {}", source.extensions.join(", "));
+        println!("    naming: {}", source.naming.as_deref().unwrap_or("{stem}"));
+        match &source.output {
+            Some(output) => println!("    output: {}", output.display()),
+            None => println!("    output: next to the recording"),
+        }
+        if args.list {
+            for recording in &recordings {
+                println!("    - {}", recording.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_models(command: &ModelsCommand, models: &ModelOptions) -> Result<()> {
+    let model_manager = models.manager()?;
+    let downloaded = |model: &CachedModel| {
+        model.downloaded
+            .map(|modified| chrono::DateTime::<chrono::Local>::from(modified).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+
+    match command {
+        ModelsCommand::List => {
+            let cached = model_manager.cached_models()?;
+            println!("Models in {}:", model_manager.cache_dir().display());
+            for kind in ModelKind::all() {
+                match cached.iter().find(|model| model.kind.name() == kind.name()) {
+                    Some(model) => println!(
+                        "  {:<14} {:>9}  downloaded {}",
+                        kind.name(), format_file_size(model.bytes), downloaded(model)
+                    ),
+                    None => println!("  {:<14} {:>9}", kind.name(), "-"),
+                }
+            }
+            println!("Total: {}", format_file_size(cached.iter().map(|model| model.bytes).sum()));
+        }
+        ModelsCommand::Info { model: kind } => {
+            println!("{} ({})", kind, kind.name());
+            println!("  path: {}", model_manager.resolve(kind).display());
+            match model_manager.cached_models()?.into_iter().find(|model| model.kind.name() == kind.name()) {
+                Some(model) => {
+                    println!("  size: {}", format_file_size(model.bytes));
+                    println!("  downloaded: {}", downloaded(&model));
+                    println!("  SHA-256: {}", model.sha256.as_deref().unwrap_or("not recorded"));
+                }
+                None => println!("  not downloaded"),
+            }
+        }
+        ModelsCommand::Remove { models } => {
+            for model in models {
+                let freed = model_manager.remove(model)?;
+                println!("🗑️  Removed {} ({})", model.name(), format_file_size(freed));
+            }
+        }
+        ModelsCommand::Prune { keep } => {
+            let removed = model_manager.prune(keep)?;
+            for (path, bytes) in &removed {
+                let name = path.strip_prefix(model_manager.cache_dir()).unwrap_or(path);
+                println!("🗑️  {} ({})", name.display(), format_file_size(*bytes));
+            }
+            let freed = removed.iter().map(|(_, bytes)| bytes).sum();
+            println!("Freed {} in {} file(s)", format_file_size(freed), removed.len());
+        }
+        ModelsCommand::Verify => return verify_models(&model_manager),
+    }
+    Ok(())
+}
+
+fn verify_models(model_manager: &ModelManager) -> Result<()> {
+    let checks = model_manager.verify_checksums()?;
+    if checks.is_empty() {
+        println!("No models in {}", model_manager.cache_dir().display());
+        return Ok(());
+    }
+
+    let mut corrupt = Vec::new();
+    for check in checks {
+        let name = check.path.strip_prefix(model_manager.cache_dir()).unwrap_or(&check.path).display().to_string();
+        match check.status {
+            ChecksumStatus::Ok => println!("✅ {}", name),
+            ChecksumStatus::Mismatch { expected, actual } => {
+                println!("❌ {}: SHA-256 is {}, expected {}", name, actual, expected);
+                corrupt.push(check.path);
+            }
+            ChecksumStatus::Missing => println!("➖ {}: missing, downloaded again when needed", name),
+            ChecksumStatus::Unrecorded => println!("⚠️  {}: no checksum recorded, so it can't be checked", name),
+        }
+    }
+
+    match corrupt.as_slice() {
+        [] => Ok(()),
+        [path, ..] => Err(AudioTranscriptionError::ModelCorrupt {
+            path: path.clone(),
+            message: format!(
+                "{} cached model file(s) failed verification; delete them to download them again",
+                corrupt.len()
+            ),
+        }),
+    }
+}
+
+/// Findings of `doctor`, printed as they are made
+#[derive(Debug, Default)]
+struct Checkup {
+    failed: usize,
+}
+
+impl Checkup {
+    fn pass(&mut self, message: impl std::fmt::Display) {
+        println!("✅ {}", message);
+    }
+
+    /// Something a run copes with, but more slowly or only after a download
+    fn warn(&mut self, message: impl std::fmt::Display) {
+        println!("⚠️  {}", message);
+    }
+
+    fn fail(&mut self, message: impl std::fmt::Display) {
+        println!("❌ {}", message);
+        self.failed += 1;
+    }
+}
+
+/// Check everything a transcription with these options depends on, and
+/// fail if a run would
+fn run_doctor(mut cli: TranscribeArgs, matches: &ArgMatches, user_config: Result<UserConfig>, models: &ModelOptions) -> Result<()> {
+    let mut checkup = Checkup::default();
+    println!("audio-transcribe {}", env!("CARGO_PKG_VERSION"));
+
+    let config_path = match &cli.config {
+        Some(path) => path.clone(),
+        None => UserConfig::default_config_path()?,
+    };
+    match user_config.and_then(|config| config.settings(cli.profile.as_deref()).map(|settings| (config, settings))) {
+        Ok((config, settings)) => {
+            let profiles: Vec<&str> = config.profiles().collect();
+            match (config_path.is_file(), profiles.is_empty()) {
+                (false, _) => checkup.pass(format!("No config file at {}; built-in defaults apply", config_path.display())),
+                (true, true) => checkup.pass(format!("Config file {}", config_path.display())),
+                (true, false) => checkup.pass(format!("Config file {} (profiles: {})", config_path.display(), profiles.join(", "))),
+            }
+            if let Err(e) = apply_settings(&mut cli, settings, matches) {
+                checkup.fail(e);
+            }
+        }
+        Err(e) => checkup.fail(e),
+    }
+    match RecordingSources::default_config_path().and_then(|path| path.is_file().then(|| RecordingSources::from_config_file(&path)).transpose()) {
+        Ok(Some(sources)) => checkup.pass(format!("Sources config with {} recording folders", sources.iter().count())),
+        Ok(None) => {}
+        Err(e) => checkup.fail(e),
+    }
+    match Corrections::load_default() {
+        Ok(Some(corrections)) => checkup.pass(format!("Corrections file with {} corrections", corrections.len())),
+        Ok(None) => {}
+        Err(e) => checkup.fail(e),
+    }
+
+    let gpu_device = (!cli.no_gpu).then(|| cli.gpu_device.first().copied().unwrap_or(0));
+    if let Err(e) = cli.gpu_backend.ensure_compiled() {
+        checkup.fail(e);
+    } else if cli.no_gpu {
+        checkup.pass("GPU disabled with --no-gpu; transcription runs on the CPU");
+    } else {
+        match GpuBackend::compiled().as_slice() {
+            [] => checkup.warn("This build has no GPU backend; transcription runs on the CPU"),
+            backends => checkup.pass(format!(
+                "GPU backends: {}",
+                backends.iter().map(GpuBackend::to_string).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+
+    let model_size = match &cli.model {
+        ModelChoice::Size(size) => size.clone(),
+        ModelChoice::Auto => auto::select_model_for(gpu_device),
+    };
+    match auto::available_memory(gpu_device) {
+        Some((memory, source)) => {
+            let place = match source {
+                auto::MemorySource::Gpu(device) => format!("on GPU {}", device),
+                auto::MemorySource::System => "in system memory".to_string(),
+            };
+            let free = format!("{} free {}", format_file_size(memory), place);
+            if model_size.memory_bytes() <= memory {
+                checkup.pass(format!("{}, enough for the {} model", free, model_size));
+            } else {
+                checkup.warn(format!("{}, less than the {} model needs; try --model {}", free, model_size, auto::select_model(memory)));
+            }
+        }
+        None => checkup.warn("Unable to determine free memory"),
+    }
+
+    match models.manager() {
+        Ok(model_manager) => {
+            let cached = model_manager.cached_models()?;
+            checkup.pass(format!(
+                "Model cache {} ({})",
+                model_manager.cache_dir().display(),
+                format_file_size(cached.iter().map(|model| model.bytes).sum())
+            ));
+            let needed = [ModelKind::Whisper(model_size.clone()), ModelKind::Segmentation, ModelKind::SpeakerEmbedding, ModelKind::Vad];
+            let missing: Vec<String> = needed
+                .iter()
+                .filter(|kind| !cached.iter().any(|model| model.kind.name() == kind.name()))
+                .map(ModelKind::name)
+                .collect();
+            match (missing.is_empty(), model_manager.download_policy()) {
+                (true, _) => checkup.pass(format!("Models for a run with {} are cached", model_size)),
+                (false, DownloadPolicy::Never) => checkup.fail(format!("Models missing, and --offline forbids downloading them: {}", missing.join(", "))),
+                (false, _) => checkup.warn(format!("Models downloaded on first use: {}", missing.join(", "))),
+            }
+            let corrupt = model_manager
+                .verify_checksums()?
+                .into_iter()
+                .filter(|check| matches!(check.status, ChecksumStatus::Mismatch { .. }))
+                .count();
+            if corrupt > 0 {
+                checkup.fail(format!("{} cached model file(s) fail verification; see `audio-transcribe models verify`", corrupt));
+            }
+            match SpeakerRegistry::in_cache_dir(model_manager.cache_dir()) {
+                Ok(registry) if registry.voiceprints().is_empty() => {}
+                Ok(registry) => checkup.pass(format!("{} enrolled speakers", registry.voiceprints().len())),
+                Err(e) => checkup.fail(e),
+            }
+        }
+        Err(e) => checkup.fail(e),
+    }
+
+    // Room for the work files of an hour of audio
+    let tmp_dir = cli.tmp_dir.clone().unwrap_or_else(std::env::temp_dir);
+    match workdir::ensure_free_space(&tmp_dir, SpaceEstimate::for_audio(std::time::Duration::from_secs(3600)).work) {
+        Ok(()) => checkup.pass(format!("Enough free space in {} for an hour of audio", tmp_dir.display())),
+        Err(e) => checkup.warn(e),
+    }
+
+    match checkup.failed {
+        0 => {
+            println!("\nEverything a run needs is in place.");
+            Ok(())
+        }
+        failed => Err(AudioTranscriptionError::Configuration(format!("{} check(s) failed", failed))),
+    }
+}
+
+/// Where the commands that need models keep them, and how missing ones
+/// are downloaded
+#[derive(Debug, Clone)]
+struct ModelOptions {
+    dir: Option<PathBuf>,  // From --model-dir; otherwise the environment or the user cache
+    policy: DownloadPolicy,
+    retry: DownloadRetry,
+}
+
+impl ModelOptions {
+    fn new(global: &GlobalArgs) -> Self {
+        Self {
+            dir: global.model_dir.clone(),
+            policy: download_policy(global),
+            retry: DownloadRetry { retries: global.download_retries, ..DownloadRetry::default() },
+        }
+    }
+
+    fn manager(&self) -> Result<ModelManager> {
+        let manager = match &self.dir {
+            Some(dir) => ModelManager::with_cache_dir(dir.clone())?,
+            None => ModelManager::new()?,
+        };
+        Ok(manager.with_download_policy(self.policy).with_download_retry(self.retry))
+    }
+}
+
+/// How `--progress` reports a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProgressFormat {
+    Text,  // Messages and the log for people to read
+    Json,  // One JSON event per line on stderr
+}
+
+/// What `--model` asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelChoice {
+    Auto,  // The largest size that fits, stepping down if it fails to load
+    Size(ModelSize),
+}
+
+impl FromStr for ModelChoice {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        if name.eq_ignore_ascii_case("auto") {
+            return Ok(ModelChoice::Auto);
+        }
+        name.parse().map(ModelChoice::Size)
+    }
+}
+
+/// Every model size and alias, plus `auto`, for `--model`
+fn model_choices() -> impl TypedValueParser<Value = ModelChoice> {
+    let sizes = ModelSize::value_variants().iter().filter_map(ValueEnum::to_possible_value);
+    PossibleValuesParser::new(sizes.chain([PossibleValue::new("auto")])).try_map(|name| name.parse::<ModelChoice>())
+}
+
+/// A `--profile` name. Before config profiles, `--profile <FILE>` wrote a
+/// chrome trace, so a name like `run.json` points to `--trace` instead.
+fn profile_name(name: &str) -> std::result::Result<String, String> {
+    if Path::new(name).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        return Err(format!("--profile now names a config file profile; use --trace {} to record a chrome trace", name));
+    }
+    Ok(name.to_string())
+}
+
+/// `--yes` downloads missing models without asking, `--offline` never
+/// downloads them
+fn download_policy(global: &GlobalArgs) -> DownloadPolicy {
+    if global.offline {
+        DownloadPolicy::Never
+    } else if global.yes {
+        DownloadPolicy::Always
+    } else {
+        DownloadPolicy::Ask
+    }
+}
+
+async fn run_vad(args: &VadArgs, models: &ModelOptions) -> Result<()> {
+    let options = VadOptions {
+        threshold: args.threshold,
+        threshold_db: args.threshold_db,
+        min_speech_secs: args.min_speech,
+        min_silence_secs: args.min_silence,
+        ..VadOptions::default()
+    };
+
+    let segments = if args.energy {
+        Vad::new(options).detect(&args.input)?
+    } else {
+        let model_manager = models.manager()?;
+        SileroVad::from_provider(&model_manager, options).await?.detect(&args.input)?
+    };
+    for segment in &segments {
+        println!("{:>9.2} → {:>9.2}  ({:.2})", segment.start, segment.end, segment.confidence);
+    }
+
+    let speech: f32 = segments.iter().map(|segment| segment.end - segment.start).sum();
+    println!("{} speech regions, {:.1}s of speech", segments.len(), speech);
+    Ok(())
+}
+
+async fn run_bench(args: &BenchArgs, config: ProcessingConfig, models: &ModelOptions) -> Result<()> {
+    let report = args.report.as_deref().map(ReportFormat::from_path).transpose()?;
+    if !args.input.is_file() {
+        return Err(AudioTranscriptionError::InputNotFound { path: args.input.clone() });
+    }
+
+    let model_manager = Arc::new(models.manager()?);
+    let mut gpu_devices: Vec<usize> = transcription::worker_devices(&config).into_iter().flatten().collect();
+    gpu_devices.dedup();
+    let mut runs = Vec::new();
+    for size in &args.model {
+        if !model_manager.ensure_models_available(size).await? {
+            println!("Model download cancelled. Cannot proceed without required models.");
+            return Ok(());
+        }
+        let transcriber = Transcriber::builder()
+            .config(ProcessingConfig { model_size: size.clone(), ..config.clone() })
+            .model_provider(model_manager.clone())
+            .build()?;
+
+        println!("⏱️  {}...", size);
+        let sampler = ResourceSampler::start(gpu_devices.clone());
+        let result = transcriber.transcribe(&args.input).await;
+        let usage = sampler.stop();
+        runs.push(BenchRun::new(&result?, usage));
+    }
+
+    println!();
+    print!("{}", bench::render_table(&runs));
+    if let (Some(format), Some(path)) = (report, &args.report) {
+        std::fs::write(path, format.render(&runs)?)
+            .map_err(|source| AudioTranscriptionError::File { path: path.clone(), source })?;
+        println!("📄 {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "listen")]
+async fn run_listen(args: &ListenArgs, config: ProcessingConfig, models: &ModelOptions) -> Result<()> {
+    let model = models.manager()?.ensure_available(&ModelKind::Whisper(config.model_size.clone())).await?;
+    let vad = VadOptions { threshold_db: args.threshold_db, min_silence_secs: args.pause, ..VadOptions::default() };
+
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            token.cancel();
+        }
+    });
+
+    tokio::task::spawn_blocking(move || live::listen(&model, &config, vad, cancel, show_live_event))
+        .await
+        .map_err(|e| AudioTranscriptionError::Audio(format!("Listening failed: {}", e)))?
+}
+
+#[cfg(feature = "listen")]
+fn list_input_devices() -> Result<()> {
+    let devices = live::input_devices()?;
+    if devices.is_empty() {
+        println!("No input devices found");
+    }
+    for (index, name) in devices.iter().enumerate() {
+        println!("{}{}", name, if index == 0 { "  (default)" } else { "" });
+    }
+    Ok(())
+}
+
+/// Record until Ctrl-C and return the recording's path. The models are
+/// already available by now, so transcription starts right after.
+#[cfg(feature = "listen")]
+async fn record(cli: &TranscribeArgs, args: &RecordArgs) -> Result<PathBuf> {
+    if cli.input.is_some() {
+        return Err(AudioTranscriptionError::Configuration(
+            "record makes its own input; leave out the input file".to_string()
+        ));
+    }
+    let path = args.output.clone().expect("clap requires --output unless --list-devices is given");
+    if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wav")) {
+        return Err(AudioTranscriptionError::Configuration(format!("Recordings are WAV files: {}", path.display())));
+    }
+    if path.exists() {
+        return Err(AudioTranscriptionError::Configuration(format!(
+            "{} already exists; choose another name so it isn't overwritten",
+            path.display()
+        )));
+    }
+
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            token.cancel();
+        }
+    });
+
+    let device = args.device.clone();
+    let recording_path = path.clone();
+    let recording = tokio::task::spawn_blocking(move || {
+        live::record(&recording_path, device.as_deref(), cancel, |device| {
+            println!("🔴 Recording from {} to {} (Ctrl-C to stop)", device, recording_path.display());
+        })
+    })
+    .await
+    .map_err(|e| AudioTranscriptionError::Audio(format!("Recording failed: {}", e)))??;
+
+    println!("\n⏹️  Recorded {:.1}s from {}", recording.duration.as_secs_f32(), recording.device);
+    Ok(path)
+}
+
+/// Print final captions line by line, below a line that is rewritten with
+/// each new hypothesis for the utterance in progress
+#[cfg(feature = "listen")]
+fn show_live_event(event: LiveEvent) {
+    const CLEAR_LINE: &str = "\r\x1b[2K";
+    match event {
+        LiveEvent::Listening { device, .. } => println!("Listening on {} (Ctrl-C to stop)\n", device),
+        LiveEvent::Partial(text) => {
+            // Only the end of a long hypothesis fits on the line
+            let width = crossterm::terminal::size().map(|(columns, _)| columns as usize).unwrap_or(80);
+            let characters: Vec<char> = text.chars().collect();
+            let tail: String = characters[characters.len().saturating_sub(width.saturating_sub(1))..].iter().collect();
+            print!("{}\x1b[2m{}\x1b[0m", CLEAR_LINE, tail);
+        }
+        LiveEvent::Final(segment) => {
+            println!("{}[{}] {}", CLEAR_LINE, format_timestamp(segment.start, '.'), segment.text.trim());
+        }
+    }
+    let _ = io::stdout().flush();
+}
+
+#[cfg(feature = "search")]
+fn open_index(index_dir: Option<&PathBuf>) -> Result<TranscriptIndex> {
+    match index_dir {
+        Some(dir) => TranscriptIndex::open(dir),
+        None => TranscriptIndex::open(&search::default_index_dir()?),
+    }
+}
+
+#[cfg(feature = "search")]
+fn run_index(args: &IndexArgs) -> Result<()> {
+    let files = search::find_transcripts(&args.paths)?;
+    let transcripts = files
+        .iter()
+        .map(|file| search::load_transcript(file).map(|result| (file.canonicalize().unwrap_or_else(|_| file.clone()), result)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let index = open_index(args.index_dir.as_ref())?;
+    let segments = index.add(transcripts.iter().map(|(file, result)| (file.as_path(), result)))?;
+    println!("Indexed {} segments from {} transcripts", segments, transcripts.len());
+    Ok(())
+}
+
+#[cfg(feature = "search")]
+fn run_search(args: &SearchArgs) -> Result<()> {
+    let hits = open_index(args.index_dir.as_ref())?.search(&args.query, args.limit)?;
+    for hit in &hits {
+        let speaker = hit.speaker.map(|speaker| format!("SPEAKER_{:02}", speaker)).unwrap_or_else(|| "-".to_string());
+        println!("{}  {}  {}  {}", hit.file.display(), speaker, format_timestamp(hit.start, '.'), hit.text);
+    }
+
+    if hits.is_empty() {
+        println!("No matches for '{}'", args.query);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "archive")]
+fn run_history(args: &HistoryArgs) -> Result<()> {
+    let archive = match &args.archive_db {
+        Some(path) => RunArchive::open(path)?,
+        None => RunArchive::open(&archive::default_archive_path()?)?,
+    };
+    let runs = match &args.input {
+        Some(input) => {
+            let mut runs = archive.find_by_hash(&archive::hash_file(input)?)?;
+            runs.truncate(args.limit);
+            runs
+        }
+        None => archive.history(args.limit)?,
+    };
+
+    for run in &runs {
+        println!(
+            "{:>5}  {}  {}  {:>8.1}s audio  {:>7.1}s  {} segments  {} speakers  {}",
+            run.id,
+            run.recorded_at,
+            run.input_hash.get(..12).unwrap_or(&run.input_hash),
+            run.audio_duration.as_secs_f32(),
+            run.processing_time.as_secs_f32(),
+            run.segments,
+            run.speakers,
+            run.input.display()
+        );
+    }
+
+    if runs.is_empty() {
+        println!("No archived runs");
+    }
+    Ok(())
+}
+
+/// Download an `s3://` or `gs://` input into the run's work directory
+/// The raw PCM layout when the input is standard input; the raw options
+/// make no sense for anything else
+fn raw_input(cli: &TranscribeArgs) -> Result<Option<RawPcm>> {
+    let from_stdin = cli.input.as_deref() == Some(Path::new(raw::STDIN));
+    match (from_stdin, cli.input_format, cli.sample_rate) {
+        (true, Some(format), Some(sample_rate)) => Ok(Some(RawPcm { format, sample_rate, channels: cli.channels })),
+        (true, _, _) => Err(AudioTranscriptionError::Configuration(
+            "Reading raw PCM from standard input needs --input-format and --sample-rate".to_string()
+        )),
+        (false, None, None) => Ok(None),
+        (false, _, _) => Err(AudioTranscriptionError::Configuration(
+            "--input-format and --sample-rate describe raw PCM on standard input; pass - as the input".to_string()
+        )),
+    }
+}
+
+/// Copy raw PCM from standard input into a WAV file in `dir` until the
+/// stream ends
+async fn read_stdin(pcm: RawPcm, dir: &Path) -> Result<PathBuf> {
+    let path = dir.join("stdin.wav");
+    println!("⬇️  Reading {:?} audio at {} Hz from standard input", pcm.format, pcm.sample_rate);
+    let spool = path.clone();
+    let frames = tokio::task::spawn_blocking(move || pcm.write_wav(io::stdin().lock(), &spool))
+        .await
+        .map_err(|e| AudioTranscriptionError::Audio(format!("Reading standard input failed: {}", e)))??;
+    tracing::info!(frames, path = %path.display(), "Read raw audio from standard input");
+    Ok(path)
+}
+
+#[cfg(feature = "cloud")]
+async fn fetch_remote_input(input: PathBuf, download_dir: &Path) -> Result<PathBuf> {
+    let Some(uri) = input.to_str().filter(|uri| storage::is_remote(uri)) else {
+        return Ok(input);
+    };
+
+    println!("⬇️  Downloading {}", uri);
+    ObjectLocation::parse(uri)?.download_to(download_dir).await
+}
+
+async fn run_diarize(args: &DiarizeArgs, models: &ModelOptions) -> Result<()> {
+    let model_manager = models.manager()?;
+    let options = DiarizationOptions {
+        similarity_threshold: args.similarity_threshold,
+        max_speakers: args.max_speakers,
+        voiceprints: SpeakerRegistry::in_cache_dir(model_manager.cache_dir())?.into_voiceprints(),
+        ..DiarizationOptions::default()
+    };
+
+    let diarizer = Diarizer::from_provider(&model_manager).await?;
+
+    let segments = diarizer.diarize(&args.input, &options)?;
+    for segment in &segments {
+        match &segment.name {
+            Some(name) => println!("{:>9.2} → {:>9.2}  {}", segment.start, segment.end, name),
+            None => println!("{:>9.2} → {:>9.2}  SPEAKER_{:02}", segment.start, segment.end, segment.speaker),
+        }
+    }
+
+    let speakers: std::collections::BTreeSet<u8> = segments.iter().map(|segment| segment.speaker).collect();
+    println!("{} speaker turns, {} speakers", segments.len(), speakers.len());
+    Ok(())
+}
+
+async fn run_speakers(command: &SpeakersCommand, models: &ModelOptions) -> Result<()> {
+    let model_manager = models.manager()?;
+    let mut registry = SpeakerRegistry::in_cache_dir(model_manager.cache_dir())?;
+
+    match command {
+        SpeakersCommand::Enroll { name, sample } => {
+            let diarizer = Diarizer::from_provider(&model_manager).await?;
+            let (embedding, speech_secs) = diarizer.voiceprint(sample)?;
+            registry.enroll(name, &embedding, speech_secs)?;
+            registry.save()?;
+            println!("🎙️  Enrolled {} from {:.1}s of speech in {}", name.trim(), speech_secs, sample.display());
+        }
+        SpeakersCommand::List => {
+            if registry.voiceprints().is_empty() {
+                println!("No speakers enrolled in {}", registry.path().display());
+                return Ok(());
+            }
+            println!("Speakers in {}:", registry.path().display());
+            for voiceprint in registry.voiceprints() {
+                println!("  {:<20} {:>7.1}s of speech", voiceprint.name, voiceprint.speech_secs);
+            }
+        }
+        SpeakersCommand::Remove { names } => {
+            for name in names {
+                if !registry.remove(name) {
+                    return Err(AudioTranscriptionError::Configuration(format!("No speaker named {} is enrolled", name)));
+                }
+                println!("🗑️  Removed {}", name);
+            }
+            registry.save()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_default_cli_arguments() {
+        // Test default values when no arguments are provided
+        let cli = Cli::try_parse_from(&["audio-transcribe"]).unwrap();
+        
+        assert!(cli.transcribe.input.is_none());
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::Medium)));
+        assert!(cli.transcribe.output.is_none());
+        assert_eq!(cli.transcribe.chunk_size, 120.0);
+        assert!(cli.transcribe.jobs.is_none());
+        assert!(!cli.transcribe.no_gpu);
+        assert!(!cli.global.verbose);
+        assert!(cli.transcribe.profile.is_none());
+        assert!(cli.global.trace.is_none());
+        assert!(cli.transcribe.post_process.is_none());
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
     fn test_model_size_flag_tiny() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--model", "tiny"]).unwrap();
-        assert!(matches!(cli.model, ModelSize::Tiny));
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::Tiny)));
     }
 
     #[test]
     fn test_model_size_flag_base() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--model", "base"]).unwrap();
-        assert!(matches!(cli.model, ModelSize::Base));
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::Base)));
     }
 
     #[test]
     fn test_model_size_flag_small() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--model", "small"]).unwrap();
-        assert!(matches!(cli.model, ModelSize::Small));
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::Small)));
     }
 
     #[test]
     fn test_model_size_flag_medium() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--model", "medium"]).unwrap();
-        assert!(matches!(cli.model, ModelSize::Medium));
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::Medium)));
     }
 
     #[test]
     fn test_model_size_flag_large() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--model", "large"]).unwrap();
-        assert!(matches!(cli.model, ModelSize::Large));
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::LargeV3)));
+        let cli = Cli::try_parse_from(["audio-transcribe", "--model", "large-v3-turbo"]).unwrap();
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::LargeV3Turbo)));
+        let cli = Cli::try_parse_from(["audio-transcribe", "--model", "large-v2"]).unwrap();
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::LargeV2)));
+        let cli = Cli::try_parse_from(["audio-transcribe", "--model", "medium.en"]).unwrap();
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::MediumEn)));
+        assert_eq!("base.en".parse::<ModelSize>(), Ok(ModelSize::BaseEn));
+        assert_eq!("Large".parse::<ModelSize>(), Ok(ModelSize::LargeV3));
+        assert!("large-v4".parse::<ModelSize>().is_err());
+    }
+
+    #[test]
+    fn test_model_auto_flag() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--model", "auto", "talk.wav"]).unwrap();
+        assert_eq!(cli.transcribe.model, ModelChoice::Auto);
+        assert!(Cli::try_parse_from(["audio-transcribe", "--model", "huge", "talk.wav"]).is_err());
     }
 
     #[test]
@@ -236,13 +2480,13 @@ mod tests {
     #[test]
     fn test_output_directory_flag() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--output", "/tmp/transcripts"]).unwrap();
-        assert_eq!(cli.output, Some(PathBuf::from("/tmp/transcripts")));
+        assert_eq!(cli.transcribe.output, Some(PathBuf::from("/tmp/transcripts")));
     }
 
     #[test]
     fn test_chunk_size_flag() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--chunk-size", "60.5"]).unwrap();
-        assert_eq!(cli.chunk_size, 60.5);
+        assert_eq!(cli.transcribe.chunk_size, 60.5);
     }
 
     #[test]
@@ -254,7 +2498,67 @@ mod tests {
     #[test]
     fn test_jobs_flag() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--jobs", "4"]).unwrap();
-        assert_eq!(cli.jobs, Some(4));
+        assert_eq!(cli.transcribe.jobs, Some(4));
+    }
+
+    #[test]
+    fn test_min_confidence_flags() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--min-confidence", "0.5", "--drop-low-confidence"]).unwrap();
+        assert_eq!(
+            text_options(&cli.transcribe).unwrap(),
+            TextOptions { min_confidence: Some(0.5), drop_unreliable: true, speaker_stats: false }
+        );
+        let cli = Cli::try_parse_from(["audio-transcribe", "--speaker-stats"]).unwrap();
+        assert!(text_options(&cli.transcribe).unwrap().speaker_stats);
+
+        let cli = Cli::try_parse_from(["audio-transcribe", "--min-confidence", "50"]).unwrap();
+        assert!(text_options(&cli.transcribe).is_err());
+        assert!(Cli::try_parse_from(["audio-transcribe", "--drop-low-confidence"]).is_err());
+    }
+
+    #[test]
+    fn test_prompt_flags() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--initial-prompt", "Kubernetes, Grafana, Tempo", "--vocab-file", "words.txt"]).unwrap();
+        assert_eq!(cli.transcribe.initial_prompt.as_deref(), Some("Kubernetes, Grafana, Tempo"));
+        assert_eq!(cli.transcribe.vocab_file, Some(PathBuf::from("words.txt")));
+    }
+
+    #[test]
+    fn test_decoding_flags() {
+        let cli = Cli::try_parse_from(["audio-transcribe"]).unwrap();
+        assert_eq!((cli.transcribe.beam_size, cli.transcribe.best_of, cli.transcribe.temperature), (None, 1, 0.0));
+        assert_eq!((cli.transcribe.temperature_increment, cli.transcribe.no_speech_threshold), (0.2, 0.6));
+
+        let cli = Cli::try_parse_from([
+            "audio-transcribe", "--beam-size", "5", "--temperature", "0.1", "--temperature-increment", "0", "--no-speech-threshold", "0.4",
+        ]).unwrap();
+        assert_eq!(cli.transcribe.beam_size, Some(5));
+        assert_eq!((cli.transcribe.temperature, cli.transcribe.temperature_increment, cli.transcribe.no_speech_threshold), (0.1, 0.0, 0.4));
+        assert!(Cli::try_parse_from(["audio-transcribe", "--beam-size", "5", "--best-of", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_no_hallucination_filter_flag() {
+        assert!(!Cli::try_parse_from(["audio-transcribe"]).unwrap().transcribe.no_hallucination_filter);
+        assert!(Cli::try_parse_from(["audio-transcribe", "--no-hallucination-filter"]).unwrap().transcribe.no_hallucination_filter);
+    }
+
+    #[test]
+    fn test_refine_flags() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--model", "base", "--refine-model", "large-v3"]).unwrap();
+        assert_eq!(cli.transcribe.refine_model, Some(ModelSize::LargeV3));
+        assert_eq!(cli.transcribe.refine_threshold, DEFAULT_REFINE_THRESHOLD);
+
+        let cli = Cli::try_parse_from(["audio-transcribe", "--refine-model", "large", "--refine-threshold", "0.4"]).unwrap();
+        assert_eq!((cli.transcribe.refine_model, cli.transcribe.refine_threshold), (Some(ModelSize::LargeV3), 0.4));
+        assert!(Cli::try_parse_from(["audio-transcribe", "--refine-model", "auto"]).is_err());
+    }
+
+    #[test]
+    fn test_max_memory_flag() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--max-memory", "512"]).unwrap();
+        assert_eq!(cli.transcribe.max_memory, Some(512));
+        assert!(Cli::try_parse_from(&["audio-transcribe", "--max-memory", "1.5GB"]).is_err());
     }
 
     #[test]
@@ -266,25 +2570,545 @@ mod tests {
     #[test]
     fn test_no_gpu_flag() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--no-gpu"]).unwrap();
-        assert!(cli.no_gpu);
+        assert!(cli.transcribe.no_gpu);
+    }
+
+    #[test]
+    fn test_gpu_device_list() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--gpu-device", "0,1"]).unwrap();
+        assert_eq!(cli.transcribe.gpu_device, vec![0, 1]);
+
+        let cli = Cli::try_parse_from(&["audio-transcribe"]).unwrap();
+        assert!(cli.transcribe.gpu_device.is_empty());
+    }
+
+    #[test]
+    fn test_gpu_backend_flag() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "talk.wav"]).unwrap();
+        assert_eq!(cli.transcribe.gpu_backend, GpuBackend::Auto);
+        let cli = Cli::try_parse_from(["audio-transcribe", "--gpu-backend", "vulkan", "talk.wav"]).unwrap();
+        assert_eq!(cli.transcribe.gpu_backend, GpuBackend::Vulkan);
+        assert!(Cli::try_parse_from(["audio-transcribe", "--gpu-backend", "opencl", "talk.wav"]).is_err());
+    }
+
+    #[test]
+    fn test_trace_flag() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--trace", "trace.json"]).unwrap();
+        assert_eq!(cli.global.trace, Some(PathBuf::from("trace.json")));
+
+        let error = Cli::try_parse_from(["audio-transcribe", "--profile", "trace.json", "talk.mp3"]).unwrap_err();
+        assert!(error.to_string().contains("use --trace trace.json"));
+    }
+
+    #[test]
+    fn test_profile_settings_fill_unset_options() {
+        let config = UserConfig::from_toml(
+            "model = \"small\"\nmax_speakers = 4\n\n[profile.podcast]\nchunk_size = 300\nformat = [\"srt\", \"json\"]\noutput = \"/srv/{stem}\"\n"
+        ).unwrap();
+        let args = ["audio-transcribe", "--profile", "podcast", "--max-speakers", "2", "talk.mp3"];
+        let matches = Cli::command().try_get_matches_from(args).unwrap();
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+        assert_eq!(cli.transcribe.profile.as_deref(), Some("podcast"));
+
+        let settings = config.settings(cli.transcribe.profile.as_deref()).unwrap();
+        apply_settings(&mut cli.transcribe, settings, &matches).unwrap();
+        assert_eq!(cli.transcribe.model, ModelChoice::Size(ModelSize::Small));
+        assert_eq!((cli.transcribe.chunk_size, cli.transcribe.max_speakers), (300.0, 2));
+        assert_eq!(cli.transcribe.format, vec!["srt", "json"]);
+        assert_eq!(cli.transcribe.output, Some(PathBuf::from("/srv/{stem}")));
+
+        let invalid = UserConfig::from_toml("model = \"huge\"").unwrap();
+        let error = apply_settings(&mut cli.transcribe, invalid.settings(None).unwrap(), &matches).unwrap_err();
+        assert_eq!(error.code(), 80);
+    }
+
+    #[test]
+    fn test_post_process_flag() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--post-process", "cleanup.toml"]).unwrap();
+        assert_eq!(cli.transcribe.post_process, Some(PathBuf::from("cleanup.toml")));
+    }
+
+    #[test]
+    fn test_corrections_flag() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--corrections", "team.toml"]).unwrap();
+        assert_eq!(cli.transcribe.corrections, Some(PathBuf::from("team.toml")));
+
+        let cli = Cli::try_parse_from(["audio-transcribe", "--speaker-names", "names.toml"]).unwrap();
+        assert_eq!(cli.transcribe.speaker_names, Some(PathBuf::from("names.toml")));
+    }
+
+    #[test]
+    fn test_split_channels_flag() {
+        assert!(Cli::try_parse_from(["audio-transcribe", "--split-channels", "call.wav"]).unwrap().transcribe.split_channels);
+        assert!(!Cli::try_parse_from(["audio-transcribe", "call.wav"]).unwrap().transcribe.split_channels);
+    }
+
+    #[test]
+    fn test_name_speakers_flag() {
+        assert!(Cli::try_parse_from(["audio-transcribe", "--name-speakers", "panel.wav"]).unwrap().transcribe.name_speakers);
+        assert!(!Cli::try_parse_from(["audio-transcribe", "panel.wav"]).unwrap().transcribe.name_speakers);
+    }
+
+    #[test]
+    fn test_transcribe_subcommand() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "transcribe", "talk.mp3", "--model", "small", "-v"]).unwrap();
+        match cli.command {
+            Some(Command::Transcribe(args)) => {
+                assert_eq!(args.input, Some(PathBuf::from("talk.mp3")));
+                assert_eq!(args.model, ModelChoice::Size(ModelSize::Small));
+            }
+            _ => panic!("expected the transcribe subcommand"),
+        }
+        assert!(cli.global.verbose);
+
+        // Global options go before or after any subcommand
+        let cli = Cli::try_parse_from(["audio-transcribe", "models", "list", "--model-dir", "/models", "--offline"]).unwrap();
+        assert_eq!(cli.global.model_dir, Some(PathBuf::from("/models")));
+        assert!(cli.global.offline);
+
+        let cli = Cli::try_parse_from(["audio-transcribe", "--yes", "doctor"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Doctor)));
+        assert!(cli.global.yes);
+    }
+
+    #[test]
+    fn test_speakers_subcommand() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "speakers", "enroll", "alice", "sample.wav"]).unwrap();
+        match cli.command {
+            Some(Command::Speakers(SpeakersCommand::Enroll { name, sample })) => {
+                assert_eq!(name, "alice");
+                assert_eq!(sample, PathBuf::from("sample.wav"));
+            }
+            _ => panic!("expected speakers enroll"),
+        }
+
+        let cli = Cli::try_parse_from(["audio-transcribe", "speakers", "remove", "alice", "bob"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Speakers(SpeakersCommand::Remove { names })) if names == ["alice", "bob"]));
+        assert!(Cli::try_parse_from(["audio-transcribe", "speakers", "remove"]).is_err());
+        assert!(matches!(
+            Cli::try_parse_from(["audio-transcribe", "speakers", "list"]).unwrap().command,
+            Some(Command::Speakers(SpeakersCommand::List))
+        ));
+    }
+
+    #[test]
+    fn test_time_range_flags() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--start", "00:13:20", "--end", "47:00", "talk.wav"]).unwrap();
+        assert_eq!(time_ranges(&cli.transcribe), [TimeRange::new(800.0, Some(2820.0))]);
+        assert!(!cli.transcribe.rebase_timestamps);
+
+        let cli = Cli::try_parse_from([
+            "audio-transcribe", "--range", "-1:00", "--range", "10:00-12:30", "--range", "1:00:00-", "--rebase-timestamps", "talk.wav",
+        ])
+        .unwrap();
+        assert_eq!(time_ranges(&cli.transcribe), [
+            TimeRange::new(0.0, Some(60.0)),
+            TimeRange::new(600.0, Some(750.0)),
+            TimeRange::new(3600.0, None),
+        ]);
+        assert!(cli.transcribe.rebase_timestamps);
+
+        assert!(time_ranges(&Cli::try_parse_from(["audio-transcribe", "talk.wav"]).unwrap().transcribe).is_empty());
+        assert!(Cli::try_parse_from(["audio-transcribe", "--start", "13:70", "talk.wav"]).is_err());
+        assert!(Cli::try_parse_from(["audio-transcribe", "--start", "1:00", "--range", "2:00-3:00", "talk.wav"]).is_err());
+    }
+
+    #[test]
+    fn test_log_file_flag() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--log-file", "meeting.wav"]).unwrap();
+        assert_eq!(cli.global.log_file, Some(None));
+        assert_eq!(cli.transcribe.input, Some(PathBuf::from("meeting.wav")));
+
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--log-file=/tmp/run.jsonl"]).unwrap();
+        assert_eq!(cli.global.log_file, Some(Some(PathBuf::from("/tmp/run.jsonl"))));
+        assert_eq!(Cli::try_parse_from(&["audio-transcribe"]).unwrap().global.log_file, None);
+    }
+
+    #[test]
+    fn test_tmp_dir_flag() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--tmp-dir", "/mnt/scratch", "meeting.wav"]).unwrap();
+        assert_eq!(cli.transcribe.tmp_dir, Some(PathBuf::from("/mnt/scratch")));
+        assert_eq!(Cli::try_parse_from(&["audio-transcribe"]).unwrap().transcribe.tmp_dir, None);
+    }
+
+    #[test]
+    fn test_progress_flag() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--progress", "json", "meeting.wav"]).unwrap();
+        assert_eq!(cli.transcribe.progress, ProgressFormat::Json);
+        assert_eq!(Cli::try_parse_from(&["audio-transcribe"]).unwrap().transcribe.progress, ProgressFormat::Text);
+        assert!(Cli::try_parse_from(&["audio-transcribe", "--progress", "xml"]).is_err());
+
+        let matches = Cli::command().get_matches_from(["audio-transcribe", "transcribe", "--progress", "json", "meeting.wav"]);
+        assert!(json_progress(&matches));
+        assert!(!json_progress(&Cli::command().get_matches_from(["audio-transcribe", "meeting.wav"])));
+    }
+
+    #[test]
+    fn test_stream_output_flag() {
+        assert!(Cli::try_parse_from(&["audio-transcribe", "--stream-output", "meeting.wav"]).unwrap().transcribe.stream_output);
+        assert!(!Cli::try_parse_from(&["audio-transcribe", "meeting.wav"]).unwrap().transcribe.stream_output);
+    }
+
+    #[test]
+    fn test_quiet_and_stdout_flags() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "call.mp3", "--stdout", "--format", "json", "-q"]).unwrap();
+        assert!(cli.transcribe.stdout && cli.global.quiet);
+        assert!(Cli::try_parse_from(&["audio-transcribe", "transcribe", "call.mp3", "--quiet"]).unwrap().global.quiet);
+
+        assert!(Cli::try_parse_from(&["audio-transcribe", "-q", "-v", "call.mp3"]).is_err());
+        assert!(Cli::try_parse_from(&["audio-transcribe", "--stdout", "--output", "out", "call.mp3"]).is_err());
+        assert!(Cli::try_parse_from(&["audio-transcribe", "--stdout", "--stream-output", "call.mp3"]).is_err());
+    }
+
+    #[test]
+    fn test_no_resume_flag() {
+        assert!(Cli::try_parse_from(&["audio-transcribe", "--no-resume", "long.wav"]).unwrap().transcribe.no_resume);
+        assert!(!Cli::try_parse_from(&["audio-transcribe", "long.wav"]).unwrap().transcribe.no_resume);
+    }
+
+    #[test]
+    fn test_offset_and_rate_flags() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--offset", "-12.5", "--rate", "1.042", "clip.wav"]).unwrap();
+        assert_eq!(cli.transcribe.offset, -12.5);
+        assert_eq!(cli.transcribe.rate, 1.042);
+
+        let cli = Cli::try_parse_from(&["audio-transcribe", "clip.wav"]).unwrap();
+        assert_eq!((cli.transcribe.offset, cli.transcribe.rate), (0.0, 1.0));
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn test_raw_stdin_options() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "-", "--input-format", "s16le", "--sample-rate", "16000"]).unwrap();
+        let pcm = raw_input(&cli.transcribe).unwrap().unwrap();
+        assert_eq!(pcm, RawPcm { format: RawFormat::S16le, sample_rate: 16_000, channels: 1 });
+
+        let cli = Cli::try_parse_from(["audio-transcribe", "-", "--input-format", "f32le"]).unwrap();
+        assert_eq!(raw_input(&cli.transcribe).unwrap_err().code(), 80);
+        let cli = Cli::try_parse_from(["audio-transcribe", "talk.wav", "--sample-rate", "8000"]).unwrap();
+        assert_eq!(raw_input(&cli.transcribe).unwrap_err().code(), 80);
+        let cli = Cli::try_parse_from(["audio-transcribe", "talk.wav"]).unwrap();
+        assert!(raw_input(&cli.transcribe).unwrap().is_none());
+        assert!(Cli::try_parse_from(["audio-transcribe", "-", "--input-format", "mp3"]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "watch")]
+    fn test_watch_flag() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--watch", "inbox", "--format", "srt"]).unwrap();
+        assert_eq!(cli.transcribe.watch, Some(PathBuf::from("inbox")));
+        assert!(cli.transcribe.input.is_none());
+        assert!(Cli::try_parse_from(["audio-transcribe", "--watch", "inbox", "a.wav"]).is_err());
+    }
+
+    #[cfg(feature = "listen")]
+    #[test]
+    fn test_record_subcommand() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--output", "notes", "record", "--output", "standup.wav"]).unwrap();
+        assert_eq!(cli.transcribe.output, Some(PathBuf::from("notes")));
+        match cli.command {
+            Some(Command::Record(args)) => assert_eq!(args.output, Some(PathBuf::from("standup.wav"))),
+            _ => panic!("expected the record subcommand"),
+        }
+
+        assert!(Cli::try_parse_from(["audio-transcribe", "record", "--list-devices"]).is_ok());
+        assert!(Cli::try_parse_from(["audio-transcribe", "record"]).is_err());
+    }
+
+    #[test]
+    fn test_models_verify_subcommand() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "models", "verify"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Models(ModelsCommand::Verify))));
+        assert!(Cli::try_parse_from(["audio-transcribe", "models"]).is_err());
+    }
+
+    #[test]
+    fn test_models_subcommands() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "models", "remove", "tiny", "vad"]).unwrap();
+        match cli.command {
+            Some(Command::Models(ModelsCommand::Remove { models })) => {
+                assert_eq!(models.iter().map(ModelKind::name).collect::<Vec<_>>(), ["tiny", "vad"]);
+            }
+            _ => panic!("expected models remove"),
+        }
+
+        let cli = Cli::try_parse_from(["audio-transcribe", "models", "prune"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Models(ModelsCommand::Prune { keep })) if keep == [ModelSize::Medium]));
+        let cli = Cli::try_parse_from(["audio-transcribe", "models", "prune", "--keep", "small,large"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Models(ModelsCommand::Prune { keep })) if keep == [ModelSize::Small, ModelSize::LargeV3]));
+
+        assert!(Cli::try_parse_from(["audio-transcribe", "models", "info", "huge"]).is_err());
+        assert!(Cli::try_parse_from(["audio-transcribe", "models", "remove"]).is_err());
+    }
+
+    #[test]
+    fn test_bench_subcommand() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--no-gpu", "bench", "--input", "sample.wav"]).unwrap();
+        assert!(cli.transcribe.no_gpu);
+        match cli.command {
+            Some(Command::Bench(args)) => {
+                assert_eq!(args.input, PathBuf::from("sample.wav"));
+                assert_eq!(args.model, [ModelSize::Tiny, ModelSize::Base, ModelSize::Small]);
+                assert_eq!(args.report, None);
+            }
+            _ => panic!("expected the bench subcommand"),
+        }
+
+        let cli = Cli::try_parse_from(["audio-transcribe", "bench", "--input", "a.wav", "--model", "base,large-v3-turbo", "--report", "bench.csv"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Bench(args)) if args.model == [ModelSize::Base, ModelSize::LargeV3Turbo]));
+        assert!(Cli::try_parse_from(["audio-transcribe", "bench"]).is_err());
+    }
+
+    #[test]
+    fn test_sources_subcommand() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "sources", "--list", "--config", "sources.toml"]).unwrap();
+
+        match cli.command {
+            Some(Command::Sources(args)) => {
+                assert!(args.list);
+                assert_eq!(args.config, Some(PathBuf::from("sources.toml")));
+            }
+            _ => panic!("expected the sources subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_vad_subcommand() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "vad", "talk.wav", "--min-silence", "0.5"]).unwrap();
+
+        assert!(cli.transcribe.input.is_none());
+        match cli.command {
+            Some(Command::Vad(args)) => {
+                assert_eq!(args.input, PathBuf::from("talk.wav"));
+                assert_eq!(args.min_silence, 0.5);
+                assert_eq!(args.min_speech, VadOptions::default().min_speech_secs);
+                assert_eq!(args.threshold, 0.5);
+                assert!(!args.energy);
+            }
+            _ => panic!("expected the vad subcommand"),
+        }
+
+        assert!(Cli::try_parse_from(&["audio-transcribe", "vad"]).is_err());
+    }
+
+    #[test]
+    fn test_diarize_subcommand() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "diarize", "panel.wav", "--max-speakers", "3"]).unwrap();
+
+        match cli.command {
+            Some(Command::Diarize(args)) => {
+                assert_eq!(args.input, PathBuf::from("panel.wav"));
+                assert_eq!(args.max_speakers, 3);
+                assert_eq!(args.similarity_threshold, DiarizationOptions::default().similarity_threshold);
+            }
+            _ => panic!("expected the diarize subcommand"),
+        }
+    }
+
+    #[cfg(feature = "search")]
+    #[test]
+    fn test_search_subcommand() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "search", "budget approval", "--limit", "5"]).unwrap();
+
+        match cli.command {
+            Some(Command::Search(args)) => {
+                assert_eq!(args.query, "budget approval");
+                assert_eq!(args.limit, 5);
+                assert!(args.index_dir.is_none());
+            }
+            _ => panic!("expected the search subcommand"),
+        }
+
+        assert!(Cli::try_parse_from(&["audio-transcribe", "index"]).is_err());
+    }
+
+    #[test]
+    fn test_note_options_from_input_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("weekly sync.wav");
+        std::fs::write(&input, b"RIFF").unwrap();
+
+        let options = note_options(&input, None);
+        assert_eq!(options.title.as_deref(), Some("weekly sync"));
+        assert_eq!(options.date.unwrap().len(), "2024-05-01".len());
+        assert_eq!(Cli::try_parse_from(&["audio-transcribe", "--format", "notes", "a.wav"]).unwrap().transcribe.format, vec!["notes"]);
+    }
+
+    #[test]
+    fn test_language_options() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--language", "de", "a.wav"]).unwrap();
+        assert_eq!(cli.transcribe.language.as_deref(), Some("de"));
+        assert!(Cli::try_parse_from(["audio-transcribe", "--detect-language", "a.wav"]).unwrap().transcribe.detect_language);
+        assert!(Cli::try_parse_from(["audio-transcribe", "--language", "de", "--detect-language", "a.wav"]).is_err());
+        assert!(Cli::try_parse_from(["audio-transcribe", "--translate", "a.wav"]).unwrap().transcribe.translate);
+    }
+
+    #[test]
+    fn test_multiple_formats() {
+        let cli = Cli::try_parse_from(["audio-transcribe", "--format", "txt,srt,json", "a.wav"]).unwrap();
+        assert_eq!(cli.transcribe.format, vec!["txt", "srt", "json"]);
+        assert!(Cli::try_parse_from(["audio-transcribe", "a.wav"]).unwrap().transcribe.format.is_empty());
+    }
+
+    #[test]
+    fn test_output_file_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let generator = TranscriptGenerator::new(None);
+
+        assert_eq!(output_file_format(&dir.path().join("talk.vtt"), &generator).as_deref(), Some("vtt"));
+        assert_eq!(output_file_format(Path::new("notes.md"), &generator).as_deref(), Some("md"));
+        assert_eq!(output_file_format(Path::new("transcripts"), &generator), None);
+        assert_eq!(output_file_format(Path::new("talk.mp4"), &generator), None);
+
+        let folder = dir.path().join("week.srt");
+        std::fs::create_dir(&folder).unwrap();
+        assert_eq!(output_file_format(&folder, &generator), None);
+    }
+
+    #[test]
+    fn test_note_options_from_meeting() {
+        let meeting = MeetingInfo {
+            title: Some("Weekly sync".to_string()),
+            date: Some("2024-05-01".to_string()),
+            attendees: vec!["Alice".to_string()],
+        };
+
+        let options = note_options(Path::new("missing.wav"), Some(&meeting));
+        assert_eq!(options.title.as_deref(), Some("Weekly sync"));
+        assert_eq!(options.date.as_deref(), Some("2024-05-01"));
+        assert_eq!(options.attendees, vec!["Alice"]);
+
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--meeting", "invite.ics", "a.wav"]).unwrap();
+        assert_eq!(cli.transcribe.meeting, Some(PathBuf::from("invite.ics")));
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn test_history_subcommand() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "history", "standup.wav", "--limit", "5"]).unwrap();
+
+        match cli.command {
+            Some(Command::History(args)) => {
+                assert_eq!(args.input, Some(PathBuf::from("standup.wav")));
+                assert_eq!(args.limit, 5);
+                assert!(args.archive_db.is_none());
+            }
+            _ => panic!("expected the history subcommand"),
+        }
+
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--archive", "--archive-db", "runs.sqlite3", "a.wav"]).unwrap();
+        assert!(cli.transcribe.archive);
+        assert_eq!(cli.transcribe.archive_db, Some(PathBuf::from("runs.sqlite3")));
+    }
+
+    #[cfg(feature = "notify")]
+    #[test]
+    fn test_notify_options() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--notify", "--notify-webhook", "https://hooks.example.com/x", "a.wav"]).unwrap();
+
+        assert!(cli.transcribe.notify);
+        assert_eq!(cli.transcribe.notify_webhook.as_deref(), Some("https://hooks.example.com/x"));
+        assert!(!Cli::try_parse_from(&["audio-transcribe", "a.wav"]).unwrap().transcribe.notify);
+    }
+
+    #[cfg(feature = "translate")]
+    #[test]
+    fn test_translate_options() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--translate-to", "de", "meeting.wav"]).unwrap();
+
+        assert_eq!(cli.transcribe.translate_to.as_deref(), Some("de"));
+        assert_eq!(cli.transcribe.translate_endpoint, chat::DEFAULT_ENDPOINT);
+        assert_eq!(cli.transcribe.translate_model, chat::DEFAULT_MODEL);
+    }
+
+    #[cfg(feature = "summarize")]
+    #[test]
+    fn test_summarize_options() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "meeting.wav"]).unwrap();
+        assert!(!cli.transcribe.summarize);
+        assert_eq!(cli.transcribe.summarize_endpoint, chat::DEFAULT_ENDPOINT);
+
+        let cli = Cli::try_parse_from(&[
+            "audio-transcribe", "--summarize", "--summarize-endpoint", "https://api.openai.com/v1",
+            "--summarize-model", "gpt-4o-mini", "meeting.wav",
+        ])
+        .unwrap();
+        assert!(cli.transcribe.summarize);
+        assert_eq!(cli.transcribe.summarize_endpoint, "https://api.openai.com/v1");
+        assert_eq!(cli.transcribe.summarize_model, "gpt-4o-mini");
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_serve_subcommand() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "--model", "small", "serve", "--port", "9000"]).unwrap();
+
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::Small)));
+        match cli.command {
+            Some(Command::Serve(args)) => {
+                assert_eq!(args.host, IpAddr::V4(Ipv4Addr::LOCALHOST));
+                assert_eq!(args.port, 9000);
+                assert_eq!(args.workers, 1);
+                assert!(args.database.is_none());
+            }
+            _ => panic!("expected the serve subcommand"),
+        }
+    }
+
+    #[cfg(feature = "grpc")]
+    #[test]
+    fn test_serve_grpc_port() {
+        let cli = Cli::try_parse_from(&["audio-transcribe", "serve", "--grpc-port", "50051"]).unwrap();
+
+        match cli.command {
+            Some(Command::Serve(args)) => {
+                assert_eq!(args.port, server::DEFAULT_PORT);
+                assert_eq!(args.grpc_port, Some(50051));
+            }
+            _ => panic!("expected the serve subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_download_flags() {
+        let policy = |args: &[&str]| download_policy(&Cli::try_parse_from(args).unwrap().global);
+        assert_eq!(policy(&["audio-transcribe", "talk.wav"]), DownloadPolicy::Ask);
+        assert_eq!(policy(&["audio-transcribe", "-y", "talk.wav"]), DownloadPolicy::Always);
+        assert_eq!(policy(&["audio-transcribe", "--download-models", "talk.wav"]), DownloadPolicy::Always);
+        assert_eq!(policy(&["audio-transcribe", "--offline", "vad", "talk.wav"]), DownloadPolicy::Never);
+        assert!(Cli::try_parse_from(["audio-transcribe", "--yes", "--offline", "talk.wav"]).is_err());
+
+        let retries = |args: &[&str]| Cli::try_parse_from(args).unwrap().global.download_retries;
+        assert_eq!(retries(&["audio-transcribe", "talk.wav"]), 4);
+        assert_eq!(retries(&["audio-transcribe", "--download-retries", "0", "talk.wav"]), 0);
+    }
+
+    #[test]
+    fn test_model_dir_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let models = dir.path().join("models");
+        let cli = Cli::try_parse_from(["audio-transcribe", "--model-dir", models.to_str().unwrap(), "models", "list"]).unwrap();
+        let manager = ModelOptions::new(&cli.global).manager().unwrap();
+        assert_eq!(manager.cache_dir(), &models);
+        assert!(models.join("whisper").is_dir());
     }
 
     #[test]
     fn test_verbose_flag_short() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "-v"]).unwrap();
-        assert!(cli.verbose);
+        assert!(cli.global.verbose);
     }
 
     #[test]
     fn test_verbose_flag_long() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--verbose"]).unwrap();
-        assert!(cli.verbose);
+        assert!(cli.global.verbose);
     }
 
     #[test]
     fn test_input_file_positional() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "test.wav"]).unwrap();
-        assert_eq!(cli.input, Some(PathBuf::from("test.wav")));
+        assert_eq!(cli.transcribe.input, Some(PathBuf::from("test.wav")));
     }
 
     #[test]
@@ -297,16 +3121,18 @@ mod tests {
             "--chunk-size", "90.0",
             "--jobs", "8",
             "--no-gpu",
+            "--word-timestamps",
             "--verbose"
         ]).unwrap();
 
-        assert_eq!(cli.input, Some(PathBuf::from("input.mp3")));
-        assert!(matches!(cli.model, ModelSize::Large));
-        assert_eq!(cli.output, Some(PathBuf::from("/tmp/output")));
-        assert_eq!(cli.chunk_size, 90.0);
-        assert_eq!(cli.jobs, Some(8));
-        assert!(cli.no_gpu);
-        assert!(cli.verbose);
+        assert_eq!(cli.transcribe.input, Some(PathBuf::from("input.mp3")));
+        assert!(matches!(cli.transcribe.model, ModelChoice::Size(ModelSize::LargeV3)));
+        assert_eq!(cli.transcribe.output, Some(PathBuf::from("/tmp/output")));
+        assert_eq!(cli.transcribe.chunk_size, 90.0);
+        assert_eq!(cli.transcribe.jobs, Some(8));
+        assert!(cli.transcribe.no_gpu);
+        assert!(cli.transcribe.word_timestamps);
+        assert!(cli.global.verbose);
     }
 
     #[test]
@@ -339,7 +3165,10 @@ mod tests {
         assert_eq!(ModelSize::Base.to_string(), "base");
         assert_eq!(ModelSize::Small.to_string(), "small");
         assert_eq!(ModelSize::Medium.to_string(), "medium");
-        assert_eq!(ModelSize::Large.to_string(), "large");
+        assert_eq!(ModelSize::TinyEn.to_string(), "tiny.en");
+        assert_eq!(ModelSize::LargeV2.to_string(), "large-v2");
+        assert_eq!(ModelSize::LargeV3.to_string(), "large-v3");
+        assert_eq!(ModelSize::LargeV3Turbo.to_string(), "large-v3-turbo");
     }
 
     #[test]
@@ -353,7 +3182,7 @@ mod tests {
         let result = Cli::try_parse_from(&["audio-transcribe", "--chunk-size=-10.0"]);
         match result {
             Ok(cli) => {
-                assert_eq!(cli.chunk_size, -10.0);
+                assert_eq!(cli.transcribe.chunk_size, -10.0);
             }
             Err(err) => {
                 // If clap rejects negative values, that's also valid behavior
@@ -366,6 +3195,6 @@ mod tests {
     #[test]
     fn test_zero_jobs() {
         let cli = Cli::try_parse_from(&["audio-transcribe", "--jobs", "0"]).unwrap();
-        assert_eq!(cli.jobs, Some(0));
+        assert_eq!(cli.transcribe.jobs, Some(0));
     }
 }
\ No newline at end of file
@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use crate::core::audio_processor::{SpeechSegment, TranscriptResult};
+use crate::error::AudioTranscriptionError;
+use crate::{ModelSize, Transcriber};
+
+create_exception!(audio_transcribe, TranscriptionError, PyException);
+
+/// Raised as `TranscriptionError(message)` with the stable error code in
+/// its `code` attribute
+impl From<AudioTranscriptionError> for PyErr {
+    fn from(error: AudioTranscriptionError) -> Self {
+        let err = TranscriptionError::new_err(error.to_string());
+        Python::with_gil(|py| {
+            let _ = err.value(py).setattr("code", error.code());
+        });
+        err
+    }
+}
+
+/// Transcriber exposed to Python as `audio_transcribe.Transcriber`
+#[pyclass(name = "Transcriber")]
+struct PyTranscriber {
+    runtime: tokio::runtime::Runtime,
+    inner: Transcriber,
+}
+
+#[pymethods]
+impl PyTranscriber {
+    #[new]
+    #[pyo3(signature = (model = "medium", gpu = true, jobs = None, chunk_duration = 120.0))]
+    fn new(model: &str, gpu: bool, jobs: Option<usize>, chunk_duration: f32) -> PyResult<Self> {
+        let mut builder = Transcriber::builder()
+            .model(parse_model(model)?)
+            .gpu(gpu)
+            .chunk_duration(chunk_duration);
+        if let Some(jobs) = jobs {
+            builder = builder.jobs(jobs);
+        }
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| TranscriptionError::new_err(format!("Failed to start runtime: {}", e)))?;
+
+        Ok(Self { runtime, inner: builder.build()? })
+    }
+
+    /// Transcribe an audio file. Releases the GIL while processing.
+    fn transcribe(&self, py: Python<'_>, path: PathBuf) -> PyResult<PyTranscriptResult> {
+        let result = py.allow_threads(|| self.runtime.block_on(self.inner.transcribe(&path)))?;
+        Ok(PyTranscriptResult::from(result))
+    }
+}
+
+#[pyclass(name = "Segment", get_all, frozen)]
+#[derive(Clone)]
+struct PySegment {
+    start: f32,
+    end: f32,
+    text: String,
+    speaker: Option<u8>,
+}
+
+#[pymethods]
+impl PySegment {
+    fn __repr__(&self) -> String {
+        format!("Segment(start={:.2}, end={:.2}, speaker={:?}, text={:?})", self.start, self.end, self.speaker, self.text)
+    }
+}
+
+impl From<SpeechSegment> for PySegment {
+    fn from(segment: SpeechSegment) -> Self {
+        Self {
+            start: segment.start,
+            end: segment.end,
+            text: segment.text,
+            speaker: segment.speaker,
+        }
+    }
+}
+
+#[pyclass(name = "TranscriptResult", get_all, frozen)]
+struct PyTranscriptResult {
+    segments: Vec<PySegment>,
+    audio_duration: f64,
+    processing_time: f64,
+    realtime_factor: f32,
+    tokens: u64,
+    tokens_per_second: f32,
+    whisper_model: String,
+    diarization_model: String,
+    language: Option<String>,
+    sample_rate: u32,
+    channels: u16,
+    codec: String,
+    file_size: u64,
+    partial: bool,
+}
+
+impl From<TranscriptResult> for PyTranscriptResult {
+    fn from(result: TranscriptResult) -> Self {
+        Self {
+            realtime_factor: result.realtime_factor(),
+            tokens_per_second: result.tokens_per_second(),
+            tokens: result.tokens,
+            audio_duration: result.audio_duration.as_secs_f64(),
+            processing_time: result.processing_time.as_secs_f64(),
+            whisper_model: result.model_info.whisper_model,
+            diarization_model: result.model_info.diarization_model,
+            language: result.model_info.language,
+            sample_rate: result.metadata.sample_rate,
+            channels: result.metadata.channels,
+            codec: result.metadata.codec,
+            file_size: result.metadata.file_size,
+            partial: result.partial,
+            segments: result.segments.into_iter().map(PySegment::from).collect(),
+        }
+    }
+}
+
+fn parse_model(name: &str) -> PyResult<ModelSize> {
+    name.parse().map_err(PyValueError::new_err)
+}
+
+#[pymodule]
+#[pyo3(name = "audio_transcribe")]
+fn audio_transcribe_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyTranscriber>()?;
+    m.add_class::<PySegment>()?;
+    m.add_class::<PyTranscriptResult>()?;
+    m.add("TranscriptionError", m.py().get_type::<TranscriptionError>())?;
+    Ok(())
+}
@@ -0,0 +1,252 @@
+//! Live captions: `GET /jobs/{id}/captions` upgrades to a WebSocket that
+//! streams a job's segments as they are transcribed, so a web UI can show
+//! captions while the rest of the file is still being worked on.
+//!
+//! Every message is a JSON text frame with a `type`:
+//!
+//! - `segment`: a transcribed segment, in timestamp order. Segments from
+//!   before the client connected are sent first. They are produced before
+//!   diarization, so `speaker` is `null`.
+//! - `retrying`: the attempt failed and the job was queued again; captions
+//!   start over once it is picked up.
+//! - `completed`: the final `segments`, with speakers, after which the
+//!   socket is closed.
+//! - `failed`: the job failed for good, with `code` and `message`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path as UrlPath, State};
+use axum::response::Response;
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use crate::core::audio_processor::{SegmentSender, SpeechSegment};
+use crate::server::jobs::{JobError, JobStatus};
+use crate::server::routes::{find_job, ApiError};
+use crate::server::AppState;
+
+/// Events a slow client can fall behind by before it misses some
+const FEED_CAPACITY: usize = 1024;
+
+/// A message on the caption socket
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CaptionEvent {
+    Segment(SpeechSegment),
+    Retrying { message: String },
+    Completed { segments: Vec<SpeechSegment> },
+    Failed { code: u8, message: String },
+}
+
+impl CaptionEvent {
+    /// Whether the job is over and the socket can be closed
+    fn is_final(&self) -> bool {
+        matches!(self, Self::Completed { .. } | Self::Failed { .. })
+    }
+}
+
+/// Caption feed of one job
+struct Feed {
+    segments: Vec<SpeechSegment>,  // Of the current attempt, replayed to new clients
+    sender: broadcast::Sender<CaptionEvent>,
+    running: bool,
+}
+
+impl Feed {
+    fn new() -> Self {
+        Self { segments: Vec::new(), sender: broadcast::channel(FEED_CAPACITY).0, running: false }
+    }
+}
+
+/// Caption feeds of the jobs that are running or have clients waiting
+#[derive(Default)]
+pub struct Captions {
+    feeds: Mutex<HashMap<String, Feed>>,
+}
+
+impl Captions {
+    /// Start a job's feed. The returned sender is handed to the transcriber;
+    /// the task publishing its segments ends once it is dropped.
+    pub fn start(self: &Arc<Self>, id: &str) -> (SegmentSender, JoinHandle<()>) {
+        {
+            let mut feeds = self.feeds.lock().unwrap();
+            let feed = feeds.entry(id.to_string()).or_insert_with(Feed::new);
+            feed.segments.clear();
+            feed.running = true;
+        }
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let captions = Arc::clone(self);
+        let id = id.to_string();
+        let publishing = tokio::spawn(async move {
+            while let Some(segment) = receiver.recv().await {
+                captions.publish(&id, segment);
+            }
+        });
+        (sender, publishing)
+    }
+
+    fn publish(&self, id: &str, segment: SpeechSegment) {
+        if let Some(feed) = self.feeds.lock().unwrap().get_mut(id) {
+            feed.segments.push(segment.clone());
+            let _ = feed.sender.send(CaptionEvent::Segment(segment));
+        }
+    }
+
+    /// End the current attempt with `event`. The feed stays around for a
+    /// job that was queued again.
+    pub fn finish(&self, id: &str, event: CaptionEvent) {
+        let mut feeds = self.feeds.lock().unwrap();
+        let Some(feed) = feeds.get_mut(id) else { return };
+        let _ = feed.sender.send(event.clone());
+        if event.is_final() {
+            feeds.remove(id);
+        } else {
+            feed.segments.clear();
+            feed.running = false;
+        }
+    }
+
+    /// Segments so far and the events that follow them, with nothing missed
+    /// in between. Subscribing to a job that isn't running yet waits for it.
+    fn subscribe(&self, id: &str) -> (Vec<SpeechSegment>, broadcast::Receiver<CaptionEvent>) {
+        let mut feeds = self.feeds.lock().unwrap();
+        let feed = feeds.entry(id.to_string()).or_insert_with(Feed::new);
+        (feed.segments.clone(), feed.sender.subscribe())
+    }
+
+    /// Forget a feed nobody is running or listening to any more
+    fn unsubscribe(&self, id: &str) {
+        let mut feeds = self.feeds.lock().unwrap();
+        if feeds.get(id).is_some_and(|feed| !feed.running && feed.sender.receiver_count() == 0) {
+            feeds.remove(id);
+        }
+    }
+}
+
+pub(super) async fn captions(
+    State(state): State<AppState>,
+    UrlPath(id): UrlPath<String>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    find_job(&state, &id)?;
+    Ok(upgrade.on_upgrade(move |socket| async move {
+        stream_captions(socket, &state, &id).await;
+        state.captions.unsubscribe(&id);
+    }))
+}
+
+async fn stream_captions(mut socket: WebSocket, state: &AppState, id: &str) {
+    let (backlog, mut events) = state.captions.subscribe(id);
+
+    // A job that finished before the client subscribed has no feed left
+    let job = match state.jobs.get(id) {
+        Ok(Some(job)) => job,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::warn!(id, error = %e, "Failed to read the job for captions");
+            return;
+        }
+    };
+    let finished = match job.status {
+        JobStatus::Completed => job.result.map(|result| CaptionEvent::Completed { segments: result.segments }),
+        JobStatus::Failed => job.error.map(|JobError { code, message }| CaptionEvent::Failed { code, message }),
+        JobStatus::Queued | JobStatus::Running => None,
+    };
+    if let Some(event) = finished {
+        drop(events);
+        let _ = send(&mut socket, &event).await;
+        let _ = socket.send(Message::Close(None)).await;
+        return;
+    }
+
+    for segment in backlog {
+        if send(&mut socket, &CaptionEvent::Segment(segment)).await.is_err() {
+            return;
+        }
+    }
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if send(&mut socket, &event).await.is_err() {
+                        return;
+                    }
+                    if event.is_final() {
+                        let _ = socket.send(Message::Close(None)).await;
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!(id, missed, "Caption client fell behind");
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            // Clients don't send anything; this only notices them leaving
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, event: &CaptionEvent) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(event).map_err(axum::Error::new)?;
+    socket.send(Message::Text(text.into())).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f32, text: &str) -> SpeechSegment {
+        SpeechSegment { start, end: start + 1.0, text: text.to_string(), ..Default::default() }
+    }
+
+    async fn next(events: &mut broadcast::Receiver<CaptionEvent>) -> CaptionEvent {
+        tokio::time::timeout(std::time::Duration::from_secs(5), events.recv()).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_late_subscribers_get_the_segments_so_far() {
+        let captions = Arc::new(Captions::default());
+        let (_, mut early) = captions.subscribe("job");
+
+        let (sender, _) = captions.start("job");
+        sender.send(segment(0.0, "Hello.")).unwrap();
+        assert!(matches!(next(&mut early).await, CaptionEvent::Segment(s) if s.text == "Hello."));
+
+        let (backlog, mut late) = captions.subscribe("job");
+        assert_eq!(backlog.len(), 1);
+        sender.send(segment(1.0, "Again.")).unwrap();
+        assert!(matches!(next(&mut late).await, CaptionEvent::Segment(s) if s.text == "Again."));
+
+        // Retried jobs start over, and finished ones are forgotten
+        captions.finish("job", CaptionEvent::Retrying { message: "out of memory".to_string() });
+        assert!(captions.subscribe("job").0.is_empty());
+        captions.finish("job", CaptionEvent::Completed { segments: Vec::new() });
+        assert!(captions.feeds.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_event_json() {
+        let json = serde_json::to_value(CaptionEvent::Segment(segment(2.0, "Hi."))).unwrap();
+        assert_eq!(json["type"], "segment");
+        assert_eq!(json["start"], 2.0);
+        assert_eq!(json["text"], "Hi.");
+
+        let json = serde_json::to_value(CaptionEvent::Failed { code: 21, message: "Unsupported".to_string() }).unwrap();
+        assert_eq!(json["type"], "failed");
+        assert_eq!(json["code"], 21);
+    }
+
+    #[test]
+    fn test_unused_feeds_are_dropped() {
+        let captions = Captions::default();
+        drop(captions.subscribe("queued"));
+        captions.unsubscribe("queued");
+        assert!(captions.feeds.lock().unwrap().is_empty());
+    }
+}
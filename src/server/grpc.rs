@@ -0,0 +1,252 @@
+//! gRPC service defined in `proto/transcription.proto`, for backends where
+//! gRPC is the standard. It shares the job queue with the REST API.
+
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use futures_util::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+use crate::core::audio_processor::SpeechSegment;
+use crate::error::AudioTranscriptionError;
+use crate::server::jobs::JobStatus;
+use crate::server::{check_input, AppState};
+use crate::transcriber::Transcriber;
+
+/// Code generated from `proto/transcription.proto`
+pub mod proto {
+    tonic::include_proto!("audio_transcribe.v1");
+}
+
+use proto::transcription_server::{Transcription, TranscriptionServer};
+use proto::transcribe_request::Input;
+
+/// Largest accepted message (2 GiB, as for REST uploads)
+const MAX_MESSAGE_BYTES: usize = 2 * 1024 * 1024 * 1024;
+
+/// Metadata key carrying the error code of a failed call
+pub const ERROR_CODE_METADATA: &str = "x-error-code";
+
+/// Implementation of the `Transcription` service
+pub struct TranscriptionService {
+    state: AppState,
+    transcriber: Arc<Transcriber>,
+}
+
+impl TranscriptionService {
+    /// `transcriber` is only used by `StreamTranscribe`; queued jobs run on
+    /// the server's workers
+    pub fn new(state: AppState, transcriber: Arc<Transcriber>) -> Self {
+        Self { state, transcriber }
+    }
+
+    /// The service ready to add to a `tonic` server
+    pub fn into_server(self) -> TranscriptionServer<Self> {
+        TranscriptionServer::new(self).max_decoding_message_size(MAX_MESSAGE_BYTES)
+    }
+}
+
+/// Maps pipeline errors onto gRPC codes; the stable error code is sent as
+/// `x-error-code` metadata
+fn status(error: AudioTranscriptionError) -> Status {
+    let mut status = match error.code() {
+        12 | 13 | 21 | 80 => Status::invalid_argument(error.to_string()),
+        _ => Status::internal(error.to_string()),
+    };
+    status.metadata_mut().insert(ERROR_CODE_METADATA, u32::from(error.code()).into());
+    status
+}
+
+fn job_status(status: JobStatus) -> proto::JobStatus {
+    match status {
+        JobStatus::Queued => proto::JobStatus::Queued,
+        JobStatus::Running => proto::JobStatus::Running,
+        JobStatus::Completed => proto::JobStatus::Completed,
+        JobStatus::Failed => proto::JobStatus::Failed,
+    }
+}
+
+fn segment(segment: SpeechSegment) -> proto::Segment {
+    proto::Segment {
+        start: segment.start,
+        end: segment.end,
+        text: segment.text,
+        speaker: segment.speaker.map(u32::from),
+    }
+}
+
+/// Write uploaded audio to the upload directory, keeping the extension of
+/// `file_name` so the decoder can pick the container format
+async fn save_upload(upload_dir: &Path, file_name: &str, content: &[u8]) -> Result<PathBuf, AudioTranscriptionError> {
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let path = upload_dir.join(format!("{}{}", uuid::Uuid::new_v4(), extension));
+
+    tokio::fs::write(&path, content)
+        .await
+        .map_err(|source| AudioTranscriptionError::File { path: path.clone(), source })?;
+    Ok(path)
+}
+
+type SegmentStream = Pin<Box<dyn Stream<Item = Result<proto::Segment, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Transcription for TranscriptionService {
+    async fn transcribe(&self, request: Request<proto::TranscribeRequest>) -> Result<Response<proto::TranscribeResponse>, Status> {
+        let request = request.into_inner();
+        let (input, uploaded) = match request.input {
+            Some(Input::Path(path)) => {
+                let path = PathBuf::from(path);
+                check_input(&path).map_err(status)?;
+                (path, false)
+            }
+            Some(Input::Content(content)) => {
+                let path = save_upload(&self.state.upload_dir, &request.file_name, &content).await.map_err(status)?;
+                (path, true)
+            }
+            None => return Err(Status::invalid_argument("Request has neither 'path' nor 'content'")),
+        };
+
+        let id = self.state.jobs.insert(&input, uploaded, request.priority, None).map_err(status)?;
+        self.state.notify.notify_one();
+
+        tracing::info!(id = %id, "Job queued");
+        Ok(Response::new(proto::TranscribeResponse {
+            id,
+            status: job_status(JobStatus::Queued).into(),
+        }))
+    }
+
+    type StreamTranscribeStream = SegmentStream;
+
+    #[allow(clippy::result_large_err)]  // `Status` is the error type tonic requires
+    async fn stream_transcribe(&self, request: Request<proto::StreamTranscribeRequest>) -> Result<Response<SegmentStream>, Status> {
+        let path = PathBuf::from(request.into_inner().path);
+        check_input(&path).map_err(status)?;
+
+        let segments = self.transcriber
+            .transcribe_stream(path)
+            .map(|item| item.map(segment).map_err(status));
+        Ok(Response::new(Box::pin(segments)))
+    }
+
+    async fn get_job(&self, request: Request<proto::GetJobRequest>) -> Result<Response<proto::Job>, Status> {
+        let id = request.into_inner().id;
+        let job = self.state.jobs
+            .get(&id)
+            .map_err(status)?
+            .ok_or_else(|| Status::not_found(format!("Unknown job '{}'", id)))?;
+
+        let (segments, partial, audio_duration) = match job.result {
+            Some(result) => (
+                result.segments.into_iter().map(segment).collect(),
+                result.partial,
+                result.audio_duration.as_secs_f64(),
+            ),
+            None => (Vec::new(), false, 0.0),
+        };
+
+        Ok(Response::new(proto::Job {
+            id: job.id,
+            status: job_status(job.status).into(),
+            priority: job.priority,
+            attempts: job.attempts,
+            error: job.error.map(|error| proto::JobError { code: error.code.into(), message: error.message }),
+            segments,
+            partial,
+            audio_duration,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::core::audio_processor::{ModelInfo, TranscriptResult};
+    use crate::server::jobs::{JobStore, DEFAULT_MAX_ATTEMPTS};
+
+    fn service(upload_dir: &Path) -> TranscriptionService {
+        let state = AppState::new(JobStore::in_memory(DEFAULT_MAX_ATTEMPTS).unwrap(), upload_dir.to_path_buf());
+        TranscriptionService::new(state, Arc::new(Transcriber::builder().build().unwrap()))
+    }
+
+    fn error_code(status: &Status) -> &str {
+        status.metadata().get(ERROR_CODE_METADATA).unwrap().to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_queues_upload() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+
+        let response = service.transcribe(Request::new(proto::TranscribeRequest {
+            input: Some(Input::Content(b"audio-bytes".to_vec())),
+            file_name: "memo.m4a".to_string(),
+            priority: 3,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+        assert_eq!(response.status(), proto::JobStatus::Queued);
+
+        let job = service.state.jobs.claim_next().unwrap().unwrap();
+        assert_eq!(job.id, response.id);
+        assert!(job.uploaded);
+        assert_eq!(job.priority, 3);
+        assert_eq!(job.input.extension().unwrap(), "m4a");
+        assert_eq!(std::fs::read(&job.input).unwrap(), b"audio-bytes");
+    }
+
+    #[tokio::test]
+    async fn test_missing_path_is_invalid_argument() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+
+        let error = service.transcribe(Request::new(proto::TranscribeRequest {
+            input: Some(Input::Path("/does/not/exist.wav".to_string())),
+            ..Default::default()
+        }))
+        .await
+        .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+        assert_eq!(error_code(&error), "12");
+
+        let error = service.stream_transcribe(Request::new(proto::StreamTranscribeRequest {
+            path: "/does/not/exist.wav".to_string(),
+        }))
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(error_code(&error), "12");
+    }
+
+    #[tokio::test]
+    async fn test_get_job_returns_transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+        let id = service.state.jobs.insert(&dir.path().join("talk.wav"), false, 0, None).unwrap();
+        service.state.jobs.complete(&id, &TranscriptResult {
+            segments: vec![SpeechSegment { start: 0.0, end: 1.5, text: "Hello.".to_string(), speaker: Some(1), ..Default::default() }],
+            audio_duration: Duration::from_secs(2),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }).unwrap();
+
+        let job = service.get_job(Request::new(proto::GetJobRequest { id: id.clone() })).await.unwrap().into_inner();
+        assert_eq!(job.status(), proto::JobStatus::Completed);
+        assert_eq!(job.segments.len(), 1);
+        assert_eq!(job.segments[0].speaker, Some(1));
+        assert_eq!(job.audio_duration, 2.0);
+
+        let error = service.get_job(Request::new(proto::GetJobRequest { id: "unknown".to_string() })).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::NotFound);
+    }
+}
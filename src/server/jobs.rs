@@ -0,0 +1,379 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use tokio::sync::Notify;
+use tracing::Instrument;
+use crate::core::audio_processor::TranscriptResult;
+use crate::error::{Result, AudioTranscriptionError};
+use crate::server::captions::{CaptionEvent, Captions};
+use crate::server::metrics::Metrics;
+use crate::transcriber::Transcriber;
+
+/// Attempts per job before it is marked failed
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Pause before a worker retries after the database itself failed
+const DATABASE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS jobs (
+        seq           INTEGER PRIMARY KEY AUTOINCREMENT,
+        id            TEXT NOT NULL UNIQUE,
+        input         TEXT NOT NULL,
+        uploaded      INTEGER NOT NULL,
+        priority      INTEGER NOT NULL DEFAULT 0,
+        language      TEXT,
+        status        TEXT NOT NULL,
+        attempts      INTEGER NOT NULL DEFAULT 0,
+        result        TEXT,
+        error_code    INTEGER,
+        error_message TEXT
+    );
+    CREATE INDEX IF NOT EXISTS jobs_queue ON jobs (status, priority DESC, seq);
+";
+
+const JOB_COLUMNS: &str = "id, input, uploaded, priority, language, status, attempts, result, error_code, error_message";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(status: &str) -> Self {
+        match status {
+            "running" => JobStatus::Running,
+            "completed" => JobStatus::Completed,
+            "failed" => JobStatus::Failed,
+            _ => JobStatus::Queued,
+        }
+    }
+}
+
+/// Error recorded for a failed job
+#[derive(Debug, Clone)]
+pub struct JobError {
+    pub code: u8,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: String,
+    pub input: PathBuf,
+    pub status: JobStatus,
+    pub priority: i64,   // Higher runs first; equal priorities run in submission order
+    pub language: Option<String>,  // Spoken language given with the job, instead of the server's
+    pub attempts: u32,
+    pub result: Option<TranscriptResult>,
+    pub error: Option<JobError>,
+    pub uploaded: bool,  // Input was uploaded and is deleted once the job is final
+}
+
+impl Job {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        let result: Option<String> = row.get("result")?;
+        let error_code: Option<u8> = row.get("error_code")?;
+        let error_message: Option<String> = row.get("error_message")?;
+        let input: String = row.get("input")?;
+        let status: String = row.get("status")?;
+
+        Ok(Job {
+            id: row.get("id")?,
+            input: PathBuf::from(input),
+            status: JobStatus::parse(&status),
+            priority: row.get("priority")?,
+            language: row.get("language")?,
+            attempts: row.get("attempts")?,
+            // Results are written by this module, so they always parse
+            result: result.and_then(|json| serde_json::from_str(&json).ok()),
+            error: error_code.map(|code| JobError { code, message: error_message.unwrap_or_default() }),
+            uploaded: row.get("uploaded")?,
+        })
+    }
+}
+
+/// Job queue persisted in SQLite, shared by the HTTP handlers and the
+/// workers. Jobs left running by a previous process are queued again when
+/// the store is opened.
+#[derive(Debug)]
+pub struct JobStore {
+    connection: Mutex<Connection>,
+    max_attempts: u32,
+}
+
+impl JobStore {
+    pub fn open(path: &Path, max_attempts: u32) -> Result<Self> {
+        Self::init(Connection::open(path)?, max_attempts)
+    }
+
+    /// A store that is lost when dropped, for tests and throwaway servers
+    pub fn in_memory(max_attempts: u32) -> Result<Self> {
+        Self::init(Connection::open_in_memory()?, max_attempts)
+    }
+
+    fn init(connection: Connection, max_attempts: u32) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        // Databases from before jobs had a language
+        let has_language: bool = connection.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('jobs') WHERE name = 'language'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_language {
+            connection.execute("ALTER TABLE jobs ADD COLUMN language TEXT", [])?;
+        }
+        let interrupted = connection.execute("UPDATE jobs SET status = 'queued' WHERE status = 'running'", [])?;
+        if interrupted > 0 {
+            tracing::info!(jobs = interrupted, "Re-queued jobs interrupted by a restart");
+        }
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+            max_attempts: max_attempts.max(1),
+        })
+    }
+
+    /// Register a queued job and return its ID. `language` overrides the
+    /// server's language setting for this job.
+    pub fn insert(&self, input: &Path, uploaded: bool, priority: i64, language: Option<&str>) -> Result<String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO jobs (id, input, uploaded, priority, language, status) VALUES (?1, ?2, ?3, ?4, ?5, 'queued')",
+            params![id, input.to_string_lossy(), uploaded, priority, language],
+        )?;
+        Ok(id)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Job>> {
+        let job = self.connection
+            .lock()
+            .unwrap()
+            .query_row(&format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS), [id], Job::from_row)
+            .optional()?;
+        Ok(job)
+    }
+
+    /// Number of jobs with `status`
+    pub fn count(&self, status: JobStatus) -> Result<u64> {
+        let count = self.connection
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM jobs WHERE status = ?1", [status.as_str()], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Mark the highest-priority queued job as running and return it
+    pub fn claim_next(&self) -> Result<Option<Job>> {
+        let job = self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                &format!(
+                    "UPDATE jobs SET status = 'running', attempts = attempts + 1
+                     WHERE seq = (SELECT seq FROM jobs WHERE status = 'queued' ORDER BY priority DESC, seq LIMIT 1)
+                     RETURNING {}",
+                    JOB_COLUMNS
+                ),
+                [],
+                Job::from_row,
+            )
+            .optional()?;
+        Ok(job)
+    }
+
+    pub fn complete(&self, id: &str, result: &TranscriptResult) -> Result<()> {
+        self.connection.lock().unwrap().execute(
+            "UPDATE jobs SET status = 'completed', result = ?2, error_code = NULL, error_message = NULL WHERE id = ?1",
+            params![id, serde_json::to_string(result)?],
+        )?;
+        Ok(())
+    }
+
+    /// Record a failed attempt. The job is queued again unless it has used
+    /// all attempts or the error won't go away on retry (bad input or
+    /// configuration). Returns the job's new status.
+    pub fn fail(&self, id: &str, error: &AudioTranscriptionError) -> Result<JobStatus> {
+        let permanent = error.is_permanent();
+        let connection = self.connection.lock().unwrap();
+
+        let attempts: u32 = connection.query_row("SELECT attempts FROM jobs WHERE id = ?1", [id], |row| row.get(0))?;
+        let status = if permanent || attempts >= self.max_attempts {
+            JobStatus::Failed
+        } else {
+            JobStatus::Queued
+        };
+
+        connection.execute(
+            "UPDATE jobs SET status = ?2, error_code = ?3, error_message = ?4 WHERE id = ?1",
+            params![id, status.as_str(), error.code(), error.to_string()],
+        )?;
+        Ok(status)
+    }
+}
+
+/// Take jobs from the store until the process stops. Run several of these
+/// to transcribe files concurrently; `notify` wakes an idle worker when a
+/// job is queued. Outcomes are recorded in `metrics`.
+pub async fn run_worker(
+    transcriber: Arc<Transcriber>,
+    jobs: Arc<JobStore>,
+    notify: Arc<Notify>,
+    metrics: Arc<Metrics>,
+    captions: Arc<Captions>,
+) {
+    loop {
+        let job = match jobs.claim_next() {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                notify.notified().await;
+                continue;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to read the job queue");
+                tokio::time::sleep(DATABASE_RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        let span = tracing::info_span!("job", id = %job.id, input = %job.input.display(), attempt = job.attempts);
+        let finished = async {
+            tracing::info!("Job started");
+            let started = Instant::now();
+            let (segments, publishing) = captions.start(&job.id);
+            let transcribed = transcriber.transcribe_with_segments(&job.input, job.language.as_deref(), segments).await;
+            let _ = publishing.await;
+            let outcome = match transcribed {
+                Ok(result) => {
+                    tracing::info!(segments = result.segments.len(), "Job completed");
+                    metrics.job_completed(&result, started.elapsed());
+                    let status = jobs.complete(&job.id, &result).map(|_| JobStatus::Completed);
+                    captions.finish(&job.id, CaptionEvent::Completed { segments: result.segments });
+                    status
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Job failed");
+                    let status = jobs.fail(&job.id, &e);
+                    if let Ok(status) = status {
+                        metrics.job_failed(status, started.elapsed());
+                    }
+                    let event = match &status {
+                        Ok(JobStatus::Queued) => CaptionEvent::Retrying { message: e.to_string() },
+                        _ => CaptionEvent::Failed { code: e.code(), message: e.to_string() },
+                    };
+                    captions.finish(&job.id, event);
+                    status
+                }
+            };
+
+            match outcome {
+                Ok(status) => status != JobStatus::Queued,
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to record job outcome");
+                    false
+                }
+            }
+        }
+        .instrument(span)
+        .await;
+
+        if finished && job.uploaded {
+            let _ = tokio::fs::remove_file(&job.input).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claims_by_priority_then_submission_order() {
+        let store = JobStore::in_memory(DEFAULT_MAX_ATTEMPTS).unwrap();
+        let first = store.insert(Path::new("a.wav"), false, 0, None).unwrap();
+        let urgent = store.insert(Path::new("b.wav"), false, 10, None).unwrap();
+        let second = store.insert(Path::new("c.wav"), false, 0, None).unwrap();
+
+        let order: Vec<String> = std::iter::from_fn(|| store.claim_next().unwrap()).map(|job| job.id).collect();
+
+        assert_eq!(order, vec![urgent, first, second]);
+        assert_eq!(store.get(&order[0]).unwrap().unwrap().status, JobStatus::Running);
+        assert_eq!(store.count(JobStatus::Running).unwrap(), 3);
+        assert_eq!(store.count(JobStatus::Queued).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_failures_retry_until_attempts_run_out() {
+        let store = JobStore::in_memory(2).unwrap();
+        let id = store.insert(Path::new("a.wav"), false, 0, None).unwrap();
+        let transient = AudioTranscriptionError::Chunk { index: 0, message: "decoder crashed".to_string() };
+
+        store.claim_next().unwrap();
+        assert_eq!(store.fail(&id, &transient).unwrap(), JobStatus::Queued);
+        store.claim_next().unwrap();
+        assert_eq!(store.fail(&id, &transient).unwrap(), JobStatus::Failed);
+
+        let job = store.get(&id).unwrap().unwrap();
+        assert_eq!(job.attempts, 2);
+        assert_eq!(job.error.unwrap().code, 22);
+    }
+
+    #[test]
+    fn test_bad_input_is_not_retried() {
+        let store = JobStore::in_memory(DEFAULT_MAX_ATTEMPTS).unwrap();
+        let id = store.insert(Path::new("a.wav"), false, 0, None).unwrap();
+        store.claim_next().unwrap();
+
+        let missing = AudioTranscriptionError::InputNotFound { path: PathBuf::from("a.wav") };
+        assert_eq!(store.fail(&id, &missing).unwrap(), JobStatus::Failed);
+    }
+
+    #[test]
+    fn test_language_kept_and_old_databases_upgraded() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("jobs.sqlite3");
+        Connection::open(&database)
+            .unwrap()
+            .execute_batch(&SCHEMA.replace("language      TEXT,", ""))
+            .unwrap();
+
+        let store = JobStore::open(&database, DEFAULT_MAX_ATTEMPTS).unwrap();
+        let id = store.insert(Path::new("a.wav"), false, 0, Some("de")).unwrap();
+        store.insert(Path::new("b.wav"), false, 0, None).unwrap();
+
+        let job = store.claim_next().unwrap().unwrap();
+        assert_eq!((job.id, job.language.as_deref()), (id, Some("de")));
+        assert_eq!(store.claim_next().unwrap().unwrap().language, None);
+    }
+
+    #[test]
+    fn test_jobs_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("jobs.sqlite3");
+
+        let store = JobStore::open(&database, DEFAULT_MAX_ATTEMPTS).unwrap();
+        let running = store.insert(Path::new("a.wav"), true, 0, None).unwrap();
+        let queued = store.insert(Path::new("b.wav"), false, 0, None).unwrap();
+        store.claim_next().unwrap();
+        drop(store);
+
+        let store = JobStore::open(&database, DEFAULT_MAX_ATTEMPTS).unwrap();
+        let job = store.get(&running).unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Queued);
+        assert!(job.uploaded);
+        assert_eq!(store.get(&queued).unwrap().unwrap().status, JobStatus::Queued);
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::core::audio_processor::TranscriptResult;
+use crate::error::Result;
+use crate::server::jobs::{JobStatus, JobStore};
+
+/// Upper bounds of the real-time factor buckets (processing time / audio
+/// duration; below 1 is faster than real time)
+const RTF_BUCKETS: [f64; 8] = [0.05, 0.1, 0.2, 0.3, 0.5, 1.0, 2.0, 5.0];
+
+/// Upper bounds of the job duration buckets in seconds
+const DURATION_BUCKETS: [f64; 8] = [1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0, 3600.0];
+
+/// Cumulative histogram in the Prometheus sense
+#[derive(Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,  // One per bound; `+Inf` is `count`
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self { bounds, counts: vec![0; bounds.len()], sum: 0.0, count: 0 }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(&mut self.counts) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum);
+        let _ = writeln!(out, "{}_count {}", name, self.count);
+    }
+}
+
+#[derive(Debug)]
+struct Counters {
+    completed: u64,
+    failed: u64,
+    retried: u64,
+    audio_seconds: f64,
+    real_time_factor: Histogram,
+    job_duration: Histogram,
+}
+
+/// Server metrics in the Prometheus text format, served on `GET /metrics`.
+/// Counters are kept in memory and start at zero with each process; queue
+/// depth is read from the job store when scraped.
+#[derive(Debug)]
+pub struct Metrics {
+    counters: Mutex<Counters>,
+    gpu: bool,  // Report GPU memory (read through `nvidia-smi`)
+}
+
+impl Metrics {
+    pub fn new(gpu: bool) -> Self {
+        Self {
+            counters: Mutex::new(Counters {
+                completed: 0,
+                failed: 0,
+                retried: 0,
+                audio_seconds: 0.0,
+                real_time_factor: Histogram::new(&RTF_BUCKETS),
+                job_duration: Histogram::new(&DURATION_BUCKETS),
+            }),
+            gpu,
+        }
+    }
+
+    /// Record a job that finished with a transcript
+    pub fn job_completed(&self, result: &TranscriptResult, elapsed: Duration) {
+        let audio_seconds = result.audio_duration.as_secs_f64();
+        let mut counters = self.counters.lock().unwrap();
+        counters.completed += 1;
+        counters.audio_seconds += audio_seconds;
+        counters.job_duration.observe(elapsed.as_secs_f64());
+        if audio_seconds > 0.0 {
+            counters.real_time_factor.observe(result.processing_time.as_secs_f64() / audio_seconds);
+        }
+    }
+
+    /// Record a failed attempt; `status` is the job's status afterwards
+    pub fn job_failed(&self, status: JobStatus, elapsed: Duration) {
+        let mut counters = self.counters.lock().unwrap();
+        if status == JobStatus::Queued {
+            counters.retried += 1;
+        } else {
+            counters.failed += 1;
+        }
+        counters.job_duration.observe(elapsed.as_secs_f64());
+    }
+
+    /// All metrics in the Prometheus text exposition format
+    pub async fn render(&self, jobs: &JobStore) -> Result<String> {
+        let queued = jobs.count(JobStatus::Queued)?;
+        let running = jobs.count(JobStatus::Running)?;
+        let gpu_memory = if self.gpu { gpu_memory_used().await } else { Vec::new() };
+
+        let mut out = String::new();
+        {
+            let counters = self.counters.lock().unwrap();
+            out.push_str("# HELP audio_transcribe_jobs_processed_total Jobs that reached a final state or were retried.\n");
+            out.push_str("# TYPE audio_transcribe_jobs_processed_total counter\n");
+            for (outcome, value) in [("completed", counters.completed), ("failed", counters.failed), ("retried", counters.retried)] {
+                let _ = writeln!(out, "audio_transcribe_jobs_processed_total{{outcome=\"{}\"}} {}", outcome, value);
+            }
+
+            out.push_str("# HELP audio_transcribe_audio_seconds_total Seconds of audio in completed jobs.\n");
+            out.push_str("# TYPE audio_transcribe_audio_seconds_total counter\n");
+            let _ = writeln!(out, "audio_transcribe_audio_seconds_total {}", counters.audio_seconds);
+
+            counters.real_time_factor.render(
+                &mut out,
+                "audio_transcribe_real_time_factor",
+                "Processing time divided by audio duration of completed jobs.",
+            );
+            counters.job_duration.render(
+                &mut out,
+                "audio_transcribe_job_duration_seconds",
+                "Wall-clock time of each job attempt.",
+            );
+        }
+
+        out.push_str("# HELP audio_transcribe_queue_depth Jobs waiting for or held by a worker.\n");
+        out.push_str("# TYPE audio_transcribe_queue_depth gauge\n");
+        let _ = writeln!(out, "audio_transcribe_queue_depth{{status=\"queued\"}} {}", queued);
+        let _ = writeln!(out, "audio_transcribe_queue_depth{{status=\"running\"}} {}", running);
+
+        if !gpu_memory.is_empty() {
+            out.push_str("# HELP audio_transcribe_gpu_memory_used_bytes GPU memory in use, per device.\n");
+            out.push_str("# TYPE audio_transcribe_gpu_memory_used_bytes gauge\n");
+            for (device, bytes) in gpu_memory {
+                let _ = writeln!(out, "audio_transcribe_gpu_memory_used_bytes{{device=\"{}\"}} {}", device, bytes);
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+/// Memory used per NVIDIA GPU, or nothing when `nvidia-smi` isn't available
+/// (e.g. Metal on macOS)
+async fn gpu_memory_used() -> Vec<(u32, u64)> {
+    let output = tokio::process::Command::new("nvidia-smi")
+        .args(["--query-gpu=index,memory.used", "--format=csv,noheader,nounits"])
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout)),
+        _ => Vec::new(),
+    }
+}
+
+/// Parse `index, MiB` lines
+fn parse_nvidia_smi(output: &str) -> Vec<(u32, u64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (index, used) = line.split_once(',')?;
+            let used: u64 = used.trim().parse().ok()?;
+            Some((index.trim().parse().ok()?, used * 1024 * 1024))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use crate::core::audio_processor::ModelInfo;
+    use crate::server::jobs::DEFAULT_MAX_ATTEMPTS;
+
+    fn result(audio_secs: u64, processing_secs: u64) -> TranscriptResult {
+        TranscriptResult {
+            audio_duration: Duration::from_secs(audio_secs),
+            processing_time: Duration::from_secs(processing_secs),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(processing_secs),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_reports_jobs_and_queue() {
+        let jobs = JobStore::in_memory(DEFAULT_MAX_ATTEMPTS).unwrap();
+        jobs.insert(Path::new("a.wav"), false, 0, None).unwrap();
+        jobs.insert(Path::new("b.wav"), false, 0, None).unwrap();
+        jobs.claim_next().unwrap();
+
+        let metrics = Metrics::new(false);
+        metrics.job_completed(&result(100, 25), Duration::from_secs(26));
+        metrics.job_failed(JobStatus::Queued, Duration::from_secs(2));
+        metrics.job_failed(JobStatus::Failed, Duration::from_secs(2));
+
+        let text = metrics.render(&jobs).await.unwrap();
+        assert!(text.contains("audio_transcribe_jobs_processed_total{outcome=\"completed\"} 1\n"));
+        assert!(text.contains("audio_transcribe_jobs_processed_total{outcome=\"retried\"} 1\n"));
+        assert!(text.contains("audio_transcribe_jobs_processed_total{outcome=\"failed\"} 1\n"));
+        assert!(text.contains("audio_transcribe_audio_seconds_total 100\n"));
+        assert!(text.contains("audio_transcribe_real_time_factor_bucket{le=\"0.2\"} 0\n"));
+        assert!(text.contains("audio_transcribe_real_time_factor_bucket{le=\"0.3\"} 1\n"));
+        assert!(text.contains("audio_transcribe_job_duration_seconds_count 3\n"));
+        assert!(text.contains("audio_transcribe_queue_depth{status=\"queued\"} 1\n"));
+        assert!(text.contains("audio_transcribe_queue_depth{status=\"running\"} 1\n"));
+        assert!(!text.contains("gpu_memory"));
+    }
+
+    #[test]
+    fn test_parse_nvidia_smi() {
+        assert_eq!(parse_nvidia_smi("0, 1024\n1, 0\n"), vec![(0, 1024 * 1024 * 1024), (1, 0)]);
+        assert!(parse_nvidia_smi("No devices were found\n").is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+//! Local REST API for running the tool as a transcription service.
+//!
+//! Jobs are persisted in SQLite, so queued and interrupted jobs resume after
+//! a restart; see [`router`] for the endpoints. With the `grpc` feature the
+//! same queue is also served over gRPC (see [`grpc`]).
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod captions;
+pub mod jobs;
+pub mod metrics;
+mod openai;
+mod routes;
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use crate::core::transcript_generator::TranscriptGenerator;
+use crate::error::{Result, AudioTranscriptionError};
+use crate::transcriber::Transcriber;
+use captions::Captions;
+use jobs::JobStore;
+use metrics::Metrics;
+
+pub use routes::router;
+
+/// Port the `serve` subcommand listens on by default
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// Where and how the server runs
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub addr: SocketAddr,
+    pub database: PathBuf,  // Uploads are kept in an `uploads` directory next to it
+    pub workers: usize,     // Files transcribed at the same time
+    pub max_attempts: u32,
+    #[cfg(feature = "grpc")]
+    pub grpc_addr: Option<SocketAddr>,  // gRPC is only served when set
+}
+
+impl ServerConfig {
+    /// Single worker with the job database in the user data directory
+    pub fn new(addr: SocketAddr) -> Result<Self> {
+        Ok(Self {
+            addr,
+            database: default_database_path()?,
+            workers: 1,
+            max_attempts: jobs::DEFAULT_MAX_ATTEMPTS,
+            #[cfg(feature = "grpc")]
+            grpc_addr: None,
+        })
+    }
+}
+
+/// `<data dir>/audio-transcribe/jobs.sqlite3`
+pub fn default_database_path() -> Result<PathBuf> {
+    let data_dir = dirs::data_dir().ok_or_else(|| AudioTranscriptionError::Configuration(
+        "Unable to determine data directory".to_string()
+    ))?;
+    Ok(data_dir.join("audio-transcribe").join("jobs.sqlite3"))
+}
+
+/// State shared by the request handlers
+#[derive(Clone)]
+pub struct AppState {
+    pub jobs: Arc<JobStore>,
+    pub generator: Arc<TranscriptGenerator>,
+    pub metrics: Arc<Metrics>,
+    pub captions: Arc<Captions>,
+    notify: Arc<Notify>,
+    upload_dir: PathBuf,
+}
+
+impl AppState {
+    /// Uploaded files are stored in `upload_dir`
+    pub fn new(jobs: JobStore, upload_dir: PathBuf) -> Self {
+        Self {
+            jobs: Arc::new(jobs),
+            generator: Arc::new(TranscriptGenerator::new(None)),
+            metrics: Arc::new(Metrics::new(false)),
+            captions: Arc::new(Captions::default()),
+            notify: Arc::new(Notify::new()),
+            upload_dir,
+        }
+    }
+}
+
+/// Reject a path input the server can't read before it is queued
+fn check_input(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Err(AudioTranscriptionError::InputNotFound { path: path.to_path_buf() });
+    }
+    if !path.is_file() {
+        return Err(AudioTranscriptionError::InputNotAFile { path: path.to_path_buf() });
+    }
+    Ok(())
+}
+
+/// Serve the REST API, and gRPC if configured, until the process is stopped
+pub async fn serve(transcriber: Transcriber, config: ServerConfig) -> Result<()> {
+    let data_dir = config.database.parent().unwrap_or_else(|| std::path::Path::new("."));
+    let upload_dir = data_dir.join("uploads");
+    std::fs::create_dir_all(&upload_dir)?;
+
+    let mut state = AppState::new(JobStore::open(&config.database, config.max_attempts)?, upload_dir);
+    state.metrics = Arc::new(Metrics::new(transcriber.config().use_gpu));
+    let transcriber = Arc::new(transcriber);
+    for _ in 0..config.workers.max(1) {
+        tokio::spawn(jobs::run_worker(
+            Arc::clone(&transcriber),
+            Arc::clone(&state.jobs),
+            Arc::clone(&state.notify),
+            Arc::clone(&state.metrics),
+            Arc::clone(&state.captions),
+        ));
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_addr) = config.grpc_addr {
+        let service = grpc::TranscriptionService::new(state.clone(), Arc::clone(&transcriber)).into_server();
+        tracing::info!(addr = %grpc_addr, "Serving gRPC");
+        let grpc = tonic::transport::Server::builder().add_service(service).serve(grpc_addr);
+        let rest = serve_rest(&config, state);
+        let (rest, grpc) = tokio::join!(rest, grpc);
+        rest?;
+        return grpc.map_err(|e| AudioTranscriptionError::Io(std::io::Error::other(e)));
+    }
+
+    serve_rest(&config, state).await
+}
+
+async fn serve_rest(config: &ServerConfig, state: AppState) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(config.addr).await?;
+    tracing::info!(addr = %config.addr, database = %config.database.display(), workers = config.workers, "Serving REST API");
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
@@ -0,0 +1,286 @@
+//! OpenAI-compatible `POST /v1/audio/transcriptions`, so clients written for
+//! OpenAI's Whisper API only need their base URL changed.
+//!
+//! The upload runs as an ordinary job: it waits its turn in the queue and
+//! shows up in the metrics, and the response is sent once it is done.
+//! `language` and `response_format` (`json`, `text`, `srt` or `vtt`) are
+//! honoured. `model` is accepted but the server's own model is used, and
+//! other fields such as `prompt` and `temperature` are ignored.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use axum::extract::multipart::MultipartRejection;
+use axum::extract::{Multipart, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+use crate::core::audio_processor::TranscriptResult;
+use crate::core::transcription::LANGUAGES;
+use crate::error::AudioTranscriptionError;
+use crate::server::jobs::JobStatus;
+use crate::server::routes::{content_type, read_upload, ApiError};
+use crate::server::AppState;
+
+/// Values of `response_format`
+const RESPONSE_FORMATS: &[&str] = &["json", "text", "srt", "vtt"];
+
+/// How often a waiting request checks on its job
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Error in OpenAI's shape, `{"error": {"message": ..., "type": ..., "code": ...}}`,
+/// which OpenAI client libraries turn into their own exceptions
+pub(super) struct OpenAiError(ApiError);
+
+impl From<ApiError> for OpenAiError {
+    fn from(error: ApiError) -> Self {
+        Self(error)
+    }
+}
+
+impl From<AudioTranscriptionError> for OpenAiError {
+    fn from(error: AudioTranscriptionError) -> Self {
+        Self(error.into())
+    }
+}
+
+impl IntoResponse for OpenAiError {
+    fn into_response(self) -> Response {
+        let kind = if self.0.status.is_client_error() { "invalid_request_error" } else { "server_error" };
+        let body = json!({ "error": { "message": self.0.message, "type": kind, "param": null, "code": self.0.code } });
+        (self.0.status, Json(body)).into_response()
+    }
+}
+
+pub(super) async fn transcriptions(
+    State(state): State<AppState>,
+    multipart: Result<Multipart, MultipartRejection>,
+) -> Result<Response, OpenAiError> {
+    let multipart = multipart.map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.body_text()))?;
+    let upload = read_upload(&state.upload_dir, multipart).await?;
+    let (language, response_format) = match options(&upload.fields) {
+        Ok(options) => options,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&upload.input).await;
+            return Err(e.into());
+        }
+    };
+
+    let id = state.jobs.insert(&upload.input, true, 0, language.as_deref())?;
+    state.notify.notify_one();
+    tracing::info!(id = %id, "OpenAI-compatible job queued");
+
+    let result = wait_for(&state, &id).await?;
+    let response = match response_format.as_str() {
+        "json" => Json(json!({ "text": plain_text(&result) })).into_response(),
+        "text" => ([(header::CONTENT_TYPE, content_type("txt"))], plain_text(&result)).into_response(),
+        name => {
+            let formatter = state.generator.require_formatter(name)?;
+            ([(header::CONTENT_TYPE, content_type(formatter.extension()))], formatter.format(&result)).into_response()
+        }
+    };
+    Ok(response)
+}
+
+/// The `language` and `response_format` fields, checked before the job is
+/// queued
+fn options(fields: &HashMap<String, String>) -> Result<(Option<String>, String), ApiError> {
+    let bad_request = |message: String| ApiError::new(StatusCode::BAD_REQUEST, message);
+
+    let response_format = fields.get("response_format").map(|format| format.trim()).unwrap_or("json");
+    if !RESPONSE_FORMATS.contains(&response_format) {
+        return Err(bad_request(format!(
+            "Unsupported response_format '{}' (supported: {})",
+            response_format,
+            RESPONSE_FORMATS.join(", ")
+        )));
+    }
+
+    let language = fields
+        .get("language")
+        .map(|language| language.trim().to_ascii_lowercase())
+        .filter(|language| !language.is_empty());
+    if let Some(language) = language.as_deref().filter(|language| !LANGUAGES.contains(language)) {
+        return Err(bad_request(format!("Unknown language '{}'; use an ISO-639-1 code such as en or de", language)));
+    }
+
+    Ok((language, response_format.to_string()))
+}
+
+/// Wait until the job is completed or has failed for good
+async fn wait_for(state: &AppState, id: &str) -> Result<TranscriptResult, ApiError> {
+    loop {
+        let job = state.jobs.get(id)?.ok_or_else(|| ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Job '{}' disappeared from the queue", id),
+        ))?;
+        match job.status {
+            JobStatus::Completed => {
+                if let Some(result) = job.result {
+                    return Ok(result);
+                }
+            }
+            JobStatus::Failed => {
+                return Err(job.error.map(ApiError::from).unwrap_or_else(|| {
+                    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Transcription failed")
+                }));
+            }
+            JobStatus::Queued | JobStatus::Running => {}
+        }
+        tokio::time::sleep(JOB_POLL_INTERVAL).await;
+    }
+}
+
+/// The transcript as one line of text, as OpenAI returns it
+fn plain_text(result: &TranscriptResult) -> String {
+    result
+        .segments
+        .iter()
+        .map(|segment| segment.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use axum::body::Body;
+    use axum::extract::Request;
+    use tower::ServiceExt;
+    use crate::core::audio_processor::{ModelInfo, SpeechSegment};
+    use crate::server::jobs::{JobStore, DEFAULT_MAX_ATTEMPTS};
+    use crate::server::router;
+
+    fn sample_result() -> TranscriptResult {
+        let segment = |start: f32, text: &str| SpeechSegment {
+            start,
+            end: start + 1.0,
+            text: text.to_string(),
+            speaker: Some(1),
+            ..Default::default()
+        };
+        TranscriptResult {
+            segments: vec![segment(0.0, " Hello."), segment(1.0, " How are you?")],
+            audio_duration: Duration::from_secs(2),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                language: Some("de".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn upload(fields: &[(&str, &str)]) -> Request<Body> {
+        let mut body = String::from(
+            "--XYZ\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"call.mp3\"\r\n\
+             Content-Type: audio/mpeg\r\n\r\n\
+             audio-bytes\r\n",
+        );
+        for (name, value) in fields {
+            body.push_str(&format!("--XYZ\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", name, value));
+        }
+        body.push_str("--XYZ--\r\n");
+        Request::post("/v1/audio/transcriptions")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=XYZ")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    async fn send(state: &AppState, request: Request<Body>) -> (StatusCode, String) {
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn state(upload_dir: &Path) -> AppState {
+        AppState::new(JobStore::in_memory(DEFAULT_MAX_ATTEMPTS).unwrap(), upload_dir.to_path_buf())
+    }
+
+    /// Stand in for a worker: finish the next job once it is queued
+    async fn complete_next_job(state: &AppState) -> crate::server::jobs::Job {
+        loop {
+            if let Some(job) = state.jobs.claim_next().unwrap() {
+                state.jobs.complete(&job.id, &sample_result()).unwrap();
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_responds_once_the_job_is_done() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(dir.path());
+
+        let request = tokio::spawn({
+            let state = state.clone();
+            async move { send(&state, upload(&[("model", "whisper-1"), ("language", "DE")])).await }
+        });
+        let job = complete_next_job(&state).await;
+        let (status, body) = request.await.unwrap();
+
+        assert_eq!(job.language.as_deref(), Some("de"));
+        assert!(job.uploaded);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, r#"{"text":"Hello. How are you?"}"#);
+
+        let request = tokio::spawn({
+            let state = state.clone();
+            async move { send(&state, upload(&[("response_format", "vtt")])).await }
+        });
+        assert_eq!(complete_next_job(&state).await.language, None);
+        let (status, body) = request.await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.starts_with("WEBVTT"));
+    }
+
+    #[tokio::test]
+    async fn test_bad_options_are_rejected_in_openai_shape() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(dir.path());
+
+        let (status, body) = send(&state, upload(&[("response_format", "verbose_json")])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let error: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(error["error"]["type"], "invalid_request_error");
+        assert!(error["error"]["message"].as_str().unwrap().contains("verbose_json"));
+
+        let (status, _) = send(&state, upload(&[("language", "klingon")])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Nothing was queued, and the uploads were removed
+        assert!(state.jobs.claim_next().unwrap().is_none());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(dir.path());
+
+        let request = tokio::spawn({
+            let state = state.clone();
+            async move { send(&state, upload(&[])).await }
+        });
+        let job = loop {
+            if let Some(job) = state.jobs.claim_next().unwrap() {
+                break job;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        let unsupported = AudioTranscriptionError::UnsupportedFormat { path: job.input.clone(), extension: Some("mp3".to_string()) };
+        state.jobs.fail(&job.id, &unsupported).unwrap();
+
+        let (status, body) = request.await.unwrap();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("\"code\":21"));
+    }
+}
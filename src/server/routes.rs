@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use axum::extract::{DefaultBodyLimit, FromRequest, Multipart, Path as UrlPath, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+use crate::error::AudioTranscriptionError;
+use crate::server::jobs::{Job, JobError, JobStatus};
+use crate::server::{captions, check_input, openai, AppState};
+
+/// Largest accepted upload (2 GiB)
+const MAX_UPLOAD_BYTES: usize = 2 * 1024 * 1024 * 1024;
+
+/// Format returned by `GET /jobs/{id}/result` when none is requested
+const DEFAULT_RESULT_FORMAT: &str = "json";
+
+/// Routes of the REST API:
+///
+/// - `POST /transcribe` with a multipart `file` upload or a JSON
+///   `{"path": "..."}` body queues a job; an optional `priority` field
+///   (default 0) moves it ahead of lower-priority jobs
+/// - `GET /jobs/{id}` reports the job's status
+/// - `GET /jobs/{id}/result?format=srt` returns the transcript in any
+///   registered output format
+/// - `GET /jobs/{id}/captions` is a WebSocket streaming the job's segments
+///   as they are transcribed (see [`captions`])
+/// - `GET /metrics` exposes throughput and queue metrics for Prometheus
+/// - `POST /v1/audio/transcriptions` follows OpenAI's transcription API,
+///   answering once the transcript is done
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/transcribe", post(submit))
+        .route("/v1/audio/transcriptions", post(openai::transcriptions))
+        .route("/jobs/{id}", get(job_status))
+        .route("/jobs/{id}/result", get(job_result))
+        .route("/jobs/{id}/captions", get(captions::captions))
+        .route("/metrics", get(metrics))
+        .layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES))
+        .with_state(state)
+}
+
+/// Error response: `{"error": "...", "code": 12}` with `code` set for
+/// pipeline errors
+pub(super) struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+    pub code: Option<u8>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self { status, message: message.into(), code: None }
+    }
+
+    fn with_code(code: u8, message: String) -> Self {
+        let status = match code {
+            12 | 13 | 21 | 80 => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self { status, message, code: Some(code) }
+    }
+}
+
+impl From<AudioTranscriptionError> for ApiError {
+    fn from(error: AudioTranscriptionError) -> Self {
+        Self::with_code(error.code(), error.to_string())
+    }
+}
+
+impl From<JobError> for ApiError {
+    fn from(error: JobError) -> Self {
+        Self::with_code(error.code, error.message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message, "code": self.code }))).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct SubmitRequest {
+    path: PathBuf,
+    #[serde(default)]
+    priority: i64,
+}
+
+async fn submit(State(state): State<AppState>, request: Request) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let is_multipart = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/form-data"));
+
+    let (input, uploaded, priority) = if is_multipart {
+        let multipart = Multipart::from_request(request, &state)
+            .await
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.body_text()))?;
+        let upload = read_upload(&state.upload_dir, multipart).await?;
+        let priority = match upload.fields.get("priority") {
+            Some(text) => text.trim().parse().map_err(|_| ApiError::new(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid priority '{}'", text),
+            ))?,
+            None => 0,
+        };
+        (upload.input, true, priority)
+    } else {
+        let Json(body) = Json::<SubmitRequest>::from_request(request, &state)
+            .await
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.body_text()))?;
+        check_input(&body.path)?;
+        (body.path, false, body.priority)
+    };
+
+    let id = state.jobs.insert(&input, uploaded, priority, None)?;
+    state.notify.notify_one();
+
+    tracing::info!(id = %id, "Job queued");
+    Ok((StatusCode::ACCEPTED, Json(json!({ "id": id, "status": JobStatus::Queued.as_str() }))))
+}
+
+/// A multipart upload
+pub(super) struct Upload {
+    pub input: PathBuf,                   // The `file` field, saved to the upload directory
+    pub fields: HashMap<String, String>,  // Every other field, as text
+}
+
+/// Stream the multipart `file` field into the upload directory and collect
+/// the other fields
+pub(super) async fn read_upload(upload_dir: &Path, mut multipart: Multipart) -> Result<Upload, ApiError> {
+    let bad_request = |e: axum::extract::multipart::MultipartError| ApiError::new(StatusCode::BAD_REQUEST, e.body_text());
+    let mut input = None;
+    let mut fields = HashMap::new();
+
+    while let Some(mut field) = multipart.next_field().await.map_err(bad_request)? {
+        match field.name().map(str::to_string) {
+            Some(name) if name == "file" => {}
+            Some(name) => {
+                fields.insert(name, field.text().await.map_err(bad_request)?);
+                continue;
+            }
+            None => continue,
+        }
+
+        // Keep the extension so the decoder can pick the container format
+        let extension = field
+            .file_name()
+            .and_then(|name| Path::new(name).extension())
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+        let path = upload_dir.join(format!("{}{}", uuid::Uuid::new_v4(), extension));
+
+        let mut file = tokio::fs::File::create(&path)
+            .await
+            .map_err(|source| AudioTranscriptionError::File { path: path.clone(), source })?;
+        while let Some(chunk) = field.chunk().await.map_err(bad_request)? {
+            file.write_all(&chunk)
+                .await
+                .map_err(|source| AudioTranscriptionError::File { path: path.clone(), source })?;
+        }
+        file.flush()
+            .await
+            .map_err(|source| AudioTranscriptionError::File { path: path.clone(), source })?;
+
+        input = Some(path);
+    }
+
+    let input = input.ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "Multipart body has no 'file' field"))?;
+    Ok(Upload { input, fields })
+}
+
+pub(super) fn find_job(state: &AppState, id: &str) -> Result<Job, ApiError> {
+    state.jobs
+        .get(id)?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("Unknown job '{}'", id)))
+}
+
+async fn job_status(State(state): State<AppState>, UrlPath(id): UrlPath<String>) -> Result<Json<Value>, ApiError> {
+    let job = find_job(&state, &id)?;
+
+    Ok(Json(json!({
+        "id": job.id,
+        "status": job.status.as_str(),
+        "priority": job.priority,
+        "attempts": job.attempts,
+        "partial": job.result.as_ref().map(|result| result.partial),
+        "error": job.error.map(|error| json!({ "code": error.code, "message": error.message })),
+    })))
+}
+
+#[derive(Deserialize)]
+struct ResultQuery {
+    format: Option<String>,
+}
+
+async fn job_result(
+    State(state): State<AppState>,
+    UrlPath(id): UrlPath<String>,
+    Query(query): Query<ResultQuery>,
+) -> Result<Response, ApiError> {
+    let job = find_job(&state, &id)?;
+    let Some(result) = job.result else {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("Job '{}' is {}, no result yet", id, job.status.as_str()),
+        ));
+    };
+
+    let name = query.format.as_deref().unwrap_or(DEFAULT_RESULT_FORMAT);
+    let formatter = state.generator.formatter(name).ok_or_else(|| ApiError::new(
+        StatusCode::BAD_REQUEST,
+        format!("Unknown output format '{}' (available: {})", name, state.generator.format_names().join(", ")),
+    ))?;
+
+    let content_type = content_type(formatter.extension());
+    Ok(([(header::CONTENT_TYPE, content_type)], formatter.format(&result)).into_response())
+}
+
+async fn metrics(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let text = state.metrics.render(&state.jobs).await?;
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")], text).into_response())
+}
+
+pub(super) fn content_type(extension: &str) -> &'static str {
+    match extension {
+        "txt" => "text/plain; charset=utf-8",
+        "srt" => "application/x-subrip; charset=utf-8",
+        "vtt" => "text/vtt; charset=utf-8",
+        "md" => "text/markdown; charset=utf-8",
+        "json" => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use axum::body::Body;
+    use tower::ServiceExt;
+    use crate::core::audio_processor::{ModelInfo, SpeechSegment, TranscriptResult};
+    use crate::server::jobs::{JobStore, DEFAULT_MAX_ATTEMPTS};
+
+    fn sample_result() -> TranscriptResult {
+        TranscriptResult {
+            segments: vec![SpeechSegment { start: 0.0, end: 1.5, text: "Hello.".to_string(), speaker: Some(1), ..Default::default() }],
+            audio_duration: Duration::from_secs(2),
+            processing_time: Duration::from_secs(1),
+            model_info: ModelInfo {
+                whisper_model: "medium".to_string(),
+                diarization_model: "pyannote".to_string(),
+                processing_time: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    async fn send(state: &AppState, request: Request<Body>) -> (StatusCode, String) {
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn state(upload_dir: &Path) -> AppState {
+        AppState::new(JobStore::in_memory(DEFAULT_MAX_ATTEMPTS).unwrap(), upload_dir.to_path_buf())
+    }
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_submit_path_queues_job() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("talk.wav");
+        std::fs::write(&input, b"RIFF").unwrap();
+        let state = state(dir.path());
+
+        let request = Request::post("/transcribe")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "path": input, "priority": 5 }).to_string()))
+            .unwrap();
+        let (status, body) = send(&state, request).await;
+
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let id = state.jobs.claim_next().unwrap().unwrap().id;
+        assert!(body.contains(&id));
+
+        let (status, body) = send(&state, get(&format!("/jobs/{}", id))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("\"status\":\"running\""));
+        assert!(body.contains("\"priority\":5"));
+
+        let (status, _) = send(&state, get(&format!("/jobs/{}/result", id))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_submit_upload_saves_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(dir.path());
+
+        let body = "--XYZ\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"memo.m4a\"\r\n\
+            Content-Type: audio/mp4\r\n\r\n\
+            audio-bytes\r\n\
+            --XYZ\r\n\
+            Content-Disposition: form-data; name=\"priority\"\r\n\r\n\
+            -1\r\n\
+            --XYZ--\r\n";
+        let request = Request::post("/transcribe")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=XYZ")
+            .body(Body::from(body))
+            .unwrap();
+        let (status, _) = send(&state, request).await;
+
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let job = state.jobs.claim_next().unwrap().unwrap();
+        assert!(job.uploaded);
+        assert_eq!(job.priority, -1);
+        assert_eq!(job.input.extension().unwrap(), "m4a");
+        assert_eq!(std::fs::read(&job.input).unwrap(), b"audio-bytes");
+    }
+
+    #[tokio::test]
+    async fn test_submit_missing_path_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(dir.path());
+
+        let request = Request::post("/transcribe")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"path": "/does/not/exist.wav"}"#))
+            .unwrap();
+        let (status, body) = send(&state, request).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("\"code\":12"));
+    }
+
+    #[tokio::test]
+    async fn test_completed_job_result_in_requested_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(dir.path());
+        let id = state.jobs.insert(&dir.path().join("talk.wav"), false, 0, None).unwrap();
+        state.jobs.complete(&id, &sample_result()).unwrap();
+
+        let (status, body) = send(&state, get(&format!("/jobs/{}/result?format=srt", id))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("00:00:00,000 --> 00:00:01,500"));
+
+        let (status, body) = send(&state, get(&format!("/jobs/{}/result", id))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("\"segments\""));
+
+        let (status, _) = send(&state, get(&format!("/jobs/{}/result?format=docx", id))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = send(&state, get("/jobs/unknown")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(dir.path());
+        state.jobs.insert(&dir.path().join("talk.wav"), false, 0, None).unwrap();
+
+        let (status, body) = send(&state, get("/metrics")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("audio_transcribe_queue_depth{status=\"queued\"} 1"));
+    }
+}
@@ -0,0 +1,545 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use futures_util::{future, stream, Stream, StreamExt};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use crate::core::audio_processor::{AudioProcessor, ProcessingConfig, SegmentSender, SpeechSegment, TranscriptResult};
+use crate::core::checkpoint::CheckpointLocation;
+use crate::core::model::ModelProvider;
+use crate::core::post_process::{PostProcessor, PostProcessorChain};
+use crate::core::progress::ProgressSink;
+use crate::core::selection::TimeRange;
+use crate::core::speakers::Voiceprint;
+use crate::core::transcription::{DecodingOptions, GpuBackend};
+use crate::error::{Result, AudioTranscriptionError};
+use crate::core::model::ModelSize;
+
+/// High-level entry point for embedding the transcription engine.
+///
+/// ```no_run
+/// # async fn run() -> audio_transcription_cli::Result<()> {
+/// use audio_transcription_cli::{ModelSize, Transcriber};
+///
+/// let transcriber = Transcriber::builder()
+///     .model(ModelSize::Small)
+///     .gpu(true)
+///     .build()?;
+/// let result = transcriber.transcribe("meeting.wav").await?;
+/// for segment in &result.segments {
+///     println!("[{:.1}s] {}", segment.start, segment.text);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Transcriber {
+    processor: Arc<AudioProcessor>,
+    post_processors: PostProcessorChain,
+    cancel: CancellationToken,
+}
+
+impl Transcriber {
+    pub fn builder() -> TranscriberBuilder {
+        TranscriberBuilder::default()
+    }
+
+    /// Transcribe and diarize a single audio file, then apply the
+    /// configured post-processors
+    pub async fn transcribe(&self, path: impl AsRef<Path>) -> Result<TranscriptResult> {
+        self.run(&self.processor, path.as_ref(), None, self.call_token(None)).await
+    }
+
+    /// Like [`transcribe`](Self::transcribe), for speech in `language` (a
+    /// code such as `de`) whatever language is configured, e.g. when each
+    /// request to a server names its own
+    pub async fn transcribe_in(&self, path: impl AsRef<Path>, language: &str) -> Result<TranscriptResult> {
+        self.run(&self.processor_for(language)?, path.as_ref(), None, self.call_token(None)).await
+    }
+
+    /// Like [`transcribe`](Self::transcribe), also sending each segment to
+    /// `segments` as soon as its chunk is done, as
+    /// [`transcribe_stream`](Self::transcribe_stream) yields them. Speech is
+    /// taken to be in `language` when one is given.
+    pub async fn transcribe_with_segments(
+        &self,
+        path: impl AsRef<Path>,
+        language: Option<&str>,
+        segments: SegmentSender,
+    ) -> Result<TranscriptResult> {
+        self.transcribe_cancellable(path, language, Some(segments), None).await
+    }
+
+    /// Like [`transcribe_with_segments`](Self::transcribe_with_segments),
+    /// with the segments optional, that also stops when `cancel` fires.
+    /// Only this call stops, returning what it has so far with `partial`
+    /// set; other calls on a shared transcriber carry on.
+    pub async fn transcribe_cancellable(
+        &self,
+        path: impl AsRef<Path>,
+        language: Option<&str>,
+        segments: Option<SegmentSender>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<TranscriptResult> {
+        let call = self.call_token(cancel);
+        // Ends the task that forwards `cancel` once the call returns
+        let _done = call.clone().drop_guard();
+        match language {
+            Some(language) => self.run(&self.processor_for(language)?, path.as_ref(), segments, call).await,
+            None => self.run(&self.processor, path.as_ref(), segments, call).await,
+        }
+    }
+
+    /// Token for one call, cancelled by [`cancel`](Self::cancel) and by
+    /// `cancel` but never affecting other calls
+    fn call_token(&self, cancel: Option<&CancellationToken>) -> CancellationToken {
+        let call = self.cancel.child_token();
+        if let Some(cancel) = cancel.cloned() {
+            let forwarded = call.clone();
+            tokio::spawn(async move {
+                tokio::select! {
+                    _ = cancel.cancelled() => forwarded.cancel(),
+                    _ = forwarded.cancelled() => {}
+                }
+            });
+        }
+        call
+    }
+
+    fn processor_for(&self, language: &str) -> Result<AudioProcessor> {
+        let mut config = self.config().clone();
+        config.language = Some(language.to_string());
+        config.detect_language = false;
+        config.validate()?;
+        Ok(self.processor.with_config(config))
+    }
+
+    async fn run(
+        &self,
+        processor: &AudioProcessor,
+        path: &Path,
+        segments: Option<SegmentSender>,
+        cancel: CancellationToken,
+    ) -> Result<TranscriptResult> {
+        check_input(path)?;
+        let mut result = match segments {
+            Some(segments) => processor.process_file_streaming(path, segments, cancel).await?,
+            None => processor.process_file(path, cancel).await?,
+        };
+        self.post_processors.run(&mut result)?;
+        Ok(result)
+    }
+
+    /// Transcribe a file, yielding segments in timestamp order as soon as
+    /// their chunk is done instead of waiting for the whole file.
+    ///
+    /// Streamed segments are produced before diarization and
+    /// post-processing, so `speaker` is always `None`; use
+    /// [`transcribe`](Self::transcribe) when speaker labels are needed. A pipeline failure ends the stream with an `Err`.
+    pub fn transcribe_stream(
+        &self,
+        path: impl Into<PathBuf>,
+    ) -> impl Stream<Item = Result<SpeechSegment>> + Send + 'static {
+        self.stream_with(path.into(), self.call_token(None))
+    }
+
+    fn stream_with(
+        &self,
+        path: PathBuf,
+        cancel: CancellationToken,
+    ) -> impl Stream<Item = Result<SpeechSegment>> + Send + 'static {
+        let processor = Arc::clone(&self.processor);
+
+        // Nothing is spawned until the first poll, so the stream can be
+        // created outside a runtime
+        stream::once(async move {
+            // Dropping the stream drops the guard and cancels the pipeline
+            let done = cancel.clone().drop_guard();
+            let (sender, receiver) = mpsc::unbounded_channel();
+            let pipeline = tokio::spawn(async move {
+                check_input(&path)?;
+                processor.process_file_streaming(&path, sender, cancel).await
+            });
+
+            // The segment channel closes when the pipeline finishes; the
+            // pipeline's outcome then decides whether a final error follows
+            let segments = stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.map(|segment| (Ok(segment), receiver))
+            });
+            let outcome = stream::once(async move {
+                let _done = done;
+                match pipeline.await {
+                    Ok(Ok(_)) => None,
+                    Ok(Err(e)) => Some(Err(e)),
+                    Err(e) => Some(Err(AudioTranscriptionError::Audio(
+                        format!("Transcription task failed: {}", e)
+                    ))),
+                }
+            })
+            .filter_map(future::ready);
+
+            segments.chain(outcome)
+        })
+        .flatten()
+    }
+
+    pub fn config(&self) -> &ProcessingConfig {
+        self.processor.config()
+    }
+
+    /// Stop scheduling new chunks; running transcriptions return what has
+    /// been completed so far with `partial` set on the result.
+    ///
+    /// This is permanent: every later call on this transcriber starts out
+    /// cancelled. To stop a single call, give it its own token with
+    /// [`transcribe_cancellable`](Self::transcribe_cancellable).
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+}
+
+/// Builder for [`Transcriber`]. Unset options keep the CLI defaults.
+#[derive(Clone, Default)]
+pub struct TranscriberBuilder {
+    config: ProcessingConfig,
+    progress: Option<Arc<dyn ProgressSink>>,
+    model_provider: Option<Arc<dyn ModelProvider>>,
+    post_processors: PostProcessorChain,
+    cancel: Option<CancellationToken>,
+    checkpoint: Option<CheckpointLocation>,
+}
+
+impl TranscriberBuilder {
+    pub fn model(mut self, model_size: ModelSize) -> Self {
+        self.config.model_size = model_size;
+        self
+    }
+
+    pub fn gpu(mut self, use_gpu: bool) -> Self {
+        self.config.use_gpu = use_gpu;
+        self
+    }
+
+    /// GPU devices to distribute chunks across
+    pub fn gpu_devices(mut self, devices: impl IntoIterator<Item = usize>) -> Self {
+        self.config.gpu_devices = devices.into_iter().collect();
+        self
+    }
+
+    /// GPU backend to require; building fails if it isn't compiled in
+    pub fn gpu_backend(mut self, backend: GpuBackend) -> Self {
+        self.config.gpu_backend = backend;
+        self
+    }
+
+    /// Number of parallel transcription jobs
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.config.parallel_jobs = jobs;
+        self
+    }
+
+    /// Longest chunk in seconds; chunks end in the last pause before it
+    pub fn chunk_duration(mut self, seconds: f32) -> Self {
+        self.config.chunk_duration = seconds;
+        self
+    }
+
+    /// Seconds of audio repeated at the start of each chunk
+    pub fn chunk_overlap(mut self, seconds: f32) -> Self {
+        self.config.chunk_overlap = seconds;
+        self
+    }
+
+    /// Extra attempts for a chunk whose inference fails before it is skipped
+    pub fn chunk_retries(mut self, retries: u32) -> Self {
+        self.config.chunk_retries = retries;
+        self
+    }
+
+    /// Speech probability, between 0 and 1, above which audio counts as speech
+    pub fn vad_threshold(mut self, threshold: f32) -> Self {
+        self.config.vad_threshold = threshold;
+        self
+    }
+
+    /// Upper bound on distinct speakers in the transcript
+    pub fn max_speakers(mut self, max_speakers: usize) -> Self {
+        self.config.max_speakers = max_speakers;
+        self
+    }
+
+    /// Spoken language as a code such as `en`, instead of detecting it
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.config.language = Some(language.into());
+        self
+    }
+
+    /// Detect the language once, from the first 30 seconds of speech
+    pub fn detect_language(mut self, enabled: bool) -> Self {
+        self.config.detect_language = enabled;
+        self
+    }
+
+    /// Time every word of the transcript
+    pub fn word_timestamps(mut self, enabled: bool) -> Self {
+        self.config.word_timestamps = enabled;
+        self
+    }
+
+    /// Translate the speech into English instead of transcribing it as spoken
+    pub fn translate(mut self, enabled: bool) -> Self {
+        self.config.translate = enabled;
+        self
+    }
+
+    /// Try the next smaller model when the chosen one fails to load, e.g.
+    /// because it doesn't fit in memory
+    pub fn model_fallback(mut self, enabled: bool) -> Self {
+        self.config.model_fallback = enabled;
+        self
+    }
+
+    /// Cap in bytes on the audio held in memory at once, which limits how
+    /// far decoding runs ahead of transcription
+    pub fn max_memory(mut self, bytes: u64) -> Self {
+        self.config.max_memory = Some(bytes);
+        self
+    }
+
+    /// Transcribe the segments below the refine threshold again with a
+    /// larger `model` once the whole file is done
+    pub fn refine_model(mut self, model: ModelSize) -> Self {
+        self.config.refine_model = Some(model);
+        self
+    }
+
+    /// Confidence, between 0 and 1, below which the refine model
+    /// transcribes a segment again
+    pub fn refine_threshold(mut self, threshold: f32) -> Self {
+        self.config.refine_threshold = threshold;
+        self
+    }
+
+    /// Drop text Whisper invents over silence and collapse repetition
+    /// loops; on by default
+    pub fn hallucination_filter(mut self, enabled: bool) -> Self {
+        self.config.hallucination_filter = enabled;
+        self
+    }
+
+    /// Whisper decoding knobs such as beam size and temperature
+    pub fn decoding(mut self, options: DecodingOptions) -> Self {
+        self.config.decoding = options;
+        self
+    }
+
+    /// Prime Whisper with domain terms and names before every chunk
+    pub fn initial_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.config.initial_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Transcribe only these parts of the file, in order and apart
+    pub fn ranges(mut self, ranges: impl IntoIterator<Item = TimeRange>) -> Self {
+        self.config.ranges = ranges.into_iter().collect();
+        self
+    }
+
+    /// Time the transcript from the start of the selected ranges rather
+    /// than the start of the file
+    pub fn rebase_timestamps(mut self, enabled: bool) -> Self {
+        self.config.rebase_timestamps = enabled;
+        self
+    }
+
+    /// Transcribe each channel on its own as one speaker, for call
+    /// recordings with a caller per channel, instead of diarizing
+    pub fn split_channels(mut self, enabled: bool) -> Self {
+        self.config.split_channels = enabled;
+        self
+    }
+
+    /// Enrolled speakers to recognize, e.g. from
+    /// [`SpeakerRegistry`](crate::SpeakerRegistry); their names replace
+    /// `SPEAKER_NN` in the transcript
+    pub fn voiceprints(mut self, voiceprints: Vec<Voiceprint>) -> Self {
+        self.config.voiceprints = voiceprints;
+        self
+    }
+
+    /// Use a validated config from [`ProcessingConfig::builder`]
+    pub fn config(mut self, config: ProcessingConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Receive stage changes, chunk completion and ETA while transcribing
+    pub fn progress(mut self, sink: Arc<dyn ProgressSink>) -> Self {
+        self.progress = Some(sink);
+        self
+    }
+
+    /// Supply models from custom storage instead of the download cache
+    pub fn model_provider(mut self, provider: Arc<dyn ModelProvider>) -> Self {
+        self.model_provider = Some(provider);
+        self
+    }
+
+    /// Append a post-processor; they run in the order they were added
+    pub fn post_processor(mut self, processor: Arc<dyn PostProcessor>) -> Self {
+        self.post_processors.push(processor);
+        self
+    }
+
+    /// Replace the post-processors, e.g. with a chain loaded from a file
+    pub fn post_processors(mut self, chain: PostProcessorChain) -> Self {
+        self.post_processors = chain;
+        self
+    }
+
+    /// Use an existing token, e.g. one shared with a Ctrl-C handler
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Save finished chunks in `dir` so an interrupted transcription of the
+    /// same file resumes instead of starting over
+    pub fn checkpoint_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.checkpoint = Some(CheckpointLocation::Dir(dir.into()));
+        self
+    }
+
+    /// Save finished chunks in the file `path`, e.g. next to the transcript
+    pub fn checkpoint_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint = Some(CheckpointLocation::File(path.into()));
+        self
+    }
+
+    pub fn build(self) -> Result<Transcriber> {
+        self.config.validate()?;
+
+        let mut processor = AudioProcessor::new(self.config);
+        if let Some(sink) = self.progress {
+            processor = processor.with_progress(sink);
+        }
+        if let Some(provider) = self.model_provider {
+            processor = processor.with_model_provider(provider);
+        }
+        processor = match self.checkpoint {
+            Some(CheckpointLocation::Dir(dir)) => processor.with_checkpoint_dir(dir),
+            Some(CheckpointLocation::File(path)) => processor.with_checkpoint_file(path),
+            None => processor,
+        };
+
+        Ok(Transcriber {
+            processor: Arc::new(processor),
+            post_processors: self.post_processors,
+            cancel: self.cancel.unwrap_or_default(),
+        })
+    }
+}
+
+fn check_input(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Err(AudioTranscriptionError::InputNotFound { path: path.to_path_buf() });
+    }
+    if !path.is_file() {
+        return Err(AudioTranscriptionError::InputNotAFile { path: path.to_path_buf() });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+    use super::*;
+
+    #[test]
+    fn test_builder_applies_options() {
+        let transcriber = Transcriber::builder()
+            .model(ModelSize::Small)
+            .gpu(false)
+            .jobs(2)
+            .chunk_duration(60.0)
+            .build()
+            .unwrap();
+
+        let config = transcriber.config();
+        assert!(matches!(config.model_size, ModelSize::Small));
+        assert!(!config.use_gpu);
+        assert_eq!(config.parallel_jobs, 2);
+        assert_eq!(config.chunk_duration, 60.0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_reports_missing_input() {
+        let transcriber = Transcriber::builder().build().unwrap();
+
+        let items: Vec<_> = transcriber.transcribe_stream("does-not-exist.wav").collect().await;
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+
+    #[test]
+    fn test_stream_starts_on_first_poll() {
+        let transcriber = Transcriber::builder().build().unwrap();
+
+        // Created outside a runtime; the pipeline only starts once polled
+        let stream = transcriber.transcribe_stream("does-not-exist.wav");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let items: Vec<_> = runtime.block_on(stream.collect());
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_stream_cancels_it() {
+        let transcriber = Transcriber::builder().build().unwrap();
+        let cancel = CancellationToken::new();
+
+        // The first poll spawns the pipeline, which hasn't run yet
+        let mut stream = Box::pin(transcriber.stream_with("does-not-exist.wav".into(), cancel.clone()));
+        assert!(stream.next().now_or_never().is_none());
+        assert!(!cancel.is_cancelled());
+
+        drop(stream);
+        assert!(cancel.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_language_is_rejected() {
+        let transcriber = Transcriber::builder().build().unwrap();
+
+        let error = transcriber.transcribe_in("meeting.wav", "klingon").await.unwrap_err();
+        assert_eq!(error.code(), 80);
+    }
+
+    #[tokio::test]
+    async fn test_cancelling_one_call_leaves_the_others() {
+        let transcriber = Transcriber::builder().build().unwrap();
+        let cancel = CancellationToken::new();
+        let call = transcriber.call_token(Some(&cancel));
+        let other = transcriber.call_token(None);
+
+        cancel.cancel();
+        tokio::time::timeout(std::time::Duration::from_secs(5), call.cancelled()).await.unwrap();
+        assert!(!other.is_cancelled());
+        assert!(!transcriber.cancellation_token().is_cancelled());
+
+        // Cancelling the transcriber itself stops every call, for good
+        transcriber.cancel();
+        assert!(other.is_cancelled());
+        assert!(transcriber.call_token(None).is_cancelled());
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_options() {
+        assert!(Transcriber::builder().jobs(0).build().is_err());
+        assert!(Transcriber::builder().chunk_duration(0.0).build().is_err());
+        assert!(Transcriber::builder().chunk_duration(f32::NAN).build().is_err());
+        assert!(Transcriber::builder().gpu(false).gpu_devices([0]).build().is_err());
+    }
+}
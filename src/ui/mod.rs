@@ -1,3 +1,5 @@
 pub mod progress_display;
 
 pub use progress_display::ProgressDisplay;
+#[cfg(feature = "tui")]
+pub use progress_display::LiveProgress;
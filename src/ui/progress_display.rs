@@ -1,33 +1,30 @@
-use std::time::Instant;
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum ProcessingStage {
-    VAD,
-    Chunking,
-    Transcription,
-    Diarization,
-    Merging,
-    Complete,
-}
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+#[cfg(feature = "tui")]
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use crate::core::audio_processor::TranscriptResult;
+use crate::core::progress::{self, ProgressEvent, ProgressSink};
 
-impl std::fmt::Display for ProcessingStage {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ProcessingStage::VAD => write!(f, "Voice Activity Detection"),
-            ProcessingStage::Chunking => write!(f, "Audio Chunking"),
-            ProcessingStage::Transcription => write!(f, "Transcription"),
-            ProcessingStage::Diarization => write!(f, "Speaker Diarization"),
-            ProcessingStage::Merging => write!(f, "Merging Results"),
-            ProcessingStage::Complete => write!(f, "Complete"),
-        }
-    }
-}
+pub use crate::core::progress::ProcessingStage;
+
+/// Width of the chunk bar in characters
+const BAR_WIDTH: usize = 30;
 
 pub struct ProgressDisplay {
     current_stage: ProcessingStage,
     total_chunks: usize,
     completed_chunks: usize,
+    completed_audio_secs: f32,
+    total_audio_secs: f32,
     start_time: Instant,
+    workers: Vec<WorkerState>,  // Indexed by worker number
+}
+
+/// What a transcription worker is doing
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct WorkerState {
+    device: Option<usize>,  // GPU, or None on the CPU
+    chunk: Option<usize>,   // None while idle
 }
 
 impl ProgressDisplay {
@@ -36,12 +33,15 @@ impl ProgressDisplay {
             current_stage: ProcessingStage::VAD,
             total_chunks: 0,
             completed_chunks: 0,
+            completed_audio_secs: 0.0,
+            total_audio_secs: 0.0,
             start_time: Instant::now(),
+            workers: Vec::new(),
         }
     }
 
     pub fn set_stage(&mut self, stage: ProcessingStage) {
-        log::info!("Processing stage: {}", stage);
+        tracing::info!(%stage, "Processing stage");
         self.current_stage = stage;
     }
 
@@ -51,56 +51,336 @@ impl ProgressDisplay {
         
         if total > 0 {
             let percentage = (completed as f32 / total as f32) * 100.0;
-            log::debug!("Progress: {}/{} ({:.1}%)", completed, total, percentage);
+            tracing::debug!(completed, total, percentage, "Progress");
         }
     }
 
-    pub fn render(&self) -> String {
-        // TODO: Implement rich terminal progress display
-        // This will be implemented in task 10
-        let elapsed = self.start_time.elapsed();
-        let elapsed_secs = elapsed.as_secs();
-        
-        let progress_info = if self.total_chunks > 0 {
-            let percentage = (self.completed_chunks as f32 / self.total_chunks as f32) * 100.0;
-            format!(" ({}/{} - {:.1}%)", self.completed_chunks, self.total_chunks, percentage)
+    /// Record progress in seconds of audio. Chunks are still being found
+    /// while the first ones are transcribed, so this gives a steadier
+    /// estimate than chunk counts alone.
+    pub fn update_audio_progress(&mut self, completed_secs: f32, total_secs: f32) {
+        self.completed_audio_secs = completed_secs;
+        self.total_audio_secs = total_secs;
+    }
+
+    /// Worker `worker` picked up chunk `index`
+    pub fn start_chunk(&mut self, worker: usize, device: Option<usize>, index: usize) {
+        if self.workers.len() <= worker {
+            self.workers.resize(worker + 1, WorkerState::default());
+        }
+        self.workers[worker] = WorkerState { device, chunk: Some(index) };
+    }
+
+    /// Chunk `index` is done, so the worker that had it is idle again
+    pub fn finish_chunk(&mut self, index: usize) {
+        for state in &mut self.workers {
+            if state.chunk == Some(index) {
+                state.chunk = None;
+            }
+        }
+    }
+
+    /// Share of the work done, in percent: of the audio once its length is
+    /// known, otherwise of the chunks found so far
+    pub fn percentage(&self) -> f32 {
+        if self.total_audio_secs > 0.0 {
+            (self.completed_audio_secs / self.total_audio_secs).min(1.0) * 100.0
+        } else if self.total_chunks > 0 {
+            (self.completed_chunks as f32 / self.total_chunks as f32) * 100.0
         } else {
-            String::new()
-        };
+            0.0
+        }
+    }
 
-        format!(
-            "Stage: {}{} | Elapsed: {}:{:02}",
-            self.current_stage,
-            progress_info,
-            elapsed_secs / 60,
-            elapsed_secs % 60
-        )
+    /// Seconds of audio transcribed per second of processing so far
+    pub fn realtime_factor(&self) -> Option<f32> {
+        let elapsed = self.start_time.elapsed().as_secs_f32();
+        (self.completed_audio_secs > 0.0 && elapsed > 0.0).then(|| self.completed_audio_secs / elapsed)
+    }
+
+    /// The current stage with an elapsed-time bar, chunk progress and the
+    /// busy workers, one line each. Chunk and worker lines are left out
+    /// until there is something to show.
+    ///
+    /// ```text
+    /// Stage: Transcription | Elapsed: 2:31
+    /// [============>                 ] 12/31 chunks, 41.3%, ETA 3:35, 8.5x realtime
+    /// Workers: #1 GPU 0 chunk 13 | #2 GPU 1 idle
+    /// ```
+    pub fn render(&self) -> String {
+        let mut output = self.stage_line();
+        if let Some(chunks) = self.chunk_line() {
+            let filled = ((self.percentage() / 100.0 * BAR_WIDTH as f32) as usize).min(BAR_WIDTH);
+            let head = if filled < BAR_WIDTH { ">" } else { "" };
+            let bar = format!("{}{}", "=".repeat(filled), head);
+            output.push_str(&format!("\n[{:<width$}] {}", bar, chunks, width = BAR_WIDTH));
+        }
+        if let Some(workers) = self.workers_line() {
+            output.push('\n');
+            output.push_str(&workers);
+        }
+        output
+    }
+
+    /// `Stage: Transcription | Elapsed: 2:31`
+    pub fn stage_line(&self) -> String {
+        format!("Stage: {} | Elapsed: {}", self.current_stage, format_duration(self.start_time.elapsed()))
+    }
+
+    /// `12/31 chunks, 41.3%, ETA 3:35, 8.5x realtime`, once chunks are found
+    pub fn chunk_line(&self) -> Option<String> {
+        if self.total_chunks == 0 {
+            return None;
+        }
+        let mut line = format!("{}/{} chunks, {:.1}%", self.completed_chunks, self.total_chunks, self.percentage());
+        if let Some(remaining) = self.estimate_remaining() {
+            line.push_str(&format!(", ETA {}", format_duration(remaining)));
+        }
+        if let Some(factor) = self.realtime_factor() {
+            line.push_str(&format!(", {:.1}x realtime", factor));
+        }
+        Some(line)
+    }
+
+    /// `Workers: #1 GPU 0 chunk 13 | #2 CPU idle`, while transcribing
+    pub fn workers_line(&self) -> Option<String> {
+        if self.workers.is_empty() || self.current_stage != ProcessingStage::Transcription {
+            return None;
+        }
+        let workers: Vec<String> = self.workers.iter().enumerate().map(|(number, state)| {
+            let device = match state.device {
+                Some(device) => format!("GPU {}", device),
+                None => "CPU".to_string(),
+            };
+            match state.chunk {
+                Some(chunk) => format!("#{} {} chunk {}", number + 1, device, chunk + 1),
+                None => format!("#{} {} idle", number + 1, device),
+            }
+        }).collect();
+        Some(format!("Workers: {}", workers.join(" | ")))
+    }
+
+    /// End-of-run summary with overall and per-stage realtime factors
+    pub fn render_summary(result: &TranscriptResult) -> String {
+        let mut output = format!(
+            "Processed {:.1}s of audio in {:.1}s ({:.1}x realtime)\n",
+            result.audio_duration.as_secs_f32(),
+            result.processing_time.as_secs_f32(),
+            result.realtime_factor()
+        );
+        if let Some(language) = &result.model_info.language {
+            output.push_str(&format!("   Language: {}\n", language));
+        }
+        if result.model_info.translated {
+            output.push_str("   Translated to English\n");
+        }
+        if !result.model_info.load_time.is_zero() {
+            output.push_str(&format!("   Model load     {:>7.1}s  (part of transcription)\n", result.model_info.load_time.as_secs_f32()));
+        }
+
+        let stage_factors = result.stage_realtime_factors();
+        for ((name, elapsed), (_, factor)) in result.stage_timings.stages().iter().zip(stage_factors) {
+            if elapsed.is_zero() {
+                continue;
+            }
+            output.push_str(&format!(
+                "   {:<14} {:>7.1}s  ({:.1}x realtime)\n",
+                name,
+                elapsed.as_secs_f32(),
+                factor
+            ));
+        }
+        if result.tokens > 0 {
+            output.push_str(&format!(
+                "   Decoded {} tokens ({:.1} tokens/s)\n",
+                result.tokens,
+                result.tokens_per_second()
+            ));
+        }
+
+        output
     }
 
     pub fn current_stage(&self) -> &ProcessingStage {
         &self.current_stage
     }
 
-    pub fn elapsed_time(&self) -> std::time::Duration {
+    pub fn elapsed_time(&self) -> Duration {
         self.start_time.elapsed()
     }
 
-    pub fn estimate_remaining(&self) -> Option<std::time::Duration> {
-        if self.completed_chunks > 0 && self.total_chunks > self.completed_chunks {
+    pub fn estimate_remaining(&self) -> Option<Duration> {
+        if self.completed_audio_secs > 0.0 {
+            progress::estimate_remaining(
+                self.start_time.elapsed(),
+                self.completed_audio_secs,
+                self.total_audio_secs,
+            )
+        } else if self.completed_chunks > 0 && self.total_chunks > self.completed_chunks {
             let elapsed = self.start_time.elapsed();
             let avg_time_per_chunk = elapsed.as_secs_f32() / self.completed_chunks as f32;
             let remaining_chunks = self.total_chunks - self.completed_chunks;
             let estimated_remaining_secs = avg_time_per_chunk * remaining_chunks as f32;
             
-            Some(std::time::Duration::from_secs_f32(estimated_remaining_secs))
+            Some(Duration::from_secs_f32(estimated_remaining_secs))
         } else {
             None
         }
     }
 }
 
+/// `m:ss`, with minutes going past 59 on long runs
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
 impl Default for ProgressDisplay {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+impl ProgressDisplay {
+    fn apply(&mut self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::StageStarted(stage) => self.set_stage(stage),
+            ProgressEvent::ChunkStarted { index, worker, device } => self.start_chunk(worker, device, index),
+            ProgressEvent::ChunkCompleted { index, completed, total, completed_audio_secs, total_audio_secs, .. } => {
+                self.finish_chunk(index);
+                self.update_progress(completed, total);
+                self.update_audio_progress(completed_audio_secs, total_audio_secs);
+            }
+        }
+    }
+}
+
+/// Shared handle so the pipeline can drive the display from worker tasks
+impl ProgressSink for Mutex<ProgressDisplay> {
+    fn report(&self, event: ProgressEvent) {
+        let Ok(mut display) = self.lock() else { return };
+        display.apply(event);
+    }
+}
+
+/// The display drawn live on stderr as a stage spinner, a chunk bar and
+/// a line of workers, hidden when stderr isn't a terminal. The bars are
+/// cleared when processing completes or the display is dropped.
+#[cfg(feature = "tui")]
+pub struct LiveProgress {
+    bars: MultiProgress,
+    state: Mutex<LiveState>,
+}
+
+#[cfg(feature = "tui")]
+struct LiveState {
+    display: ProgressDisplay,
+    stage: Option<ProgressBar>,    // Each line is added once there is something to show
+    chunks: Option<ProgressBar>,
+    workers: Option<ProgressBar>,
+}
+
+#[cfg(feature = "tui")]
+impl LiveProgress {
+    /// Draw among `bars`, so log lines printed through them stay above
+    pub fn new(bars: &MultiProgress) -> Self {
+        Self {
+            bars: bars.clone(),
+            state: Mutex::new(LiveState { display: ProgressDisplay::new(), stage: None, chunks: None, workers: None }),
+        }
+    }
+
+    /// Clear the bars, leaving the terminal as it was before
+    pub fn finish(&self) {
+        let Ok(mut state) = self.state.lock() else { return };
+        for bar in [state.stage.take(), state.chunks.take(), state.workers.take()].into_iter().flatten() {
+            bar.finish_and_clear();
+            self.bars.remove(&bar);
+        }
+    }
+
+    fn line(&self, template: &str) -> ProgressBar {
+        let bar = self.bars.add(ProgressBar::new(BAR_WIDTH as u64));
+        if let Ok(style) = ProgressStyle::with_template(template) {
+            bar.set_style(style.progress_chars("=> "));
+        }
+        bar
+    }
+}
+
+#[cfg(feature = "tui")]
+impl ProgressSink for LiveProgress {
+    fn report(&self, event: ProgressEvent) {
+        if matches!(event, ProgressEvent::StageStarted(ProcessingStage::Complete)) {
+            self.finish();
+            return;
+        }
+        let Ok(mut state) = self.state.lock() else { return };
+        let state = &mut *state;
+        state.display.apply(event);
+
+        let stage = state.stage.get_or_insert_with(|| self.line("{spinner} Stage: {msg} | Elapsed: {elapsed}")).clone();
+        let display = &state.display;
+        stage.set_message(display.current_stage().to_string());
+        // Model downloads draw their own bars before transcription starts,
+        // so the spinner only ticks on its own while chunks are transcribed
+        if *display.current_stage() == ProcessingStage::Transcription {
+            stage.enable_steady_tick(Duration::from_millis(200));
+        } else {
+            stage.disable_steady_tick();
+        }
+
+        let chunk_line = display.chunk_line();
+        let position = (display.percentage() / 100.0 * BAR_WIDTH as f32) as u64;
+        let workers_line = display.workers_line();
+        if let Some(line) = chunk_line {
+            let chunks = state.chunks.get_or_insert_with(|| self.line("[{bar:30}] {msg}"));
+            chunks.set_position(position);
+            chunks.set_message(line);
+        }
+        match workers_line {
+            Some(line) => state.workers.get_or_insert_with(|| self.line("{msg}")).set_message(line),
+            None => {
+                if let Some(workers) = state.workers.take() {
+                    workers.finish_and_clear();
+                    self.bars.remove(&workers);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tui")]
+impl Drop for LiveProgress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_shows_chunks_and_workers() {
+        let mut display = ProgressDisplay::new();
+        assert!(display.render().starts_with("Stage: Voice Activity Detection | Elapsed: 0:0"));
+        assert_eq!(display.render().lines().count(), 1);
+
+        display.set_stage(ProcessingStage::Transcription);
+        display.start_chunk(0, Some(0), 0);
+        display.start_chunk(1, None, 1);
+        display.finish_chunk(0);
+        display.update_progress(1, 4);
+        display.update_audio_progress(15.0, 60.0);
+
+        let rendered = display.render();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("[=======>                      ] 1/4 chunks, 25.0%"), "{}", lines[1]);
+        assert_eq!(lines[2], "Workers: #1 GPU 0 idle | #2 CPU chunk 2");
+
+        display.set_stage(ProcessingStage::Diarization);
+        assert_eq!(display.render().lines().count(), 2);
+    }
+}